@@ -0,0 +1,111 @@
+use anyhow::{Context, Result};
+use lending_anchor::{
+    account_data::reserve::{
+        borrow_rate_point::BorrowRatePoint, reserve_config::ReserveConfig,
+        reserve_fees::ReserveFees, term_offer::TermOffer,
+    },
+    math::common::BPS_SCALE,
+};
+use serde::Deserialize;
+use std::{fs, path::Path};
+
+/// TOML representation of `ReserveConfig`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReserveConfigFile {
+    pub loan_to_value_ratio: u8,
+    pub liquidation_bonus: u8,
+    pub liquidation_threshold: u8,
+    /// (utilization, borrow APY) points in basis points
+    pub borrow_rate_curve: Vec<(u16, u32)>,
+    pub fees: ReserveFeesFile,
+    #[serde(default)]
+    pub isolated: bool,
+    #[serde(default)]
+    pub debt_ceiling: u64,
+    #[serde(default)]
+    pub borrowable_in_isolation: bool,
+    #[serde(default)]
+    pub emode_category: u8,
+    #[serde(default = "default_borrow_weight")]
+    pub borrow_weight: u16,
+    #[serde(default)]
+    pub price_guard_bps_per_slot: u16,
+    #[serde(default)]
+    pub price_guard_confirmations: u8,
+    #[serde(default)]
+    pub twap_fallback_max_slots: u64,
+    #[serde(default)]
+    pub twap_liquidation_bonus: u8,
+    /// (term in slots, fixed borrow APY in basis points) offers
+    #[serde(default)]
+    pub term_offers: Vec<(u64, u32)>,
+    #[serde(default)]
+    pub term_penalty_rate_bps: u32,
+    #[serde(default)]
+    pub insurance_interest_bps: u16,
+    #[serde(default)]
+    pub insurance_liquidation_bps: u16,
+    /// The liquidation bonus if omitted, which keeps the bonus fixed
+    #[serde(default)]
+    pub min_liquidation_bonus: Option<u8>,
+}
+
+/// TOML representation of `ReserveFees`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReserveFeesFile {
+    pub borrow_fee_wad: u64,
+    pub flash_loan_fee_wad: u64,
+    pub host_fee_percentage: u8,
+}
+
+fn default_borrow_weight() -> u16 {
+    BPS_SCALE
+}
+
+impl ReserveConfigFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("failed to parse {}", path.display()))
+    }
+}
+
+impl From<ReserveConfigFile> for ReserveConfig {
+    fn from(file: ReserveConfigFile) -> Self {
+        Self {
+            loan_to_value_ratio: file.loan_to_value_ratio,
+            liquidation_bonus: file.liquidation_bonus,
+            liquidation_threshold: file.liquidation_threshold,
+            borrow_rate_curve: file
+                .borrow_rate_curve
+                .into_iter()
+                .map(|(utilization_bps, rate_bps)| BorrowRatePoint::new(utilization_bps, rate_bps))
+                .collect(),
+            fees: ReserveFees {
+                borrow_fee_wad: file.fees.borrow_fee_wad,
+                flash_loan_fee_wad: file.fees.flash_loan_fee_wad,
+                host_fee_percentage: file.fees.host_fee_percentage,
+            },
+            isolated: file.isolated,
+            debt_ceiling: file.debt_ceiling,
+            borrowable_in_isolation: file.borrowable_in_isolation,
+            emode_category: file.emode_category,
+            borrow_weight: file.borrow_weight,
+            price_guard_bps_per_slot: file.price_guard_bps_per_slot,
+            price_guard_confirmations: file.price_guard_confirmations,
+            twap_fallback_max_slots: file.twap_fallback_max_slots,
+            twap_liquidation_bonus: file.twap_liquidation_bonus,
+            term_offers: file
+                .term_offers
+                .into_iter()
+                .map(|(term_slots, rate_bps)| TermOffer::new(term_slots, rate_bps))
+                .collect(),
+            term_penalty_rate_bps: file.term_penalty_rate_bps,
+            insurance_interest_bps: file.insurance_interest_bps,
+            insurance_liquidation_bps: file.insurance_liquidation_bps,
+            min_liquidation_bonus: file.min_liquidation_bonus.unwrap_or(file.liquidation_bonus),
+        }
+    }
+}
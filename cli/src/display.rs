@@ -0,0 +1,222 @@
+use anchor_lang::prelude::Pubkey;
+use anyhow::Result;
+use lending_anchor::{
+    account_data::{obligation::Obligation, reserve::Reserve},
+    math::{common::WAD, rate::Rate},
+};
+
+fn wad_to_f64(value: u128) -> f64 {
+    value as f64 / WAD as f64
+}
+
+fn percent(rate: Rate) -> String {
+    format!("{:.2}%", wad_to_f64(rate.to_scaled_val()) * 100.0)
+}
+
+fn stale(stale: bool) -> &'static str {
+    if stale {
+        " (stale)"
+    } else {
+        ""
+    }
+}
+
+pub fn print_reserve(pubkey: &Pubkey, reserve: &Reserve) -> Result<()> {
+    let liquidity = &reserve.liquidity;
+    let collateral = &reserve.collateral;
+
+    println!("Reserve {}", pubkey);
+    println!("  Lending market:           {}", reserve.lending_market);
+    println!(
+        "  Last update slot:         {}{}",
+        reserve.last_update.slot,
+        stale(reserve.last_update.stale)
+    );
+    println!();
+    println!("Liquidity");
+    println!(
+        "  Mint:                     {} (decimals {})",
+        liquidity.mint_pubkey, liquidity.mint_decimals
+    );
+    println!("  Token program:            {}", liquidity.token_program_id);
+    println!("  Supply:                   {}", liquidity.supply_pubkey);
+    println!("  Fee receiver:             {}", liquidity.fee_receiver);
+    println!("  Oracle:                   {}", liquidity.oracle_pubkey);
+    println!("  Available amount:         {}", liquidity.available_amount);
+    println!("  Borrowed amount:          {}", liquidity.borrowed_amount);
+    println!(
+        "  Term borrowed amount:     {}",
+        reserve.term_borrowed_amount
+    );
+    println!("  Market price:             {}", liquidity.market_price);
+    println!(
+        "  Cumulative borrow rate:   {:.6}",
+        wad_to_f64(liquidity.cumulative_borrow_rate_wads)
+    );
+    println!(
+        "  Utilization rate:         {}",
+        percent(reserve.utilization_rate()?)
+    );
+    println!(
+        "  Current borrow rate:      {}",
+        percent(reserve.current_borrow_rate()?)
+    );
+    println!();
+    println!("Collateral");
+    println!("  Mint:                     {}", collateral.mint_pubkey);
+    println!("  Supply:                   {}", collateral.supply_pubkey);
+    println!(
+        "  Mint total supply:        {}",
+        collateral.mint_total_supply
+    );
+    println!(
+        "  Exchange rate:            {:.6}",
+        wad_to_f64(reserve.collateral_exchange_rate()?.0.to_scaled_val())
+    );
+    println!();
+    println!(
+        "Isolated debt:              {}",
+        reserve.isolated_debt_value()?
+    );
+    for debt in &reserve.isolated_debts {
+        println!(
+            "  {}: {} ({})",
+            debt.borrow_reserve, debt.borrowed_amount, debt.market_value
+        );
+    }
+    let price_guard = &reserve.price_guard;
+    if price_guard.guarded {
+        println!(
+            "Price guarded:              pending {} at slot {} ({} confirmations)",
+            price_guard.pending_price, price_guard.pending_slot, price_guard.confirmations
+        );
+    }
+    println!(
+        "TWAP:                       {} at slot {}",
+        reserve.twap.price, reserve.twap.slot
+    );
+    if reserve.twap.fallback {
+        println!("Price fallback:             market price is the TWAP");
+    }
+    if let Some(insurance_vault) = reserve.insurance_vault {
+        println!("Insurance vault:            {}", insurance_vault);
+    }
+    println!(
+        "Insurance pending:          {}",
+        reserve.insurance_pending_amount()
+    );
+    if let Some(reward_pool) = reserve.deposit_reward_pool {
+        println!("Deposit reward pool:        {}", reward_pool);
+    }
+    if let Some(reward_pool) = reserve.borrow_reward_pool {
+        println!("Borrow reward pool:         {}", reward_pool);
+    }
+    println!("Config: {:#?}", reserve.config);
+    Ok(())
+}
+
+pub fn print_obligation(pubkey: &Pubkey, obligation: &Obligation) -> Result<()> {
+    println!("Obligation {}", pubkey);
+    println!("  Lending market:           {}", obligation.lending_market);
+    println!("  Owner:                    {}", obligation.owner);
+    println!(
+        "  Last update slot:         {}{}",
+        obligation.last_update.slot,
+        stale(obligation.last_update.stale)
+    );
+    println!("  E-mode category:          {}", obligation.emode_category);
+    if let Some(isolated_reserve) = obligation.isolated_reserve {
+        println!("  Isolated reserve:         {}", isolated_reserve);
+        for debt in &obligation.isolated_debts {
+            println!(
+                "  Isolated debt:            {} of {}",
+                debt.borrowed_amount, debt.borrow_reserve
+            );
+        }
+    }
+    if let Some(delegate) = obligation.delegate {
+        println!("  Delegate:                 {}", delegate.authority);
+        println!("  Delegate permissions:     {:#06b}", delegate.permissions);
+        match delegate.borrow_allowance {
+            Some(allowance) => println!("  Delegate allowance:       {}", allowance),
+            None => println!("  Delegate allowance:       unlimited"),
+        }
+    }
+    if let Some(referrer) = obligation.referrer {
+        println!("  Referrer:                 {}", referrer);
+    }
+    if obligation.price_guarded {
+        println!("  Price guarded:            borrows and liquidations are blocked");
+    }
+    if obligation.price_fallback {
+        println!("  Price fallback:           valued at the TWAP, borrows are blocked");
+    }
+    println!();
+    println!("  Deposited value:          {}", obligation.deposited_value);
+    println!("  Borrowed value:           {}", obligation.borrowed_value);
+    println!(
+        "  Unweighted borrowed value: {}",
+        obligation.unweighted_borrowed_value
+    );
+    println!(
+        "  Allowed borrow value:     {}",
+        obligation.allowed_borrow_value
+    );
+    println!(
+        "  Unhealthy borrow value:   {}",
+        obligation.unhealthy_borrow_value
+    );
+    if obligation.borrowed_value == 0 {
+        println!("  Health factor:            -");
+    } else {
+        println!(
+            "  Health factor:            {:.4}",
+            wad_to_f64(
+                Rate::from_ratio(obligation.unhealthy_borrow_value, obligation.borrowed_value)?
+                    .to_scaled_val()
+            )
+        );
+    }
+    println!();
+    println!("Deposits");
+    for collateral in &obligation.deposits {
+        println!(
+            "  {}  amount {}  value {}",
+            collateral.deposit_reserve, collateral.deposited_amount, collateral.market_value
+        );
+    }
+    println!();
+    println!("Borrows");
+    for liquidity in &obligation.borrows {
+        println!(
+            "  {}  amount {}  value {}",
+            liquidity.borrow_reserve, liquidity.borrowed_amount, liquidity.market_value
+        );
+    }
+    if !obligation.term_loans.is_empty() {
+        println!();
+        println!("Term loans");
+        for term_loan in &obligation.term_loans {
+            println!(
+                "  {}  amount {}  principal {}  rate {}bps  matures at slot {}  value {}",
+                term_loan.borrow_reserve,
+                term_loan.borrowed_amount,
+                term_loan.principal_amount,
+                term_loan.rate_bps,
+                term_loan.maturity_slot,
+                term_loan.market_value
+            );
+        }
+    }
+    if !obligation.rewards.is_empty() {
+        println!();
+        println!("Rewards");
+        for reward in &obligation.rewards {
+            println!(
+                "  {}  staked {}  unclaimed {}",
+                reward.reward_pool, reward.staked_amount, reward.unclaimed_amount
+            );
+        }
+    }
+    Ok(())
+}
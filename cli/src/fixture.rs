@@ -0,0 +1,22 @@
+use anyhow::{Context, Result};
+use serde_json::json;
+use solana_sdk::{pubkey::Pubkey, rent::Rent};
+use std::{fs, path::Path};
+
+/// Write `<pubkey>.json` in the format `solana account --output json` produces,
+/// which `solana-test-validator --account <pubkey> <file>` loads at genesis
+pub fn write_account(dir: &Path, pubkey: &Pubkey, owner: &Pubkey, data: Vec<u8>) -> Result<()> {
+    let account = json!({
+        "pubkey": pubkey.to_string(),
+        "account": {
+            "lamports": Rent::default().minimum_balance(data.len()),
+            "data": [base64::encode(&data), "base64"],
+            "owner": owner.to_string(),
+            "executable": false,
+            "rentEpoch": 0,
+        },
+    });
+    let path = dir.join(format!("{}.json", pubkey));
+    fs::write(&path, serde_json::to_string_pretty(&account)?)
+        .with_context(|| format!("failed to write {}", path.display()))
+}
@@ -0,0 +1,413 @@
+mod config;
+mod display;
+mod fixture;
+
+use anchor_lang::{AnchorDeserialize, Discriminator, InstructionData, ToAccountMetas};
+use anyhow::{bail, Result};
+use clap::{Parser, Subcommand};
+use config::ReserveConfigFile;
+use lending_anchor::{
+    account_data::{obligation::Obligation, reserve::Reserve, v1},
+    test_utils::{PythPrice, PythProduct},
+};
+use lending_client::{
+    instruction, pda,
+    rpc::{load_keypair, rpc_url, RpcClient},
+    state,
+};
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[clap(name = "lending-cli", about = "Operate lending-anchor markets")]
+struct Cli {
+    /// RPC URL or moniker (localhost, devnet, testnet, mainnet-beta)
+    #[clap(short, long, global = true, default_value = "localhost")]
+    url: String,
+
+    /// Keypair paying for and signing transactions, defaults to the Solana CLI keypair
+    #[clap(short, long, global = true)]
+    keypair: Option<PathBuf>,
+
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a lending market owned by the keypair
+    CreateMarket {
+        /// Quote currency symbol matching the Pyth products, e.g. USD
+        #[clap(long, default_value = "USD")]
+        quote_currency: String,
+
+        /// Oracle program that owns the Pyth accounts of the reserves
+        #[clap(long)]
+        oracle_program_id: Pubkey,
+    },
+    /// Add a reserve to a lending market from a TOML config
+    AddReserve {
+        #[clap(long)]
+        market: Pubkey,
+
+        /// Path to a TOML file mapped onto ReserveConfig
+        #[clap(long)]
+        config: PathBuf,
+
+        #[clap(long)]
+        liquidity_mint: Pubkey,
+
+        /// Initial liquidity deposited from the source account
+        #[clap(long)]
+        liquidity_amount: u64,
+
+        /// Defaults to the keypair's associated token account
+        #[clap(long)]
+        source_liquidity: Option<Pubkey>,
+
+        #[clap(long)]
+        pyth_product: Pubkey,
+
+        #[clap(long)]
+        pyth_price: Pubkey,
+    },
+    /// Replace the config of a reserve with a TOML config
+    UpdateReserve {
+        #[clap(long)]
+        reserve: Pubkey,
+
+        #[clap(long)]
+        config: PathBuf,
+    },
+    /// Accept the oracle price held back by the price guard of a reserve
+    OverridePriceGuard {
+        #[clap(long)]
+        reserve: Pubkey,
+    },
+    /// Transfer ownership of a lending market
+    SetMarketOwner {
+        #[clap(long)]
+        market: Pubkey,
+
+        #[clap(long)]
+        new_owner: Pubkey,
+    },
+    /// Migrate a lending market with its reserves and obligations to the current account version
+    Migrate {
+        #[clap(long)]
+        market: Pubkey,
+    },
+    /// Print a reserve with its computed rates
+    ShowReserve { reserve: Pubkey },
+    /// Print an obligation with its health
+    ShowObligation { obligation: Pubkey },
+    /// Write Pyth product and price accounts for `solana-test-validator --account`
+    PythFixture {
+        /// Aggregate price, scaled by 10^expo
+        #[clap(long, allow_hyphen_values = true)]
+        price: i64,
+
+        #[clap(long, default_value = "-8", allow_hyphen_values = true)]
+        expo: i32,
+
+        #[clap(long, default_value = "0")]
+        conf: u64,
+
+        #[clap(long, default_value = "0")]
+        valid_slot: u64,
+
+        #[clap(long, default_value = "USD")]
+        quote_currency: String,
+
+        /// Oracle program registered on the lending market
+        #[clap(long)]
+        oracle_program_id: Pubkey,
+
+        #[clap(long, default_value = ".")]
+        out_dir: PathBuf,
+    },
+    /// Publish a new price on a mock oracle price account
+    SetMockPrice {
+        #[clap(long)]
+        price_account: Pubkey,
+
+        /// Aggregate price, scaled by 10^expo of the account
+        #[clap(long, allow_hyphen_values = true)]
+        price: i64,
+
+        #[clap(long, default_value = "0")]
+        conf: u64,
+    },
+}
+
+/// Each migration reallocs one account, a few fit in a transaction
+const MIGRATIONS_PER_TRANSACTION: usize = 5;
+
+fn quote_currency(symbol: &str) -> Result<[u8; 32]> {
+    let bytes = symbol.as_bytes();
+    if bytes.len() > 32 {
+        bail!("quote currency must be at most 32 bytes");
+    }
+    let mut quote_currency = [0u8; 32];
+    quote_currency[..bytes.len()].copy_from_slice(bytes);
+    Ok(quote_currency)
+}
+
+struct Context {
+    rpc: RpcClient,
+    keypair: Option<PathBuf>,
+}
+
+impl Context {
+    /// Only instructions sending transactions need the keypair
+    fn payer(&self) -> Result<Keypair> {
+        load_keypair(self.keypair.clone())
+    }
+
+    fn send(
+        &self,
+        payer: &Keypair,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<()> {
+        let mut all_signers = vec![payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &all_signers,
+            self.rpc.get_latest_blockhash()?,
+        );
+        let signature = self.rpc.send_and_confirm_transaction(&transaction)?;
+        println!("Signature: {}", signature);
+        Ok(())
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let ctx = Context {
+        rpc: RpcClient::new(rpc_url(&cli.url)),
+        keypair: cli.keypair,
+    };
+
+    match cli.command {
+        Command::CreateMarket {
+            quote_currency: symbol,
+            oracle_program_id,
+        } => {
+            let payer = ctx.payer()?;
+            let lending_market = Keypair::new();
+            ctx.send(
+                &payer,
+                &[instruction::init_lending_market(
+                    payer.pubkey(),
+                    lending_market.pubkey(),
+                    oracle_program_id,
+                    quote_currency(&symbol)?,
+                )],
+                &[&lending_market],
+            )?;
+            println!("Lending market: {}", lending_market.pubkey());
+        }
+        Command::AddReserve {
+            market,
+            config,
+            liquidity_mint,
+            liquidity_amount,
+            source_liquidity,
+            pyth_product,
+            pyth_price,
+        } => {
+            let reserve_config = ReserveConfigFile::load(&config)?.into();
+            let lending_market = state::decode_lending_market(&ctx.rpc.get_account_data(&market)?)?;
+            let payer = ctx.payer()?;
+            let owner = payer.pubkey();
+            // liquidityのmintはlegacyのtoken programかToken-2022のもの
+            let liquidity_token_program = ctx.rpc.get_account_owner(&liquidity_mint)?;
+            let source_liquidity = source_liquidity.unwrap_or_else(|| {
+                pda::associated_token_address(&owner, &liquidity_mint, &liquidity_token_program)
+            });
+
+            let reserve = Keypair::new();
+            let liquidity_supply = Keypair::new();
+            let liquidity_fee_receiver = Keypair::new();
+            let collateral_mint = Keypair::new();
+            let collateral_supply = Keypair::new();
+            ctx.send(
+                &payer,
+                &[instruction::init_reserve(
+                    liquidity_amount,
+                    reserve_config,
+                    source_liquidity,
+                    reserve.pubkey(),
+                    liquidity_mint,
+                    liquidity_token_program,
+                    liquidity_supply.pubkey(),
+                    liquidity_fee_receiver.pubkey(),
+                    collateral_mint.pubkey(),
+                    collateral_supply.pubkey(),
+                    pyth_product,
+                    pyth_price,
+                    market,
+                    lending_market.bump_seed,
+                    owner,
+                    owner,
+                )?],
+                &[
+                    &reserve,
+                    &liquidity_supply,
+                    &liquidity_fee_receiver,
+                    &collateral_mint,
+                    &collateral_supply,
+                ],
+            )?;
+            println!("Reserve: {}", reserve.pubkey());
+        }
+        Command::UpdateReserve { reserve, config } => {
+            let reserve_config = ReserveConfigFile::load(&config)?.into();
+            let reserve_account = state::decode_reserve(&ctx.rpc.get_account_data(&reserve)?)?;
+            let payer = ctx.payer()?;
+            ctx.send(
+                &payer,
+                &[instruction::update_reserve_config(
+                    reserve_config,
+                    reserve,
+                    &reserve_account,
+                    payer.pubkey(),
+                )],
+                &[],
+            )?;
+        }
+        Command::OverridePriceGuard { reserve } => {
+            let reserve_account = state::decode_reserve(&ctx.rpc.get_account_data(&reserve)?)?;
+            let payer = ctx.payer()?;
+            ctx.send(
+                &payer,
+                &[instruction::override_reserve_price_guard(
+                    reserve,
+                    &reserve_account,
+                    payer.pubkey(),
+                )],
+                &[],
+            )?;
+        }
+        Command::SetMarketOwner { market, new_owner } => {
+            let payer = ctx.payer()?;
+            ctx.send(
+                &payer,
+                &[instruction::set_lending_market_owner(
+                    market,
+                    payer.pubkey(),
+                    new_owner,
+                )],
+                &[],
+            )?;
+        }
+        Command::Migrate { market } => {
+            let payer = ctx.payer()?;
+            let market_data = ctx.rpc.get_account_data(&market)?;
+            if market_data[state::VERSION_OFFSET] == v1::VERSION {
+                ctx.send(
+                    &payer,
+                    &[instruction::migrate_lending_market(market, payer.pubkey())],
+                    &[],
+                )?;
+            }
+
+            let outdated = |discriminator: [u8; 8]| {
+                ctx.rpc.get_program_accounts(
+                    &lending_anchor::ID,
+                    &[
+                        (0, &discriminator),
+                        (state::VERSION_OFFSET, &[v1::VERSION]),
+                        (state::LENDING_MARKET_OFFSET, market.as_ref()),
+                    ],
+                )
+            };
+            let mut instructions: Vec<Instruction> = outdated(Reserve::discriminator())?
+                .into_iter()
+                .map(|(reserve, _)| instruction::migrate_reserve(reserve, payer.pubkey()))
+                .collect();
+            // obligationのborrowは移行したreserveの利率を使うので、reserveの後に移行する
+            for (obligation, data) in outdated(Obligation::discriminator())? {
+                let old = v1::ObligationV1::deserialize(&mut &data[8..])?;
+                let borrow_reserves: Vec<Pubkey> = old
+                    .borrows
+                    .iter()
+                    .map(|liquidity| liquidity.borrow_reserve)
+                    .collect();
+                instructions.push(instruction::migrate_obligation(
+                    obligation,
+                    &borrow_reserves,
+                    payer.pubkey(),
+                ));
+            }
+            for chunk in instructions.chunks(MIGRATIONS_PER_TRANSACTION) {
+                ctx.send(&payer, chunk, &[])?;
+            }
+            println!("Migrated {} accounts", instructions.len());
+        }
+        Command::ShowReserve { reserve } => {
+            let data = ctx.rpc.get_account_data(&reserve)?;
+            display::print_reserve(&reserve, &state::decode_reserve(&data)?)?;
+        }
+        Command::ShowObligation { obligation } => {
+            let data = ctx.rpc.get_account_data(&obligation)?;
+            display::print_obligation(&obligation, &state::decode_obligation(&data)?)?;
+        }
+        Command::PythFixture {
+            price,
+            expo,
+            conf,
+            valid_slot,
+            quote_currency: symbol,
+            oracle_program_id,
+            out_dir,
+        } => {
+            let product = Keypair::new().pubkey();
+            let price_account = Keypair::new().pubkey();
+            let mut pyth_price = PythPrice::new(product, price, expo, valid_slot);
+            pyth_price.conf = conf;
+            fixture::write_account(
+                &out_dir,
+                &product,
+                &oracle_program_id,
+                PythProduct::new(price_account, &symbol).to_bytes(),
+            )?;
+            fixture::write_account(
+                &out_dir,
+                &price_account,
+                &oracle_program_id,
+                pyth_price.to_bytes(),
+            )?;
+            println!("Pyth product: {}", product);
+            println!("Pyth price: {}", price_account);
+        }
+        Command::SetMockPrice {
+            price_account,
+            price,
+            conf,
+        } => {
+            let payer = ctx.payer()?;
+            ctx.send(
+                &payer,
+                &[Instruction {
+                    program_id: mock_oracle::ID,
+                    accounts: mock_oracle::accounts::UpdatePrice {
+                        price: price_account,
+                    }
+                    .to_account_metas(None),
+                    data: mock_oracle::instruction::SetPrice { price, conf }.data(),
+                }],
+                &[],
+            )?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,118 @@
+#[path = "../src/config.rs"]
+mod config;
+
+use config::ReserveConfigFile;
+use lending_anchor::{
+    account_data::reserve::{
+        borrow_rate_point::BorrowRatePoint, reserve_config::ReserveConfig,
+        reserve_fees::ReserveFees,
+    },
+    test_utils::{PythPrice, PythProduct},
+};
+use solana_sdk::pubkey::Pubkey;
+use std::{fs, path::Path, process::Command, str::FromStr};
+
+#[test]
+fn test_example_reserve_config() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("reserve_config.example.toml");
+    let config = ReserveConfig::from(ReserveConfigFile::load(&path).unwrap());
+
+    assert_eq!(
+        config,
+        ReserveConfig {
+            loan_to_value_ratio: 50,
+            liquidation_bonus: 5,
+            liquidation_threshold: 55,
+            borrow_rate_curve: vec![
+                BorrowRatePoint::new(0, 0),
+                BorrowRatePoint::new(8_000, 400),
+                BorrowRatePoint::new(10_000, 3_000),
+            ],
+            fees: ReserveFees {
+                borrow_fee_wad: 100_000_000_000_000,
+                flash_loan_fee_wad: 3_000_000_000_000_000,
+                host_fee_percentage: 20,
+            },
+            borrow_weight: 10_000,
+            min_liquidation_bonus: 5,
+            ..ReserveConfig::default()
+        }
+    );
+}
+
+#[test]
+fn test_reserve_config_file() {
+    let minimal = r#"
+        loan_to_value_ratio = 70
+        liquidation_bonus = 8
+        liquidation_threshold = 75
+        borrow_rate_curve = [[0, 100], [9000, 500], [10000, 5000]]
+
+        [fees]
+        borrow_fee_wad = 0
+        flash_loan_fee_wad = 0
+        host_fee_percentage = 0
+    "#;
+    // 省略したfieldは既定値になる
+    let config = ReserveConfig::from(toml::from_str::<ReserveConfigFile>(minimal).unwrap());
+    assert_eq!(
+        config.borrow_rate_curve[1],
+        BorrowRatePoint::new(9_000, 500)
+    );
+    assert_eq!(config.borrow_weight, 10_000);
+    assert_eq!(config.emode_category, 0);
+    assert!(!config.isolated);
+
+    // 綴りを間違えたフィールドは無視されずにエラーになる
+    let misspelled = minimal.replace(
+        "liquidation_bonus = 8",
+        "liquidation_bonus = 8\nborow_weight = 5",
+    );
+    assert!(toml::from_str::<ReserveConfigFile>(&misspelled).is_err());
+}
+
+/// Account the fixture wrote as `solana account --output json` does
+fn read_fixture(dir: &Path, pubkey: &Pubkey) -> (Pubkey, Vec<u8>) {
+    let content = fs::read_to_string(dir.join(format!("{}.json", pubkey))).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(json["pubkey"], pubkey.to_string());
+    let account = &json["account"];
+    let owner = Pubkey::from_str(account["owner"].as_str().unwrap()).unwrap();
+    let data = base64::decode(account["data"][0].as_str().unwrap()).unwrap();
+    (owner, data)
+}
+
+#[test]
+fn test_pyth_fixture() {
+    let out_dir = std::env::temp_dir().join(format!("lending-cli-{}", Pubkey::new_unique()));
+    fs::create_dir_all(&out_dir).unwrap();
+    let oracle_program_id = Pubkey::new_unique();
+    let output = Command::new(env!("CARGO_BIN_EXE_lending-cli"))
+        .args(["pyth-fixture", "--price", "2000000000", "--conf", "5"])
+        .args(["--oracle-program-id", &oracle_program_id.to_string()])
+        .arg("--out-dir")
+        .arg(&out_dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // 出力されたアドレスのファイルにPythのアカウントが書かれる
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let find = |label: &str| {
+        let line = stdout.lines().find(|line| line.starts_with(label)).unwrap();
+        Pubkey::from_str(line[label.len()..].trim()).unwrap()
+    };
+    let product = find("Pyth product:");
+    let price = find("Pyth price:");
+
+    let (owner, data) = read_fixture(&out_dir, &product);
+    assert_eq!(owner, oracle_program_id);
+    assert_eq!(data, PythProduct::new(price, "USD").to_bytes());
+    let (owner, data) = read_fixture(&out_dir, &price);
+    assert_eq!(owner, oracle_program_id);
+    let mut pyth_price = PythPrice::new(product, 2_000_000_000, -8, 0);
+    pyth_price.conf = 5;
+    assert_eq!(data, pyth_price.to_bytes());
+
+    fs::remove_dir_all(&out_dir).unwrap();
+}
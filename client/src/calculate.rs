@@ -0,0 +1,48 @@
+use anchor_lang::prelude::*;
+use lending_anchor::{
+    account_data::{
+        obligation::Obligation,
+        reserve::{CalculateBorrowResult, CalculateRepayResult, Reserve},
+    },
+    errors::LendingError,
+    instructions::borrow_obligation_liquidity::calculate_obligation_borrow,
+};
+
+/// Same calculation as `borrow_obligation_liquidity` by `obligation_authority` for a refreshed
+/// reserve and obligation. `isolated_reserve` is the obligation's isolated collateral reserve,
+/// if it has one.
+pub fn calculate_borrow(
+    reserve: &Reserve,
+    reserve_pubkey: Pubkey,
+    obligation: &Obligation,
+    obligation_authority: Pubkey,
+    isolated_reserve: Option<&Reserve>,
+    liquidity_amount: u64,
+) -> Result<CalculateBorrowResult> {
+    let result =
+        calculate_obligation_borrow(obligation, reserve_pubkey, reserve, liquidity_amount)?;
+
+    // delegateのallowanceとisolated reserveのdebt ceilingもaccountを変えずに確かめる
+    let borrow_value = reserve.market_value(result.borrow_amount)?;
+    obligation
+        .clone()
+        .use_borrow_allowance(obligation_authority, borrow_value)?;
+    if obligation.isolated_reserve.is_some() {
+        isolated_reserve
+            .ok_or(LendingError::InvalidAccountInput)?
+            .clone()
+            .add_isolated_debt(reserve_pubkey, reserve, result.borrow_amount)?;
+    }
+    Ok(result)
+}
+
+/// Same calculation as `repay_obligation_liquidity` for a refreshed reserve and obligation
+pub fn calculate_repay(
+    reserve: &Reserve,
+    reserve_pubkey: Pubkey,
+    obligation: &Obligation,
+    liquidity_amount: u64,
+) -> Result<CalculateRepayResult> {
+    let (liquidity, _) = obligation.find_liquidity_in_borrows(reserve_pubkey)?;
+    reserve.calculate_repay(liquidity_amount, liquidity.borrowed_amount)
+}
@@ -0,0 +1,1205 @@
+use crate::pda::{
+    insurance_vault, lending_market_authority, native_liquidity, referrer_token_account,
+    referrer_token_state, reward_vault,
+};
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, system_program, sysvar},
+    InstructionData,
+};
+use anchor_spl::{associated_token, token};
+use lending_anchor::{
+    account_data::{
+        emode_category::EModeCategory,
+        liquidation_pair::LiquidationPairConfig,
+        obligation::{obligation_delegate::ObligationDelegate, Obligation},
+        reserve::reserve_config::ReserveConfig,
+        reserve::Reserve,
+        reward_pool::{RewardPool, RewardSide},
+    },
+    accounts, instruction as ix,
+};
+
+fn build(
+    accounts: impl ToAccountMetas,
+    remaining_accounts: Vec<AccountMeta>,
+    data: impl InstructionData,
+) -> Instruction {
+    let mut account_metas = accounts.to_account_metas(None);
+    account_metas.extend(remaining_accounts);
+    Instruction {
+        program_id: lending_anchor::ID,
+        accounts: account_metas,
+        data: data.data(),
+    }
+}
+
+/// Isolated collateral reserve has to follow borrows and repays of an isolated obligation
+fn isolated_reserve_meta(obligation: &Obligation) -> Vec<AccountMeta> {
+    obligation
+        .isolated_reserve
+        .iter()
+        .map(|reserve| AccountMeta::new(*reserve, false))
+        .collect()
+}
+
+/// Referrer token state and token account of `mint` follow the isolated reserve in borrows of
+/// a referred obligation
+fn referrer_metas(obligation: &Obligation, mint: &Pubkey) -> Vec<AccountMeta> {
+    obligation
+        .referrer
+        .iter()
+        .flat_map(|referrer| {
+            [
+                AccountMeta::new(referrer_token_state(referrer, mint), false),
+                AccountMeta::new(referrer_token_account(referrer, mint), false),
+            ]
+        })
+        .collect()
+}
+
+/// Reward pool of `reserve` on `side` follows the other remaining accounts when it exists
+fn reward_pool_meta(reserve: &Reserve, side: RewardSide) -> Vec<AccountMeta> {
+    reserve
+        .reward_pool(side)
+        .iter()
+        .map(|reward_pool| AccountMeta::new(*reward_pool, false))
+        .collect()
+}
+
+fn borrow_remaining_metas(obligation: &Obligation, reserve: &Reserve) -> Vec<AccountMeta> {
+    let mut metas = isolated_reserve_meta(obligation);
+    metas.extend(referrer_metas(obligation, &reserve.liquidity.mint_pubkey));
+    metas.extend(reward_pool_meta(reserve, RewardSide::Borrow));
+    metas
+}
+
+fn repay_remaining_metas(obligation: &Obligation, reserve: &Reserve) -> Vec<AccountMeta> {
+    let mut metas = isolated_reserve_meta(obligation);
+    metas.extend(reward_pool_meta(reserve, RewardSide::Borrow));
+    metas
+}
+
+pub fn init_lending_market(
+    owner: Pubkey,
+    lending_market: Pubkey,
+    oracle_program_id: Pubkey,
+    quote_currency: [u8; 32],
+) -> Instruction {
+    build(
+        accounts::InitLendingMarket {
+            owner,
+            lending_market,
+            system_program: system_program::ID,
+            token_program: token::ID,
+            oracle: oracle_program_id,
+        },
+        vec![],
+        ix::InitLendingMarket { quote_currency },
+    )
+}
+
+pub fn set_lending_market_owner(
+    lending_market: Pubkey,
+    owner: Pubkey,
+    new_owner: Pubkey,
+) -> Instruction {
+    build(
+        accounts::SetLendingMarketOwner {
+            lending_market,
+            owner,
+        },
+        vec![],
+        ix::SetLendingMarketOwner { new_owner },
+    )
+}
+
+pub fn set_emode_category(
+    lending_market: Pubkey,
+    owner: Pubkey,
+    category: EModeCategory,
+) -> Instruction {
+    build(
+        accounts::SetEModeCategory {
+            lending_market,
+            owner,
+        },
+        vec![],
+        ix::SetEmodeCategory { category },
+    )
+}
+
+pub fn set_liquidation_config(
+    lending_market: Pubkey,
+    owner: Pubkey,
+    close_factor: u8,
+    full_close_value: u128,
+) -> Instruction {
+    build(
+        accounts::SetLiquidationConfig {
+            lending_market,
+            owner,
+        },
+        vec![],
+        ix::SetLiquidationConfig {
+            close_factor,
+            full_close_value,
+        },
+    )
+}
+
+pub fn set_liquidation_pair(
+    lending_market: Pubkey,
+    owner: Pubkey,
+    deposit_reserve: Pubkey,
+    borrow_reserve: Pubkey,
+    config: Option<LiquidationPairConfig>,
+) -> Instruction {
+    build(
+        accounts::SetLiquidationPair {
+            lending_market,
+            deposit_reserve,
+            borrow_reserve,
+            owner,
+        },
+        vec![],
+        ix::SetLiquidationPair { config },
+    )
+}
+
+/// Accounts which are created by `init_reserve` must sign with their keypairs.
+/// `liquidity_token_program` is the owner of the liquidity mint.
+#[allow(clippy::too_many_arguments)]
+pub fn init_reserve(
+    liquidity_amount: u64,
+    reserve_config: ReserveConfig,
+    source_liquidity: Pubkey,
+    reserve: Pubkey,
+    reserve_liquidity_mint: Pubkey,
+    liquidity_token_program: Pubkey,
+    reserve_liquidity_supply: Pubkey,
+    reserve_liquidity_fee_receiver: Pubkey,
+    reserve_collateral_mint: Pubkey,
+    reserve_collateral_supply: Pubkey,
+    pyth_product: Pubkey,
+    pyth_price: Pubkey,
+    lending_market: Pubkey,
+    lending_market_bump_seed: u8,
+    lending_market_owner: Pubkey,
+    user_transfer_authority: Pubkey,
+) -> Result<Instruction> {
+    Ok(build(
+        accounts::InitReserve {
+            source_liquidity,
+            destination_collateral: associated_token::get_associated_token_address(
+                &user_transfer_authority,
+                &reserve_collateral_mint,
+            ),
+            reserve,
+            reserve_liquidity_mint,
+            reserve_liquidity_supply,
+            reserve_liquidity_fee_receiver,
+            reserve_collateral_mint,
+            reserve_collateral_supply,
+            pyth_product,
+            pyth_price,
+            lending_market,
+            lending_market_authority: lending_market_authority(
+                &lending_market,
+                lending_market_bump_seed,
+            )?,
+            lending_market_owner,
+            user_transfer_authority,
+            system_program: system_program::ID,
+            token_program: token::ID,
+            liquidity_token_program,
+            associated_token_program: associated_token::ID,
+            rent: sysvar::rent::ID,
+        },
+        vec![],
+        ix::InitReserve {
+            liquidity_amount,
+            reserve_config,
+        },
+    ))
+}
+
+pub fn refresh_reserve(reserve_pubkey: Pubkey, reserve: &Reserve) -> Instruction {
+    build(
+        accounts::RefreshReserve {
+            reserve: reserve_pubkey,
+            reserve_liquidity_oracle: reserve.liquidity.oracle_pubkey,
+        },
+        vec![],
+        ix::RefreshReserve {},
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn deposit_reserve_liquidity(
+    liquidity_amount: u64,
+    reserve_pubkey: Pubkey,
+    reserve: &Reserve,
+    lending_market_bump_seed: u8,
+    source_liquidity: Pubkey,
+    destination_collateral: Pubkey,
+    user_transfer_authority: Pubkey,
+) -> Result<Instruction> {
+    Ok(build(
+        accounts::DepositReserveLiquidity {
+            source_liquidity,
+            destination_collateral,
+            reserve: reserve_pubkey,
+            reserve_liquidity_supply: reserve.liquidity.supply_pubkey,
+            reserve_liquidity_mint: reserve.liquidity.mint_pubkey,
+            reserve_collateral_mint: reserve.collateral.mint_pubkey,
+            lending_market: reserve.lending_market,
+            lending_market_authority: lending_market_authority(
+                &reserve.lending_market,
+                lending_market_bump_seed,
+            )?,
+            user_transfer_authority,
+            token_program: token::ID,
+            liquidity_token_program: reserve.liquidity.token_program_id,
+        },
+        vec![],
+        ix::DepositReserveLiquidity { liquidity_amount },
+    ))
+}
+
+/// `source_wallet` pays `lamports`, which are wrapped into the native SOL reserve
+pub fn deposit_reserve_liquidity_native(
+    lamports: u64,
+    reserve_pubkey: Pubkey,
+    reserve: &Reserve,
+    lending_market_bump_seed: u8,
+    source_wallet: Pubkey,
+    destination_collateral: Pubkey,
+) -> Result<Instruction> {
+    Ok(build(
+        accounts::DepositReserveLiquidityNative {
+            source_wallet,
+            destination_collateral,
+            reserve: reserve_pubkey,
+            reserve_liquidity_supply: reserve.liquidity.supply_pubkey,
+            reserve_collateral_mint: reserve.collateral.mint_pubkey,
+            lending_market: reserve.lending_market,
+            lending_market_authority: lending_market_authority(
+                &reserve.lending_market,
+                lending_market_bump_seed,
+            )?,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        vec![],
+        ix::DepositReserveLiquidityNative { lamports },
+    ))
+}
+
+pub fn redeem_reserve_collateral(
+    collateral_amount: u64,
+    reserve_pubkey: Pubkey,
+    reserve: &Reserve,
+    lending_market_bump_seed: u8,
+    source_collateral: Pubkey,
+    destination_liquidity: Pubkey,
+    user_transfer_authority: Pubkey,
+) -> Result<Instruction> {
+    Ok(build(
+        accounts::RedeemReserveCollateral {
+            source_collateral,
+            destination_liquidity,
+            reserve: reserve_pubkey,
+            reserve_collateral_mint: reserve.collateral.mint_pubkey,
+            reserve_liquidity_supply: reserve.liquidity.supply_pubkey,
+            reserve_liquidity_mint: reserve.liquidity.mint_pubkey,
+            lending_market: reserve.lending_market,
+            lending_market_authority: lending_market_authority(
+                &reserve.lending_market,
+                lending_market_bump_seed,
+            )?,
+            user_transfer_authority,
+            token_program: token::ID,
+            liquidity_token_program: reserve.liquidity.token_program_id,
+        },
+        vec![],
+        ix::RedeemReserveCollateral { collateral_amount },
+    ))
+}
+
+/// The obligation account is created by the program and must sign with its keypair
+pub fn init_obligation(
+    obligation: Pubkey,
+    lending_market: Pubkey,
+    obligation_owner: Pubkey,
+) -> Instruction {
+    build(
+        accounts::InitObligation {
+            obligation,
+            lending_market,
+            obligation_owner,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        vec![],
+        ix::InitObligation {},
+    )
+}
+
+/// Deposit, borrow and term loan reserves are passed in the order of the obligation.
+/// `emode_price_reserve` is a reserve priced by the shared oracle of the obligation's e-mode
+/// category, refreshed beforehand.
+pub fn refresh_obligation(
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+    emode_price_reserve: Option<Pubkey>,
+) -> Instruction {
+    let remaining_accounts = obligation
+        .deposits
+        .iter()
+        .map(|collateral| collateral.deposit_reserve)
+        .chain(
+            obligation
+                .borrows
+                .iter()
+                .map(|liquidity| liquidity.borrow_reserve),
+        )
+        .chain(
+            obligation
+                .term_loans
+                .iter()
+                .map(|term_loan| term_loan.borrow_reserve),
+        )
+        .chain(emode_price_reserve)
+        .map(|pubkey| AccountMeta::new_readonly(pubkey, false))
+        .collect();
+
+    build(
+        accounts::RefreshObligation {
+            obligation: obligation_pubkey,
+            lending_market: obligation.lending_market,
+        },
+        remaining_accounts,
+        ix::RefreshObligation {},
+    )
+}
+
+pub fn set_obligation_emode(
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+    emode_category: u8,
+) -> Instruction {
+    build(
+        accounts::SetObligationEMode {
+            obligation: obligation_pubkey,
+            lending_market: obligation.lending_market,
+            obligation_owner: obligation.owner,
+        },
+        vec![],
+        ix::SetObligationEmode { emode_category },
+    )
+}
+
+/// `delegate` of `None` removes the current delegate
+pub fn set_obligation_delegate(
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+    delegate: Option<ObligationDelegate>,
+) -> Instruction {
+    build(
+        accounts::SetObligationDelegate {
+            obligation: obligation_pubkey,
+            obligation_owner: obligation.owner,
+        },
+        vec![],
+        ix::SetObligationDelegate { delegate },
+    )
+}
+
+pub fn init_referrer(lending_market: Pubkey, owner: Pubkey) -> Instruction {
+    build(
+        accounts::InitReferrer {
+            referrer: crate::pda::referrer(&lending_market, &owner),
+            lending_market,
+            owner,
+            system_program: system_program::ID,
+        },
+        vec![],
+        ix::InitReferrer {},
+    )
+}
+
+/// Token account for host fees of `referrer` in the liquidity of `reserve`, paid by `payer`
+pub fn init_referrer_token_state(
+    referrer: Pubkey,
+    reserve_pubkey: Pubkey,
+    reserve: &Reserve,
+    lending_market_bump_seed: u8,
+    payer: Pubkey,
+) -> Result<Instruction> {
+    let mint = reserve.liquidity.mint_pubkey;
+    Ok(build(
+        accounts::InitReferrerTokenState {
+            referrer_token_state: referrer_token_state(&referrer, &mint),
+            referrer_token_account: referrer_token_account(&referrer, &mint),
+            referrer,
+            reserve: reserve_pubkey,
+            reserve_liquidity_mint: mint,
+            lending_market: reserve.lending_market,
+            lending_market_authority: lending_market_authority(
+                &reserve.lending_market,
+                lending_market_bump_seed,
+            )?,
+            payer,
+            liquidity_token_program: reserve.liquidity.token_program_id,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        vec![],
+        ix::InitReferrerTokenState {},
+    ))
+}
+
+pub fn set_obligation_referrer(
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+    referrer: Pubkey,
+) -> Instruction {
+    build(
+        accounts::SetObligationReferrer {
+            obligation: obligation_pubkey,
+            referrer,
+            obligation_owner: obligation.owner,
+        },
+        vec![],
+        ix::SetObligationReferrer {},
+    )
+}
+
+/// Claim all host fees accrued to the referrer of `referrer_owner` in `mint`
+pub fn claim_referrer_fees(
+    lending_market: Pubkey,
+    lending_market_bump_seed: u8,
+    referrer_owner: Pubkey,
+    mint: Pubkey,
+    liquidity_token_program: Pubkey,
+    destination_liquidity: Pubkey,
+) -> Result<Instruction> {
+    let referrer = crate::pda::referrer(&lending_market, &referrer_owner);
+    Ok(build(
+        accounts::ClaimReferrerFees {
+            referrer_token_state: referrer_token_state(&referrer, &mint),
+            referrer_token_account: referrer_token_account(&referrer, &mint),
+            referrer,
+            mint,
+            destination_liquidity,
+            lending_market,
+            lending_market_authority: lending_market_authority(
+                &lending_market,
+                lending_market_bump_seed,
+            )?,
+            owner: referrer_owner,
+            liquidity_token_program,
+        },
+        vec![],
+        ix::ClaimReferrerFees {},
+    ))
+}
+
+/// `obligation_authority` is the owner or a delegate with the deposit permission
+#[allow(clippy::too_many_arguments)]
+pub fn deposit_obligation_collateral(
+    collateral_amount: u64,
+    reserve_pubkey: Pubkey,
+    reserve: &Reserve,
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+    obligation_authority: Pubkey,
+    source_collateral: Pubkey,
+    user_transfer_authority: Pubkey,
+) -> Instruction {
+    build(
+        accounts::DepositObligationCollateral {
+            source_collateral,
+            destination_collateral: reserve.collateral.supply_pubkey,
+            deposit_reserve: reserve_pubkey,
+            obligation: obligation_pubkey,
+            lending_market: obligation.lending_market,
+            obligation_authority,
+            user_transfer_authority,
+            token_program: token::ID,
+        },
+        reward_pool_meta(reserve, RewardSide::Deposit),
+        ix::DepositObligationCollateral { collateral_amount },
+    )
+}
+
+/// A delegate can only withdraw to a `destination_collateral` owned by the obligation owner
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw_obligation_collateral(
+    collateral_amount: u64,
+    reserve_pubkey: Pubkey,
+    reserve: &Reserve,
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+    obligation_authority: Pubkey,
+    lending_market_bump_seed: u8,
+    destination_collateral: Pubkey,
+) -> Result<Instruction> {
+    Ok(build(
+        accounts::WithdrawObligationCollateral {
+            source_collateral: reserve.collateral.supply_pubkey,
+            destination_collateral,
+            withdraw_reserve: reserve_pubkey,
+            obligation: obligation_pubkey,
+            lending_market: obligation.lending_market,
+            lending_market_authority: lending_market_authority(
+                &obligation.lending_market,
+                lending_market_bump_seed,
+            )?,
+            obligation_authority,
+            token_program: token::ID,
+        },
+        reward_pool_meta(reserve, RewardSide::Deposit),
+        ix::WithdrawObligationCollateral { collateral_amount },
+    ))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn borrow_obligation_liquidity(
+    liquidity_amount: u64,
+    reserve_pubkey: Pubkey,
+    reserve: &Reserve,
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+    obligation_authority: Pubkey,
+    lending_market_bump_seed: u8,
+    destination_liquidity: Pubkey,
+) -> Result<Instruction> {
+    Ok(build(
+        accounts::BorrowObligationLiquidity {
+            source_liquidity: reserve.liquidity.supply_pubkey,
+            destination_liquidity,
+            borrow_reserve: reserve_pubkey,
+            borrow_reserve_liquidity_fee_receiver: reserve.liquidity.fee_receiver,
+            borrow_reserve_liquidity_mint: reserve.liquidity.mint_pubkey,
+            obligation: obligation_pubkey,
+            lending_market: obligation.lending_market,
+            lending_market_authority: lending_market_authority(
+                &obligation.lending_market,
+                lending_market_bump_seed,
+            )?,
+            obligation_authority,
+            liquidity_token_program: reserve.liquidity.token_program_id,
+        },
+        borrow_remaining_metas(obligation, reserve),
+        ix::BorrowObligationLiquidity { liquidity_amount },
+    ))
+}
+
+/// The borrowed lamports are unwrapped into the obligation owner's wallet
+#[allow(clippy::too_many_arguments)]
+pub fn borrow_obligation_liquidity_native(
+    liquidity_amount: u64,
+    reserve_pubkey: Pubkey,
+    reserve: &Reserve,
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+    obligation_authority: Pubkey,
+    lending_market_bump_seed: u8,
+) -> Result<Instruction> {
+    Ok(build(
+        accounts::BorrowObligationLiquidityNative {
+            source_liquidity: reserve.liquidity.supply_pubkey,
+            borrow_reserve: reserve_pubkey,
+            borrow_reserve_liquidity_fee_receiver: reserve.liquidity.fee_receiver,
+            native_mint: token::spl_token::native_mint::ID,
+            native_liquidity: native_liquidity(&obligation_pubkey),
+            obligation: obligation_pubkey,
+            lending_market: obligation.lending_market,
+            lending_market_authority: lending_market_authority(
+                &obligation.lending_market,
+                lending_market_bump_seed,
+            )?,
+            obligation_authority,
+            obligation_owner: obligation.owner,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        borrow_remaining_metas(obligation, reserve),
+        ix::BorrowObligationLiquidityNative { liquidity_amount },
+    ))
+}
+
+/// Locks `rate_bps` for `term_slots`, which must match one of the reserve's term offers
+#[allow(clippy::too_many_arguments)]
+pub fn borrow_obligation_liquidity_term(
+    liquidity_amount: u64,
+    term_slots: u64,
+    rate_bps: u32,
+    reserve_pubkey: Pubkey,
+    reserve: &Reserve,
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+    obligation_authority: Pubkey,
+    lending_market_bump_seed: u8,
+    destination_liquidity: Pubkey,
+) -> Result<Instruction> {
+    let mut remaining_accounts = isolated_reserve_meta(obligation);
+    remaining_accounts.extend(referrer_metas(obligation, &reserve.liquidity.mint_pubkey));
+    Ok(build(
+        accounts::BorrowObligationLiquidity {
+            source_liquidity: reserve.liquidity.supply_pubkey,
+            destination_liquidity,
+            borrow_reserve: reserve_pubkey,
+            borrow_reserve_liquidity_fee_receiver: reserve.liquidity.fee_receiver,
+            borrow_reserve_liquidity_mint: reserve.liquidity.mint_pubkey,
+            obligation: obligation_pubkey,
+            lending_market: obligation.lending_market,
+            lending_market_authority: lending_market_authority(
+                &obligation.lending_market,
+                lending_market_bump_seed,
+            )?,
+            obligation_authority,
+            liquidity_token_program: reserve.liquidity.token_program_id,
+        },
+        remaining_accounts,
+        ix::BorrowObligationLiquidityTerm {
+            liquidity_amount,
+            term_slots,
+            rate_bps,
+        },
+    ))
+}
+
+pub fn repay_obligation_liquidity(
+    liquidity_amount: u64,
+    reserve_pubkey: Pubkey,
+    reserve: &Reserve,
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+    source_liquidity: Pubkey,
+    user_transfer_authority: Pubkey,
+) -> Instruction {
+    build(
+        accounts::RepayObligationLiquidity {
+            source_liquidity,
+            destination_liquidity: reserve.liquidity.supply_pubkey,
+            repay_reserve: reserve_pubkey,
+            repay_reserve_liquidity_mint: reserve.liquidity.mint_pubkey,
+            obligation: obligation_pubkey,
+            lending_market: obligation.lending_market,
+            user_transfer_authority,
+            liquidity_token_program: reserve.liquidity.token_program_id,
+        },
+        repay_remaining_metas(obligation, reserve),
+        ix::RepayObligationLiquidity { liquidity_amount },
+    )
+}
+
+/// `source_wallet` pays the repaid lamports
+pub fn repay_obligation_liquidity_native(
+    liquidity_amount: u64,
+    reserve_pubkey: Pubkey,
+    reserve: &Reserve,
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+    source_wallet: Pubkey,
+) -> Instruction {
+    build(
+        accounts::RepayObligationLiquidityNative {
+            source_wallet,
+            destination_liquidity: reserve.liquidity.supply_pubkey,
+            repay_reserve: reserve_pubkey,
+            obligation: obligation_pubkey,
+            lending_market: obligation.lending_market,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        repay_remaining_metas(obligation, reserve),
+        ix::RepayObligationLiquidityNative { liquidity_amount },
+    )
+}
+
+pub fn repay_obligation_term_loan(
+    liquidity_amount: u64,
+    reserve_pubkey: Pubkey,
+    reserve: &Reserve,
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+    source_liquidity: Pubkey,
+    user_transfer_authority: Pubkey,
+) -> Instruction {
+    build(
+        accounts::RepayObligationLiquidity {
+            source_liquidity,
+            destination_liquidity: reserve.liquidity.supply_pubkey,
+            repay_reserve: reserve_pubkey,
+            repay_reserve_liquidity_mint: reserve.liquidity.mint_pubkey,
+            obligation: obligation_pubkey,
+            lending_market: obligation.lending_market,
+            user_transfer_authority,
+            liquidity_token_program: reserve.liquidity.token_program_id,
+        },
+        isolated_reserve_meta(obligation),
+        ix::RepayObligationTermLoan { liquidity_amount },
+    )
+}
+
+/// Liquidator repays from `source_liquidity` and receives collateral into `destination_collateral`
+#[allow(clippy::too_many_arguments)]
+pub fn liquidate_obligation(
+    liquidity_amount: u64,
+    repay_reserve_pubkey: Pubkey,
+    repay_reserve: &Reserve,
+    withdraw_reserve_pubkey: Pubkey,
+    withdraw_reserve: &Reserve,
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+    lending_market_bump_seed: u8,
+    source_liquidity: Pubkey,
+    destination_collateral: Pubkey,
+    user_transfer_authority: Pubkey,
+) -> Result<Instruction> {
+    let mut remaining_accounts = repay_remaining_metas(obligation, repay_reserve);
+    remaining_accounts.extend(reward_pool_meta(withdraw_reserve, RewardSide::Deposit));
+    Ok(build(
+        accounts::LiquidateObligation {
+            source_liquidity,
+            destination_collateral,
+            repay_reserve: repay_reserve_pubkey,
+            repay_reserve_liquidity_supply: repay_reserve.liquidity.supply_pubkey,
+            repay_reserve_liquidity_mint: repay_reserve.liquidity.mint_pubkey,
+            withdraw_reserve: withdraw_reserve_pubkey,
+            withdraw_reserve_collateral_supply: withdraw_reserve.collateral.supply_pubkey,
+            obligation: obligation_pubkey,
+            lending_market: obligation.lending_market,
+            lending_market_authority: lending_market_authority(
+                &obligation.lending_market,
+                lending_market_bump_seed,
+            )?,
+            user_transfer_authority,
+            token_program: token::ID,
+            liquidity_token_program: repay_reserve.liquidity.token_program_id,
+        },
+        remaining_accounts,
+        ix::LiquidateObligation { liquidity_amount },
+    ))
+}
+
+/// Liquidates the term loan from `repay_reserve`, which any liquidator can do once it's
+/// overdue
+#[allow(clippy::too_many_arguments)]
+pub fn liquidate_obligation_term_loan(
+    liquidity_amount: u64,
+    repay_reserve_pubkey: Pubkey,
+    repay_reserve: &Reserve,
+    withdraw_reserve_pubkey: Pubkey,
+    withdraw_reserve: &Reserve,
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+    lending_market_bump_seed: u8,
+    source_liquidity: Pubkey,
+    destination_collateral: Pubkey,
+    user_transfer_authority: Pubkey,
+) -> Result<Instruction> {
+    let mut remaining_accounts = isolated_reserve_meta(obligation);
+    remaining_accounts.extend(reward_pool_meta(withdraw_reserve, RewardSide::Deposit));
+    Ok(build(
+        accounts::LiquidateObligation {
+            source_liquidity,
+            destination_collateral,
+            repay_reserve: repay_reserve_pubkey,
+            repay_reserve_liquidity_supply: repay_reserve.liquidity.supply_pubkey,
+            repay_reserve_liquidity_mint: repay_reserve.liquidity.mint_pubkey,
+            withdraw_reserve: withdraw_reserve_pubkey,
+            withdraw_reserve_collateral_supply: withdraw_reserve.collateral.supply_pubkey,
+            obligation: obligation_pubkey,
+            lending_market: obligation.lending_market,
+            lending_market_authority: lending_market_authority(
+                &obligation.lending_market,
+                lending_market_bump_seed,
+            )?,
+            user_transfer_authority,
+            token_program: token::ID,
+            liquidity_token_program: repay_reserve.liquidity.token_program_id,
+        },
+        remaining_accounts,
+        ix::LiquidateObligationTermLoan { liquidity_amount },
+    ))
+}
+
+/// Writes off the debt an obligation with no deposits still owes to `reserve`, which anyone
+/// can do
+pub fn socialize_bad_debt(
+    reserve_pubkey: Pubkey,
+    reserve: &Reserve,
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+    lending_market_bump_seed: u8,
+) -> Result<Instruction> {
+    Ok(build(
+        accounts::SocializeBadDebt {
+            obligation: obligation_pubkey,
+            reserve: reserve_pubkey,
+            reserve_liquidity_supply: reserve.liquidity.supply_pubkey,
+            reserve_insurance_vault: insurance_vault(&reserve_pubkey),
+            reserve_liquidity_mint: reserve.liquidity.mint_pubkey,
+            lending_market: obligation.lending_market,
+            lending_market_authority: lending_market_authority(
+                &obligation.lending_market,
+                lending_market_bump_seed,
+            )?,
+            liquidity_token_program: reserve.liquidity.token_program_id,
+        },
+        repay_remaining_metas(obligation, reserve),
+        ix::SocializeBadDebt {},
+    ))
+}
+
+/// Reward pool of `reserve` on `side` which emits nothing until funded
+pub fn init_reward_pool(
+    side: RewardSide,
+    reserve_pubkey: Pubkey,
+    reserve: &Reserve,
+    lending_market_bump_seed: u8,
+    reward_mint: Pubkey,
+    reward_token_program: Pubkey,
+    owner: Pubkey,
+) -> Result<Instruction> {
+    let reward_pool = crate::pda::reward_pool(&reserve_pubkey, side);
+    Ok(build(
+        accounts::InitRewardPool {
+            reward_pool,
+            reward_vault: reward_vault(&reward_pool),
+            reserve: reserve_pubkey,
+            reward_mint,
+            lending_market: reserve.lending_market,
+            lending_market_authority: lending_market_authority(
+                &reserve.lending_market,
+                lending_market_bump_seed,
+            )?,
+            owner,
+            reward_token_program,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        vec![],
+        ix::InitRewardPool { side },
+    ))
+}
+
+/// Adds `amount` from `source_reward` and emits `emission_per_slot` until `end_slot`
+pub fn fund_reward_pool(
+    amount: u64,
+    emission_per_slot: u64,
+    end_slot: u64,
+    reward_pool_pubkey: Pubkey,
+    reward_pool: &RewardPool,
+    source_reward: Pubkey,
+    owner: Pubkey,
+) -> Instruction {
+    build(
+        accounts::FundRewardPool {
+            reward_pool: reward_pool_pubkey,
+            reward_vault: reward_pool.vault,
+            reward_mint: reward_pool.reward_mint,
+            source_reward,
+            lending_market: reward_pool.lending_market,
+            owner,
+            reward_token_program: reward_pool.reward_token_program,
+        },
+        vec![],
+        ix::FundRewardPool {
+            amount,
+            emission_per_slot,
+            end_slot,
+        },
+    )
+}
+
+pub fn claim_rewards(
+    reward_pool_pubkey: Pubkey,
+    reward_pool: &RewardPool,
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+    lending_market_bump_seed: u8,
+    destination_reward: Pubkey,
+) -> Result<Instruction> {
+    Ok(build(
+        accounts::ClaimRewards {
+            obligation: obligation_pubkey,
+            reward_pool: reward_pool_pubkey,
+            reward_vault: reward_pool.vault,
+            reward_mint: reward_pool.reward_mint,
+            destination_reward,
+            lending_market: reward_pool.lending_market,
+            lending_market_authority: lending_market_authority(
+                &reward_pool.lending_market,
+                lending_market_bump_seed,
+            )?,
+            obligation_owner: obligation.owner,
+            reward_token_program: reward_pool.reward_token_program,
+        },
+        vec![],
+        ix::ClaimRewards {},
+    ))
+}
+
+/// Stake the obligation's position in the reward pool. Anyone can send it.
+pub fn sync_rewards(reward_pool_pubkey: Pubkey, obligation_pubkey: Pubkey) -> Instruction {
+    build(
+        accounts::SyncRewards {
+            obligation: obligation_pubkey,
+            reward_pool: reward_pool_pubkey,
+        },
+        vec![],
+        ix::SyncRewards {},
+    )
+}
+
+pub fn init_insurance_vault(
+    reserve_pubkey: Pubkey,
+    reserve: &Reserve,
+    lending_market_bump_seed: u8,
+    owner: Pubkey,
+) -> Result<Instruction> {
+    Ok(build(
+        accounts::InitInsuranceVault {
+            insurance_vault: insurance_vault(&reserve_pubkey),
+            reserve: reserve_pubkey,
+            reserve_liquidity_mint: reserve.liquidity.mint_pubkey,
+            lending_market: reserve.lending_market,
+            lending_market_authority: lending_market_authority(
+                &reserve.lending_market,
+                lending_market_bump_seed,
+            )?,
+            owner,
+            liquidity_token_program: reserve.liquidity.token_program_id,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        vec![],
+        ix::InitInsuranceVault {},
+    ))
+}
+
+fn update_insurance_vault_accounts(
+    reserve_pubkey: Pubkey,
+    reserve: &Reserve,
+    lending_market_bump_seed: u8,
+    owner_liquidity: Pubkey,
+    owner: Pubkey,
+) -> Result<accounts::UpdateInsuranceVault> {
+    Ok(accounts::UpdateInsuranceVault {
+        reserve: reserve_pubkey,
+        reserve_liquidity_supply: reserve.liquidity.supply_pubkey,
+        insurance_vault: insurance_vault(&reserve_pubkey),
+        reserve_liquidity_mint: reserve.liquidity.mint_pubkey,
+        owner_liquidity,
+        lending_market: reserve.lending_market,
+        lending_market_authority: lending_market_authority(
+            &reserve.lending_market,
+            lending_market_bump_seed,
+        )?,
+        owner,
+        liquidity_token_program: reserve.liquidity.token_program_id,
+    })
+}
+
+/// Adds `amount` from `source_liquidity` to the insurance vault of `reserve`
+pub fn fund_insurance_vault(
+    amount: u64,
+    reserve_pubkey: Pubkey,
+    reserve: &Reserve,
+    lending_market_bump_seed: u8,
+    source_liquidity: Pubkey,
+    owner: Pubkey,
+) -> Result<Instruction> {
+    Ok(build(
+        update_insurance_vault_accounts(
+            reserve_pubkey,
+            reserve,
+            lending_market_bump_seed,
+            source_liquidity,
+            owner,
+        )?,
+        vec![],
+        ix::FundInsuranceVault { amount },
+    ))
+}
+
+/// Withdraws `amount` of the insurance vault of `reserve` into `destination_liquidity`.
+/// `u64::MAX` withdraws everything.
+pub fn withdraw_insurance_vault(
+    amount: u64,
+    reserve_pubkey: Pubkey,
+    reserve: &Reserve,
+    lending_market_bump_seed: u8,
+    destination_liquidity: Pubkey,
+    owner: Pubkey,
+) -> Result<Instruction> {
+    Ok(build(
+        update_insurance_vault_accounts(
+            reserve_pubkey,
+            reserve,
+            lending_market_bump_seed,
+            destination_liquidity,
+            owner,
+        )?,
+        vec![],
+        ix::WithdrawInsuranceVault { amount },
+    ))
+}
+
+pub fn update_reserve_config(
+    reserve_config: ReserveConfig,
+    reserve_pubkey: Pubkey,
+    reserve: &Reserve,
+    owner: Pubkey,
+) -> Instruction {
+    build(
+        accounts::UpdateReserveConfig {
+            reserve: reserve_pubkey,
+            collateral_supply: reserve.collateral.supply_pubkey,
+            lending_market: reserve.lending_market,
+            owner,
+        },
+        vec![],
+        ix::UpdateReserveConfig { reserve_config },
+    )
+}
+
+/// Accept the price held back by the price guard of the reserve
+pub fn override_reserve_price_guard(
+    reserve_pubkey: Pubkey,
+    reserve: &Reserve,
+    owner: Pubkey,
+) -> Instruction {
+    build(
+        accounts::OverrideReservePriceGuard {
+            reserve: reserve_pubkey,
+            lending_market: reserve.lending_market,
+            owner,
+        },
+        vec![],
+        ix::OverrideReservePriceGuard {},
+    )
+}
+
+/// Simulate after refreshing the obligation and decode `ObligationHealth` from the return data
+pub fn get_obligation_health(obligation_pubkey: Pubkey) -> Instruction {
+    build(
+        accounts::GetObligationHealth {
+            obligation: obligation_pubkey,
+        },
+        vec![],
+        ix::GetObligationHealth {},
+    )
+}
+
+/// Simulate after refreshing the reserve and obligation and decode `BorrowQuote` from the
+/// return data. `u64::MAX` quotes the maximum borrow.
+pub fn quote_borrow(
+    liquidity_amount: u64,
+    reserve_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+) -> Instruction {
+    build(
+        accounts::QuoteBorrow {
+            borrow_reserve: reserve_pubkey,
+            obligation: obligation_pubkey,
+            lending_market: obligation.lending_market,
+        },
+        obligation
+            .isolated_reserve
+            .iter()
+            .map(|reserve| AccountMeta::new_readonly(*reserve, false))
+            .collect(),
+        ix::QuoteBorrow { liquidity_amount },
+    )
+}
+
+/// Simulate after refreshing the reserve and obligation and decode `WithdrawQuote` from the
+/// return data. `u64::MAX` quotes the maximum withdrawal.
+pub fn quote_withdraw(
+    collateral_amount: u64,
+    reserve_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+) -> Instruction {
+    build(
+        accounts::QuoteWithdraw {
+            withdraw_reserve: reserve_pubkey,
+            obligation: obligation_pubkey,
+            lending_market: obligation.lending_market,
+        },
+        vec![],
+        ix::QuoteWithdraw { collateral_amount },
+    )
+}
+
+/// Simulate after refreshing the reserve and obligation and decode `RepayQuote` from the
+/// return data. `u64::MAX` quotes repaying the whole borrow.
+pub fn quote_repay(
+    liquidity_amount: u64,
+    reserve_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+) -> Instruction {
+    build(
+        accounts::QuoteRepay {
+            repay_reserve: reserve_pubkey,
+            obligation: obligation_pubkey,
+            lending_market: obligation.lending_market,
+        },
+        vec![],
+        ix::QuoteRepay { liquidity_amount },
+    )
+}
+
+pub fn migrate_lending_market(lending_market_pubkey: Pubkey, payer: Pubkey) -> Instruction {
+    build(
+        accounts::MigrateLendingMarket {
+            lending_market: lending_market_pubkey,
+            payer,
+            system_program: system_program::ID,
+        },
+        vec![],
+        ix::MigrateLendingMarket {},
+    )
+}
+
+pub fn migrate_reserve(reserve_pubkey: Pubkey, payer: Pubkey) -> Instruction {
+    build(
+        accounts::MigrateReserve {
+            reserve: reserve_pubkey,
+            payer,
+            system_program: system_program::ID,
+        },
+        vec![],
+        ix::MigrateReserve {},
+    )
+}
+
+/// `borrow_reserves` are the reserves of the obligation's borrows in its old layout, in order
+pub fn migrate_obligation(
+    obligation_pubkey: Pubkey,
+    borrow_reserves: &[Pubkey],
+    payer: Pubkey,
+) -> Instruction {
+    build(
+        accounts::MigrateObligation {
+            obligation: obligation_pubkey,
+            payer,
+            system_program: system_program::ID,
+        },
+        borrow_reserves
+            .iter()
+            .map(|pubkey| AccountMeta::new_readonly(*pubkey, false))
+            .collect(),
+        ix::MigrateObligation {},
+    )
+}
@@ -0,0 +1,8 @@
+pub mod calculate;
+pub mod instruction;
+pub mod pda;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+pub mod state;
+
+pub use lending_anchor::ID as PROGRAM_ID;
@@ -0,0 +1,100 @@
+use anchor_lang::prelude::*;
+use lending_anchor::{
+    account_data::reward_pool::RewardSide,
+    constants::{
+        INSURANCE_VAULT_SEED, NATIVE_LIQUIDITY_SEED, REFERRER_SEED, REFERRER_TOKEN_ACCOUNT_SEED,
+        REFERRER_TOKEN_STATE_SEED, REWARD_POOL_SEED, REWARD_VAULT_SEED,
+    },
+    errors::LendingError,
+};
+
+/// Derive the lending market authority from the bump seed stored in `LendingMarket`
+pub fn lending_market_authority(lending_market: &Pubkey, bump_seed: u8) -> Result<Pubkey> {
+    Pubkey::create_program_address(
+        &[lending_market.as_ref(), &[bump_seed]],
+        &lending_anchor::ID,
+    )
+    .map_err(|_| error!(LendingError::InvalidMarketAuthority))
+}
+
+/// Find the lending market authority and its bump seed before the market exists
+pub fn find_lending_market_authority(lending_market: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[lending_market.as_ref()], &lending_anchor::ID)
+}
+
+/// Associated token account of `mint` under `token_program`, which is either the legacy
+/// token program or Token-2022
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &anchor_spl::associated_token::ID,
+    )
+    .0
+}
+
+/// Token account native SOL borrows of `obligation` are unwrapped through
+pub fn native_liquidity(obligation: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[NATIVE_LIQUIDITY_SEED, obligation.as_ref()],
+        &lending_anchor::ID,
+    )
+    .0
+}
+
+/// Referrer `owner` registers in `lending_market`
+pub fn referrer(lending_market: &Pubkey, owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[REFERRER_SEED, lending_market.as_ref(), owner.as_ref()],
+        &lending_anchor::ID,
+    )
+    .0
+}
+
+/// Host fee tracking of `referrer` in `mint`
+pub fn referrer_token_state(referrer: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[REFERRER_TOKEN_STATE_SEED, referrer.as_ref(), mint.as_ref()],
+        &lending_anchor::ID,
+    )
+    .0
+}
+
+/// Token account host fees of `referrer` in `mint` are paid into
+pub fn referrer_token_account(referrer: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            REFERRER_TOKEN_ACCOUNT_SEED,
+            referrer.as_ref(),
+            mint.as_ref(),
+        ],
+        &lending_anchor::ID,
+    )
+    .0
+}
+
+/// Reward pool of the deposits or borrows of `reserve`
+pub fn reward_pool(reserve: &Pubkey, side: RewardSide) -> Pubkey {
+    Pubkey::find_program_address(
+        &[REWARD_POOL_SEED, reserve.as_ref(), &[side as u8]],
+        &lending_anchor::ID,
+    )
+    .0
+}
+
+/// Token account rewards of `reward_pool` are paid from
+pub fn reward_vault(reward_pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[REWARD_VAULT_SEED, reward_pool.as_ref()],
+        &lending_anchor::ID,
+    )
+    .0
+}
+
+/// Token account holding the insurance fund of `reserve`
+pub fn insurance_vault(reserve: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[INSURANCE_VAULT_SEED, reserve.as_ref()],
+        &lending_anchor::ID,
+    )
+    .0
+}
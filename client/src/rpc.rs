@@ -0,0 +1,213 @@
+use anchor_lang::AnchorDeserialize;
+use anyhow::{anyhow, bail, Result};
+use serde_json::{json, Value};
+use solana_sdk::{
+    hash::Hash,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signature},
+    transaction::Transaction,
+};
+use std::{path::PathBuf, str::FromStr, thread::sleep, time::Duration};
+
+const CONFIRM_RETRIES: usize = 60;
+const CONFIRM_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Resolve a cluster moniker to its RPC URL
+pub fn rpc_url(moniker: &str) -> String {
+    match moniker {
+        "localhost" | "l" => "http://localhost:8899",
+        "devnet" | "d" => "https://api.devnet.solana.com",
+        "testnet" | "t" => "https://api.testnet.solana.com",
+        "mainnet-beta" | "m" => "https://api.mainnet-beta.solana.com",
+        url => url,
+    }
+    .to_string()
+}
+
+/// Read the keypair at `path`, defaulting to the Solana CLI keypair
+pub fn load_keypair(path: Option<PathBuf>) -> Result<Keypair> {
+    let path = match path {
+        Some(path) => path,
+        None => PathBuf::from(std::env::var("HOME")?).join(".config/solana/id.json"),
+    };
+    read_keypair_file(&path).map_err(|e| anyhow!("failed to read {}: {}", path.display(), e))
+}
+
+/// Minimal JSON RPC client for the few methods the CLI and keeper need
+pub struct RpcClient {
+    url: String,
+}
+
+impl RpcClient {
+    pub fn new(url: String) -> Self {
+        Self { url }
+    }
+
+    fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let response: Value = ureq::post(&self.url)
+            .send_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": params,
+            }))?
+            .into_json()?;
+
+        if let Some(error) = response.get("error") {
+            bail!("{} failed: {}", method, error);
+        }
+        response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| anyhow!("{} returned no result", method))
+    }
+
+    pub fn get_account_data(&self, pubkey: &Pubkey) -> Result<Vec<u8>> {
+        let result = self.request(
+            "getAccountInfo",
+            json!([pubkey.to_string(), { "encoding": "base64", "commitment": "confirmed" }]),
+        )?;
+        let data = result["value"]["data"][0]
+            .as_str()
+            .ok_or_else(|| anyhow!("account {} not found", pubkey))?;
+        Ok(base64::decode(data)?)
+    }
+
+    pub fn get_account_owner(&self, pubkey: &Pubkey) -> Result<Pubkey> {
+        let result = self.request(
+            "getAccountInfo",
+            json!([pubkey.to_string(), { "encoding": "base64", "commitment": "confirmed" }]),
+        )?;
+        let owner = result["value"]["owner"]
+            .as_str()
+            .ok_or_else(|| anyhow!("account {} not found", pubkey))?;
+        Ok(Pubkey::from_str(owner)?)
+    }
+
+    /// Accounts owned by `program_id` whose data match every `(offset, bytes)` filter
+    pub fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: &[(usize, &[u8])],
+    ) -> Result<Vec<(Pubkey, Vec<u8>)>> {
+        let filters: Vec<Value> = filters
+            .iter()
+            .map(|(offset, bytes)| {
+                json!({ "memcmp": { "offset": offset, "bytes": bs58::encode(bytes).into_string() } })
+            })
+            .collect();
+        let result = self.request(
+            "getProgramAccounts",
+            json!([program_id.to_string(), {
+                "encoding": "base64",
+                "commitment": "confirmed",
+                "filters": filters,
+            }]),
+        )?;
+        result
+            .as_array()
+            .ok_or_else(|| anyhow!("invalid getProgramAccounts response"))?
+            .iter()
+            .map(|account| {
+                let pubkey = Pubkey::from_str(
+                    account["pubkey"]
+                        .as_str()
+                        .ok_or_else(|| anyhow!("invalid getProgramAccounts response"))?,
+                )?;
+                let data = account["account"]["data"][0]
+                    .as_str()
+                    .ok_or_else(|| anyhow!("invalid getProgramAccounts response"))?;
+                Ok((pubkey, base64::decode(data)?))
+            })
+            .collect()
+    }
+
+    /// Simulate `transaction` and return the data of `addresses` after it has run
+    fn simulate(&self, transaction: &Transaction, config: Value) -> Result<Value> {
+        let wire_transaction = base64::encode(bincode::serialize(transaction)?);
+        let mut config = config;
+        config["encoding"] = json!("base64");
+        config["commitment"] = json!("confirmed");
+        config["sigVerify"] = json!(false);
+        let result = self.request("simulateTransaction", json!([wire_transaction, config]))?;
+        let value = result["value"].clone();
+        if !value["err"].is_null() {
+            bail!("simulation failed: {} {}", value["err"], value["logs"]);
+        }
+        Ok(value)
+    }
+
+    pub fn simulate_transaction(
+        &self,
+        transaction: &Transaction,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<Vec<u8>>> {
+        let addresses: Vec<String> = addresses.iter().map(ToString::to_string).collect();
+        let value = self.simulate(
+            transaction,
+            json!({ "accounts": { "encoding": "base64", "addresses": addresses } }),
+        )?;
+        value["accounts"]
+            .as_array()
+            .ok_or_else(|| anyhow!("invalid simulateTransaction response"))?
+            .iter()
+            .map(|account| {
+                let data = account["data"][0]
+                    .as_str()
+                    .ok_or_else(|| anyhow!("invalid simulateTransaction response"))?;
+                Ok(base64::decode(data)?)
+            })
+            .collect()
+    }
+
+    /// Simulate a transaction ending with a view instruction and decode its return data
+    pub fn simulate_return_data<T: AnchorDeserialize>(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<T> {
+        let value = self.simulate(transaction, json!({}))?;
+        let data = value["returnData"]["data"][0]
+            .as_str()
+            .ok_or_else(|| anyhow!("simulation returned no data"))?;
+        Ok(T::try_from_slice(&base64::decode(data)?)?)
+    }
+
+    pub fn get_latest_blockhash(&self) -> Result<Hash> {
+        let result = self.request("getLatestBlockhash", json!([{ "commitment": "confirmed" }]))?;
+        let blockhash = result["value"]["blockhash"]
+            .as_str()
+            .ok_or_else(|| anyhow!("invalid getLatestBlockhash response"))?;
+        Ok(Hash::from_str(blockhash)?)
+    }
+
+    pub fn send_and_confirm_transaction(&self, transaction: &Transaction) -> Result<Signature> {
+        let wire_transaction = base64::encode(bincode::serialize(transaction)?);
+        let result = self.request(
+            "sendTransaction",
+            json!([wire_transaction, { "encoding": "base64", "preflightCommitment": "confirmed" }]),
+        )?;
+        let signature = Signature::from_str(
+            result
+                .as_str()
+                .ok_or_else(|| anyhow!("invalid sendTransaction response"))?,
+        )?;
+
+        for _ in 0..CONFIRM_RETRIES {
+            let result = self.request("getSignatureStatuses", json!([[signature.to_string()]]))?;
+            let status = &result["value"][0];
+            if !status.is_null() {
+                if !status["err"].is_null() {
+                    bail!("transaction {} failed: {}", signature, status["err"]);
+                }
+                if matches!(
+                    status["confirmationStatus"].as_str(),
+                    Some("confirmed") | Some("finalized")
+                ) {
+                    return Ok(signature);
+                }
+            }
+            sleep(CONFIRM_INTERVAL);
+        }
+        bail!("transaction {} was not confirmed", signature)
+    }
+}
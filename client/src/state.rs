@@ -0,0 +1,42 @@
+use anchor_lang::{prelude::*, Discriminator};
+use lending_anchor::{
+    account_data::{
+        last_update::LastUpdate, lending_market::LendingMarket, obligation::Obligation,
+        reserve::Reserve,
+    },
+    errors::LendingError,
+    utils::{byte_length::ByteLength, versioned::Versioned},
+};
+
+/// Decode an account of the current layout, checking its discriminator and version
+fn decode<T: AccountDeserialize + Discriminator + Versioned>(data: &[u8]) -> Result<T> {
+    // 古いlayoutのaccountはmigrateするまで読めない
+    if data.get(..8) == Some(&T::discriminator()[..])
+        && data.get(VERSION_OFFSET) != Some(&T::VERSION)
+    {
+        return Err(error!(LendingError::OutdatedAccountVersion));
+    }
+    T::try_deserialize(&mut &data[..])
+}
+
+/// Decode a `LendingMarket` account, checking its discriminator and version
+pub fn decode_lending_market(data: &[u8]) -> Result<LendingMarket> {
+    decode(data)
+}
+
+/// Decode a `Reserve` account, checking its discriminator and version
+pub fn decode_reserve(data: &[u8]) -> Result<Reserve> {
+    decode(data)
+}
+
+/// Decode an `Obligation` account, checking its discriminator and version
+pub fn decode_obligation(data: &[u8]) -> Result<Obligation> {
+    decode(data)
+}
+
+/// Offset of `lending_market` in `Reserve` and `Obligation` data, after the discriminator,
+/// version and last update, for `getProgramAccounts` filters
+pub const LENDING_MARKET_OFFSET: usize = 8 + 1 + LastUpdate::LEN;
+
+/// Offset of `version` in every account, right after the discriminator
+pub const VERSION_OFFSET: usize = 8;
@@ -0,0 +1,230 @@
+use anchor_lang::prelude::*;
+use lending_anchor::{
+    account_data::{
+        obligation::{
+            obligation_collateral::ObligationCollateral, obligation_delegate::ObligationDelegate,
+            obligation_liquidity::ObligationLiquidity, Obligation,
+        },
+        reserve::Reserve,
+        reward_pool::RewardSide,
+        v1,
+    },
+    constants::RESERVE_VERSION,
+    errors::LendingError,
+    math::common::{BPS_SCALE, WAD},
+};
+use lending_client::{calculate, instruction, pda, state};
+
+/// Account with every field zeroed, as freshly allocated
+fn zeroed<T: AnchorDeserialize>() -> T {
+    // 余った分は読まれない
+    T::deserialize(&mut &[0; 4096][..]).unwrap()
+}
+
+fn obligation(deposit_reserve: Pubkey, borrow_reserve: Pubkey) -> Obligation {
+    let mut obligation: Obligation = zeroed();
+    obligation.lending_market = Pubkey::new_unique();
+    obligation.owner = Pubkey::new_unique();
+    let mut collateral = ObligationCollateral::new(deposit_reserve);
+    collateral.deposit(80).unwrap();
+    obligation.deposits.push(collateral);
+    let mut liquidity = ObligationLiquidity::new(borrow_reserve, WAD as u128);
+    liquidity.borrow(10).unwrap();
+    obligation.borrows.push(liquidity);
+    obligation
+}
+
+#[test]
+fn test_refresh_obligation_accounts() {
+    let deposit_reserve = Pubkey::new_unique();
+    let borrow_reserve = Pubkey::new_unique();
+    let emode_price_reserve = Pubkey::new_unique();
+    let obligation_pubkey = Pubkey::new_unique();
+    let obligation = obligation(deposit_reserve, borrow_reserve);
+
+    // depositとborrowのreserveをobligationの順に並べ、最後にe-modeの価格を取るreserveを置く
+    let ix =
+        instruction::refresh_obligation(obligation_pubkey, &obligation, Some(emode_price_reserve));
+    let keys: Vec<Pubkey> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+    assert_eq!(
+        keys,
+        vec![
+            obligation_pubkey,
+            obligation.lending_market,
+            deposit_reserve,
+            borrow_reserve,
+            emode_price_reserve
+        ]
+    );
+    assert!(ix.accounts[2..].iter().all(|meta| !meta.is_writable));
+}
+
+#[test]
+fn test_borrow_remaining_accounts() {
+    let deposit_reserve = Pubkey::new_unique();
+    let borrow_reserve = Pubkey::new_unique();
+    let referrer = Pubkey::new_unique();
+    let reward_pool = Pubkey::new_unique();
+    let mut reserve: Reserve = zeroed();
+    reserve.liquidity.mint_pubkey = Pubkey::new_unique();
+    reserve.set_reward_pool(RewardSide::Borrow, reward_pool);
+    let mut obligation = obligation(deposit_reserve, borrow_reserve);
+    obligation.isolated_reserve = Some(deposit_reserve);
+    obligation.referrer = Some(referrer);
+    let (_, bump_seed) = pda::find_lending_market_authority(&obligation.lending_market);
+
+    // isolated collateralのreserveはdebtを記録するので書き込み可能で渡し、
+    // borrowではその後にreferrerのaccount、最後にborrow側のreward poolが続く
+    let borrow = instruction::borrow_obligation_liquidity(
+        1,
+        borrow_reserve,
+        &reserve,
+        Pubkey::new_unique(),
+        &obligation,
+        obligation.owner,
+        bump_seed,
+        Pubkey::new_unique(),
+    )
+    .unwrap();
+    let repay = instruction::repay_obligation_liquidity(
+        1,
+        borrow_reserve,
+        &reserve,
+        Pubkey::new_unique(),
+        &obligation,
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let mint = reserve.liquidity.mint_pubkey;
+    let borrow_keys: Vec<Pubkey> = borrow.accounts.iter().map(|meta| meta.pubkey).collect();
+    assert_eq!(
+        borrow_keys[borrow_keys.len() - 4..],
+        [
+            deposit_reserve,
+            pda::referrer_token_state(&referrer, &mint),
+            pda::referrer_token_account(&referrer, &mint),
+            reward_pool,
+        ]
+    );
+    let repay_keys: Vec<Pubkey> = repay.accounts.iter().map(|meta| meta.pubkey).collect();
+    assert_eq!(
+        repay_keys[repay_keys.len() - 2..],
+        [deposit_reserve, reward_pool]
+    );
+    for meta in borrow.accounts[borrow.accounts.len() - 4..]
+        .iter()
+        .chain(&repay.accounts[repay.accounts.len() - 2..])
+    {
+        assert!(meta.is_writable && !meta.is_signer);
+    }
+}
+
+#[test]
+fn test_decode_accounts() {
+    let mut reserve: Reserve = zeroed();
+    reserve.version = RESERVE_VERSION;
+    reserve.liquidity.available_amount = 100;
+    let mut data = vec![];
+    reserve.try_serialize(&mut data).unwrap();
+    assert_eq!(
+        state::decode_reserve(&data)
+            .unwrap()
+            .liquidity
+            .available_amount,
+        100
+    );
+
+    // discriminatorが違うaccountはdecodeしない
+    assert!(state::decode_obligation(&data).is_err());
+
+    // 移行していないaccountもdecodeしない
+    data[state::VERSION_OFFSET] = v1::VERSION;
+    assert_eq!(
+        state::decode_reserve(&data).err().map(ProgramError::from),
+        Some(ProgramError::from(error!(
+            LendingError::OutdatedAccountVersion
+        )))
+    );
+}
+
+#[test]
+fn test_calculate() {
+    let deposit_reserve = Pubkey::new_unique();
+    let borrow_reserve = Pubkey::new_unique();
+    let mut reserve: Reserve = zeroed();
+    reserve.liquidity.market_price = 1;
+    reserve.liquidity.available_amount = 100;
+    reserve.config.borrow_weight = BPS_SCALE;
+    let mut obligation = obligation(deposit_reserve, borrow_reserve);
+    obligation.borrowed_value = 10;
+    obligation.allowed_borrow_value = 40;
+    let owner = obligation.owner;
+
+    // 借りられるのは残りの30まで
+    let result =
+        calculate::calculate_borrow(&reserve, borrow_reserve, &obligation, owner, None, u64::MAX)
+            .unwrap();
+    assert_eq!(result.borrow_amount, 30);
+    assert!(
+        calculate::calculate_borrow(&reserve, borrow_reserve, &obligation, owner, None, 31)
+            .is_err()
+    );
+
+    // delegateはallowanceの分までしか借りられない
+    let delegate = Pubkey::new_unique();
+    obligation.delegate = Some(ObligationDelegate {
+        authority: delegate,
+        permissions: ObligationDelegate::BORROW,
+        borrow_allowance: Some(20),
+    });
+    calculate::calculate_borrow(&reserve, borrow_reserve, &obligation, delegate, None, 20).unwrap();
+    assert!(
+        calculate::calculate_borrow(&reserve, borrow_reserve, &obligation, delegate, None, 21)
+            .is_err()
+    );
+
+    // isolated collateralに対してはborrowable_in_isolationのreserveからdebt ceilingまで借りられる
+    obligation.isolated_reserve = Some(deposit_reserve);
+    let mut isolated_reserve: Reserve = zeroed();
+    isolated_reserve.config.isolated = true;
+    isolated_reserve.config.debt_ceiling = 25;
+    assert!(calculate::calculate_borrow(
+        &reserve,
+        borrow_reserve,
+        &obligation,
+        owner,
+        Some(&isolated_reserve),
+        20
+    )
+    .is_err());
+    reserve.config.borrowable_in_isolation = true;
+    assert!(
+        calculate::calculate_borrow(&reserve, borrow_reserve, &obligation, owner, None, 20)
+            .is_err()
+    );
+    calculate::calculate_borrow(
+        &reserve,
+        borrow_reserve,
+        &obligation,
+        owner,
+        Some(&isolated_reserve),
+        25,
+    )
+    .unwrap();
+    assert!(calculate::calculate_borrow(
+        &reserve,
+        borrow_reserve,
+        &obligation,
+        owner,
+        Some(&isolated_reserve),
+        26
+    )
+    .is_err());
+
+    // 返済は借入の残高まで
+    let result =
+        calculate::calculate_repay(&reserve, borrow_reserve, &obligation, u64::MAX).unwrap();
+    assert_eq!(result.settle_amount, 10);
+    assert_eq!(result.repay_amount, 10);
+    assert!(calculate::calculate_repay(&reserve, Pubkey::new_unique(), &obligation, 1).is_err());
+}
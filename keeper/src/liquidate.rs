@@ -0,0 +1,144 @@
+use anchor_lang::prelude::Pubkey;
+use anyhow::{anyhow, Result};
+use lending_anchor::{
+    account_data::{lending_market::LendingMarket, obligation::Obligation, reserve::Reserve},
+    constants::LIQUIDATION_CLOSE_AMOUNT,
+};
+use lending_client::instruction;
+use solana_sdk::instruction::Instruction;
+use std::collections::HashMap;
+
+/// Liquidation the keeper sends for an unhealthy obligation
+pub struct LiquidationTarget {
+    pub repay_reserve: Pubkey,
+    pub withdraw_reserve: Pubkey,
+    /// Whether the repaid borrow is a term loan
+    pub term_loan: bool,
+    /// Most liquidity a single liquidation can repay under the close factor
+    pub max_amount: u64,
+}
+
+/// Repay the largest borrow or term loan and seize the largest deposit
+pub fn select_target(
+    obligation: &Obligation,
+    lending_market: &LendingMarket,
+) -> Result<Option<LiquidationTarget>> {
+    let borrows = obligation
+        .borrows
+        .iter()
+        .map(|liquidity| (liquidity.clone(), false));
+    let term_loans = obligation
+        .term_loans
+        .iter()
+        .map(|term_loan| (term_loan.as_liquidity(), true));
+    let (liquidity, term_loan) = match borrows
+        .chain(term_loans)
+        .max_by_key(|(liquidity, _)| liquidity.market_value)
+    {
+        Some(borrow) => borrow,
+        None => return Ok(None),
+    };
+    let collateral = match obligation
+        .deposits
+        .iter()
+        .max_by_key(|collateral| collateral.market_value)
+    {
+        Some(collateral) => collateral,
+        None => return Ok(None),
+    };
+    if liquidity.market_value == 0 || collateral.market_value == 0 {
+        return Ok(None);
+    }
+
+    // programと同じくclose factorまでに切り詰め、少額の借入は全て返済する
+    let max_amount = if liquidity.borrowed_amount < LIQUIDATION_CLOSE_AMOUNT as u128 {
+        liquidity.borrowed_amount
+    } else {
+        let close_factor = lending_market.liquidation_close_factor(
+            collateral.deposit_reserve,
+            liquidity.borrow_reserve,
+            liquidity.market_value,
+        );
+        obligation.max_liquidation_amount(&liquidity, close_factor)?
+    };
+    Ok(Some(LiquidationTarget {
+        repay_reserve: liquidity.borrow_reserve,
+        withdraw_reserve: collateral.deposit_reserve,
+        term_loan,
+        max_amount: u64::try_from(max_amount).unwrap_or(u64::MAX),
+    }))
+}
+
+/// Refresh every reserve of the obligation and its e-mode price reserve, followed by the
+/// obligation itself
+pub fn refresh_ixs(
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+    lending_market: &LendingMarket,
+    reserves: &HashMap<Pubkey, Reserve>,
+) -> Result<Vec<Instruction>> {
+    let reserve_keys: Vec<Pubkey> = obligation
+        .deposits
+        .iter()
+        .map(|collateral| collateral.deposit_reserve)
+        .chain(
+            obligation
+                .borrows
+                .iter()
+                .map(|liquidity| liquidity.borrow_reserve),
+        )
+        .chain(
+            obligation
+                .term_loans
+                .iter()
+                .map(|term_loan| term_loan.borrow_reserve),
+        )
+        .collect();
+
+    // refresh_obligationと同じ条件でe-modeの共有oracleを使うreserveを渡す
+    let emode_category = obligation.emode_category;
+    let mut in_category = emode_category != 0;
+    for reserve_pubkey in &reserve_keys {
+        let reserve = reserves
+            .get(reserve_pubkey)
+            .ok_or_else(|| anyhow!("reserve {} not found", reserve_pubkey))?;
+        in_category &= reserve.config.emode_category == emode_category;
+    }
+    let emode_oracle = if in_category {
+        lending_market.find_emode_category(emode_category)?.oracle
+    } else {
+        None
+    };
+    let emode_price_reserve = match emode_oracle {
+        Some(oracle) => Some(
+            reserves
+                .iter()
+                .find(|(_, reserve)| reserve.liquidity.oracle_pubkey == oracle)
+                .map(|(reserve_pubkey, _)| *reserve_pubkey)
+                .ok_or_else(|| anyhow!("no reserve priced by e-mode oracle {}", oracle))?,
+        ),
+        None => None,
+    };
+
+    let mut instructions = vec![];
+    let refreshed_keys: Vec<Pubkey> = reserve_keys
+        .into_iter()
+        .chain(emode_price_reserve)
+        .collect();
+    for (i, reserve_pubkey) in refreshed_keys.iter().enumerate() {
+        if refreshed_keys[..i].contains(reserve_pubkey) {
+            continue;
+        }
+        let reserve = reserves
+            .get(reserve_pubkey)
+            .ok_or_else(|| anyhow!("reserve {} not found", reserve_pubkey))?;
+        instructions.push(instruction::refresh_reserve(*reserve_pubkey, reserve));
+    }
+    instructions.push(instruction::refresh_obligation(
+        obligation_pubkey,
+        obligation,
+        emode_price_reserve,
+    ));
+
+    Ok(instructions)
+}
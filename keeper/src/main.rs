@@ -0,0 +1,233 @@
+mod liquidate;
+
+use anchor_lang::{solana_program::program_pack::Pack, Discriminator};
+use anchor_spl::{associated_token::get_associated_token_address, token::spl_token};
+use anyhow::{anyhow, bail, Result};
+use clap::Parser;
+use lending_anchor::{
+    account_data::{lending_market::LendingMarket, obligation::Obligation, reserve::Reserve},
+    utils::versioned::Versioned,
+};
+use lending_client::{
+    instruction, pda,
+    rpc::{load_keypair, rpc_url, RpcClient},
+    state::{self, LENDING_MARKET_OFFSET, VERSION_OFFSET},
+};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use spl_associated_token_account::create_associated_token_account;
+use std::{collections::HashMap, path::PathBuf, thread::sleep, time::Duration};
+
+#[derive(Parser)]
+#[clap(
+    name = "lending-keeper",
+    about = "Liquidate unhealthy obligations of a lending market"
+)]
+struct Args {
+    /// RPC URL or moniker (localhost, devnet, testnet, mainnet-beta)
+    #[clap(short, long, default_value = "localhost")]
+    url: String,
+
+    /// Liquidator keypair, defaults to the Solana CLI keypair. It repays from and receives
+    /// collateral into its associated token accounts.
+    #[clap(short, long)]
+    keypair: Option<PathBuf>,
+
+    #[clap(long)]
+    market: Pubkey,
+
+    /// Seconds between scans
+    #[clap(long, default_value = "10")]
+    interval: u64,
+
+    /// Scan once and exit
+    #[clap(long)]
+    once: bool,
+}
+
+struct Keeper {
+    rpc: RpcClient,
+    liquidator: Keypair,
+    market: Pubkey,
+}
+
+impl Keeper {
+    /// Market accounts of one type in the current layout, found by discriminator, version and
+    /// lending market. Accounts that still fail to decode are logged and skipped.
+    fn market_accounts<T: Discriminator + Versioned>(
+        &self,
+        decode: fn(&[u8]) -> anchor_lang::Result<T>,
+    ) -> Result<HashMap<Pubkey, T>> {
+        Ok(self
+            .rpc
+            .get_program_accounts(
+                &lending_anchor::ID,
+                &[
+                    (0, &T::discriminator()),
+                    (VERSION_OFFSET, &[T::VERSION]),
+                    (LENDING_MARKET_OFFSET, self.market.as_ref()),
+                ],
+            )?
+            .into_iter()
+            .filter_map(|(pubkey, data)| match decode(&data) {
+                Ok(account) => Some((pubkey, account)),
+                Err(err) => {
+                    eprintln!("Skipping account {}: {}", pubkey, err);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    fn scan(&self) -> Result<()> {
+        let lending_market =
+            state::decode_lending_market(&self.rpc.get_account_data(&self.market)?)?;
+        let reserves = self.market_accounts::<Reserve>(state::decode_reserve)?;
+        let obligations = self.market_accounts::<Obligation>(state::decode_obligation)?;
+
+        let mut liquidated = 0;
+        for (pubkey, obligation) in &obligations {
+            if !obligation.has_borrows() {
+                continue;
+            }
+            match self.liquidate_if_unhealthy(&lending_market, &reserves, *pubkey, obligation) {
+                Ok(true) => liquidated += 1,
+                Ok(false) => {}
+                Err(err) => eprintln!("Obligation {}: {:#}", pubkey, err),
+            }
+        }
+        println!(
+            "Scanned {} obligations, liquidated {}",
+            obligations.len(),
+            liquidated
+        );
+        Ok(())
+    }
+
+    fn liquidate_if_unhealthy(
+        &self,
+        lending_market: &LendingMarket,
+        reserves: &HashMap<Pubkey, Reserve>,
+        obligation_pubkey: Pubkey,
+        obligation: &Obligation,
+    ) -> Result<bool> {
+        let liquidator = self.liquidator.pubkey();
+        let mut instructions =
+            liquidate::refresh_ixs(obligation_pubkey, obligation, lending_market, reserves)?;
+
+        // refreshをsimulateして手数料をかけずに最新のhealthを得る
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&liquidator),
+            &[&self.liquidator],
+            self.rpc.get_latest_blockhash()?,
+        );
+        let data = self
+            .rpc
+            .simulate_transaction(&transaction, &[obligation_pubkey])?;
+        let obligation = state::decode_obligation(&data[0])?;
+        // price guard中はliquidateできない
+        if !obligation.is_unhealthy() || obligation.price_guarded {
+            return Ok(false);
+        }
+
+        let target = liquidate::select_target(&obligation, lending_market)?
+            .ok_or_else(|| anyhow!("obligation has no deposits to seize"))?;
+        let repay_pubkey = target.repay_reserve;
+        let withdraw_pubkey = target.withdraw_reserve;
+        let repay_reserve = reserves
+            .get(&repay_pubkey)
+            .ok_or_else(|| anyhow!("reserve {} not found", repay_pubkey))?;
+        let withdraw_reserve = reserves
+            .get(&withdraw_pubkey)
+            .ok_or_else(|| anyhow!("reserve {} not found", withdraw_pubkey))?;
+
+        let source_liquidity = pda::associated_token_address(
+            &liquidator,
+            &repay_reserve.liquidity.mint_pubkey,
+            &repay_reserve.liquidity.token_program_id,
+        );
+        let liquidity_amount = self
+            .token_balance(&source_liquidity)?
+            .min(target.max_amount);
+        if liquidity_amount == 0 {
+            bail!(
+                "no {} to repay with in {}",
+                repay_reserve.liquidity.mint_pubkey,
+                source_liquidity
+            );
+        }
+
+        let collateral_mint = withdraw_reserve.collateral.mint_pubkey;
+        let destination_collateral = get_associated_token_address(&liquidator, &collateral_mint);
+        if self.rpc.get_account_data(&destination_collateral).is_err() {
+            instructions.insert(
+                0,
+                create_associated_token_account(&liquidator, &liquidator, &collateral_mint),
+            );
+        }
+
+        // term loanはそれ専用の命令で返済する
+        let liquidate = if target.term_loan {
+            instruction::liquidate_obligation_term_loan
+        } else {
+            instruction::liquidate_obligation
+        };
+        instructions.push(liquidate(
+            liquidity_amount,
+            repay_pubkey,
+            repay_reserve,
+            withdraw_pubkey,
+            withdraw_reserve,
+            obligation_pubkey,
+            &obligation,
+            lending_market.bump_seed,
+            source_liquidity,
+            destination_collateral,
+            liquidator,
+        )?);
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&liquidator),
+            &[&self.liquidator],
+            self.rpc.get_latest_blockhash()?,
+        );
+        let signature = self.rpc.send_and_confirm_transaction(&transaction)?;
+        println!(
+            "Liquidated obligation {} repaying {} for {}: {}",
+            obligation_pubkey, repay_pubkey, withdraw_pubkey, signature
+        );
+        Ok(true)
+    }
+
+    fn token_balance(&self, token_account: &Pubkey) -> Result<u64> {
+        let data = self.rpc.get_account_data(token_account)?;
+        // Token-2022のaccountは拡張の分だけ長い
+        let data = data
+            .get(..spl_token::state::Account::LEN)
+            .ok_or_else(|| anyhow!("{} is not a token account", token_account))?;
+        Ok(spl_token::state::Account::unpack(data)?.amount)
+    }
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let keeper = Keeper {
+        rpc: RpcClient::new(rpc_url(&args.url)),
+        liquidator: load_keypair(args.keypair)?,
+        market: args.market,
+    };
+
+    loop {
+        if let Err(err) = keeper.scan() {
+            eprintln!("Scan failed: {:#}", err);
+        }
+        if args.once {
+            return Ok(());
+        }
+        sleep(Duration::from_secs(args.interval));
+    }
+}
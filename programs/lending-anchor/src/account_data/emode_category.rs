@@ -0,0 +1,31 @@
+use crate::utils::byte_length::ByteLength;
+use anchor_lang::prelude::*;
+
+/// Efficiency mode category for correlated assets
+#[derive(Clone, Copy, Debug, Default, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct EModeCategory {
+    /// Category id, 0 is reserved for no category
+    pub id: u8,
+
+    /// Target ratio of the value of borrows to deposits, as a percentage
+    pub loan_to_value_ratio: u8,
+
+    /// Loan to value ratio at which an obligation can be liquidated, as a percentage
+    pub liquidation_threshold: u8,
+
+    /// Bonus a liquidator gets when the borrowed value reaches the deposited value,
+    /// as a percentage
+    pub liquidation_bonus: u8,
+
+    /// Oracle account pricing every asset in this category, if shared. Its price is read
+    /// through a reserve of the market using it, so the reserve's price guard and TWAP apply.
+    pub oracle: Option<Pubkey>,
+
+    /// Bonus a liquidator gets when the borrowed value just reaches the unhealthy borrow
+    /// value, as a percentage
+    pub min_liquidation_bonus: u8,
+}
+
+impl ByteLength for EModeCategory {
+    const LEN: usize = 1 + 1 + 1 + 1 + (1 + 32) + 1;
+}
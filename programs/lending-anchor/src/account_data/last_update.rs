@@ -1,4 +1,4 @@
-use crate::{utils::byte_length::ByteLength, errors::LendingError};
+use crate::{errors::LendingError, utils::byte_length::ByteLength};
 use anchor_lang::{prelude::*, solana_program::clock::Slot};
 
 /// Number of slots to consider stale after
@@ -22,7 +22,9 @@ impl LastUpdate {
     }
 
     pub fn slots_elapsed(&self, slot: u64) -> Result<u64> {
-        let slots_elapsed = slot.checked_sub(self.slot).ok_or(LendingError::MathOverflow)?;
+        let slots_elapsed = slot
+            .checked_sub(self.slot)
+            .ok_or(LendingError::MathOverflow)?;
         Ok(slots_elapsed)
     }
 
@@ -32,7 +34,7 @@ impl LastUpdate {
     }
 
     pub fn mark_stale(&mut self) {
-        self.stale= true;
+        self.stale = true;
     }
 
     /// Check if marked stale or last update slot is too long ago
@@ -1,4 +1,16 @@
-use crate::{constants::PROGRAM_VERSION, utils::byte_length::ByteLength};
+use crate::{
+    account_data::{
+        emode_category::EModeCategory,
+        liquidation_pair::{LiquidationPair, LiquidationPairConfig},
+        v1::LendingMarketV1,
+    },
+    constants::{
+        LENDING_MARKET_VERSION, LIQUIDATION_CLOSE_FACTOR, MAX_EMODE_CATEGORIES,
+        MAX_LIQUIDATION_PAIRS,
+    },
+    errors::LendingError,
+    utils::{byte_length::ByteLength, migration::Layout, versioned::Versioned},
+};
 use anchor_lang::prelude::*;
 
 #[account]
@@ -17,10 +29,57 @@ pub struct LendingMarket {
 
     pub token_program_id: Pubkey,
     pub oracle_program_id: Pubkey,
+
+    /// Efficiency mode categories reserves and obligations can opt into
+    pub emode_categories: Vec<EModeCategory>,
+
+    /// Share of an obligation's borrowed value a single liquidation can repay, as a percentage
+    pub liquidation_close_factor: u8,
+
+    /// Borrows worth less than this in quote currency can be repaid in full by one liquidation
+    pub liquidation_full_close_value: u128,
+
+    /// Liquidation terms for pairs of collateral and borrow reserves, replacing the market's
+    pub liquidation_pairs: Vec<LiquidationPair>,
 }
 
 impl ByteLength for LendingMarket {
-    const LEN: usize = 1 + 1 + 32 + 32 + 32 + 32;
+    const LEN: usize = 1
+        + 1
+        + 32
+        + 32
+        + 32
+        + 32
+        + (4 + EModeCategory::LEN * MAX_EMODE_CATEGORIES)
+        + 1
+        + 16
+        + (4 + LiquidationPair::LEN * MAX_LIQUIDATION_PAIRS);
+}
+
+impl Versioned for LendingMarket {
+    const VERSION: u8 = LENDING_MARKET_VERSION;
+}
+
+impl Layout for LendingMarket {
+    type Previous = LendingMarketV1;
+    const SINCE: u8 = 2;
+}
+
+impl From<LendingMarketV1> for LendingMarket {
+    fn from(lending_market: LendingMarketV1) -> Self {
+        Self {
+            version: LENDING_MARKET_VERSION,
+            bump_seed: lending_market.bump_seed,
+            owner: lending_market.owner,
+            quote_currency: lending_market.quote_currency,
+            token_program_id: lending_market.token_program_id,
+            oracle_program_id: lending_market.oracle_program_id,
+            emode_categories: vec![],
+            liquidation_close_factor: LIQUIDATION_CLOSE_FACTOR,
+            liquidation_full_close_value: 0,
+            liquidation_pairs: vec![],
+        }
+    }
 }
 
 pub struct InitLendingMarketParams {
@@ -34,11 +93,103 @@ pub struct InitLendingMarketParams {
 impl LendingMarket {
     /// Create a new lending market
     pub fn init(&mut self, params: InitLendingMarketParams) {
-        self.version = PROGRAM_VERSION;
+        self.version = LENDING_MARKET_VERSION;
         self.bump_seed = params.bump_seed;
         self.owner = params.owner;
         self.quote_currency = params.quote_currency;
         self.token_program_id = params.token_program_id;
         self.oracle_program_id = params.oracle_program_id;
+        self.emode_categories = vec![];
+        self.liquidation_close_factor = LIQUIDATION_CLOSE_FACTOR;
+        self.liquidation_full_close_value = 0;
+        self.liquidation_pairs = vec![];
+    }
+
+    pub fn find_emode_category(&self, id: u8) -> Result<&EModeCategory> {
+        self.emode_categories
+            .iter()
+            .find(|category| category.id == id)
+            .ok_or(error!(LendingError::InvalidEModeCategory))
+    }
+
+    /// Add a new efficiency mode category or replace the one with the same id
+    pub fn set_emode_category(&mut self, category: EModeCategory) -> Result<()> {
+        if let Some(existing) = self
+            .emode_categories
+            .iter_mut()
+            .find(|existing| existing.id == category.id)
+        {
+            *existing = category;
+            return Ok(());
+        }
+
+        require_gt!(
+            MAX_EMODE_CATEGORIES,
+            self.emode_categories.len(),
+            LendingError::EModeCategoryLimit
+        );
+        self.emode_categories.push(category);
+        Ok(())
+    }
+
+    pub fn find_liquidation_pair(
+        &self,
+        deposit_reserve: Pubkey,
+        borrow_reserve: Pubkey,
+    ) -> Option<&LiquidationPairConfig> {
+        self.liquidation_pairs
+            .iter()
+            .find(|pair| {
+                pair.deposit_reserve == deposit_reserve && pair.borrow_reserve == borrow_reserve
+            })
+            .map(|pair| &pair.config)
+    }
+
+    /// Set the liquidation terms of a pair of reserves, or remove them with `None`
+    pub fn set_liquidation_pair(
+        &mut self,
+        deposit_reserve: Pubkey,
+        borrow_reserve: Pubkey,
+        config: Option<LiquidationPairConfig>,
+    ) -> Result<()> {
+        let index = self.liquidation_pairs.iter().position(|pair| {
+            pair.deposit_reserve == deposit_reserve && pair.borrow_reserve == borrow_reserve
+        });
+        match (index, config) {
+            (Some(index), Some(config)) => self.liquidation_pairs[index].config = config,
+            (Some(index), None) => {
+                self.liquidation_pairs.remove(index);
+            }
+            (None, Some(config)) => {
+                require_gt!(
+                    MAX_LIQUIDATION_PAIRS,
+                    self.liquidation_pairs.len(),
+                    LendingError::LiquidationPairLimit
+                );
+                self.liquidation_pairs.push(LiquidationPair {
+                    deposit_reserve,
+                    borrow_reserve,
+                    config,
+                });
+            }
+            (None, None) => {}
+        }
+        Ok(())
+    }
+
+    /// Close factor of a liquidation seizing `deposit_reserve` collateral to repay a
+    /// `borrow_reserve` borrow worth `borrow_value`, as a percentage
+    pub fn liquidation_close_factor(
+        &self,
+        deposit_reserve: Pubkey,
+        borrow_reserve: Pubkey,
+        borrow_value: u128,
+    ) -> u8 {
+        // 少額の借入は残さないよう一度に全て返済させる
+        if borrow_value < self.liquidation_full_close_value {
+            return 100;
+        }
+        self.find_liquidation_pair(deposit_reserve, borrow_reserve)
+            .map_or(self.liquidation_close_factor, |pair| pair.close_factor)
     }
 }
@@ -0,0 +1,36 @@
+use crate::utils::byte_length::ByteLength;
+use anchor_lang::prelude::*;
+
+/// Liquidation terms for a pair of collateral and borrow reserves, replacing the market's
+#[derive(Clone, Copy, Debug, Default, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct LiquidationPairConfig {
+    /// Share of the obligation's borrowed value a single liquidation can repay, as a percentage
+    pub close_factor: u8,
+
+    /// Bonus a liquidator gets when the borrowed value reaches the deposited value,
+    /// as a percentage
+    pub liquidation_bonus: u8,
+
+    /// Bonus a liquidator gets when the borrowed value just reaches the unhealthy borrow
+    /// value, as a percentage
+    pub min_liquidation_bonus: u8,
+}
+
+impl ByteLength for LiquidationPairConfig {
+    const LEN: usize = 1 + 1 + 1;
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct LiquidationPair {
+    /// Reserve whose collateral is seized
+    pub deposit_reserve: Pubkey,
+
+    /// Reserve whose borrow is repaid
+    pub borrow_reserve: Pubkey,
+
+    pub config: LiquidationPairConfig,
+}
+
+impl ByteLength for LiquidationPair {
+    const LEN: usize = 32 + 32 + LiquidationPairConfig::LEN;
+}
@@ -1,4 +1,9 @@
+pub mod emode_category;
 pub mod last_update;
 pub mod lending_market;
+pub mod liquidation_pair;
 pub mod obligation;
+pub mod referrer;
 pub mod reserve;
+pub mod reward_pool;
+pub mod v1;
@@ -1,15 +1,27 @@
 pub mod obligation_collateral;
+pub mod obligation_delegate;
+pub mod obligation_isolated_debt;
 pub mod obligation_liquidity;
+pub mod obligation_reward;
+pub mod obligation_term_loan;
 
 use self::{
-    obligation_collateral::ObligationCollateral, obligation_liquidity::ObligationLiquidity,
+    obligation_collateral::ObligationCollateral, obligation_delegate::ObligationDelegate,
+    obligation_isolated_debt::ObligationIsolatedDebt, obligation_liquidity::ObligationLiquidity,
+    obligation_reward::ObligationReward, obligation_term_loan::ObligationTermLoan,
 };
 use crate::{
-    account_data::last_update::LastUpdate,
-    constants::{MAX_OBLIGATION_RESERVE, PROGRAM_VERSION},
+    account_data::{
+        emode_category::EModeCategory,
+        last_update::LastUpdate,
+        reserve::Reserve,
+        reward_pool::{RewardPool, RewardSide},
+        v1::{self, ObligationV1},
+    },
+    constants::{MAX_OBLIGATION_RESERVE, MAX_OBLIGATION_REWARDS, OBLIGATION_VERSION},
     errors::LendingError,
-    math::rate::Rate,
-    utils::byte_length::ByteLength,
+    math::{common::WAD, rate::Rate},
+    utils::{byte_length::ByteLength, migration::Layout, versioned::Versioned},
 };
 use anchor_lang::prelude::*;
 
@@ -22,13 +34,115 @@ pub struct Obligation {
     pub deposits: Vec<ObligationCollateral>,
     pub borrows: Vec<ObligationLiquidity>,
     pub deposited_value: u128,
+    /// Borrowed value weighted by each reserve's borrow weight, used for risk checks
     pub borrowed_value: u128,
+    /// Borrowed value at face market value, for display
+    pub unweighted_borrowed_value: u128,
     pub allowed_borrow_value: u128,
     pub unhealthy_borrow_value: u128,
+    /// Isolated collateral reserve backing every borrow, if any
+    pub isolated_reserve: Option<Pubkey>,
+    /// Liquidity borrowed against the isolated reserve, counted in its debt ceiling
+    pub isolated_debts: Vec<ObligationIsolatedDebt>,
+    /// Efficiency mode category opted into, 0 if none
+    pub emode_category: u8,
+    /// Authority acting on behalf of the owner, if any
+    pub delegate: Option<ObligationDelegate>,
+    /// Referrer credited with the host fees of every borrow, if any
+    pub referrer: Option<Pubkey>,
+    /// Rewards earned from the reward pools of its deposits and borrows
+    pub rewards: Vec<ObligationReward>,
+    /// True if a reserve held back its price at the last refresh, which blocks borrows and
+    /// liquidations
+    pub price_guarded: bool,
+    /// True if a reserve fell back to its TWAP at the last refresh, which blocks borrows and
+    /// withdrawals against borrows
+    pub price_fallback: bool,
+    /// Fixed-rate term loans, tracked apart from the variable borrows
+    pub term_loans: Vec<ObligationTermLoan>,
 }
 
 impl ByteLength for Obligation {
-    const LEN: usize = 0;
+    /// deposits, borrows and term loans share MAX_OBLIGATION_RESERVE entries, sized for the
+    /// largest one
+    const LEN: usize = 1
+        + LastUpdate::LEN
+        + 32
+        + 32
+        + 4
+        + 4
+        + MAX_OBLIGATION_RESERVE * ObligationTermLoan::LEN
+        + 16 * 5
+        + (1 + 32)
+        + (4 + MAX_OBLIGATION_RESERVE * ObligationIsolatedDebt::LEN)
+        + 1
+        + (1 + ObligationDelegate::LEN)
+        + (1 + 32)
+        + 4
+        + MAX_OBLIGATION_REWARDS * ObligationReward::LEN
+        + 1
+        + 1
+        + 4;
+}
+
+impl Versioned for Obligation {
+    const VERSION: u8 = OBLIGATION_VERSION;
+}
+
+impl Layout for Obligation {
+    type Previous = ObligationV1;
+    const SINCE: u8 = 2;
+}
+
+/// borrow weightが無かったのでborrowed valueはそのまま額面の価値になる。
+/// 孤立モードも無かったのでisolated collateralへのdebtも無い。
+/// borrowの利率はborrow reserveが要るので、migrate_obligationでreserveの現在の値に合わせる
+impl From<ObligationV1> for Obligation {
+    fn from(obligation: ObligationV1) -> Self {
+        Self {
+            version: OBLIGATION_VERSION,
+            // 新しいfieldを計算し直すまでrefreshさせる
+            last_update: LastUpdate {
+                slot: obligation.last_update.slot,
+                stale: true,
+            },
+            lending_market: obligation.lending_market,
+            owner: obligation.owner,
+            deposits: obligation
+                .deposits
+                .into_iter()
+                .map(|collateral| ObligationCollateral {
+                    deposit_reserve: collateral.deposit_reserve,
+                    deposited_amount: collateral.deposited_amount,
+                    market_value: collateral.market_value,
+                })
+                .collect(),
+            borrows: obligation
+                .borrows
+                .into_iter()
+                .map(|liquidity| ObligationLiquidity {
+                    borrow_reserve: liquidity.borrow_reserve,
+                    cumulative_borrow_rate_wads: WAD as u128,
+                    borrowed_amount: v1::wads_to_amount(liquidity.borrowed_amount_wads),
+                    market_value: liquidity.market_value,
+                })
+                .collect(),
+            deposited_value: obligation.deposited_value,
+            borrowed_value: obligation.borrowed_value,
+            unweighted_borrowed_value: obligation.borrowed_value,
+            allowed_borrow_value: obligation.allowed_borrow_value,
+            unhealthy_borrow_value: obligation.unhealthy_borrow_value,
+            isolated_reserve: None,
+            isolated_debts: vec![],
+            emode_category: 0,
+            delegate: None,
+            referrer: None,
+            rewards: vec![],
+            price_guarded: false,
+            price_fallback: false,
+            term_loans: vec![],
+        }
+    }
 }
 
 pub struct InitObligationParams {
@@ -41,18 +155,133 @@ pub struct InitObligationParams {
 
 impl Obligation {
     pub fn init(&mut self, params: InitObligationParams) {
-        self.version = PROGRAM_VERSION;
+        self.version = OBLIGATION_VERSION;
         self.last_update = LastUpdate::new(params.current_slot);
         self.lending_market = params.lending_market;
         self.owner = params.owner;
         self.deposits = params.deposits;
         self.borrows = params.borrows;
+        self.isolated_reserve = None;
+        self.isolated_debts = vec![];
+        self.emode_category = 0;
+        self.delegate = None;
+        self.referrer = None;
+        self.rewards = vec![];
+        self.price_guarded = false;
+        self.price_fallback = false;
+        self.term_loans = vec![];
+    }
+
+    /// Whether `authority` is the owner or a delegate allowed to sign with `permissions`
+    pub fn is_authorized(&self, authority: Pubkey, permissions: u8) -> bool {
+        authority == self.owner
+            || self.delegate.is_some_and(|delegate| {
+                delegate.authority == authority && delegate.has_permissions(permissions)
+            })
+    }
+
+    /// Spend the delegate's borrow allowance when `authority` borrows as the delegate
+    pub fn use_borrow_allowance(&mut self, authority: Pubkey, borrow_value: u128) -> Result<()> {
+        if authority == self.owner {
+            return Ok(());
+        }
+        match self.delegate.as_mut() {
+            Some(delegate) => delegate.use_borrow_allowance(borrow_value),
+            None => Err(LendingError::InvalidObligationOwner.into()),
+        }
+    }
+
+    /// Recalculate deposit and borrow values from freshly refreshed reserves.
+    /// `emode` is the obligation's category when every deposit and borrow belongs to it,
+    /// and `emode_price` the guarded price of its shared oracle, if any.
+    pub fn refresh(
+        &mut self,
+        deposit_reserves: &[Reserve],
+        borrow_reserves: &[Reserve],
+        term_loan_reserves: &[Reserve],
+        emode: Option<&EModeCategory>,
+        emode_price: Option<u128>,
+        current_slot: u64,
+    ) -> Result<()> {
+        let mut deposited_value = 0u128;
+        let mut allowed_borrow_value = 0u128;
+        let mut unhealthy_borrow_value = 0u128;
+        for (collateral, reserve) in self.deposits.iter_mut().zip(deposit_reserves) {
+            let liquidity_amount = reserve
+                .collateral_exchange_rate()?
+                .collateral_to_liquidity(collateral.deposited_amount)?;
+            let market_price = emode_price.unwrap_or(reserve.liquidity.market_price);
+            collateral.market_value =
+                reserve.market_value_at(liquidity_amount as u128, market_price)?;
+
+            let (loan_to_value_ratio, liquidation_threshold) = match emode {
+                Some(category) => (category.loan_to_value_ratio, category.liquidation_threshold),
+                None => (
+                    reserve.config.loan_to_value_ratio,
+                    reserve.config.liquidation_threshold,
+                ),
+            };
+
+            deposited_value = deposited_value
+                .checked_add(collateral.market_value)
+                .ok_or(LendingError::MathOverflow)?;
+            allowed_borrow_value = allowed_borrow_value
+                .checked_add(
+                    Rate::from_percent(loan_to_value_ratio).try_apply(collateral.market_value)?,
+                )
+                .ok_or(LendingError::MathOverflow)?;
+            unhealthy_borrow_value = unhealthy_borrow_value
+                .checked_add(
+                    Rate::from_percent(liquidation_threshold).try_apply(collateral.market_value)?,
+                )
+                .ok_or(LendingError::MathOverflow)?;
+        }
+
+        let mut borrowed_value = 0u128;
+        let mut unweighted_borrowed_value = 0u128;
+        for (liquidity, reserve) in self.borrows.iter_mut().zip(borrow_reserves) {
+            liquidity.accrue_interest(reserve.liquidity.cumulative_borrow_rate_wads)?;
+            let market_price = emode_price.unwrap_or(reserve.liquidity.market_price);
+            liquidity.market_value =
+                reserve.market_value_at(liquidity.borrowed_amount, market_price)?;
+
+            unweighted_borrowed_value = unweighted_borrowed_value
+                .checked_add(liquidity.market_value)
+                .ok_or(LendingError::MathOverflow)?;
+            borrowed_value = borrowed_value
+                .checked_add(reserve.borrow_weight().try_apply(liquidity.market_value)?)
+                .ok_or(LendingError::MathOverflow)?;
+        }
+        for (term_loan, reserve) in self.term_loans.iter_mut().zip(term_loan_reserves) {
+            term_loan.accrue_interest(current_slot)?;
+            let market_price = emode_price.unwrap_or(reserve.liquidity.market_price);
+            term_loan.market_value =
+                reserve.market_value_at(term_loan.borrowed_amount, market_price)?;
+
+            unweighted_borrowed_value = unweighted_borrowed_value
+                .checked_add(term_loan.market_value)
+                .ok_or(LendingError::MathOverflow)?;
+            borrowed_value = borrowed_value
+                .checked_add(reserve.borrow_weight().try_apply(term_loan.market_value)?)
+                .ok_or(LendingError::MathOverflow)?;
+        }
+
+        self.deposited_value = deposited_value;
+        self.borrowed_value = borrowed_value;
+        self.unweighted_borrowed_value = unweighted_borrowed_value;
+        self.allowed_borrow_value = allowed_borrow_value;
+        self.unhealthy_borrow_value = unhealthy_borrow_value;
+
+        Ok(())
     }
 
     pub fn withdraw(&mut self, withdraw_amount: u64, collateral_index: usize) -> Result<()> {
         let collateral = &mut self.deposits[collateral_index];
         if withdraw_amount == collateral.deposited_amount {
             self.deposits.remove(collateral_index);
+            if self.deposits.is_empty() && !self.has_borrows() {
+                self.isolated_reserve = None;
+            }
         } else {
             collateral.withdraw(withdraw_amount)?;
         }
@@ -61,7 +290,7 @@ impl Obligation {
 
     pub fn repay(&mut self, settle_amount: u128, liquidity_index: usize) -> Result<()> {
         let liquidity = &mut self.borrows[liquidity_index];
-        if settle_amount == liquidity.borrowed_amount_wads {
+        if settle_amount == liquidity.borrowed_amount {
             self.borrows.remove(liquidity_index);
         } else {
             liquidity.repay(settle_amount)?;
@@ -70,6 +299,93 @@ impl Obligation {
         Ok(())
     }
 
+    /// Whether the obligation owes anything, variable or fixed
+    pub fn has_borrows(&self) -> bool {
+        !self.borrows.is_empty() || !self.term_loans.is_empty()
+    }
+
+    /// Number of reserves the obligation holds a position in, limited to MAX_OBLIGATION_RESERVE
+    fn position_count(&self) -> usize {
+        self.deposits.len() + self.borrows.len() + self.term_loans.len()
+    }
+
+    /// Check that a deposit into `deposit_reserve` keeps isolated collateral on its own
+    pub fn check_collateral_isolation(
+        &self,
+        deposit_reserve: Pubkey,
+        isolated: bool,
+    ) -> Result<()> {
+        if let Some(isolated_reserve) = self.isolated_reserve {
+            require_keys_eq!(
+                isolated_reserve,
+                deposit_reserve,
+                LendingError::IsolatedCollateral
+            );
+        } else if isolated {
+            require!(
+                self.deposits
+                    .iter()
+                    .all(|collateral| collateral.deposit_reserve == deposit_reserve),
+                LendingError::IsolatedCollateral
+            );
+        }
+        Ok(())
+    }
+
+    /// Record liquidity of `borrow_reserve` borrowed against isolated collateral
+    pub fn add_isolated_debt(&mut self, borrow_reserve: Pubkey, borrow_amount: u128) -> Result<()> {
+        let index = match self
+            .isolated_debts
+            .iter()
+            .position(|debt| debt.borrow_reserve == borrow_reserve)
+        {
+            Some(index) => index,
+            None => {
+                self.isolated_debts
+                    .push(ObligationIsolatedDebt::new(borrow_reserve));
+                self.isolated_debts.len() - 1
+            }
+        };
+        let debt = &mut self.isolated_debts[index];
+        debt.borrowed_amount = debt
+            .borrowed_amount
+            .checked_add(borrow_amount)
+            .ok_or(LendingError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Remove liquidity of `borrow_reserve` repaid against isolated collateral, returning the
+    /// amount actually removed. Everything is removed once nothing is owed to the reserve.
+    pub fn remove_isolated_debt(&mut self, borrow_reserve: Pubkey, repay_amount: u128) -> u128 {
+        let index = match self
+            .isolated_debts
+            .iter()
+            .position(|debt| debt.borrow_reserve == borrow_reserve)
+        {
+            Some(index) => index,
+            None => return 0,
+        };
+        let owed = self
+            .borrows
+            .iter()
+            .any(|liquidity| liquidity.borrow_reserve == borrow_reserve)
+            || self
+                .term_loans
+                .iter()
+                .any(|term_loan| term_loan.borrow_reserve == borrow_reserve);
+        let debt = &mut self.isolated_debts[index];
+        let removed = if owed {
+            repay_amount.min(debt.borrowed_amount)
+        } else {
+            debt.borrowed_amount
+        };
+        debt.borrowed_amount -= removed;
+        if debt.borrowed_amount == 0 {
+            self.isolated_debts.remove(index);
+        }
+        removed
+    }
+
     pub fn find_or_add_collateral_to_deposits(
         &mut self,
         deposit_reserve: Pubkey,
@@ -78,7 +394,7 @@ impl Obligation {
             return Ok(&mut self.deposits[collateral_index]);
         }
 
-        if self.deposits.len() + self.borrows.len() >= MAX_OBLIGATION_RESERVE {
+        if self.position_count() >= MAX_OBLIGATION_RESERVE {
             msg!(
                 "Obligation cannot have more than {} deposits and borrows combined",
                 MAX_OBLIGATION_RESERVE
@@ -114,16 +430,17 @@ impl Obligation {
     pub fn find_or_add_liquidity_to_borrows(
         &mut self,
         borrow_reserve: Pubkey,
+        cumulative_borrow_rate_wads: u128,
     ) -> Result<&mut ObligationLiquidity> {
         if let Some(liquidity_index) = self._find_liquidity_index_in_borrows(borrow_reserve) {
             return Ok(&mut self.borrows[liquidity_index]);
         }
         require_gt!(
             MAX_OBLIGATION_RESERVE,
-            self.deposits.len() + self.borrows.len(),
+            self.position_count(),
             LendingError::ObligationReserveLimit
         );
-        let liquidity = ObligationLiquidity::new(borrow_reserve);
+        let liquidity = ObligationLiquidity::new(borrow_reserve, cumulative_borrow_rate_wads);
         self.borrows.push(liquidity);
         Ok(self.borrows.last_mut().unwrap())
     }
@@ -148,6 +465,148 @@ impl Obligation {
         Ok((&self.borrows[liquidity_index], liquidity_index))
     }
 
+    /// Add a term loan, at most one per reserve
+    pub fn add_term_loan(&mut self, term_loan: ObligationTermLoan) -> Result<()> {
+        require!(
+            self._find_term_loan_index(term_loan.borrow_reserve)
+                .is_none(),
+            LendingError::TermLoanExists
+        );
+        require_gt!(
+            MAX_OBLIGATION_RESERVE,
+            self.position_count(),
+            LendingError::ObligationReserveLimit
+        );
+        self.term_loans.push(term_loan);
+        Ok(())
+    }
+
+    fn _find_term_loan_index(&self, borrow_reserve: Pubkey) -> Option<usize> {
+        self.term_loans
+            .iter()
+            .position(|term_loan| term_loan.borrow_reserve == borrow_reserve)
+    }
+
+    pub fn find_term_loan(&self, borrow_reserve: Pubkey) -> Result<(&ObligationTermLoan, usize)> {
+        let term_loan_index = self
+            ._find_term_loan_index(borrow_reserve)
+            .ok_or(LendingError::TermLoanNotFound)?;
+        Ok((&self.term_loans[term_loan_index], term_loan_index))
+    }
+
+    /// Settle `settle_amount` of the term loan at `term_loan_index`, returning the principal
+    /// repaid with it
+    pub fn repay_term_loan(&mut self, settle_amount: u128, term_loan_index: usize) -> Result<u64> {
+        let term_loan = &mut self.term_loans[term_loan_index];
+        let principal_repaid = term_loan.repay(settle_amount)?;
+        if term_loan.borrowed_amount == 0 {
+            self.term_loans.remove(term_loan_index);
+        }
+        Ok(principal_repaid)
+    }
+
+    /// Amount of the position in `reserve` that earns rewards on `side`.
+    /// Borrows count in shares of the cumulative borrow rate, which interest doesn't change.
+    pub fn staked_amount(&self, reserve: Pubkey, side: RewardSide) -> Result<u64> {
+        match side {
+            RewardSide::Deposit => Ok(self
+                ._find_collateral_index_in_deposits(reserve)
+                .map_or(0, |index| self.deposits[index].deposited_amount)),
+            RewardSide::Borrow => match self._find_liquidity_index_in_borrows(reserve) {
+                Some(index) => {
+                    let liquidity = &self.borrows[index];
+                    let shares = liquidity
+                        .borrowed_amount
+                        .checked_mul(WAD.into())
+                        .and_then(|amount| {
+                            amount.checked_div(liquidity.cumulative_borrow_rate_wads)
+                        })
+                        .ok_or(LendingError::MathOverflow)?;
+                    Ok(u64::try_from(shares).map_err(|_| LendingError::MathOverflow)?)
+                }
+                None => Ok(0),
+            },
+        }
+    }
+
+    /// Settle the rewards earned from `reward_pool`, already accrued, and stake `staked_amount`
+    /// in it from now on
+    pub fn update_reward(
+        &mut self,
+        reward_pool_key: Pubkey,
+        reward_pool: &mut RewardPool,
+        staked_amount: u64,
+    ) -> Result<()> {
+        let index = match self
+            .rewards
+            .iter()
+            .position(|reward| reward.reward_pool == reward_pool_key)
+        {
+            Some(index) => index,
+            None if staked_amount == 0 => return Ok(()),
+            None => {
+                require_gt!(
+                    MAX_OBLIGATION_REWARDS,
+                    self.rewards.len(),
+                    LendingError::ObligationRewardLimit
+                );
+                self.rewards.push(ObligationReward::new(
+                    reward_pool_key,
+                    reward_pool.reward_index,
+                ));
+                self.rewards.len() - 1
+            }
+        };
+
+        let reward = &mut self.rewards[index];
+        reward.settle(reward_pool.reward_index)?;
+        reward_pool.restake(reward.staked_amount, staked_amount)?;
+        reward.staked_amount = staked_amount;
+        if reward.staked_amount == 0 && reward.unclaimed_amount == 0 {
+            self.rewards.remove(index);
+        }
+        Ok(())
+    }
+
+    /// Liquidation bonus rate growing linearly from `min_bonus` percent when the borrowed value
+    /// reaches the unhealthy borrow value to `max_bonus` percent when it reaches the deposited
+    /// value
+    pub fn liquidation_bonus(&self, min_bonus: u8, max_bonus: u8) -> Result<Rate> {
+        let min_rate = Rate::from_percent(min_bonus);
+        let max_rate = Rate::from_percent(max_bonus);
+        let range = self
+            .deposited_value
+            .saturating_sub(self.unhealthy_borrow_value);
+        // unhealthy borrow valueと同じくborrow weightを掛けた借入の価値で比べる
+        let excess = self
+            .borrowed_value
+            .saturating_sub(self.unhealthy_borrow_value);
+        // 借入がcollateralの価値に届いていればbonusは最大
+        if excess >= range {
+            return Ok(max_rate);
+        }
+        Rate::from_ratio(excess, range)?
+            .try_mul(max_rate.try_sub(min_rate)?)?
+            .try_add(min_rate)
+    }
+
+    /// Take the settled rewards of `reward_pool`
+    pub fn claim_reward(&mut self, reward_pool: Pubkey) -> u64 {
+        let index = match self
+            .rewards
+            .iter()
+            .position(|reward| reward.reward_pool == reward_pool)
+        {
+            Some(index) => index,
+            None => return 0,
+        };
+        let amount = std::mem::take(&mut self.rewards[index].unclaimed_amount);
+        if self.rewards[index].staked_amount == 0 {
+            self.rewards.remove(index);
+        }
+        amount
+    }
+
     /// Calculate the maximum collateral value that can be withdrawn
     pub fn max_withdraw_value(&self, withdraw_collateral_ltv: Rate) -> Result<u128> {
         if self.allowed_borrow_value <= self.borrowed_value {
@@ -156,12 +615,29 @@ impl Obligation {
         if withdraw_collateral_ltv == Rate::zero() {
             return Ok(self.deposited_value);
         }
-        let ltv: u64 = withdraw_collateral_ltv.into();
-        self.allowed_borrow_value
-            .checked_sub(self.borrowed_value)
-            .ok_or(LendingError::MathOverflow)?
-            .checked_div(ltv as u128)
-            .ok_or(LendingError::MathOverflow.into())
+        withdraw_collateral_ltv.try_unapply(
+            self.allowed_borrow_value
+                .checked_sub(self.borrowed_value)
+                .ok_or(LendingError::MathOverflow)?,
+        )
+    }
+
+    /// Maximum amount of `liquidity` a single liquidation can repay with `close_factor`
+    pub fn max_liquidation_amount(
+        &self,
+        liquidity: &ObligationLiquidity,
+        close_factor: u8,
+    ) -> Result<u128> {
+        let max_liquidation_value = Rate::from_percent(close_factor)
+            .try_apply(self.unweighted_borrowed_value)?
+            .min(liquidity.market_value);
+        let max_liquidation_pct = Rate::from_ratio(max_liquidation_value, liquidity.market_value)?;
+        max_liquidation_pct.try_apply(liquidity.borrowed_amount)
+    }
+
+    /// Whether the refreshed obligation can be liquidated
+    pub fn is_unhealthy(&self) -> bool {
+        self.has_borrows() && self.borrowed_value >= self.unhealthy_borrow_value
     }
 
     pub fn remaining_borrow_value(&self) -> Result<u128> {
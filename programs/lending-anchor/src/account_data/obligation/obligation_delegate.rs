@@ -0,0 +1,44 @@
+use crate::{errors::LendingError, utils::byte_length::ByteLength};
+use anchor_lang::prelude::*;
+
+/// Authority the owner lets act on the obligation, such as a bot or a vault
+#[derive(Clone, Copy, Debug, Default, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct ObligationDelegate {
+    pub authority: Pubkey,
+
+    /// Bitmask of the instructions the delegate may sign
+    pub permissions: u8,
+
+    /// Borrow value in quote currency the delegate may still take on, unlimited if none
+    pub borrow_allowance: Option<u128>,
+}
+
+impl ByteLength for ObligationDelegate {
+    const LEN: usize = 32 + 1 + (1 + 16);
+}
+
+impl ObligationDelegate {
+    /// Repaying is open to anyone, so this only records the intent of the owner
+    pub const REPAY: u8 = 1 << 0;
+    pub const DEPOSIT: u8 = 1 << 1;
+    pub const BORROW: u8 = 1 << 2;
+    /// Collateral can only be withdrawn to token accounts of the owner
+    pub const WITHDRAW: u8 = 1 << 3;
+    pub const ALL: u8 = Self::REPAY | Self::DEPOSIT | Self::BORROW | Self::WITHDRAW;
+
+    pub fn has_permissions(&self, permissions: u8) -> bool {
+        self.permissions & permissions == permissions
+    }
+
+    /// Spend `borrow_value` of the allowance
+    pub fn use_borrow_allowance(&mut self, borrow_value: u128) -> Result<()> {
+        if let Some(allowance) = self.borrow_allowance {
+            self.borrow_allowance = Some(
+                allowance
+                    .checked_sub(borrow_value)
+                    .ok_or(LendingError::DelegateAllowanceExceeded)?,
+            );
+        }
+        Ok(())
+    }
+}
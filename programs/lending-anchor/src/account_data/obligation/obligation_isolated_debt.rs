@@ -0,0 +1,24 @@
+use crate::utils::byte_length::ByteLength;
+use anchor_lang::prelude::*;
+
+/// Liquidity of one borrow reserve the obligation borrowed against its isolated collateral
+#[derive(Clone, Copy, Debug, Default, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct ObligationIsolatedDebt {
+    pub borrow_reserve: Pubkey,
+    /// Liquidity borrowed, in tokens of the borrow reserve, counted in the isolated reserve's
+    /// debt until repaid
+    pub borrowed_amount: u128,
+}
+
+impl ByteLength for ObligationIsolatedDebt {
+    const LEN: usize = 32 + 16;
+}
+
+impl ObligationIsolatedDebt {
+    pub fn new(borrow_reserve: Pubkey) -> Self {
+        Self {
+            borrow_reserve,
+            borrowed_amount: 0,
+        }
+    }
+}
@@ -1,11 +1,12 @@
-use crate::{errors::LendingError, utils::byte_length::ByteLength};
+use crate::{errors::LendingError, math::rate::Rate, utils::byte_length::ByteLength};
 use anchor_lang::prelude::*;
 
 #[derive(Clone, Debug, Default, PartialEq, AnchorSerialize, AnchorDeserialize)]
 pub struct ObligationLiquidity {
     pub borrow_reserve: Pubkey,
     pub cumulative_borrow_rate_wads: u128,
-    pub borrowed_amount_wads: u128,
+    /// Liquidity borrowed, in tokens of the borrow reserve including accrued interest
+    pub borrowed_amount: u128,
     pub market_value: u128,
 }
 
@@ -14,26 +15,44 @@ impl ByteLength for ObligationLiquidity {
 }
 
 impl ObligationLiquidity {
-    pub fn new(borrow_reserve: Pubkey) -> Self {
+    pub fn new(borrow_reserve: Pubkey, cumulative_borrow_rate_wads: u128) -> Self {
         Self {
             borrow_reserve,
-            cumulative_borrow_rate_wads: 1,
-            borrowed_amount_wads: 0,
+            cumulative_borrow_rate_wads,
+            borrowed_amount: 0,
             market_value: 0,
         }
     }
 
     pub fn repay(&mut self, settle_amount: u128) -> Result<()> {
-        self.borrowed_amount_wads = self
-            .borrowed_amount_wads
+        self.borrowed_amount = self
+            .borrowed_amount
             .checked_sub(settle_amount)
             .ok_or(LendingError::MathOverflow)?;
         Ok(())
     }
 
+    /// reserveのcumulative borrow rateに追いつくまで利息を加える
+    pub fn accrue_interest(&mut self, cumulative_borrow_rate_wads: u128) -> Result<()> {
+        require_gte!(
+            cumulative_borrow_rate_wads,
+            self.cumulative_borrow_rate_wads,
+            LendingError::MathOverflow
+        );
+        if cumulative_borrow_rate_wads > self.cumulative_borrow_rate_wads {
+            let compounded_interest_rate = Rate::from_ratio(
+                cumulative_borrow_rate_wads,
+                self.cumulative_borrow_rate_wads,
+            )?;
+            self.borrowed_amount = compounded_interest_rate.try_apply(self.borrowed_amount)?;
+            self.cumulative_borrow_rate_wads = cumulative_borrow_rate_wads;
+        }
+        Ok(())
+    }
+
     pub fn borrow(&mut self, borrow_amount: u128) -> Result<()> {
-        self.borrowed_amount_wads = self
-            .borrowed_amount_wads
+        self.borrowed_amount = self
+            .borrowed_amount
             .checked_add(borrow_amount)
             .ok_or(LendingError::MathOverflow)?;
         Ok(())
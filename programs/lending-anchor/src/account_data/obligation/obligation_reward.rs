@@ -0,0 +1,43 @@
+use crate::{errors::LendingError, math::common::WAD, utils::byte_length::ByteLength};
+use anchor_lang::prelude::*;
+
+/// Rewards an obligation earns from one reward pool
+#[derive(Clone, Debug, Default, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct ObligationReward {
+    pub reward_pool: Pubkey,
+    /// Collateral deposited or borrow shares counted in the pool
+    pub staked_amount: u64,
+    /// Reward index of the pool when the rewards were last settled, scaled by WAD
+    pub reward_index: u128,
+    pub unclaimed_amount: u64,
+}
+
+impl ByteLength for ObligationReward {
+    const LEN: usize = 32 + 8 + 16 + 8;
+}
+
+impl ObligationReward {
+    pub fn new(reward_pool: Pubkey, reward_index: u128) -> Self {
+        Self {
+            reward_pool,
+            staked_amount: 0,
+            reward_index,
+            unclaimed_amount: 0,
+        }
+    }
+
+    /// staked_amountに対してreward indexの増加分を報酬として加える
+    pub fn settle(&mut self, reward_index: u128) -> Result<()> {
+        let earned = reward_index
+            .checked_sub(self.reward_index)
+            .and_then(|increase| increase.checked_mul(self.staked_amount.into()))
+            .ok_or(LendingError::MathOverflow)?
+            / u128::from(WAD);
+        self.unclaimed_amount = u64::try_from(earned)
+            .ok()
+            .and_then(|earned| self.unclaimed_amount.checked_add(earned))
+            .ok_or(LendingError::MathOverflow)?;
+        self.reward_index = reward_index;
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{
+    account_data::{
+        obligation::obligation_liquidity::ObligationLiquidity, reserve::term_offer::TermOffer,
+    },
+    constants::SLOTS_PER_YEAR,
+    errors::LendingError,
+    math::{common::WAD, rate::Rate},
+    utils::byte_length::ByteLength,
+};
+use anchor_lang::prelude::*;
+
+/// Fixed-rate loan borrowed from one reserve until its maturity
+#[derive(Clone, Debug, Default, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct ObligationTermLoan {
+    pub borrow_reserve: Pubkey,
+    /// Principal still owed, counted in the reserve's term borrows
+    pub principal_amount: u64,
+    /// Principal plus the interest accrued until `accrued_slot`
+    pub borrowed_amount: u128,
+    /// Borrow APY locked until maturity, in basis points
+    pub rate_bps: u32,
+    /// Borrow APY after maturity, in basis points
+    pub penalty_rate_bps: u32,
+    pub maturity_slot: u64,
+    pub accrued_slot: u64,
+    pub market_value: u128,
+}
+
+impl ByteLength for ObligationTermLoan {
+    const LEN: usize = 32 + 8 + 16 + 4 + 4 + 8 + 8 + 16;
+}
+
+impl ObligationTermLoan {
+    pub fn new(
+        borrow_reserve: Pubkey,
+        borrow_amount: u128,
+        offer: &TermOffer,
+        penalty_rate_bps: u32,
+        current_slot: u64,
+    ) -> Result<Self> {
+        Ok(Self {
+            borrow_reserve,
+            principal_amount: u64::try_from(borrow_amount)
+                .map_err(|_| LendingError::MathOverflow)?,
+            borrowed_amount: borrow_amount,
+            rate_bps: offer.rate_bps,
+            penalty_rate_bps,
+            maturity_slot: current_slot
+                .checked_add(offer.term_slots)
+                .ok_or(LendingError::MathOverflow)?,
+            accrued_slot: current_slot,
+            market_value: 0,
+        })
+    }
+
+    /// Whether the loan is past its maturity
+    pub fn is_overdue(&self, current_slot: u64) -> bool {
+        current_slot >= self.maturity_slot
+    }
+
+    /// 満期までは固定金利，満期後はpenalty rateで利息を加える
+    pub fn accrue_interest(&mut self, current_slot: u64) -> Result<()> {
+        if current_slot <= self.accrued_slot {
+            return Ok(());
+        }
+        let matured_slot = self.maturity_slot.clamp(self.accrued_slot, current_slot);
+        self.borrowed_amount = Self::compound(
+            self.borrowed_amount,
+            self.rate_bps,
+            matured_slot - self.accrued_slot,
+        )?;
+        self.borrowed_amount = Self::compound(
+            self.borrowed_amount,
+            self.penalty_rate_bps,
+            current_slot - matured_slot,
+        )?;
+        self.accrued_slot = current_slot;
+        Ok(())
+    }
+
+    fn compound(amount: u128, rate_bps: u32, slots: u64) -> Result<u128> {
+        if slots == 0 {
+            return Ok(amount);
+        }
+        let slot_interest_rate =
+            Rate::from_bps(rate_bps).try_div(Rate::from_ratio(SLOTS_PER_YEAR as u128, 1)?)?;
+        Rate::one()
+            .try_add(slot_interest_rate)?
+            .try_pow(slots)?
+            .try_apply(amount)
+    }
+
+    /// 返済額に比例した元本を減らし，減らした元本を返す
+    pub fn repay(&mut self, settle_amount: u128) -> Result<u64> {
+        let principal_repaid = if settle_amount >= self.borrowed_amount {
+            self.principal_amount
+        } else {
+            let principal = Rate::from_ratio(settle_amount, self.borrowed_amount)?
+                .try_apply(self.principal_amount as u128)?;
+            u64::try_from(principal).map_err(|_| LendingError::MathOverflow)?
+        };
+        self.borrowed_amount = self
+            .borrowed_amount
+            .checked_sub(settle_amount)
+            .ok_or(LendingError::MathOverflow)?;
+        self.principal_amount = self
+            .principal_amount
+            .checked_sub(principal_repaid)
+            .ok_or(LendingError::MathOverflow)?;
+        Ok(principal_repaid)
+    }
+
+    /// The loan as a variable borrow, for the liquidation amounts shared with them
+    pub fn as_liquidity(&self) -> ObligationLiquidity {
+        ObligationLiquidity {
+            borrow_reserve: self.borrow_reserve,
+            cumulative_borrow_rate_wads: WAD.into(),
+            borrowed_amount: self.borrowed_amount,
+            market_value: self.market_value,
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{
+    constants::{REFERRER_TOKEN_STATE_VERSION, REFERRER_VERSION},
+    errors::LendingError,
+    utils::{byte_length::ByteLength, versioned::Versioned},
+};
+use anchor_lang::prelude::*;
+
+/// Referrer registered in a lending market, credited with the host fees of the obligations
+/// it referred
+#[account]
+pub struct Referrer {
+    pub version: u8,
+
+    pub bump_seed: u8,
+
+    pub lending_market: Pubkey,
+
+    /// Wallet which registered the referrer and claims its fees
+    pub owner: Pubkey,
+}
+
+impl ByteLength for Referrer {
+    const LEN: usize = 1 + 1 + 32 + 32;
+}
+
+impl Versioned for Referrer {
+    const VERSION: u8 = REFERRER_VERSION;
+}
+
+impl Referrer {
+    pub fn init(&mut self, bump_seed: u8, lending_market: Pubkey, owner: Pubkey) {
+        self.version = REFERRER_VERSION;
+        self.bump_seed = bump_seed;
+        self.lending_market = lending_market;
+        self.owner = owner;
+    }
+}
+
+/// Host fees of one liquidity mint accrued to a referrer
+#[account]
+pub struct ReferrerTokenState {
+    pub version: u8,
+
+    pub bump_seed: u8,
+
+    pub referrer: Pubkey,
+
+    pub mint: Pubkey,
+
+    /// Token account the host fees are paid into, owned by the lending market authority
+    pub token_account: Pubkey,
+
+    /// Host fees received over the lifetime of the referrer
+    pub accrued_amount: u64,
+
+    /// Host fees the owner has claimed
+    pub claimed_amount: u64,
+}
+
+impl ByteLength for ReferrerTokenState {
+    const LEN: usize = 1 + 1 + 32 + 32 + 32 + 8 + 8;
+}
+
+impl Versioned for ReferrerTokenState {
+    const VERSION: u8 = REFERRER_TOKEN_STATE_VERSION;
+}
+
+impl ReferrerTokenState {
+    pub fn init(&mut self, bump_seed: u8, referrer: Pubkey, mint: Pubkey, token_account: Pubkey) {
+        self.version = REFERRER_TOKEN_STATE_VERSION;
+        self.bump_seed = bump_seed;
+        self.referrer = referrer;
+        self.mint = mint;
+        self.token_account = token_account;
+        self.accrued_amount = 0;
+        self.claimed_amount = 0;
+    }
+
+    pub fn accrue(&mut self, amount: u64) -> Result<()> {
+        self.accrued_amount = self
+            .accrued_amount
+            .checked_add(amount)
+            .ok_or(LendingError::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn claim(&mut self, amount: u64) -> Result<()> {
+        self.claimed_amount = self
+            .claimed_amount
+            .checked_add(amount)
+            .ok_or(LendingError::MathOverflow)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,46 @@
+use crate::{math::common::BPS_SCALE, utils::byte_length::ByteLength};
+use anchor_lang::prelude::*;
+
+/// Point of a piecewise-linear borrow rate curve
+#[derive(Clone, Copy, Debug, Default, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct BorrowRatePoint {
+    /// Utilization rate, in basis points
+    pub utilization_bps: u16,
+
+    /// Borrow APY at this utilization, in basis points
+    pub rate_bps: u32,
+}
+
+impl ByteLength for BorrowRatePoint {
+    const LEN: usize = 2 + 4;
+}
+
+impl BorrowRatePoint {
+    pub fn new(utilization_bps: u16, rate_bps: u32) -> Self {
+        Self {
+            utilization_bps,
+            rate_bps,
+        }
+    }
+
+    /// Curve of the former config, from `min_borrow_rate` through `optimal_borrow_rate` at the
+    /// optimal utilization to `max_borrow_rate`, all as percentages
+    pub fn kink_curve(
+        optimal_utilization_rate: u8,
+        min_borrow_rate: u8,
+        optimal_borrow_rate: u8,
+        max_borrow_rate: u8,
+    ) -> Vec<Self> {
+        let optimal = Self::new(
+            u16::from(optimal_utilization_rate) * 100,
+            u32::from(optimal_borrow_rate) * 100,
+        );
+        let max = Self::new(BPS_SCALE, u32::from(max_borrow_rate) * 100);
+        // optimal utilizationが0なら最初からoptimal rateになる
+        if optimal_utilization_rate == 0 {
+            vec![optimal, max]
+        } else {
+            vec![Self::new(0, u32::from(min_borrow_rate) * 100), optimal, max]
+        }
+    }
+}
@@ -1,26 +1,24 @@
 use crate::{errors::LendingError, math::rate::Rate};
+use anchor_lang::prelude::*;
 
+/// Collateral minted per unit of liquidity
 #[derive(Clone, Copy, Debug)]
 pub struct CollateralExchangeRate(pub Rate);
 
 /// collateral と liquidityの相互変換のためのstruct
 impl CollateralExchangeRate {
     /// Reserve Collateral を liquidityに変換
-    pub fn collateral_to_liquidity(&self, collateral_amount: u64) -> Result<u64, LendingError> {
-        collateral_amount
-            .checked_div(self.0.into())
-            .ok_or(LendingError::InvalidConfig)
+    pub fn collateral_to_liquidity(&self, collateral_amount: u64) -> Result<u64> {
+        self.0
+            .try_unapply(collateral_amount as u128)?
+            .try_into()
+            .map_err(|_| error!(LendingError::MathOverflow))
     }
 
-    pub fn liquidity_to_collateral(&self, liquidity_amount: u64) -> Result<u64, LendingError> {
-        liquidity_amount
-            .checked_mul(self.0.into())
-            .ok_or(LendingError::InvalidConfig)
-    }
-}
-
-impl From<CollateralExchangeRate> for Rate {
-    fn from(exchange_rate: CollateralExchangeRate) -> Self {
-        exchange_rate.0
+    pub fn liquidity_to_collateral(&self, liquidity_amount: u64) -> Result<u64> {
+        self.0
+            .try_apply(liquidity_amount as u128)?
+            .try_into()
+            .map_err(|_| error!(LendingError::MathOverflow))
     }
 }
@@ -1,24 +1,46 @@
+pub mod borrow_rate_point;
 pub mod collateral_exchange_rate;
 pub mod reserve_collateral;
 pub mod reserve_config;
 pub mod reserve_fees;
+pub mod reserve_isolated_debt;
 pub mod reserve_liquidity;
+pub mod reserve_price_guard;
+pub mod reserve_twap;
+pub mod term_offer;
 
 use crate::{
     account_data::{
         last_update::LastUpdate,
+        obligation::{
+            obligation_collateral::ObligationCollateral, obligation_liquidity::ObligationLiquidity,
+            Obligation,
+        },
         reserve::{
+            borrow_rate_point::BorrowRatePoint,
             collateral_exchange_rate::CollateralExchangeRate,
-            reserve_collateral::ReserveCollateral, reserve_config::ReserveConfig,
-            reserve_fees::FeeCalculation, reserve_liquidity::ReserveLiquidity,
+            reserve_collateral::ReserveCollateral,
+            reserve_config::ReserveConfig,
+            reserve_fees::{FeeCalculation, ReserveFees},
+            reserve_isolated_debt::ReserveIsolatedDebt,
+            reserve_liquidity::ReserveLiquidity,
+            reserve_price_guard::ReservePriceGuard,
+            reserve_twap::ReserveTwap,
+            term_offer::TermOffer,
         },
+        reward_pool::RewardSide,
+        v1::{self, ReserveV1},
     },
-    constants::PROGRAM_VERSION,
+    constants::{LIQUIDATION_CLOSE_AMOUNT, MAX_ISOLATED_DEBTS, RESERVE_VERSION},
     errors::LendingError,
-    math::common::Decimal,
-    utils::byte_length::ByteLength,
+    math::{
+        common::{BPS_SCALE, WAD},
+        rate::Rate,
+    },
+    utils::{byte_length::ByteLength, migration::Layout, versioned::Versioned},
 };
 use anchor_lang::{prelude::*, solana_program::clock::Slot};
+use anchor_spl::token;
 
 #[account]
 pub struct Reserve {
@@ -36,10 +58,131 @@ pub struct Reserve {
 
     /// Reserve configuration values
     pub config: ReserveConfig,
+
+    /// Liquidity lent against this reserve as isolated collateral, per borrow reserve
+    pub isolated_debts: Vec<ReserveIsolatedDebt>,
+
+    /// Reward pool of the collateral deposited in obligations, if any
+    pub deposit_reward_pool: Option<Pubkey>,
+
+    /// Reward pool of the liquidity borrowed by obligations, if any
+    pub borrow_reward_pool: Option<Pubkey>,
+
+    /// Circuit breaker on the liquidity market price
+    pub price_guard: ReservePriceGuard,
+
+    /// Time-weighted market price, the fallback while the oracle is unavailable
+    pub twap: ReserveTwap,
+
+    /// Principal lent out in fixed-rate term loans, whose interest is only added when repaid
+    pub term_borrowed_amount: u64,
+
+    /// Token account of the insurance fund, if created
+    pub insurance_vault: Option<Pubkey>,
+
+    /// Insurance share of interest and liquidation bonuses not swept into the vault yet,
+    /// scaled by WAD. It isn't part of the total supply.
+    pub insurance_pending_wads: u128,
 }
 
 impl ByteLength for Reserve {
-    const LEN: usize = 1 + LastUpdate::LEN + 32 + ReserveLiquidity::LEN + ReserveCollateral::LEN;
+    const LEN: usize = 1
+        + LastUpdate::LEN
+        + 32
+        + ReserveLiquidity::LEN
+        + ReserveCollateral::LEN
+        + ReserveConfig::LEN
+        + (4 + ReserveIsolatedDebt::LEN * MAX_ISOLATED_DEBTS)
+        + (1 + 32)
+        + (1 + 32)
+        + ReservePriceGuard::LEN
+        + ReserveTwap::LEN
+        + 8
+        + (1 + 32)
+        + 16;
+}
+
+impl Versioned for Reserve {
+    const VERSION: u8 = RESERVE_VERSION;
+}
+
+impl Layout for Reserve {
+    type Previous = ReserveV1;
+    const SINCE: u8 = 2;
+}
+
+/// 後から追加された設定は全て無効な状態で始め、borrowは額面で数える。
+/// version 1には孤立モードが無かったのでisolated collateralへのdebtも無く、
+/// liquidityは全てlegacyのtoken programのもの
+impl From<ReserveV1> for Reserve {
+    fn from(reserve: ReserveV1) -> Self {
+        let liquidity = reserve.liquidity;
+        let collateral = reserve.collateral;
+        let config = reserve.config;
+        Self {
+            version: RESERVE_VERSION,
+            last_update: LastUpdate {
+                slot: reserve.last_update.slot,
+                stale: reserve.last_update.stale,
+            },
+            lending_market: reserve.lending_market,
+            liquidity: ReserveLiquidity {
+                mint_pubkey: liquidity.mint_pubkey,
+                mint_decimals: liquidity.mint_decimals,
+                token_program_id: token::ID,
+                supply_pubkey: liquidity.supply_pubkey,
+                fee_receiver: liquidity.fee_receiver,
+                oracle_pubkey: liquidity.oracle_pubkey,
+                available_amount: liquidity.available_amount,
+                borrowed_amount: v1::wads_to_amount(liquidity.borrowed_amount_wads),
+                cumulative_borrow_rate_wads: liquidity.cumulative_borrow_rate_wads,
+                market_price: liquidity.market_price,
+            },
+            collateral: ReserveCollateral {
+                mint_pubkey: collateral.mint_pubkey,
+                mint_total_supply: collateral.mint_total_supply,
+                supply_pubkey: collateral.supply_pubkey,
+            },
+            config: ReserveConfig {
+                loan_to_value_ratio: config.loan_to_value_ratio,
+                liquidation_bonus: config.liquidation_bonus,
+                liquidation_threshold: config.liquidation_threshold,
+                borrow_rate_curve: BorrowRatePoint::kink_curve(
+                    config.optimal_utilization_rate,
+                    config.min_borrow_rate,
+                    config.optimal_borrow_rate,
+                    config.max_borrow_rate,
+                ),
+                fees: ReserveFees {
+                    borrow_fee_wad: config.fees.borrow_fee_wad,
+                    flash_loan_fee_wad: config.fees.flash_loan_fee_wad,
+                    host_fee_percentage: config.fees.host_fee_percentage,
+                },
+                isolated: false,
+                debt_ceiling: 0,
+                borrowable_in_isolation: false,
+                emode_category: 0,
+                borrow_weight: BPS_SCALE,
+                price_guard_bps_per_slot: 0,
+                price_guard_confirmations: 0,
+                twap_fallback_max_slots: 0,
+                twap_liquidation_bonus: 0,
+                term_offers: vec![],
+                term_penalty_rate_bps: 0,
+                insurance_interest_bps: 0,
+                insurance_liquidation_bps: 0,
+                min_liquidation_bonus: config.liquidation_bonus,
+            },
+            isolated_debts: vec![],
+            deposit_reward_pool: None,
+            borrow_reward_pool: None,
+            price_guard: ReservePriceGuard::new(reserve.last_update.slot),
+            twap: ReserveTwap::new(liquidity.market_price, reserve.last_update.slot),
+            term_borrowed_amount: 0,
+            insurance_vault: None,
+            insurance_pending_wads: 0,
+        }
+    }
 }
 
 pub struct InitReserveParams {
@@ -73,14 +216,82 @@ pub struct CalculateRepayResult {
     pub repay_amount: u64,
 }
 
+#[derive(Debug)]
+pub struct CalculateLiquidationResult {
+    pub settle_amount: u128,
+    pub repay_amount: u64,
+    pub withdraw_amount: u64,
+}
+
 impl Reserve {
     pub fn init(&mut self, params: InitReserveParams) {
-        self.version = PROGRAM_VERSION;
+        self.version = RESERVE_VERSION;
         self.last_update = LastUpdate::new(params.current_slot);
         self.lending_market = params.lending_market;
         self.liquidity = params.liquidity;
         self.collateral = params.collateral;
         self.config = params.config;
+        self.isolated_debts = vec![];
+        self.deposit_reward_pool = None;
+        self.borrow_reward_pool = None;
+        self.price_guard = ReservePriceGuard::new(params.current_slot);
+        self.twap = ReserveTwap::new(self.liquidity.market_price, params.current_slot);
+        self.term_borrowed_amount = 0;
+        self.insurance_vault = None;
+        self.insurance_pending_wads = 0;
+    }
+
+    pub fn reward_pool(&self, side: RewardSide) -> Option<Pubkey> {
+        match side {
+            RewardSide::Deposit => self.deposit_reward_pool,
+            RewardSide::Borrow => self.borrow_reward_pool,
+        }
+    }
+
+    pub fn set_reward_pool(&mut self, side: RewardSide, reward_pool: Pubkey) {
+        match side {
+            RewardSide::Deposit => self.deposit_reward_pool = Some(reward_pool),
+            RewardSide::Borrow => self.borrow_reward_pool = Some(reward_pool),
+        }
+    }
+
+    /// oracleの価格をprice guardに通してmarket priceを更新する
+    pub fn update_market_price(&mut self, price: u128, slot: u64) {
+        if let Some(price) =
+            self.price_guard
+                .observe(self.liquidity.market_price, price, slot, &self.config)
+        {
+            self.liquidity.market_price = price;
+            self.twap.update(price, slot);
+        }
+        self.twap.fallback = false;
+    }
+
+    /// oracleが使えない間はTWAPをmarket priceとする
+    pub fn fall_back_to_twap(&mut self, slot: u64) -> bool {
+        if !self
+            .twap
+            .is_usable(slot, self.config.twap_fallback_max_slots)
+        {
+            return false;
+        }
+        self.liquidity.market_price = self.twap.price;
+        self.twap.fallback = true;
+        true
+    }
+
+    /// 保留中の価格をownerの判断で受け入れる
+    pub fn override_price_guard(&mut self, slot: u64) -> Result<u128> {
+        require!(
+            self.price_guard.guarded,
+            LendingError::ReservePriceNotGuarded
+        );
+        self.liquidity.market_price = self.price_guard.pending_price;
+        self.price_guard = ReservePriceGuard::new(slot);
+        self.twap.update(self.liquidity.market_price, slot);
+        // 価格が変わったのでrefreshし直させる
+        self.last_update.mark_stale();
+        Ok(self.liquidity.market_price)
     }
 
     /// liquidityをdepositしてmintすべきcollateralの値を返す
@@ -111,12 +322,242 @@ impl Reserve {
         Ok(liquidity_amount)
     }
 
+    /// term loanの元本も含め，insuranceの取り分を除いた総供給量を返す
+    pub fn total_supply(&self) -> Result<u128> {
+        self.liquidity
+            .total_supply()?
+            .checked_add(self.term_borrowed_amount.into())
+            .and_then(|total_supply| {
+                total_supply.checked_sub(self.insurance_pending_amount().into())
+            })
+            .ok_or(error!(LendingError::MathOverflow))
+    }
+
+    /// supplyに残っているinsuranceの取り分
+    pub fn insurance_pending_amount(&self) -> u64 {
+        u64::try_from(self.insurance_pending_wads / WAD as u128).unwrap_or(u64::MAX)
+    }
+
+    /// 利息の`insurance_interest_bps`をinsuranceの取り分にする
+    fn set_aside_interest(&mut self, interest_amount: u128) -> Result<()> {
+        let share_wads = interest_amount
+            .checked_mul(WAD as u128)
+            .and_then(|share| share.checked_mul(self.config.insurance_interest_bps.into()))
+            .ok_or(LendingError::MathOverflow)?
+            / BPS_SCALE as u128;
+        self.insurance_pending_wads = self
+            .insurance_pending_wads
+            .checked_add(share_wads)
+            .ok_or(LendingError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// `repay_amount`を返済するliquidatorが`liquidation_bonus`のbonusのうち
+    /// insuranceに上乗せして払う量
+    pub fn liquidation_insurance_amount(
+        &self,
+        repay_amount: u64,
+        liquidation_bonus: Rate,
+    ) -> Result<u64> {
+        let insurance_amount = Rate::from_bps(self.config.insurance_liquidation_bps.into())
+            .try_mul(liquidation_bonus)?
+            .try_apply(repay_amount as u128)?;
+        Ok(u64::try_from(insurance_amount).map_err(|_| LendingError::MathOverflow)?)
+    }
+
+    /// supplyに届いたinsuranceの取り分を記録する
+    pub fn deposit_insurance(&mut self, insurance_amount: u64) -> Result<()> {
+        self.liquidity.deposit(insurance_amount)?;
+        self.insurance_pending_wads = self
+            .insurance_pending_wads
+            .checked_add(insurance_amount as u128 * WAD as u128)
+            .ok_or(LendingError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// insuranceの取り分を`max_amount`まで使い，使った量を返す
+    pub fn take_insurance_pending(&mut self, max_amount: u64) -> u64 {
+        let amount = self.insurance_pending_amount().min(max_amount);
+        self.insurance_pending_wads -= amount as u128 * WAD as u128;
+        amount
+    }
+
+    /// vaultに移せるinsuranceの取り分をsupplyから引き出し，その量を返す
+    pub fn sweep_insurance(&mut self) -> Result<u64> {
+        let amount = self.take_insurance_pending(self.liquidity.available_amount);
+        self.liquidity.withdraw(amount)?;
+        Ok(amount)
+    }
+
+    /// term loanも含めた総供給量に対する借入量の比率を返す
+    pub fn utilization_rate(&self) -> Result<Rate> {
+        let total_supply = self.total_supply()?;
+        if total_supply == 0 {
+            return Ok(Rate::zero());
+        }
+        let borrowed_amount = self
+            .liquidity
+            .borrowed_amount
+            .checked_add(self.term_borrowed_amount.into())
+            .ok_or(LendingError::MathOverflow)?;
+        Rate::from_ratio(borrowed_amount, total_supply)
+    }
+
     /// Collateral exchange rateを返す
     pub fn collateral_exchange_rate(&self) -> Result<CollateralExchangeRate> {
-        let total_liquidity = self.liquidity.total_supply()?;
+        let total_liquidity = self.total_supply()?;
         self.collateral.exchange_rate(total_liquidity)
     }
 
+    /// liquidityの量をquote currencyでの価値に変換する
+    pub fn market_value(&self, liquidity_amount: u128) -> Result<u128> {
+        self.market_value_at(liquidity_amount, self.liquidity.market_price)
+    }
+
+    /// 指定したpriceでliquidityの量をquote currencyでの価値に変換する
+    pub fn market_value_at(&self, liquidity_amount: u128, market_price: u128) -> Result<u128> {
+        let decimals = 10u64
+            .checked_pow(self.liquidity.mint_decimals as u32)
+            .ok_or(LendingError::MathOverflow)?;
+        liquidity_amount
+            .checked_mul(market_price)
+            .ok_or(LendingError::MathOverflow)?
+            .checked_div(decimals as u128)
+            .ok_or(error!(LendingError::MathOverflow))
+    }
+
+    /// 借入価値に掛けるborrow weightを返す
+    pub fn borrow_weight(&self) -> Rate {
+        Rate::from_bps(self.config.borrow_weight.into())
+    }
+
+    /// utilizationに応じた現在の借入APYを返す
+    pub fn current_borrow_rate(&self) -> Result<Rate> {
+        let utilization_rate = self.utilization_rate()?;
+        self.config.borrow_rate(utilization_rate)
+    }
+
+    /// 前回の更新から経過したslot分の利息を加える
+    pub fn accrue_interest(&mut self, current_slot: Slot) -> Result<()> {
+        let slots_elapsed = self.last_update.slots_elapsed(current_slot)?;
+        if slots_elapsed > 0 {
+            let current_borrow_rate = self.current_borrow_rate()?;
+            let borrowed_amount = self.liquidity.borrowed_amount;
+            self.liquidity
+                .compound_interest(current_borrow_rate, slots_elapsed)?;
+            let interest_amount = self
+                .liquidity
+                .borrowed_amount
+                .checked_sub(borrowed_amount)
+                .ok_or(LendingError::MathOverflow)?;
+            self.set_aside_interest(interest_amount)?;
+        }
+        Ok(())
+    }
+
+    /// `term_slots`と`rate_bps`に一致する公開中のterm offerを返す
+    pub fn find_term_offer(&self, term_slots: u64, rate_bps: u32) -> Result<&TermOffer> {
+        self.config
+            .term_offers
+            .iter()
+            .find(|offer| offer.term_slots == term_slots && offer.rate_bps == rate_bps)
+            .ok_or(error!(LendingError::TermOfferNotFound))
+    }
+
+    /// term loanとしてliquidityを貸し出す
+    pub fn borrow_term(&mut self, borrow_amount: u128) -> Result<()> {
+        let borrow_amount = u64::try_from(borrow_amount).map_err(|_| LendingError::MathOverflow)?;
+        self.liquidity.withdraw(borrow_amount)?;
+        self.term_borrowed_amount = self
+            .term_borrowed_amount
+            .checked_add(borrow_amount)
+            .ok_or(LendingError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// term loanの返済を受け取る。元本を超えた分は利息として預金者とinsuranceのものになる
+    pub fn repay_term(&mut self, repay_amount: u64, principal_amount: u64) -> Result<()> {
+        self.liquidity.deposit(repay_amount)?;
+        self.set_aside_interest(repay_amount.saturating_sub(principal_amount).into())?;
+        self.term_borrowed_amount = self
+            .term_borrowed_amount
+            .checked_sub(principal_amount)
+            .ok_or(LendingError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// isolated collateralに対する`borrow_reserve`からの借入をtokenの量で記録し、
+    /// borrow reserveの今の価格での価値がdebt ceilingを超えないか確かめる
+    pub fn add_isolated_debt(
+        &mut self,
+        borrow_reserve_key: Pubkey,
+        borrow_reserve: &Reserve,
+        borrow_amount: u128,
+    ) -> Result<()> {
+        let index = match self
+            .isolated_debts
+            .iter()
+            .position(|debt| debt.borrow_reserve == borrow_reserve_key)
+        {
+            Some(index) => index,
+            None => {
+                require_gt!(
+                    MAX_ISOLATED_DEBTS,
+                    self.isolated_debts.len(),
+                    LendingError::IsolatedDebtLimit
+                );
+                self.isolated_debts
+                    .push(ReserveIsolatedDebt::new(borrow_reserve_key));
+                self.isolated_debts.len() - 1
+            }
+        };
+        let debt = &mut self.isolated_debts[index];
+        debt.borrowed_amount = debt
+            .borrowed_amount
+            .checked_add(borrow_amount)
+            .ok_or(LendingError::MathOverflow)?;
+        debt.market_value = borrow_reserve.market_value(debt.borrowed_amount)?;
+
+        require_gte!(
+            self.config.debt_ceiling as u128,
+            self.isolated_debt_value()?,
+            LendingError::DebtCeilingExceeded
+        );
+        Ok(())
+    }
+
+    /// 返済されたisolated collateralに対する`borrow_reserve`からの借入を取り除く
+    pub fn remove_isolated_debt(
+        &mut self,
+        borrow_reserve_key: Pubkey,
+        borrow_reserve: &Reserve,
+        repay_amount: u128,
+    ) -> Result<()> {
+        if let Some(index) = self
+            .isolated_debts
+            .iter()
+            .position(|debt| debt.borrow_reserve == borrow_reserve_key)
+        {
+            let debt = &mut self.isolated_debts[index];
+            debt.borrowed_amount = debt.borrowed_amount.saturating_sub(repay_amount);
+            if debt.borrowed_amount == 0 {
+                self.isolated_debts.remove(index);
+            } else {
+                debt.market_value = borrow_reserve.market_value(debt.borrowed_amount)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// isolated collateralに対する借入の合計の価値
+    pub fn isolated_debt_value(&self) -> Result<u128> {
+        self.isolated_debts.iter().try_fold(0u128, |total, debt| {
+            total
+                .checked_add(debt.market_value)
+                .ok_or(error!(LendingError::MathOverflow))
+        })
+    }
+
     pub fn calculate_borrow(
         &self,
         amount_to_borrow: u64,
@@ -126,6 +567,8 @@ impl Reserve {
             .checked_pow(self.liquidity.mint_decimals as u32)
             .ok_or(LendingError::MathOverflow)?;
         if amount_to_borrow == u64::MAX {
+            // borrow weightを考慮した借入可能な価値
+            let max_borrow_value = self.borrow_weight().try_unapply(max_borrow_value)?;
             let borrow_amount = max_borrow_value
                 .checked_mul(decimals.into())
                 .ok_or(LendingError::MathOverflow)?
@@ -136,9 +579,9 @@ impl Reserve {
             let (borrow_fee, host_fee) = self
                 .config
                 .fees
-                .calculate_borrow_fees(amount_to_borrow as u128, FeeCalculation::Inclusive)?;
-            let receive_amount = borrow_amount
-                .try_floor_u64()?
+                .calculate_borrow_fees(borrow_amount, FeeCalculation::Inclusive)?;
+            let receive_amount = u64::try_from(borrow_amount)
+                .map_err(|_| LendingError::MathOverflow)?
                 .checked_sub(borrow_fee)
                 .ok_or(LendingError::MathOverflow)?;
 
@@ -159,11 +602,9 @@ impl Reserve {
             let borrow_amount = borrow_amount
                 .checked_add(borrow_fee as u128)
                 .ok_or(LendingError::MathOverflow)?;
-            let borrow_value = borrow_amount
-                .checked_mul(self.liquidity.market_price)
-                .ok_or(LendingError::MathOverflow)?
-                .checked_div(decimals as u128)
-                .ok_or(LendingError::MathOverflow)?;
+            let borrow_value = self
+                .borrow_weight()
+                .try_apply(self.market_value(borrow_amount)?)?;
             require_gte!(max_borrow_value, borrow_value, LendingError::BorrowTooLarge);
             Ok(CalculateBorrowResult {
                 borrow_amount,
@@ -184,11 +625,60 @@ impl Reserve {
         } else {
             (amount_to_repay as u128).min(borrowed_amount)
         };
-        let repay_amount = settle_amount.try_ceil_u64()?;
+        let repay_amount = u64::try_from(settle_amount).map_err(|_| LendingError::MathOverflow)?;
 
         Ok(CalculateRepayResult {
             settle_amount,
             repay_amount,
         })
     }
+
+    /// Liquidate `liquidity` of an unhealthy obligation up to `close_factor` percent of its
+    /// borrowed value, seizing `collateral` of this reserve worth the repaid value plus
+    /// the `liquidation_bonus` rate
+    pub fn calculate_liquidation(
+        &self,
+        amount_to_liquidate: u64,
+        obligation: &Obligation,
+        liquidity: &ObligationLiquidity,
+        collateral: &ObligationCollateral,
+        close_factor: u8,
+        liquidation_bonus: Rate,
+    ) -> Result<CalculateLiquidationResult> {
+        let bonus_rate = liquidation_bonus.try_add(Rate::one())?;
+
+        // 少額の借入はclose factorに関係なく全て返済させる
+        let settle_amount = if liquidity.borrowed_amount < LIQUIDATION_CLOSE_AMOUNT as u128 {
+            liquidity.borrowed_amount
+        } else {
+            (amount_to_liquidate as u128)
+                .min(obligation.max_liquidation_amount(liquidity, close_factor)?)
+        };
+        let liquidation_pct = Rate::from_ratio(settle_amount, liquidity.borrowed_amount)?;
+        let liquidation_value =
+            bonus_rate.try_apply(liquidation_pct.try_apply(liquidity.market_value)?)?;
+
+        let (settle_amount, withdraw_amount) = if liquidation_value > collateral.market_value {
+            // collateralが足りなければ全て渡し、その価値の分だけ返済させる
+            let repay_pct = Rate::from_ratio(collateral.market_value, liquidation_value)?;
+            (
+                repay_pct.try_apply(settle_amount)?,
+                collateral.deposited_amount,
+            )
+        } else {
+            let withdraw_pct = Rate::from_ratio(liquidation_value, collateral.market_value)?;
+            let withdraw_amount = withdraw_pct.try_apply(collateral.deposited_amount as u128)?;
+            (
+                settle_amount,
+                u64::try_from(withdraw_amount).map_err(|_| LendingError::MathOverflow)?,
+            )
+        };
+        let repay_amount = u64::try_from(settle_amount).map_err(|_| LendingError::MathOverflow)?;
+
+        Ok(CalculateLiquidationResult {
+            settle_amount,
+            repay_amount,
+            withdraw_amount,
+        })
+    }
 }
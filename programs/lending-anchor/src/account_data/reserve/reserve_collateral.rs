@@ -33,16 +33,10 @@ impl ReserveCollateral {
     /// 現在のcollateral exchange rateを返す
     pub fn exchange_rate(&self, total_liquidity: u128) -> Result<CollateralExchangeRate> {
         let rate = if self.mint_total_supply == 0 || total_liquidity == 0 {
-            Rate::try_from(INITIAL_COLLATERAL_RATE)
+            Rate::from(INITIAL_COLLATERAL_RATE)
         } else {
-            let mint_total_supply = self.mint_total_supply;
-            Rate::try_from(
-                mint_total_supply
-                    .checked_div(total_liquidity as u64)
-                    .ok_or(error!(LendingError::MathOverflow))?,
-            )
-        }
-        .map_err(|_| LendingError::MathOverflow)?;
+            Rate::from_ratio(self.mint_total_supply as u128, total_liquidity)?
+        };
 
         Ok(CollateralExchangeRate(rate))
     }
@@ -1,34 +1,240 @@
-use crate::{account_data::reserve::reserve_fees::ReserveFees, utils::byte_length::ByteLength};
+use crate::{
+    account_data::{
+        lending_market::LendingMarket,
+        reserve::{
+            borrow_rate_point::BorrowRatePoint, reserve_fees::ReserveFees, term_offer::TermOffer,
+        },
+    },
+    constants::{MAX_BORROW_RATE_POINTS, MAX_TERM_OFFERS},
+    errors::LendingError,
+    math::{
+        common::{BPS_SCALE, WAD},
+        rate::Rate,
+    },
+    require_lt_100, require_lte_100,
+    utils::byte_length::ByteLength,
+};
 use anchor_lang::prelude::*;
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, AnchorSerialize, AnchorDeserialize)]
+#[derive(Clone, Debug, Default, PartialEq, AnchorSerialize, AnchorDeserialize)]
 pub struct ReserveConfig {
-    /// Optimal utilization rate, as a percentage
-    pub optimal_utilization_rate: u8,
-
     /// Target ratio of the value of borrows to deposits, as a percentage
     /// 0 if uses as collateral is disabled
     pub loan_to_value_ratio: u8,
 
     /// Bonus a liquidator gets when repaying part of an unhealthy obligation, as a percentage
+    /// Reached once the obligation's borrowed value is as large as its deposited value
     pub liquidation_bonus: u8,
 
     /// Loan to value ratio at which an obligation can be liquidated, as a percentage
     pub liquidation_threshold: u8,
 
-    /// Min borrow APY
-    pub min_borrow_rate: u8,
-
-    /// Optimal (uitilization) borrow APY
-    pub optimal_borrow_rate: u8,
-
-    /// Max borrow APY
-    pub max_borrow_rate: u8,
+    /// Borrow APY by utilization, interpolated linearly between the points
+    /// Sorted by utilization from 0 to 100%, with rates which never decrease
+    pub borrow_rate_curve: Vec<BorrowRatePoint>,
 
     /// Program owner fees assessed, separate from gains due to interest accrual
     pub fees: ReserveFees,
+
+    /// Isolated collateral cannot be combined with other collateral in an obligation
+    pub isolated: bool,
+
+    /// Max total debt in quote currency which can be backed by this reserve when isolated
+    pub debt_ceiling: u64,
+
+    /// True if this reserve can be borrowed against isolated collateral
+    pub borrowable_in_isolation: bool,
+
+    /// Efficiency mode category of the lending market, 0 if none
+    pub emode_category: u8,
+
+    /// Multiplier applied to the value of borrows for risk checks, in basis points
+    /// 10000 if borrows count at face value
+    pub borrow_weight: u16,
+
+    /// Max move of the oracle price per slot since the last accepted price, in basis points
+    /// 0 if the price guard is disabled
+    pub price_guard_bps_per_slot: u16,
+
+    /// Refreshes in separate slots which have to confirm a guarded price before it's accepted
+    pub price_guard_confirmations: u8,
+
+    /// Max slots since the last accepted price the TWAP stands in for an unavailable oracle
+    /// 0 if the TWAP fallback is disabled
+    pub twap_fallback_max_slots: u64,
+
+    /// Bonus a liquidator gets while the collateral price falls back to the TWAP, as a percentage
+    /// Must not exceed the liquidation bonus
+    pub twap_liquidation_bonus: u8,
+
+    /// Fixed rates offered for term loans, sorted by term
+    /// Empty if term loans are disabled
+    pub term_offers: Vec<TermOffer>,
+
+    /// Borrow APY of term loans after maturity, in basis points
+    pub term_penalty_rate_bps: u32,
+
+    /// Share of accrued interest set aside for the insurance vault, in basis points
+    pub insurance_interest_bps: u16,
+
+    /// Share of the liquidation bonus liquidators pay into the insurance vault on top of the
+    /// repayment, in basis points
+    pub insurance_liquidation_bps: u16,
+
+    /// Bonus a liquidator gets when the obligation has just become unhealthy, as a percentage
+    /// Must not exceed the liquidation bonus
+    pub min_liquidation_bonus: u8,
 }
 
 impl ByteLength for ReserveConfig {
-    const LEN: usize = 1 + 1 + 1 + 1 + 1 + 1 + 1 + ReserveFees::LEN;
+    const LEN: usize = 1
+        + 1
+        + 1
+        + (4 + MAX_BORROW_RATE_POINTS * BorrowRatePoint::LEN)
+        + ReserveFees::LEN
+        + 1
+        + 8
+        + 1
+        + 1
+        + 2
+        + 2
+        + 1
+        + 8
+        + 1
+        + (4 + MAX_TERM_OFFERS * TermOffer::LEN)
+        + 4
+        + 2
+        + 2
+        + 1;
+}
+
+impl ReserveConfig {
+    pub fn validate(&self, lending_market: &LendingMarket) -> Result<()> {
+        require_lt_100!(self.loan_to_value_ratio, LendingError::InvalidConfig);
+        require_gt!(
+            self.liquidation_threshold,
+            self.loan_to_value_ratio,
+            LendingError::InvalidConfig
+        );
+        require_lte_100!(self.liquidation_threshold, LendingError::InvalidConfig);
+        self.validate_borrow_rate_curve()?;
+        require_gt!(WAD, self.fees.borrow_fee_wad, LendingError::InvalidConfig);
+        require_gt!(
+            WAD,
+            self.fees.flash_loan_fee_wad,
+            LendingError::InvalidConfig
+        );
+        require_gte!(self.borrow_weight, BPS_SCALE, LendingError::InvalidConfig);
+        if self.isolated {
+            require_neq!(self.debt_ceiling, 0, LendingError::InvalidConfig);
+        }
+        if self.price_guard_bps_per_slot != 0 {
+            require_neq!(
+                self.price_guard_confirmations,
+                0,
+                LendingError::InvalidConfig
+            );
+        }
+        require_gte!(
+            self.liquidation_bonus,
+            self.twap_liquidation_bonus,
+            LendingError::InvalidConfig
+        );
+        require_gte!(
+            self.liquidation_bonus,
+            self.min_liquidation_bonus,
+            LendingError::InvalidConfig
+        );
+        if self.emode_category != 0 {
+            lending_market.find_emode_category(self.emode_category)?;
+        }
+        self.validate_term_offers()?;
+        require_gte!(
+            BPS_SCALE,
+            self.insurance_interest_bps,
+            LendingError::InvalidConfig
+        );
+        require_gte!(
+            BPS_SCALE,
+            self.insurance_liquidation_bps,
+            LendingError::InvalidConfig
+        );
+        Ok(())
+    }
+
+    fn validate_term_offers(&self) -> Result<()> {
+        let offers = &self.term_offers;
+        require_gte!(MAX_TERM_OFFERS, offers.len(), LendingError::InvalidConfig);
+        for offer in offers {
+            require_neq!(offer.term_slots, 0, LendingError::InvalidConfig);
+            // 満期後の金利が満期前より低くならないようにする
+            require_gte!(
+                self.term_penalty_rate_bps,
+                offer.rate_bps,
+                LendingError::InvalidConfig
+            );
+        }
+        for window in offers.windows(2) {
+            require_gt!(
+                window[1].term_slots,
+                window[0].term_slots,
+                LendingError::InvalidConfig
+            );
+        }
+        Ok(())
+    }
+
+    fn validate_borrow_rate_curve(&self) -> Result<()> {
+        let curve = &self.borrow_rate_curve;
+        require_gte!(curve.len(), 2, LendingError::InvalidConfig);
+        require_gte!(
+            MAX_BORROW_RATE_POINTS,
+            curve.len(),
+            LendingError::InvalidConfig
+        );
+        require_eq!(curve[0].utilization_bps, 0, LendingError::InvalidConfig);
+        require_eq!(
+            curve[curve.len() - 1].utilization_bps,
+            BPS_SCALE,
+            LendingError::InvalidConfig
+        );
+        for window in curve.windows(2) {
+            require_gt!(
+                window[1].utilization_bps,
+                window[0].utilization_bps,
+                LendingError::InvalidConfig
+            );
+            require_gte!(
+                window[1].rate_bps,
+                window[0].rate_bps,
+                LendingError::InvalidConfig
+            );
+        }
+        Ok(())
+    }
+
+    /// utilizationを挟む2点の間で線形補間した借入APYを返す
+    pub fn borrow_rate(&self, utilization_rate: Rate) -> Result<Rate> {
+        let (first, rest) = self
+            .borrow_rate_curve
+            .split_first()
+            .ok_or(LendingError::InvalidConfig)?;
+        let mut lower = first;
+        for upper in rest {
+            let upper_utilization = Rate::from_bps(upper.utilization_bps.into());
+            if utilization_rate <= upper_utilization {
+                let lower_utilization = Rate::from_bps(lower.utilization_bps.into());
+                let lower_rate = Rate::from_bps(lower.rate_bps);
+                let upper_rate = Rate::from_bps(upper.rate_bps);
+                let normalized_rate = utilization_rate
+                    .try_sub(lower_utilization)?
+                    .try_div(upper_utilization.try_sub(lower_utilization)?)?;
+                return normalized_rate
+                    .try_mul(upper_rate.try_sub(lower_rate)?)?
+                    .try_add(lower_rate);
+            }
+            lower = upper;
+        }
+        Ok(Rate::from_bps(lower.rate_bps))
+    }
 }
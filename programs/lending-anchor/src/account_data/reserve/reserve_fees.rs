@@ -1,11 +1,4 @@
-use crate::{
-    errors::LendingError,
-    math::{
-        common::{Decimal, WAD},
-        rate::Rate,
-    },
-    utils::byte_length::ByteLength,
-};
+use crate::{errors::LendingError, math::rate::Rate, utils::byte_length::ByteLength};
 use anchor_lang::prelude::*;
 
 /// Additional fee information on a reserve
@@ -41,7 +34,6 @@ impl ReserveFees {
         fee_calculation: FeeCalculation,
     ) -> Result<(u64, u64)> {
         let borrow_fee_rate = Rate::from_scaled_val(fee_wad);
-        let borrow_fee_rate_uint: u64 = borrow_fee_rate.into();
         let host_fee_rate = Rate::from_percent(self.host_fee_percentage);
         if borrow_fee_rate > Rate::zero() && amount > 0 {
             let need_to_assess_host_fee = host_fee_rate > Rate::zero();
@@ -53,18 +45,11 @@ impl ReserveFees {
 
             let borrow_fee_amount = match fee_calculation {
                 // Calculate fee to be added to borrow: fee = amount * rate
-                FeeCalculation::Exclusive => amount
-                    .checked_mul(borrow_fee_rate_uint as u128)
-                    .ok_or(LendingError::MathOverflow)?,
+                FeeCalculation::Exclusive => borrow_fee_rate.try_apply(amount)?,
                 // Calculate fee to be subtracted from borrow: fee = amount * (rate / (rate + 1))
-                FeeCalculation::Inclusive => {
-                    let borrow_fee_rate = borrow_fee_rate_uint
-                        .checked_div(borrow_fee_rate_uint + WAD)
-                        .ok_or(LendingError::MathOverflow)?;
-                    amount
-                        .checked_mul(borrow_fee_rate as u128)
-                        .ok_or(LendingError::MathOverflow)?
-                }
+                FeeCalculation::Inclusive => borrow_fee_rate
+                    .try_div(borrow_fee_rate.try_add(Rate::one())?)?
+                    .try_apply(amount)?,
             };
 
             let borrow_fee_decimal = borrow_fee_amount.max(minimum_fee.into());
@@ -73,13 +58,11 @@ impl ReserveFees {
                 return Err(LendingError::BorrowTooSmall.into());
             }
 
-            let borrow_fee = borrow_fee_decimal.try_round_u64()?;
+            let borrow_fee =
+                u64::try_from(borrow_fee_decimal).map_err(|_| LendingError::MathOverflow)?;
             let host_fee = if need_to_assess_host_fee {
-                let host_fee_rate: u64 = host_fee_rate.into();
-                borrow_fee_decimal
-                    .checked_mul(host_fee_rate as u128)
-                    .ok_or(LendingError::MathOverflow)?
-                    .try_round_u64()?
+                u64::try_from(host_fee_rate.try_apply(borrow_fee_decimal)?)
+                    .map_err(|_| LendingError::MathOverflow)?
                     .max(1u64)
             } else {
                 0
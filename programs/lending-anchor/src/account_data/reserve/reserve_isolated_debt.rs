@@ -0,0 +1,29 @@
+use crate::utils::byte_length::ByteLength;
+use anchor_lang::prelude::*;
+
+/// Liquidity of one borrow reserve lent against the reserve as isolated collateral
+#[derive(Clone, Copy, Debug, Default, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct ReserveIsolatedDebt {
+    pub borrow_reserve: Pubkey,
+
+    /// Liquidity borrowed, in tokens of the borrow reserve
+    pub borrowed_amount: u128,
+
+    /// Value of the borrowed liquidity in quote currency, at the borrow reserve's price when
+    /// it was last borrowed or repaid against the reserve
+    pub market_value: u128,
+}
+
+impl ByteLength for ReserveIsolatedDebt {
+    const LEN: usize = 32 + 16 + 16;
+}
+
+impl ReserveIsolatedDebt {
+    pub fn new(borrow_reserve: Pubkey) -> Self {
+        Self {
+            borrow_reserve,
+            borrowed_amount: 0,
+            market_value: 0,
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{
+    constants::SLOTS_PER_YEAR,
     errors::LendingError,
-    math::common::{Decimal, WAD},
+    math::{common::WAD, rate::Rate},
     utils::byte_length::ByteLength,
 };
 use anchor_lang::prelude::*;
@@ -13,6 +14,9 @@ pub struct ReserveLiquidity {
     /// Reserve liquidity mint decimals
     pub mint_decimals: u8,
 
+    /// Token program owning the liquidity mint, the legacy one or Token-2022
+    pub token_program_id: Pubkey,
+
     /// Reserve liquidity supply address
     pub supply_pubkey: Pubkey,
 
@@ -25,8 +29,8 @@ pub struct ReserveLiquidity {
     /// Reserve liquidity available
     pub available_amount: u64,
 
-    /// Reserve liquidity borrowed
-    pub borrowed_amount_wads: u128,
+    /// Reserve liquidity borrowed, in tokens including accrued interest
+    pub borrowed_amount: u128,
 
     /// Reserve liquidity cumulative borrow rate
     pub cumulative_borrow_rate_wads: u128,
@@ -36,7 +40,7 @@ pub struct ReserveLiquidity {
 }
 
 impl ByteLength for ReserveLiquidity {
-    const LEN: usize = 32 + 1 + 32 + 32 + 32 + 8 + 16 + 16 + 16;
+    const LEN: usize = 32 + 1 + 32 + 32 + 32 + 32 + 8 + 16 + 16 + 16;
 }
 
 pub struct NewReserveLiquidityParams {
@@ -46,6 +50,9 @@ pub struct NewReserveLiquidityParams {
     /// Reserve liquidity mint decimals
     pub mint_decimals: u8,
 
+    /// Token program owning the liquidity mint
+    pub token_program_id: Pubkey,
+
     /// Reserve liquidity supply address
     pub supply_pubkey: Pubkey,
 
@@ -64,11 +71,12 @@ impl ReserveLiquidity {
         Self {
             mint_pubkey: params.mint_pubkey,
             mint_decimals: params.mint_decimals,
+            token_program_id: params.token_program_id,
             supply_pubkey: params.supply_pubkey,
             fee_receiver: params.fee_receiver,
             oracle_pubkey: params.oracle_pubkey,
             available_amount: 0,
-            borrowed_amount_wads: 0,
+            borrowed_amount: 0,
             cumulative_borrow_rate_wads: WAD as u128,
             market_price: params.market_price,
         }
@@ -77,7 +85,7 @@ impl ReserveLiquidity {
     /// 借りられたtoken量込みの総供給可能量を返す
     pub fn total_supply(&self) -> Result<u128> {
         (self.available_amount as u128)
-            .checked_add(self.borrowed_amount_wads)
+            .checked_add(self.borrowed_amount)
             .ok_or(error!(LendingError::MathOverflow))
     }
 
@@ -108,31 +116,59 @@ impl ReserveLiquidity {
     }
 
     pub fn borrow_(&mut self, borrow_decimal: u128) -> Result<()> {
-        let borrow_amount = borrow_decimal.try_floor_u64()?;
+        let borrow_amount =
+            u64::try_from(borrow_decimal).map_err(|_| LendingError::MathOverflow)?;
         require_gte!(
             self.available_amount,
             borrow_amount,
             LendingError::InsufficientLiquidity
         );
 
-        self.available_amount
+        self.available_amount = self
+            .available_amount
             .checked_sub(borrow_amount)
             .ok_or(LendingError::MathOverflow)?;
-        self.borrowed_amount_wads = self
-            .borrowed_amount_wads
+        self.borrowed_amount = self
+            .borrowed_amount
             .checked_add(borrow_decimal)
             .ok_or(LendingError::MathOverflow)?;
 
         Ok(())
     }
 
+    /// 総供給量に対する借入量の比率を返す
+    pub fn utilization_rate(&self) -> Result<Rate> {
+        let total_supply = self.total_supply()?;
+        if total_supply == 0 {
+            return Ok(Rate::zero());
+        }
+        Rate::from_ratio(self.borrowed_amount, total_supply)
+    }
+
+    /// 経過したslot分の利息を借入量とcumulative borrow rateに複利で加える
+    pub fn compound_interest(
+        &mut self,
+        current_borrow_rate: Rate,
+        slots_elapsed: u64,
+    ) -> Result<()> {
+        let slot_interest_rate =
+            current_borrow_rate.try_div(Rate::from_ratio(SLOTS_PER_YEAR as u128, 1)?)?;
+        let compounded_interest_rate = Rate::one()
+            .try_add(slot_interest_rate)?
+            .try_pow(slots_elapsed)?;
+        self.cumulative_borrow_rate_wads =
+            compounded_interest_rate.try_apply(self.cumulative_borrow_rate_wads)?;
+        self.borrowed_amount = compounded_interest_rate.try_apply(self.borrowed_amount)?;
+        Ok(())
+    }
+
     pub fn repay(&mut self, repay_amount: u64, settle_amount: u128) -> Result<()> {
         self.available_amount = self
             .available_amount
             .checked_add(repay_amount)
             .ok_or(LendingError::MathOverflow)?;
-        self.borrowed_amount_wads = self
-            .borrowed_amount_wads
+        self.borrowed_amount = self
+            .borrowed_amount
             .checked_sub(settle_amount)
             .ok_or(LendingError::MathOverflow)?;
         Ok(())
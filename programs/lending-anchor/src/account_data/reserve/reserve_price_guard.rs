@@ -0,0 +1,101 @@
+use crate::{
+    account_data::reserve::reserve_config::ReserveConfig, math::common::BPS_SCALE,
+    utils::byte_length::ByteLength,
+};
+use anchor_lang::prelude::*;
+
+/// Circuit breaker holding back oracle prices which moved too far since the last accepted one
+#[derive(Clone, Copy, Debug, Default, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct ReservePriceGuard {
+    /// Slot the market price was last accepted at
+    pub accepted_slot: u64,
+
+    /// True while an oracle price is held back, which blocks borrows and liquidations
+    pub guarded: bool,
+
+    /// Last oracle price seen while guarded
+    pub pending_price: u128,
+
+    /// Slot `pending_price` was seen at
+    pub pending_slot: u64,
+
+    /// Refreshes in separate slots which confirmed `pending_price` so far
+    pub confirmations: u8,
+}
+
+impl ByteLength for ReservePriceGuard {
+    const LEN: usize = 8 + 1 + 16 + 8 + 1;
+}
+
+impl ReservePriceGuard {
+    pub fn new(accepted_slot: u64) -> Self {
+        Self {
+            accepted_slot,
+            ..Self::default()
+        }
+    }
+
+    /// Check an oracle `price` against the accepted `market_price` and return the price to
+    /// accept, if any. A price which moved further than the config allows is held back until
+    /// the next refreshes in `price_guard_confirmations` separate slots stay close to it.
+    pub fn observe(
+        &mut self,
+        market_price: u128,
+        price: u128,
+        slot: u64,
+        config: &ReserveConfig,
+    ) -> Option<u128> {
+        let bps_per_slot = config.price_guard_bps_per_slot;
+        // 初回の価格とguard無効時はそのまま受け入れる
+        if bps_per_slot == 0
+            || market_price == 0
+            || is_within_move(market_price, self.accepted_slot, price, slot, bps_per_slot)
+        {
+            *self = Self::new(slot);
+            return Some(price);
+        }
+
+        if self.guarded
+            && is_within_move(
+                self.pending_price,
+                self.pending_slot,
+                price,
+                slot,
+                bps_per_slot,
+            )
+        {
+            // 同じslotでのrefreshは確認に数えない
+            if slot > self.pending_slot {
+                self.confirmations = self.confirmations.saturating_add(1);
+                self.pending_price = price;
+                self.pending_slot = slot;
+            }
+            if self.confirmations >= config.price_guard_confirmations {
+                *self = Self::new(slot);
+                return Some(price);
+            }
+        } else {
+            self.guarded = true;
+            self.pending_price = price;
+            self.pending_slot = slot;
+            self.confirmations = 0;
+        }
+        None
+    }
+}
+
+/// Whether `to_price` is within `bps_per_slot` of `from_price` for every slot in between
+fn is_within_move(
+    from_price: u128,
+    from_slot: u64,
+    to_price: u128,
+    to_slot: u64,
+    bps_per_slot: u16,
+) -> bool {
+    let slots_elapsed = to_slot.saturating_sub(from_slot).max(1);
+    let max_move = from_price
+        .saturating_mul(bps_per_slot.into())
+        .saturating_mul(slots_elapsed.into())
+        / u128::from(BPS_SCALE);
+    from_price.abs_diff(to_price) <= max_move
+}
@@ -0,0 +1,55 @@
+use crate::{constants::PRICE_TWAP_WINDOW_SLOTS, utils::byte_length::ByteLength};
+use anchor_lang::prelude::*;
+
+/// Time-weighted average of the accepted market prices, used while the oracle is unavailable
+#[derive(Clone, Copy, Debug, Default, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct ReserveTwap {
+    /// Time-weighted average price in quote currency
+    pub price: u128,
+
+    /// Slot the average was last updated at
+    pub slot: u64,
+
+    /// True while the market price falls back to the average, which blocks borrows and
+    /// withdrawals against borrows
+    pub fallback: bool,
+}
+
+impl ByteLength for ReserveTwap {
+    const LEN: usize = 16 + 8 + 1;
+}
+
+impl ReserveTwap {
+    pub fn new(price: u128, slot: u64) -> Self {
+        Self {
+            price,
+            slot,
+            fallback: false,
+        }
+    }
+
+    /// Weight an accepted `price` into the average by the slots elapsed since the last one.
+    /// A price older than `PRICE_TWAP_WINDOW_SLOTS` no longer counts.
+    pub fn update(&mut self, price: u128, slot: u64) {
+        let elapsed = slot.saturating_sub(self.slot).min(PRICE_TWAP_WINDOW_SLOTS);
+        // 最初の価格はそのまま使う
+        if self.price == 0 || elapsed == PRICE_TWAP_WINDOW_SLOTS {
+            self.price = price;
+        } else if elapsed != 0 {
+            let window = u128::from(PRICE_TWAP_WINDOW_SLOTS);
+            let elapsed = u128::from(elapsed);
+            self.price = self
+                .price
+                .saturating_mul(window - elapsed)
+                .saturating_add(price.saturating_mul(elapsed))
+                / window;
+        }
+        self.slot = slot;
+        self.fallback = false;
+    }
+
+    /// Whether the average can stand in for the oracle price at `slot`
+    pub fn is_usable(&self, slot: u64, max_slots: u64) -> bool {
+        max_slots != 0 && self.price != 0 && slot.saturating_sub(self.slot) <= max_slots
+    }
+}
@@ -0,0 +1,25 @@
+use crate::utils::byte_length::ByteLength;
+use anchor_lang::prelude::*;
+
+/// Fixed borrow rate the market owner offers for loans of one term
+#[derive(Clone, Copy, Debug, Default, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct TermOffer {
+    /// Slots from the borrow until the loan matures
+    pub term_slots: u64,
+
+    /// Borrow APY locked until maturity, in basis points
+    pub rate_bps: u32,
+}
+
+impl ByteLength for TermOffer {
+    const LEN: usize = 8 + 4;
+}
+
+impl TermOffer {
+    pub fn new(term_slots: u64, rate_bps: u32) -> Self {
+        Self {
+            term_slots,
+            rate_bps,
+        }
+    }
+}
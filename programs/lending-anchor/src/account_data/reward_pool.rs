@@ -0,0 +1,150 @@
+use crate::{
+    constants::REWARD_POOL_VERSION,
+    errors::LendingError,
+    math::common::WAD,
+    utils::{byte_length::ByteLength, versioned::Versioned},
+};
+use anchor_lang::prelude::*;
+
+/// Positions of a reserve a reward pool pays for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub enum RewardSide {
+    /// Collateral deposited in obligations
+    Deposit,
+    /// Liquidity borrowed by obligations, counted in borrow shares so interest doesn't earn rewards
+    Borrow,
+}
+
+/// Rewards emitted to the deposits or borrows of one reserve
+#[account]
+pub struct RewardPool {
+    pub version: u8,
+
+    pub bump_seed: u8,
+
+    pub lending_market: Pubkey,
+
+    pub reserve: Pubkey,
+
+    pub side: RewardSide,
+
+    pub reward_mint: Pubkey,
+
+    pub reward_token_program: Pubkey,
+
+    /// Token account holding the rewards, owned by the lending market authority
+    pub vault: Pubkey,
+
+    /// Rewards emitted per slot, shared by every staked amount
+    pub emission_per_slot: u64,
+
+    /// Slot the emission stops at
+    pub end_slot: u64,
+
+    /// Slot the reward index was last updated at
+    pub last_update_slot: u64,
+
+    /// Rewards emitted per staked amount, scaled by WAD
+    pub reward_index: u128,
+
+    /// Staked amounts of every obligation
+    pub total_staked: u128,
+
+    /// Funded rewards not emitted yet
+    pub unallocated_amount: u64,
+}
+
+impl ByteLength for RewardPool {
+    const LEN: usize = 1 + 1 + 32 + 32 + 1 + 32 + 32 + 32 + 8 + 8 + 8 + 16 + 16 + 8;
+}
+
+pub struct InitRewardPoolParams {
+    pub current_slot: u64,
+    pub bump_seed: u8,
+    pub lending_market: Pubkey,
+    pub reserve: Pubkey,
+    pub side: RewardSide,
+    pub reward_mint: Pubkey,
+    pub reward_token_program: Pubkey,
+    pub vault: Pubkey,
+}
+
+impl Versioned for RewardPool {
+    const VERSION: u8 = REWARD_POOL_VERSION;
+}
+
+impl RewardPool {
+    pub fn init(&mut self, params: InitRewardPoolParams) {
+        self.version = REWARD_POOL_VERSION;
+        self.bump_seed = params.bump_seed;
+        self.lending_market = params.lending_market;
+        self.reserve = params.reserve;
+        self.side = params.side;
+        self.reward_mint = params.reward_mint;
+        self.reward_token_program = params.reward_token_program;
+        self.vault = params.vault;
+        self.emission_per_slot = 0;
+        self.end_slot = params.current_slot;
+        self.last_update_slot = params.current_slot;
+        self.reward_index = 0;
+        self.total_staked = 0;
+        self.unallocated_amount = 0;
+    }
+
+    /// Emit the rewards of the slots since the last update into the reward index.
+    /// Nothing is emitted while nothing is staked, and never more than was funded.
+    pub fn accrue(&mut self, current_slot: u64) -> Result<()> {
+        let slot = current_slot.min(self.end_slot);
+        if slot <= self.last_update_slot {
+            return Ok(());
+        }
+        if self.total_staked > 0 {
+            let emitted = u128::from(slot - self.last_update_slot)
+                .checked_mul(self.emission_per_slot.into())
+                .ok_or(LendingError::MathOverflow)?
+                .min(self.unallocated_amount.into());
+            self.reward_index = emitted
+                .checked_mul(WAD.into())
+                .ok_or(LendingError::MathOverflow)?
+                .checked_div(self.total_staked)
+                .and_then(|increase| self.reward_index.checked_add(increase))
+                .ok_or(LendingError::MathOverflow)?;
+            self.unallocated_amount -= emitted as u64;
+        }
+        self.last_update_slot = slot;
+        Ok(())
+    }
+
+    /// Add `amount` to the rewards to emit
+    pub fn fund(&mut self, amount: u64) -> Result<()> {
+        self.unallocated_amount = self
+            .unallocated_amount
+            .checked_add(amount)
+            .ok_or(LendingError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Emit `emission_per_slot` from now until `end_slot`, once accrued at the old rate
+    pub fn set_emission(
+        &mut self,
+        current_slot: u64,
+        emission_per_slot: u64,
+        end_slot: u64,
+    ) -> Result<()> {
+        require_gte!(end_slot, current_slot, LendingError::InvalidConfig);
+        self.emission_per_slot = emission_per_slot;
+        self.end_slot = end_slot;
+        self.last_update_slot = current_slot;
+        Ok(())
+    }
+
+    /// Replace `old_staked` of an obligation in the total with `new_staked`
+    pub fn restake(&mut self, old_staked: u64, new_staked: u64) -> Result<()> {
+        self.total_staked = self
+            .total_staked
+            .checked_sub(old_staked.into())
+            .and_then(|total| total.checked_add(new_staked.into()))
+            .ok_or(LendingError::MathOverflow)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+//! Account layouts written with version 1, kept to migrate accounts to the current version.
+//! They are frozen copies, so changing the current structs never changes how old accounts
+//! are read.
+
+use crate::{math::common::WAD, utils::migration::Layout};
+use anchor_lang::prelude::*;
+
+pub const VERSION: u8 = 1;
+
+/// Convert an amount scaled by WAD to token units, rounding up so debt is never lost
+pub fn wads_to_amount(wads: u128) -> u128 {
+    wads.div_ceil(WAD as u128)
+}
+
+#[derive(Clone, Copy, Debug, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct LastUpdateV1 {
+    pub slot: u64,
+    pub stale: bool,
+}
+
+#[derive(Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct LendingMarketV1 {
+    pub version: u8,
+    pub bump_seed: u8,
+    pub owner: Pubkey,
+    pub quote_currency: [u8; 32],
+    pub token_program_id: Pubkey,
+    pub oracle_program_id: Pubkey,
+}
+
+impl Layout for LendingMarketV1 {
+    type Previous = Self;
+    const SINCE: u8 = VERSION;
+}
+
+#[derive(Clone, Copy, Debug, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct ReserveLiquidityV1 {
+    pub mint_pubkey: Pubkey,
+    pub mint_decimals: u8,
+    pub supply_pubkey: Pubkey,
+    pub fee_receiver: Pubkey,
+    pub oracle_pubkey: Pubkey,
+    pub available_amount: u64,
+    /// Borrowed liquidity scaled by WAD
+    pub borrowed_amount_wads: u128,
+    pub cumulative_borrow_rate_wads: u128,
+    pub market_price: u128,
+}
+
+#[derive(Clone, Copy, Debug, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct ReserveCollateralV1 {
+    pub mint_pubkey: Pubkey,
+    pub mint_total_supply: u64,
+    pub supply_pubkey: Pubkey,
+}
+
+#[derive(Clone, Copy, Debug, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct ReserveFeesV1 {
+    pub borrow_fee_wad: u64,
+    pub flash_loan_fee_wad: u64,
+    pub host_fee_percentage: u8,
+}
+
+#[derive(Clone, Copy, Debug, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct ReserveConfigV1 {
+    pub optimal_utilization_rate: u8,
+    pub loan_to_value_ratio: u8,
+    pub liquidation_bonus: u8,
+    pub liquidation_threshold: u8,
+    pub min_borrow_rate: u8,
+    pub optimal_borrow_rate: u8,
+    pub max_borrow_rate: u8,
+    pub fees: ReserveFeesV1,
+}
+
+#[derive(Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct ReserveV1 {
+    pub version: u8,
+    pub last_update: LastUpdateV1,
+    pub lending_market: Pubkey,
+    pub liquidity: ReserveLiquidityV1,
+    pub collateral: ReserveCollateralV1,
+    pub config: ReserveConfigV1,
+}
+
+impl Layout for ReserveV1 {
+    type Previous = Self;
+    const SINCE: u8 = VERSION;
+}
+
+#[derive(Clone, Debug, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct ObligationCollateralV1 {
+    pub deposit_reserve: Pubkey,
+    pub deposited_amount: u64,
+    pub market_value: u128,
+}
+
+#[derive(Clone, Debug, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct ObligationLiquidityV1 {
+    pub borrow_reserve: Pubkey,
+    /// Always 1, the rate was never tracked per borrow
+    pub cumulative_borrow_rate_wads: u128,
+    /// Borrowed liquidity scaled by WAD
+    pub borrowed_amount_wads: u128,
+    pub market_value: u128,
+}
+
+#[derive(Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct ObligationV1 {
+    pub version: u8,
+    pub last_update: LastUpdateV1,
+    pub lending_market: Pubkey,
+    pub owner: Pubkey,
+    pub deposits: Vec<ObligationCollateralV1>,
+    pub borrows: Vec<ObligationLiquidityV1>,
+    pub deposited_value: u128,
+    pub borrowed_value: u128,
+    pub allowed_borrow_value: u128,
+    pub unhealthy_borrow_value: u128,
+}
+
+impl Layout for ObligationV1 {
+    type Previous = Self;
+    const SINCE: u8 = VERSION;
+}
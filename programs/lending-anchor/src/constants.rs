@@ -2,8 +2,19 @@ use anchor_lang::prelude::*;
 
 use crate::math::common::WAD;
 
+/// 各account typeの現在のlayoutのversion。layoutを変えたtypeだけ上げる
 #[constant]
-pub const PROGRAM_VERSION: u8 = 1;
+pub const LENDING_MARKET_VERSION: u8 = 2;
+#[constant]
+pub const RESERVE_VERSION: u8 = 2;
+#[constant]
+pub const OBLIGATION_VERSION: u8 = 2;
+#[constant]
+pub const REFERRER_VERSION: u8 = 1;
+#[constant]
+pub const REFERRER_TOKEN_STATE_VERSION: u8 = 1;
+#[constant]
+pub const REWARD_POOL_VERSION: u8 = 1;
 
 /// collateral と liquidityの比率
 /// もし5ならば，colalteralはliquidityの5倍価値が薄いということ
@@ -14,3 +25,69 @@ pub const INITIAL_COLLATERAL_RATE: u64 = INITIAL_COLLATERAL_RATIO * WAD;
 
 #[constant]
 pub const MAX_OBLIGATION_RESERVE: usize = 10;
+
+/// 400msごとのslotで1年間に進むslot数
+#[constant]
+pub const SLOTS_PER_YEAR: u64 = 1000 * 60 * 60 * 24 * 365 / 400;
+
+/// 報酬を受け取れるreward poolの数。claimすれば空く
+#[constant]
+pub const MAX_OBLIGATION_REWARDS: usize = MAX_OBLIGATION_RESERVE;
+
+/// market priceのTWAPを平均するslot数。これより古い価格は効かなくなる
+#[constant]
+pub const PRICE_TWAP_WINDOW_SLOTS: u64 = 150;
+
+/// 借入金利カーブの点の最大数
+#[constant]
+pub const MAX_BORROW_RATE_POINTS: usize = 11;
+
+/// reserveが公開できるterm offerの最大数
+#[constant]
+pub const MAX_TERM_OFFERS: usize = 4;
+
+#[constant]
+pub const MAX_EMODE_CATEGORIES: usize = 8;
+
+/// 一度のliquidationで返済できる借入価値の割合の初期値。lending marketごとに変えられる
+#[constant]
+pub const LIQUIDATION_CLOSE_FACTOR: u8 = 50;
+
+#[constant]
+pub const MAX_LIQUIDATION_PAIRS: usize = 16;
+
+/// isolated collateralのreserveに対して借入を記録できるborrow reserveの最大数
+#[constant]
+pub const MAX_ISOLATED_DEBTS: usize = 8;
+
+/// これより少ない借入は一度のliquidationで全て返済できる
+#[constant]
+pub const LIQUIDATION_CLOSE_AMOUNT: u64 = 2;
+
+/// Seed of the token account native SOL borrows are unwrapped through, with the obligation
+#[constant]
+pub const NATIVE_LIQUIDITY_SEED: &[u8] = b"native_liquidity";
+
+/// Seed of a referrer, with the lending market and the owner
+#[constant]
+pub const REFERRER_SEED: &[u8] = b"referrer";
+
+/// Seed of a referrer's fee tracking for one mint, with the referrer and the mint
+#[constant]
+pub const REFERRER_TOKEN_STATE_SEED: &[u8] = b"referrer_token_state";
+
+/// Seed of the token account a referrer's host fees are paid into, with the referrer and the mint
+#[constant]
+pub const REFERRER_TOKEN_ACCOUNT_SEED: &[u8] = b"referrer_token_account";
+
+/// Seed of a reserve's reward pool, with the reserve and the reward side
+#[constant]
+pub const REWARD_POOL_SEED: &[u8] = b"reward_pool";
+
+/// Seed of the token account holding a reward pool's rewards, with the reward pool
+#[constant]
+pub const REWARD_VAULT_SEED: &[u8] = b"reward_vault";
+
+/// Seed of the token account holding a reserve's insurance fund, with the reserve
+#[constant]
+pub const INSURANCE_VAULT_SEED: &[u8] = b"insurance_vault";
@@ -88,4 +88,97 @@ pub enum LendingError {
 
     #[msg("Repay amount is too small")]
     RepayTooSmall,
+
+    #[msg("Isolated collateral cannot be combined with other collateral")]
+    IsolatedCollateral,
+
+    #[msg("Reserve cannot be borrowed against isolated collateral")]
+    NotBorrowableInIsolation,
+
+    #[msg("Debt ceiling of isolated collateral is exceeded")]
+    DebtCeilingExceeded,
+
+    #[msg("E-mode category is invalid")]
+    InvalidEModeCategory,
+
+    #[msg("E-mode categories exceeds the limit")]
+    EModeCategoryLimit,
+
+    #[msg("Obligation must have no borrows")]
+    ObligationBorrowsNotEmpty,
+
+    #[msg("Obligation is healthy and cannot be liquidated")]
+    ObligationHealthy,
+
+    #[msg("Liquidation amount is too small")]
+    LiquidationTooSmall,
+
+    #[msg("Account has to be migrated to the current version")]
+    OutdatedAccountVersion,
+
+    #[msg("Account version cannot be migrated")]
+    UnsupportedAccountVersion,
+
+    #[msg("Borrow exceeds the allowance of the obligation delegate")]
+    DelegateAllowanceExceeded,
+
+    #[msg("Obligation delegate can only withdraw to the owner")]
+    InvalidDelegateDestination,
+
+    #[msg("Liquidity mint has an unsupported extension")]
+    UnsupportedMintExtension,
+
+    #[msg("Less liquidity arrived than the transfer fee allows")]
+    InsufficientLiquidityReceived,
+
+    #[msg("Reserve liquidity is not native SOL")]
+    ReserveNotNative,
+
+    #[msg("Obligation already has a referrer")]
+    ReferrerAlreadySet,
+
+    #[msg("Referrer owner is invalid")]
+    InvalidReferrerOwner,
+
+    #[msg("Referrer accounts do not match the obligation")]
+    InvalidReferrer,
+
+    #[msg("Obligation reward pools exceeds the limit")]
+    ObligationRewardLimit,
+
+    #[msg("Reserve price is guarded after a large move")]
+    ReservePriceGuarded,
+
+    #[msg("Reserve price is not guarded")]
+    ReservePriceNotGuarded,
+
+    #[msg("Reserve price falls back to the TWAP while the oracle is unavailable")]
+    ReservePriceFallback,
+
+    #[msg("Reserve has no term offer with this term and rate")]
+    TermOfferNotFound,
+
+    #[msg("Obligation already has a term loan from this reserve")]
+    TermLoanExists,
+
+    #[msg("Obligation has no term loan from this reserve")]
+    TermLoanNotFound,
+
+    #[msg("Obligation still has deposits")]
+    ObligationHasDeposits,
+
+    #[msg("Reserve already has an insurance vault")]
+    InsuranceVaultExists,
+
+    #[msg("Reserve has no insurance vault")]
+    InsuranceVaultNotFound,
+
+    #[msg("Liquidation pairs exceeds the limit")]
+    LiquidationPairLimit,
+
+    #[msg("Borrow reserves of isolated debt exceeds the limit")]
+    IsolatedDebtLimit,
+
+    #[msg("Reserve still has collateral deposited in obligations")]
+    ReserveHasCollateralDeposits,
 }
@@ -0,0 +1,322 @@
+use crate::account_data::{
+    obligation::obligation_delegate::ObligationDelegate,
+    reserve::{reserve_config::ReserveConfig, Reserve},
+    reward_pool::RewardSide,
+};
+use anchor_lang::prelude::*;
+
+/// Reserve balances after an instruction has been applied
+#[derive(Clone, Copy, Debug, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct ReserveSnapshot {
+    /// Collateral exchange rate, scaled by WAD
+    pub collateral_exchange_rate: u128,
+
+    /// Reserve liquidity available
+    pub available_amount: u64,
+
+    /// Reserve liquidity borrowed, in tokens including accrued interest
+    pub borrowed_amount: u128,
+
+    /// Reserve collateral mint supply
+    pub collateral_mint_total_supply: u64,
+
+    /// Reserve liquidity market price in quote currency
+    pub market_price: u128,
+}
+
+impl ReserveSnapshot {
+    pub fn new(reserve: &Reserve) -> Result<Self> {
+        Ok(Self {
+            collateral_exchange_rate: reserve.collateral_exchange_rate()?.0.to_scaled_val(),
+            available_amount: reserve.liquidity.available_amount,
+            borrowed_amount: reserve.liquidity.borrowed_amount,
+            collateral_mint_total_supply: reserve.collateral.mint_total_supply,
+            market_price: reserve.liquidity.market_price,
+        })
+    }
+}
+
+#[event]
+pub struct InitLendingMarketEvent {
+    pub lending_market: Pubkey,
+    pub owner: Pubkey,
+    pub quote_currency: [u8; 32],
+}
+
+#[event]
+pub struct SetLendingMarketOwnerEvent {
+    pub lending_market: Pubkey,
+    pub old_owner: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+#[event]
+pub struct InitReserveEvent {
+    pub lending_market: Pubkey,
+    pub reserve: Pubkey,
+    pub liquidity_mint: Pubkey,
+    pub liquidity_amount: u64,
+    pub collateral_amount: u64,
+    pub reserve_state: ReserveSnapshot,
+}
+
+#[event]
+pub struct UpdateReserveConfigEvent {
+    pub lending_market: Pubkey,
+    pub reserve: Pubkey,
+    pub old_config: ReserveConfig,
+    pub new_config: ReserveConfig,
+}
+
+/// Emitted when a refresh holds back the oracle price or accepts a held back one
+#[event]
+pub struct ReservePriceGuardEvent {
+    pub reserve: Pubkey,
+    pub market_price: u128,
+    pub oracle_price: u128,
+    pub guarded: bool,
+}
+
+/// Emitted when a refresh falls back to the TWAP or gets an oracle price again
+#[event]
+pub struct ReservePriceFallbackEvent {
+    pub reserve: Pubkey,
+    pub market_price: u128,
+    pub fallback: bool,
+}
+
+#[event]
+pub struct OverrideReservePriceGuardEvent {
+    pub lending_market: Pubkey,
+    pub reserve: Pubkey,
+    pub market_price: u128,
+}
+
+#[event]
+pub struct DepositReserveLiquidityEvent {
+    pub lending_market: Pubkey,
+    pub reserve: Pubkey,
+    pub liquidity_amount: u64,
+    pub collateral_amount: u64,
+    pub reserve_state: ReserveSnapshot,
+}
+
+#[event]
+pub struct RedeemReserveCollateralEvent {
+    pub lending_market: Pubkey,
+    pub reserve: Pubkey,
+    pub collateral_amount: u64,
+    pub liquidity_amount: u64,
+    pub reserve_state: ReserveSnapshot,
+}
+
+#[event]
+pub struct SetObligationDelegateEvent {
+    pub lending_market: Pubkey,
+    pub obligation: Pubkey,
+    pub delegate: Option<ObligationDelegate>,
+}
+
+#[event]
+pub struct InitReferrerEvent {
+    pub lending_market: Pubkey,
+    pub referrer: Pubkey,
+    pub owner: Pubkey,
+}
+
+#[event]
+pub struct InitReferrerTokenStateEvent {
+    pub referrer: Pubkey,
+    pub referrer_token_state: Pubkey,
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+}
+
+#[event]
+pub struct SetObligationReferrerEvent {
+    pub lending_market: Pubkey,
+    pub obligation: Pubkey,
+    pub referrer: Pubkey,
+}
+
+#[event]
+pub struct ClaimReferrerFeesEvent {
+    pub referrer: Pubkey,
+    pub referrer_token_state: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct DepositObligationCollateralEvent {
+    pub lending_market: Pubkey,
+    pub obligation: Pubkey,
+    pub reserve: Pubkey,
+    pub collateral_amount: u64,
+    pub referrer: Option<Pubkey>,
+}
+
+#[event]
+pub struct WithdrawObligationCollateralEvent {
+    pub lending_market: Pubkey,
+    pub obligation: Pubkey,
+    pub reserve: Pubkey,
+    pub collateral_amount: u64,
+}
+
+#[event]
+pub struct BorrowObligationLiquidityEvent {
+    pub lending_market: Pubkey,
+    pub obligation: Pubkey,
+    pub reserve: Pubkey,
+    pub borrow_amount: u128,
+    pub receive_amount: u64,
+    pub borrow_fee: u64,
+    pub host_fee: u64,
+    pub referrer: Option<Pubkey>,
+    pub reserve_state: ReserveSnapshot,
+}
+
+#[event]
+pub struct RepayObligationLiquidityEvent {
+    pub lending_market: Pubkey,
+    pub obligation: Pubkey,
+    pub reserve: Pubkey,
+    pub settle_amount: u128,
+    pub repay_amount: u64,
+    pub reserve_state: ReserveSnapshot,
+}
+
+#[event]
+pub struct LiquidateObligationEvent {
+    pub lending_market: Pubkey,
+    pub obligation: Pubkey,
+    pub repay_reserve: Pubkey,
+    pub withdraw_reserve: Pubkey,
+    pub liquidator: Pubkey,
+    pub settle_amount: u128,
+    pub repay_amount: u64,
+    pub withdraw_amount: u64,
+    /// Share of the liquidation bonus paid into the insurance fund on top of the repayment
+    pub insurance_amount: u64,
+    pub reserve_state: ReserveSnapshot,
+}
+
+#[event]
+pub struct BorrowObligationTermLoanEvent {
+    pub lending_market: Pubkey,
+    pub obligation: Pubkey,
+    pub reserve: Pubkey,
+    pub borrow_amount: u128,
+    pub receive_amount: u64,
+    pub borrow_fee: u64,
+    pub host_fee: u64,
+    pub referrer: Option<Pubkey>,
+    pub rate_bps: u32,
+    pub maturity_slot: u64,
+    pub reserve_state: ReserveSnapshot,
+}
+
+#[event]
+pub struct RepayObligationTermLoanEvent {
+    pub lending_market: Pubkey,
+    pub obligation: Pubkey,
+    pub reserve: Pubkey,
+    pub settle_amount: u128,
+    pub repay_amount: u64,
+    /// Part of the settled amount which was principal, the rest being interest
+    pub principal_amount: u64,
+    pub reserve_state: ReserveSnapshot,
+}
+
+#[event]
+pub struct LiquidateObligationTermLoanEvent {
+    pub lending_market: Pubkey,
+    pub obligation: Pubkey,
+    pub repay_reserve: Pubkey,
+    pub withdraw_reserve: Pubkey,
+    pub liquidator: Pubkey,
+    pub settle_amount: u128,
+    pub repay_amount: u64,
+    pub withdraw_amount: u64,
+    /// Share of the liquidation bonus paid into the insurance fund on top of the repayment
+    pub insurance_amount: u64,
+    /// True if the loan was past maturity
+    pub overdue: bool,
+    pub reserve_state: ReserveSnapshot,
+}
+
+#[event]
+pub struct SocializeBadDebtEvent {
+    pub lending_market: Pubkey,
+    pub obligation: Pubkey,
+    pub reserve: Pubkey,
+    /// Debt written off the obligation
+    pub settle_amount: u128,
+    /// Part of the debt covered by the reserve's insurance fund
+    pub covered_amount: u64,
+    /// Loss taken out of the reserve's total supply
+    pub socialized_amount: u128,
+    pub reserve_state: ReserveSnapshot,
+}
+
+#[event]
+pub struct InitInsuranceVaultEvent {
+    pub lending_market: Pubkey,
+    pub reserve: Pubkey,
+    pub insurance_vault: Pubkey,
+}
+
+#[event]
+pub struct FundInsuranceVaultEvent {
+    pub reserve: Pubkey,
+    pub amount: u64,
+    /// Insurance share of interest and liquidation bonuses moved from the supply
+    pub swept_amount: u64,
+}
+
+#[event]
+pub struct WithdrawInsuranceVaultEvent {
+    pub reserve: Pubkey,
+    pub amount: u64,
+    /// Insurance share of interest and liquidation bonuses moved from the supply
+    pub swept_amount: u64,
+}
+
+#[event]
+pub struct InitRewardPoolEvent {
+    pub lending_market: Pubkey,
+    pub reserve: Pubkey,
+    pub reward_pool: Pubkey,
+    pub side: RewardSide,
+    pub reward_mint: Pubkey,
+}
+
+#[event]
+pub struct FundRewardPoolEvent {
+    pub reward_pool: Pubkey,
+    pub amount: u64,
+    pub emission_per_slot: u64,
+    pub end_slot: u64,
+}
+
+#[event]
+pub struct ClaimRewardsEvent {
+    pub obligation: Pubkey,
+    pub reward_pool: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct SyncRewardsEvent {
+    pub obligation: Pubkey,
+    pub reward_pool: Pubkey,
+    pub staked_amount: u64,
+}
+
+#[event]
+pub struct MigrateAccountEvent {
+    pub account: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+}
@@ -1,46 +1,74 @@
 use crate::{
     account_data::{
         lending_market::LendingMarket,
-        obligation::Obligation,
+        obligation::{obligation_delegate::ObligationDelegate, Obligation},
         reserve::{CalculateBorrowResult, Reserve},
+        reward_pool::RewardSide,
     },
+    constants::{LENDING_MARKET_VERSION, OBLIGATION_VERSION, RESERVE_VERSION},
     errors::LendingError,
+    events::{BorrowObligationLiquidityEvent, ReserveSnapshot},
+    utils::{
+        referral::ReferrerAccounts, remaining_accounts::update_remaining_account,
+        rewards::update_obligation_rewards, token_interface,
+    },
 };
 use anchor_lang::prelude::*;
-use anchor_spl::token::{transfer, Token, TokenAccount, Transfer};
 
 #[derive(Accounts)]
 pub struct BorrowObligationLiquidity<'info> {
     #[account(
+        mut,
         constraint = source_liquidity.key() == borrow_reserve.liquidity.supply_pubkey @ LendingError::InvalidAccountInput,
     )]
-    pub source_liquidity: Box<Account<'info, TokenAccount>>,
+    /// CHECK: address checked against the reserve
+    pub source_liquidity: UncheckedAccount<'info>,
 
     #[account(
-        constraint = destination_liuqidity.key() != borrow_reserve.liquidity.supply_pubkey @ LendingError::InvalidAccountInput,
+        mut,
+        constraint = destination_liquidity.key() != borrow_reserve.liquidity.supply_pubkey @ LendingError::InvalidAccountInput,
     )]
-    pub destination_liuqidity: Box<Account<'info, TokenAccount>>,
+    /// CHECK: checked by the liquidity token program, owned by the obligation owner when a delegate borrows
+    pub destination_liquidity: UncheckedAccount<'info>,
 
     #[account(
+        mut,
         constraint = borrow_reserve.lending_market.key() == lending_market.key() @ LendingError::InvalidAccountInput,
         constraint = !borrow_reserve.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ReserveStale,
+        constraint = !borrow_reserve.price_guard.guarded @ LendingError::ReservePriceGuarded,
+        constraint = !borrow_reserve.twap.fallback @ LendingError::ReservePriceFallback,
+        constraint = borrow_reserve.version == RESERVE_VERSION @ LendingError::OutdatedAccountVersion,
     )]
     pub borrow_reserve: Box<Account<'info, Reserve>>,
 
     #[account(
+        mut,
         constraint = borrow_reserve_liquidity_fee_receiver.key() == borrow_reserve.liquidity.fee_receiver @ LendingError::InvalidAccountInput,
     )]
-    pub borrow_reserve_liquidity_fee_receiver: Box<Account<'info, TokenAccount>>,
+    /// CHECK: address checked against the reserve
+    pub borrow_reserve_liquidity_fee_receiver: UncheckedAccount<'info>,
+
+    /// CHECK: address checked against the reserve
+    #[account(address = borrow_reserve.liquidity.mint_pubkey @ LendingError::InvalidAccountInput)]
+    pub borrow_reserve_liquidity_mint: UncheckedAccount<'info>,
 
     #[account(
+        mut,
         constraint = obligation.lending_market.key() == lending_market.key() @ LendingError::InvalidAccountInput,
-        constraint = obligation.owner.key() == obligation_owner.key() @ LendingError::InvalidObligationOwner,
+        constraint = obligation.is_authorized(obligation_authority.key(), ObligationDelegate::BORROW) @ LendingError::InvalidObligationOwner,
+        constraint = obligation.owner == obligation_authority.key() || token_interface::token_account_owner(&destination_liquidity)? == obligation.owner @ LendingError::InvalidDelegateDestination,
         constraint = !obligation.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ObligationStale,
+        constraint = !obligation.price_guarded @ LendingError::ReservePriceGuarded,
+        constraint = !obligation.price_fallback @ LendingError::ReservePriceFallback,
         constraint = !obligation.deposits.is_empty() @ LendingError::ObligatinoDepositsEmpty,
         constraint = obligation.deposited_value != 0 @ LendingError::ObligationDepositsZero,
+        constraint = obligation.version == OBLIGATION_VERSION @ LendingError::OutdatedAccountVersion,
     )]
     pub obligation: Box<Account<'info, Obligation>>,
 
+    #[account(
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
     pub lending_market: Box<Account<'info, LendingMarket>>,
 
     #[account(
@@ -50,88 +78,226 @@ pub struct BorrowObligationLiquidity<'info> {
     /// CHECK:
     pub lending_market_authority: UncheckedAccount<'info>,
 
-    pub obligation_owner: Signer<'info>,
-
-    /// CHECK:
-    pub host_fee_receiver: UncheckedAccount<'info>,
+    /// Obligation owner or its delegate, limited by the delegate's borrow allowance
+    pub obligation_authority: Signer<'info>,
 
-    pub token_program: Program<'info, Token>,
+    /// CHECK: token program recorded by the reserve
+    #[account(address = borrow_reserve.liquidity.token_program_id @ LendingError::InvalidTokenProgram)]
+    pub liquidity_token_program: UncheckedAccount<'info>,
 }
 
 impl<'info> BorrowObligationLiquidity<'info> {
-    fn into_transfer_host_fee_ctx(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
-            from: self.source_liquidity.to_account_info(),
-            to: self.host_fee_receiver.to_account_info(),
-            authority: self.lending_market_authority.to_account_info(),
-        };
-        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    /// Transfer out of the liquidity supply
+    fn transfer_liquidity(
+        &self,
+        to: &AccountInfo<'info>,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        token_interface::transfer_checked(
+            &self.liquidity_token_program,
+            &self.source_liquidity,
+            &self.borrow_reserve_liquidity_mint,
+            to,
+            &self.lending_market_authority,
+            amount,
+            self.borrow_reserve.liquidity.mint_decimals,
+            signer_seeds,
+        )
     }
 
-    fn into_transfer_owner_fee_ctx(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
-            from: self.source_liquidity.to_account_info(),
-            to: self.borrow_reserve_liquidity_fee_receiver.to_account_info(),
-            authority: self.lending_market_authority.to_account_info(),
-        };
-        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
-    }
+    /// Pay the borrow fee, with the host fee going to the referrer if any, and transfer
+    /// `receive_amount` to the borrower. Returns the host fee paid to the referrer.
+    pub(crate) fn transfer_borrowed_liquidity(
+        &self,
+        remaining_accounts: &[AccountInfo<'info>],
+        borrow_fee: u64,
+        host_fee: u64,
+        receive_amount: u64,
+    ) -> Result<u64> {
+        let lending_market_key = self.lending_market.key();
+        let authority_signer_seeds: &[&[u8]] = &[
+            lending_market_key.as_ref(),
+            &[self.lending_market.bump_seed],
+        ];
+        // referrerがいなければhost feeもfee receiverに入る
+        let referrer_accounts = ReferrerAccounts::load(
+            &self.obligation,
+            self.borrow_reserve.liquidity.mint_pubkey,
+            remaining_accounts,
+        )?;
+        let mut owner_fee = borrow_fee;
+        let mut paid_host_fee = 0;
+        if let Some(referrer_accounts) = referrer_accounts.filter(|_| host_fee > 0) {
+            owner_fee = owner_fee
+                .checked_sub(host_fee)
+                .ok_or(LendingError::MathOverflow)?;
+            referrer_accounts
+                .accrue(|to| self.transfer_liquidity(to, host_fee, &[authority_signer_seeds]))?;
+            paid_host_fee = host_fee;
+        }
+        if owner_fee > 0 {
+            self.transfer_liquidity(
+                &self.borrow_reserve_liquidity_fee_receiver,
+                owner_fee,
+                &[authority_signer_seeds],
+            )?;
+        }
 
-    fn into_transfer_liquidity_ctx(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
-            from: self.source_liquidity.to_account_info(),
-            to: self.destination_liuqidity.to_account_info(),
-            authority: self.lending_market_authority.to_account_info(),
-        };
-        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+        self.transfer_liquidity(
+            &self.destination_liquidity,
+            receive_amount,
+            &[authority_signer_seeds],
+        )?;
+        Ok(paid_host_fee)
     }
 }
 
-pub fn process_borrow_obligation_liquidity(
-    ctx: Context<BorrowObligationLiquidity>,
+/// Borrow amounts for `liquidity_amount` of `borrow_reserve`, checking the obligation's
+/// borrow limit and isolation mode. The isolated reserve's debt ceiling is left to the caller.
+pub fn calculate_obligation_borrow(
+    obligation: &Obligation,
+    borrow_reserve_key: Pubkey,
+    borrow_reserve: &Reserve,
     liquidity_amount: u64,
-) -> Result<()> {
+) -> Result<CalculateBorrowResult> {
     require_neq!(liquidity_amount, 0, LendingError::InvalidAmount);
 
-    let remaining_borrow_value = ctx.accounts.obligation.remaining_borrow_value()?;
+    let remaining_borrow_value = obligation.remaining_borrow_value()?;
     require_neq!(remaining_borrow_value, 0, LendingError::BorrowTooLarge);
 
+    let result = borrow_reserve.calculate_borrow(liquidity_amount, remaining_borrow_value)?;
+    require_neq!(result.receive_amount, 0, LendingError::BorrowTooSmall);
+
+    // isolated collateralに対してはborrowable_in_isolationのreserveからしか借りられず、collateral自身も借りられない
+    if let Some(isolated_reserve) = obligation.isolated_reserve {
+        require!(
+            borrow_reserve.config.borrowable_in_isolation,
+            LendingError::NotBorrowableInIsolation
+        );
+        require_keys_neq!(
+            borrow_reserve_key,
+            isolated_reserve,
+            LendingError::NotBorrowableInIsolation
+        );
+    }
+
+    Ok(result)
+}
+
+/// Spend the delegate's allowance and the isolated reserve's debt ceiling on `borrow_amount`
+/// of `borrow_reserve`. The isolated reserve is the first of `remaining_accounts`.
+pub fn use_borrow_value(
+    obligation: &mut Obligation,
+    obligation_authority: Pubkey,
+    remaining_accounts: &[AccountInfo],
+    borrow_reserve_key: Pubkey,
+    borrow_reserve: &Reserve,
+    borrow_amount: u128,
+) -> Result<()> {
+    let borrow_value = borrow_reserve.market_value(borrow_amount)?;
+    obligation.use_borrow_allowance(obligation_authority, borrow_value)?;
+
+    if let Some(isolated_reserve) = obligation.isolated_reserve {
+        update_remaining_account(
+            remaining_accounts.first(),
+            isolated_reserve,
+            |reserve: &mut Reserve| {
+                reserve.add_isolated_debt(borrow_reserve_key, borrow_reserve, borrow_amount)
+            },
+        )?;
+        obligation.add_isolated_debt(borrow_reserve_key, borrow_amount)?;
+    }
+    Ok(())
+}
+
+/// Record a borrow of `liquidity_amount` in the obligation and the borrow reserve, spending
+/// the delegate's allowance and the isolated reserve's debt ceiling.
+/// The isolated reserve is the first of `remaining_accounts`.
+pub fn borrow_obligation(
+    obligation: &mut Obligation,
+    borrow_reserve_key: Pubkey,
+    borrow_reserve: &mut Reserve,
+    obligation_authority: Pubkey,
+    remaining_accounts: &[AccountInfo],
+    liquidity_amount: u64,
+) -> Result<CalculateBorrowResult> {
+    let result = calculate_obligation_borrow(
+        obligation,
+        borrow_reserve_key,
+        borrow_reserve,
+        liquidity_amount,
+    )?;
+    let borrow_amount = result.borrow_amount;
+
+    use_borrow_value(
+        obligation,
+        obligation_authority,
+        remaining_accounts,
+        borrow_reserve_key,
+        borrow_reserve,
+        borrow_amount,
+    )?;
+
+    borrow_reserve.liquidity.borrow_(borrow_amount)?;
+    borrow_reserve.last_update.mark_stale();
+
+    obligation
+        .find_or_add_liquidity_to_borrows(
+            borrow_reserve_key,
+            borrow_reserve.liquidity.cumulative_borrow_rate_wads,
+        )?
+        .borrow(borrow_amount)?;
+    obligation.last_update.mark_stale();
+
+    update_obligation_rewards(
+        obligation,
+        borrow_reserve_key,
+        borrow_reserve,
+        RewardSide::Borrow,
+        remaining_accounts,
+    )?;
+
+    Ok(result)
+}
+
+pub fn process_borrow_obligation_liquidity<'info>(
+    ctx: Context<'_, '_, '_, 'info, BorrowObligationLiquidity<'info>>,
+    liquidity_amount: u64,
+) -> Result<()> {
+    let borrow_reserve_key = ctx.accounts.borrow_reserve.key();
     let CalculateBorrowResult {
         borrow_amount,
         receive_amount,
         borrow_fee,
         host_fee,
-    } = ctx
-        .accounts
-        .borrow_reserve
-        .calculate_borrow(liquidity_amount, remaining_borrow_value)?;
-    require_neq!(receive_amount, 0, LendingError::BorrowTooSmall);
-
-    ctx.accounts
-        .borrow_reserve
-        .liquidity
-        .borrow_(borrow_amount)?;
-    ctx.accounts.borrow_reserve.last_update.mark_stale();
-
-    ctx.accounts
-        .obligation
-        .find_or_add_liquidity_to_borrows(ctx.accounts.borrow_reserve.key())?
-        .borrow(borrow_amount)?;
-    ctx.accounts.obligation.last_update.mark_stale();
-
-    // fee transfers
-    let mut owner_fee = borrow_fee;
-    if host_fee > 0 {
-        owner_fee = owner_fee
-            .checked_sub(host_fee)
-            .ok_or(LendingError::MathOverflow)?;
-        transfer(ctx.accounts.into_transfer_host_fee_ctx(), host_fee)?;
-    }
-    if owner_fee > 0 {
-        transfer(ctx.accounts.into_transfer_owner_fee_ctx(), owner_fee)?;
-    }
+    } = borrow_obligation(
+        &mut ctx.accounts.obligation,
+        borrow_reserve_key,
+        &mut ctx.accounts.borrow_reserve,
+        ctx.accounts.obligation_authority.key(),
+        ctx.remaining_accounts,
+        liquidity_amount,
+    )?;
+
+    let paid_host_fee = ctx.accounts.transfer_borrowed_liquidity(
+        ctx.remaining_accounts,
+        borrow_fee,
+        host_fee,
+        receive_amount,
+    )?;
 
-    transfer(ctx.accounts.into_transfer_liquidity_ctx(), receive_amount)?;
+    emit!(BorrowObligationLiquidityEvent {
+        lending_market: ctx.accounts.lending_market.key(),
+        obligation: ctx.accounts.obligation.key(),
+        reserve: ctx.accounts.borrow_reserve.key(),
+        borrow_amount,
+        receive_amount,
+        borrow_fee,
+        host_fee: paid_host_fee,
+        referrer: ctx.accounts.obligation.referrer,
+        reserve_state: ReserveSnapshot::new(&ctx.accounts.borrow_reserve)?,
+    });
 
     Ok(())
 }
@@ -0,0 +1,230 @@
+use crate::{
+    account_data::{
+        lending_market::LendingMarket,
+        obligation::{obligation_delegate::ObligationDelegate, Obligation},
+        reserve::{CalculateBorrowResult, Reserve},
+    },
+    constants::{
+        LENDING_MARKET_VERSION, NATIVE_LIQUIDITY_SEED, OBLIGATION_VERSION, RESERVE_VERSION,
+    },
+    errors::LendingError,
+    events::{BorrowObligationLiquidityEvent, ReserveSnapshot},
+    instructions::borrow_obligation_liquidity::borrow_obligation,
+    utils::referral::ReferrerAccounts,
+};
+use anchor_lang::{prelude::*, system_program};
+use anchor_spl::token::{
+    close_account, spl_token::native_mint, transfer, CloseAccount, Mint, Token, TokenAccount,
+    Transfer,
+};
+
+/// Borrows from a native SOL reserve and unwraps the liquidity into the owner's wallet
+#[derive(Accounts)]
+pub struct BorrowObligationLiquidityNative<'info> {
+    /// CHECK: address checked against the reserve
+    #[account(
+        mut,
+        constraint = source_liquidity.key() == borrow_reserve.liquidity.supply_pubkey @ LendingError::InvalidAccountInput,
+    )]
+    pub source_liquidity: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = borrow_reserve.lending_market.key() == lending_market.key() @ LendingError::InvalidAccountInput,
+        constraint = borrow_reserve.liquidity.mint_pubkey == native_mint::ID @ LendingError::ReserveNotNative,
+        constraint = !borrow_reserve.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ReserveStale,
+        constraint = !borrow_reserve.price_guard.guarded @ LendingError::ReservePriceGuarded,
+        constraint = !borrow_reserve.twap.fallback @ LendingError::ReservePriceFallback,
+        constraint = borrow_reserve.version == RESERVE_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub borrow_reserve: Box<Account<'info, Reserve>>,
+
+    /// CHECK: address checked against the reserve
+    #[account(
+        mut,
+        constraint = borrow_reserve_liquidity_fee_receiver.key() == borrow_reserve.liquidity.fee_receiver @ LendingError::InvalidAccountInput,
+    )]
+    pub borrow_reserve_liquidity_fee_receiver: UncheckedAccount<'info>,
+
+    #[account(address = native_mint::ID @ LendingError::ReserveNotNative)]
+    pub native_mint: Box<Account<'info, Mint>>,
+
+    /// Temporary account the borrowed liquidity is unwrapped through, closed in the same instruction
+    #[account(
+        init,
+        seeds = [NATIVE_LIQUIDITY_SEED, obligation.key().as_ref()],
+        bump,
+        token::mint = native_mint,
+        token::authority = lending_market_authority,
+        payer = obligation_authority,
+    )]
+    pub native_liquidity: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = obligation.lending_market.key() == lending_market.key() @ LendingError::InvalidAccountInput,
+        constraint = obligation.is_authorized(obligation_authority.key(), ObligationDelegate::BORROW) @ LendingError::InvalidObligationOwner,
+        constraint = !obligation.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ObligationStale,
+        constraint = !obligation.price_guarded @ LendingError::ReservePriceGuarded,
+        constraint = !obligation.price_fallback @ LendingError::ReservePriceFallback,
+        constraint = !obligation.deposits.is_empty() @ LendingError::ObligatinoDepositsEmpty,
+        constraint = obligation.deposited_value != 0 @ LendingError::ObligationDepositsZero,
+        constraint = obligation.version == OBLIGATION_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub obligation: Box<Account<'info, Obligation>>,
+
+    #[account(
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+
+    #[account(
+        seeds = [lending_market.key().as_ref()],
+        bump = lending_market.bump_seed,
+    )]
+    /// CHECK:
+    pub lending_market_authority: UncheckedAccount<'info>,
+
+    /// Obligation owner or its delegate, which pays the rent of the temporary account
+    #[account(mut)]
+    pub obligation_authority: Signer<'info>,
+
+    /// Wallet of the obligation owner, which receives the lamports
+    #[account(
+        mut,
+        address = obligation.owner @ LendingError::InvalidDelegateDestination,
+    )]
+    pub obligation_owner: SystemAccount<'info>,
+
+    #[account(
+        constraint = lending_market.token_program_id == *token_program.key
+    )]
+    pub token_program: Program<'info, Token>,
+
+    pub system_program: Program<'info, System>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+impl<'info> BorrowObligationLiquidityNative<'info> {
+    fn into_transfer_liquidity_ctx(
+        &self,
+        to: &AccountInfo<'info>,
+    ) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        let cpi_accounts = Transfer {
+            from: self.source_liquidity.to_account_info(),
+            to: to.clone(),
+            authority: self.lending_market_authority.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+
+    fn into_close_native_liquidity_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, CloseAccount<'info>> {
+        let cpi_accounts = CloseAccount {
+            account: self.native_liquidity.to_account_info(),
+            destination: self.obligation_authority.to_account_info(),
+            authority: self.lending_market_authority.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+
+    fn into_transfer_lamports_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, system_program::Transfer<'info>> {
+        let cpi_accounts = system_program::Transfer {
+            from: self.obligation_authority.to_account_info(),
+            to: self.obligation_owner.to_account_info(),
+        };
+        CpiContext::new(self.system_program.to_account_info(), cpi_accounts)
+    }
+}
+
+pub fn process_borrow_obligation_liquidity_native<'info>(
+    ctx: Context<'_, '_, '_, 'info, BorrowObligationLiquidityNative<'info>>,
+    liquidity_amount: u64,
+) -> Result<()> {
+    let borrow_reserve_key = ctx.accounts.borrow_reserve.key();
+    let CalculateBorrowResult {
+        borrow_amount,
+        receive_amount,
+        borrow_fee,
+        host_fee,
+    } = borrow_obligation(
+        &mut ctx.accounts.obligation,
+        borrow_reserve_key,
+        &mut ctx.accounts.borrow_reserve,
+        ctx.accounts.obligation_authority.key(),
+        ctx.remaining_accounts,
+        liquidity_amount,
+    )?;
+
+    // fee transfers
+    let lending_market_key = ctx.accounts.lending_market.key();
+    let authority_signer_seeds: &[&[u8]] = &[
+        lending_market_key.as_ref(),
+        &[ctx.accounts.lending_market.bump_seed],
+    ];
+    // referrerがいなければhost feeもfee receiverに入る
+    let referrer_accounts = ReferrerAccounts::load(
+        &ctx.accounts.obligation,
+        native_mint::ID,
+        ctx.remaining_accounts,
+    )?;
+    let mut owner_fee = borrow_fee;
+    let mut paid_host_fee = 0;
+    if let Some(referrer_accounts) = referrer_accounts.filter(|_| host_fee > 0) {
+        owner_fee = owner_fee
+            .checked_sub(host_fee)
+            .ok_or(LendingError::MathOverflow)?;
+        referrer_accounts.accrue(|to| {
+            transfer(
+                ctx.accounts
+                    .into_transfer_liquidity_ctx(to)
+                    .with_signer(&[authority_signer_seeds]),
+                host_fee,
+            )
+        })?;
+        paid_host_fee = host_fee;
+    }
+    if owner_fee > 0 {
+        transfer(
+            ctx.accounts
+                .into_transfer_liquidity_ctx(&ctx.accounts.borrow_reserve_liquidity_fee_receiver)
+                .with_signer(&[authority_signer_seeds]),
+            owner_fee,
+        )?;
+    }
+
+    // 一時的なaccountに移してからcloseすることで，rentと一緒にlamportsとして受け取る
+    transfer(
+        ctx.accounts
+            .into_transfer_liquidity_ctx(&ctx.accounts.native_liquidity.to_account_info())
+            .with_signer(&[authority_signer_seeds]),
+        receive_amount,
+    )?;
+    close_account(
+        ctx.accounts
+            .into_close_native_liquidity_ctx()
+            .with_signer(&[authority_signer_seeds]),
+    )?;
+    // delegateが借りた場合はrentを除いた分をownerに渡す
+    if ctx.accounts.obligation_authority.key() != ctx.accounts.obligation_owner.key() {
+        system_program::transfer(ctx.accounts.into_transfer_lamports_ctx(), receive_amount)?;
+    }
+
+    emit!(BorrowObligationLiquidityEvent {
+        lending_market: ctx.accounts.lending_market.key(),
+        obligation: ctx.accounts.obligation.key(),
+        reserve: ctx.accounts.borrow_reserve.key(),
+        borrow_amount,
+        receive_amount,
+        borrow_fee,
+        host_fee: paid_host_fee,
+        referrer: ctx.accounts.obligation.referrer,
+        reserve_state: ReserveSnapshot::new(&ctx.accounts.borrow_reserve)?,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use crate::{
+    account_data::{
+        obligation::obligation_term_loan::ObligationTermLoan, reserve::CalculateBorrowResult,
+    },
+    events::{BorrowObligationTermLoanEvent, ReserveSnapshot},
+    instructions::borrow_obligation_liquidity::{
+        calculate_obligation_borrow, use_borrow_value, BorrowObligationLiquidity,
+    },
+};
+use anchor_lang::prelude::*;
+
+/// Borrows `liquidity_amount` at the fixed rate of the reserve's term offer for `term_slots`.
+/// `rate_bps` has to match the offer, so that the owner can't change it under the borrower.
+/// Takes the same accounts as `borrow_obligation_liquidity`.
+pub fn process_borrow_obligation_liquidity_term<'info>(
+    ctx: Context<'_, '_, '_, 'info, BorrowObligationLiquidity<'info>>,
+    liquidity_amount: u64,
+    term_slots: u64,
+    rate_bps: u32,
+) -> Result<()> {
+    let current_slot = Clock::get()?.slot;
+    let borrow_reserve_key = ctx.accounts.borrow_reserve.key();
+    let obligation = &mut ctx.accounts.obligation;
+    let borrow_reserve = &mut ctx.accounts.borrow_reserve;
+    let offer = *borrow_reserve.find_term_offer(term_slots, rate_bps)?;
+
+    let CalculateBorrowResult {
+        borrow_amount,
+        receive_amount,
+        borrow_fee,
+        host_fee,
+    } = calculate_obligation_borrow(
+        obligation,
+        borrow_reserve_key,
+        borrow_reserve,
+        liquidity_amount,
+    )?;
+    use_borrow_value(
+        obligation,
+        ctx.accounts.obligation_authority.key(),
+        ctx.remaining_accounts,
+        borrow_reserve_key,
+        borrow_reserve,
+        borrow_amount,
+    )?;
+
+    // 変動金利の借入とは別に元本を貸し出す
+    borrow_reserve.borrow_term(borrow_amount)?;
+    borrow_reserve.last_update.mark_stale();
+
+    let term_loan = ObligationTermLoan::new(
+        borrow_reserve_key,
+        borrow_amount,
+        &offer,
+        borrow_reserve.config.term_penalty_rate_bps,
+        current_slot,
+    )?;
+    let maturity_slot = term_loan.maturity_slot;
+    obligation.add_term_loan(term_loan)?;
+    obligation.last_update.mark_stale();
+
+    let paid_host_fee = ctx.accounts.transfer_borrowed_liquidity(
+        ctx.remaining_accounts,
+        borrow_fee,
+        host_fee,
+        receive_amount,
+    )?;
+
+    emit!(BorrowObligationTermLoanEvent {
+        lending_market: ctx.accounts.lending_market.key(),
+        obligation: ctx.accounts.obligation.key(),
+        reserve: borrow_reserve_key,
+        borrow_amount,
+        receive_amount,
+        borrow_fee,
+        host_fee: paid_host_fee,
+        referrer: ctx.accounts.obligation.referrer,
+        rate_bps,
+        maturity_slot,
+        reserve_state: ReserveSnapshot::new(&ctx.accounts.borrow_reserve)?,
+    });
+
+    Ok(())
+}
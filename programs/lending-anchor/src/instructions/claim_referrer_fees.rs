@@ -0,0 +1,100 @@
+use crate::{
+    account_data::{
+        lending_market::LendingMarket,
+        referrer::{Referrer, ReferrerTokenState},
+    },
+    constants::{
+        LENDING_MARKET_VERSION, REFERRER_TOKEN_STATE_SEED, REFERRER_TOKEN_STATE_VERSION,
+        REFERRER_VERSION,
+    },
+    errors::LendingError,
+    events::ClaimReferrerFeesEvent,
+    utils::token_interface::{self, LiquidityMint},
+};
+use anchor_lang::prelude::*;
+
+/// Transfers all host fees accrued to the referrer in one mint to the owner
+#[derive(Accounts)]
+pub struct ClaimReferrerFees<'info> {
+    #[account(
+        mut,
+        seeds = [REFERRER_TOKEN_STATE_SEED, referrer.key().as_ref(), mint.key().as_ref()],
+        bump = referrer_token_state.bump_seed,
+        constraint = referrer_token_state.version == REFERRER_TOKEN_STATE_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub referrer_token_state: Box<Account<'info, ReferrerTokenState>>,
+
+    /// CHECK: address checked against the referrer token state
+    #[account(
+        mut,
+        address = referrer_token_state.token_account @ LendingError::InvalidReferrer,
+    )]
+    pub referrer_token_account: UncheckedAccount<'info>,
+
+    #[account(
+        constraint = referrer.owner == owner.key() @ LendingError::InvalidReferrerOwner,
+        constraint = referrer.version == REFERRER_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub referrer: Box<Account<'info, Referrer>>,
+
+    /// CHECK: address checked against the referrer token state
+    #[account(address = referrer_token_state.mint @ LendingError::InvalidReferrer)]
+    pub mint: UncheckedAccount<'info>,
+
+    /// CHECK: checked by the liquidity token program
+    #[account(mut)]
+    pub destination_liquidity: UncheckedAccount<'info>,
+
+    #[account(
+        address = referrer.lending_market @ LendingError::InvalidAccountInput,
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+
+    #[account(
+        seeds = [lending_market.key().as_ref()],
+        bump = lending_market.bump_seed,
+    )]
+    /// CHECK:
+    pub lending_market_authority: UncheckedAccount<'info>,
+
+    pub owner: Signer<'info>,
+
+    /// CHECK: token program owning the referrer token account
+    #[account(
+        address = *referrer_token_account.owner @ LendingError::InvalidTokenProgram,
+        constraint = token_interface::is_token_program(liquidity_token_program.key) @ LendingError::InvalidTokenProgram,
+    )]
+    pub liquidity_token_program: UncheckedAccount<'info>,
+}
+
+pub fn process_claim_referrer_fees(ctx: Context<ClaimReferrerFees>) -> Result<()> {
+    let amount = token_interface::token_account_amount(&ctx.accounts.referrer_token_account)?;
+    require_neq!(amount, 0, LendingError::InvalidAmount);
+
+    let lending_market_key = ctx.accounts.lending_market.key();
+    let authority_signer_seeds: &[&[u8]] = &[
+        lending_market_key.as_ref(),
+        &[ctx.accounts.lending_market.bump_seed],
+    ];
+    let liquidity_mint = LiquidityMint::load(&ctx.accounts.mint)?;
+    token_interface::transfer_checked(
+        &ctx.accounts.liquidity_token_program,
+        &ctx.accounts.referrer_token_account,
+        &ctx.accounts.mint,
+        &ctx.accounts.destination_liquidity,
+        &ctx.accounts.lending_market_authority,
+        amount,
+        liquidity_mint.decimals,
+        &[authority_signer_seeds],
+    )?;
+    ctx.accounts.referrer_token_state.claim(amount)?;
+
+    emit!(ClaimReferrerFeesEvent {
+        referrer: ctx.accounts.referrer.key(),
+        referrer_token_state: ctx.accounts.referrer_token_state.key(),
+        mint: ctx.accounts.mint.key(),
+        amount,
+    });
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use crate::{
+    account_data::{
+        lending_market::LendingMarket, obligation::Obligation, reward_pool::RewardPool,
+    },
+    constants::{LENDING_MARKET_VERSION, OBLIGATION_VERSION, REWARD_POOL_VERSION},
+    errors::LendingError,
+    events::ClaimRewardsEvent,
+    utils::token_interface::{self, LiquidityMint},
+};
+use anchor_lang::prelude::*;
+
+/// Pays the rewards an obligation earned from a reward pool to its owner.
+/// Also starts earning for positions opened before the pool.
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    #[account(
+        mut,
+        constraint = obligation.owner == obligation_owner.key() @ LendingError::InvalidObligationOwner,
+        constraint = obligation.lending_market == reward_pool.lending_market @ LendingError::InvalidAccountInput,
+        constraint = obligation.version == OBLIGATION_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub obligation: Box<Account<'info, Obligation>>,
+
+    #[account(
+        mut,
+        has_one = lending_market @ LendingError::InvalidAccountInput,
+        constraint = reward_pool.version == REWARD_POOL_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub reward_pool: Box<Account<'info, RewardPool>>,
+
+    /// CHECK: address checked against the reward pool
+    #[account(
+        mut,
+        address = reward_pool.vault @ LendingError::InvalidAccountInput,
+    )]
+    pub reward_vault: UncheckedAccount<'info>,
+
+    /// CHECK: address checked against the reward pool
+    #[account(address = reward_pool.reward_mint @ LendingError::InvalidAccountInput)]
+    pub reward_mint: UncheckedAccount<'info>,
+
+    /// CHECK: checked by the reward token program
+    #[account(mut)]
+    pub destination_reward: UncheckedAccount<'info>,
+
+    #[account(
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+
+    #[account(
+        seeds = [lending_market.key().as_ref()],
+        bump = lending_market.bump_seed,
+    )]
+    /// CHECK:
+    pub lending_market_authority: UncheckedAccount<'info>,
+
+    pub obligation_owner: Signer<'info>,
+
+    /// CHECK: token program recorded by the reward pool
+    #[account(address = reward_pool.reward_token_program @ LendingError::InvalidTokenProgram)]
+    pub reward_token_program: UncheckedAccount<'info>,
+}
+
+pub fn process_claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+    let reward_pool_key = ctx.accounts.reward_pool.key();
+    let reward_pool = &mut ctx.accounts.reward_pool;
+    let obligation = &mut ctx.accounts.obligation;
+    reward_pool.accrue(Clock::get()?.slot)?;
+    let staked_amount = obligation.staked_amount(reward_pool.reserve, reward_pool.side)?;
+    obligation.update_reward(reward_pool_key, reward_pool, staked_amount)?;
+    let amount = obligation.claim_reward(reward_pool_key);
+
+    if amount > 0 {
+        let lending_market_key = ctx.accounts.lending_market.key();
+        let authority_signer_seeds: &[&[u8]] = &[
+            lending_market_key.as_ref(),
+            &[ctx.accounts.lending_market.bump_seed],
+        ];
+        token_interface::transfer_checked(
+            &ctx.accounts.reward_token_program,
+            &ctx.accounts.reward_vault,
+            &ctx.accounts.reward_mint,
+            &ctx.accounts.destination_reward,
+            &ctx.accounts.lending_market_authority,
+            amount,
+            LiquidityMint::load(&ctx.accounts.reward_mint)?.decimals,
+            &[authority_signer_seeds],
+        )?;
+    }
+
+    emit!(ClaimRewardsEvent {
+        obligation: ctx.accounts.obligation.key(),
+        reward_pool: reward_pool_key,
+        amount,
+    });
+    Ok(())
+}
@@ -1,6 +1,14 @@
 use crate::{
-    account_data::{lending_market::LendingMarket, obligation::Obligation, reserve::Reserve},
+    account_data::{
+        lending_market::LendingMarket,
+        obligation::{obligation_delegate::ObligationDelegate, Obligation},
+        reserve::Reserve,
+        reward_pool::RewardSide,
+    },
+    constants::{LENDING_MARKET_VERSION, OBLIGATION_VERSION, RESERVE_VERSION},
     errors::LendingError,
+    events::DepositObligationCollateralEvent,
+    utils::rewards::update_obligation_rewards,
 };
 use anchor_lang::prelude::*;
 use anchor_spl::token::{transfer, Token, TokenAccount, Transfer};
@@ -8,32 +16,42 @@ use anchor_spl::token::{transfer, Token, TokenAccount, Transfer};
 #[derive(Accounts)]
 pub struct DepositObligationCollateral<'info> {
     #[account(
+        mut,
         constraint = source_collateral.key() != deposit_reserve.collateral.supply_pubkey @ LendingError::InvalidAccountInput
     )]
     pub source_collateral: Box<Account<'info, TokenAccount>>,
 
     #[account(
+        mut,
         constraint = destination_collateral.key() == deposit_reserve.collateral.supply_pubkey @ LendingError::InvalidAccountInput
     )]
     pub destination_collateral: Box<Account<'info, TokenAccount>>,
 
     #[account(
+        constraint = deposit_reserve.lending_market == lending_market.key() @ LendingError::InvalidAccountInput,
         constraint = !deposit_reserve.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ReserveStale,
         constraint = deposit_reserve.config.loan_to_value_ratio != 0 @ LendingError::InvalidConfig,
+        constraint = deposit_reserve.version == RESERVE_VERSION @ LendingError::OutdatedAccountVersion,
     )]
     pub deposit_reserve: Box<Account<'info, Reserve>>,
 
     #[account(
+        mut,
         constraint = obligation.lending_market.key() == lending_market.key() @ LendingError::InvalidMarketOwner,
+        constraint = obligation.version == OBLIGATION_VERSION @ LendingError::OutdatedAccountVersion,
     )]
     pub obligation: Box<Account<'info, Obligation>>,
 
+    #[account(
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
     pub lending_market: Box<Account<'info, LendingMarket>>,
 
+    /// Obligation owner or its delegate
     #[account(
-        constraint = obligation.owner == obligation_owner.key() @ LendingError::InvalidObligationOwner,
+        constraint = obligation.is_authorized(obligation_authority.key(), ObligationDelegate::DEPOSIT) @ LendingError::InvalidObligationOwner,
     )]
-    pub obligation_owner: Signer<'info>,
+    pub obligation_authority: Signer<'info>,
 
     /// CHECK:
     pub user_transfer_authority: UncheckedAccount<'info>,
@@ -59,14 +77,39 @@ pub fn process_deposit_obligation_collateral(
     ctx: Context<DepositObligationCollateral>,
     collateral_amount: u64,
 ) -> Result<()> {
+    let deposit_reserve = ctx.accounts.deposit_reserve.key();
+    let isolated = ctx.accounts.deposit_reserve.config.isolated;
+    ctx.accounts
+        .obligation
+        .check_collateral_isolation(deposit_reserve, isolated)?;
+
     ctx.accounts
         .obligation
-        .find_or_add_collateral_to_deposits(ctx.accounts.deposit_reserve.key())?;
+        .find_or_add_collateral_to_deposits(deposit_reserve)?
+        .deposit(collateral_amount)?;
+    if isolated {
+        ctx.accounts.obligation.isolated_reserve = Some(deposit_reserve);
+    }
     ctx.accounts.obligation.last_update.mark_stale();
+    update_obligation_rewards(
+        &mut ctx.accounts.obligation,
+        deposit_reserve,
+        &ctx.accounts.deposit_reserve,
+        RewardSide::Deposit,
+        ctx.remaining_accounts,
+    )?;
 
     transfer(
         ctx.accounts.into_transfer_collateral_ctx(),
         collateral_amount,
     )?;
+
+    emit!(DepositObligationCollateralEvent {
+        lending_market: ctx.accounts.lending_market.key(),
+        obligation: ctx.accounts.obligation.key(),
+        reserve: ctx.accounts.deposit_reserve.key(),
+        collateral_amount,
+        referrer: ctx.accounts.obligation.referrer,
+    });
     Ok(())
 }
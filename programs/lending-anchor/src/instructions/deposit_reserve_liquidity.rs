@@ -1,29 +1,47 @@
 use crate::{
     account_data::{lending_market::LendingMarket, reserve::Reserve},
+    constants::{LENDING_MARKET_VERSION, RESERVE_VERSION},
     errors::LendingError,
+    events::{DepositReserveLiquidityEvent, ReserveSnapshot},
+    utils::token_interface,
 };
 use anchor_lang::prelude::*;
-use anchor_spl::token::{mint_to, transfer, Mint, MintTo, Token, TokenAccount, Transfer};
+use anchor_spl::token::{mint_to, Mint, MintTo, Token, TokenAccount};
 
 #[derive(Accounts)]
 pub struct DepositReserveLiquidity<'info> {
-    pub source_liquidity: Box<Account<'info, TokenAccount>>,
+    /// CHECK: checked by the liquidity token program
+    #[account(mut)]
+    pub source_liquidity: UncheckedAccount<'info>,
+    #[account(mut)]
     pub destination_collateral: Box<Account<'info, TokenAccount>>,
 
     #[account(
-        address = reserve.lending_market @ LendingError::InvalidAccountInput,
+        mut,
+        constraint = reserve.lending_market == lending_market.key() @ LendingError::InvalidAccountInput,
         constraint = reserve.liquidity.supply_pubkey == reserve_liquidity_supply.key() @ LendingError::InvalidAccountInput,
         constraint = reserve.collateral.mint_pubkey == reserve_collateral_mint.key() @ LendingError::InvalidAccountInput,
         constraint = reserve.liquidity.supply_pubkey != source_liquidity.key() @ LendingError::InvalidAccountInput,
         constraint = reserve.collateral.supply_pubkey != destination_collateral.key() @ LendingError::InvalidAccountInput,
         constraint = !reserve.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ReserveStale,
+        constraint = reserve.version == RESERVE_VERSION @ LendingError::OutdatedAccountVersion,
     )]
     pub reserve: Box<Account<'info, Reserve>>,
 
-    pub reserve_liquidity_supply: Box<Account<'info, TokenAccount>>,
+    /// CHECK: address checked against the reserve
+    #[account(mut)]
+    pub reserve_liquidity_supply: UncheckedAccount<'info>,
 
+    /// CHECK: address checked against the reserve
+    #[account(address = reserve.liquidity.mint_pubkey @ LendingError::InvalidAccountInput)]
+    pub reserve_liquidity_mint: UncheckedAccount<'info>,
+
+    #[account(mut)]
     pub reserve_collateral_mint: Box<Account<'info, Mint>>,
 
+    #[account(
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
     pub lending_market: Box<Account<'info, LendingMarket>>,
 
     /// CHECK:
@@ -40,24 +58,31 @@ pub struct DepositReserveLiquidity<'info> {
         constraint = lending_market.token_program_id == *token_program.key
     )]
     pub token_program: Program<'info, Token>,
+
+    /// CHECK: token program recorded by the reserve
+    #[account(address = reserve.liquidity.token_program_id @ LendingError::InvalidTokenProgram)]
+    pub liquidity_token_program: UncheckedAccount<'info>,
 }
 
 impl<'info> DepositReserveLiquidity<'info> {
-    pub fn into_transfer_user_liquidity_ctx(
-        &self,
-    ) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
-            from: self.source_liquidity.to_account_info(),
-            to: self.reserve_liquidity_supply.to_account_info(),
-            authority: self.user_transfer_authority.to_account_info(),
-        };
-        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    /// Transfer the liquidity of the user into the supply and return how much arrived
+    pub fn transfer_user_liquidity(&self, liquidity_amount: u64) -> Result<u64> {
+        token_interface::transfer_checked_received(
+            &self.liquidity_token_program,
+            &self.source_liquidity,
+            &self.reserve_liquidity_mint,
+            &self.reserve_liquidity_supply,
+            &self.user_transfer_authority,
+            liquidity_amount,
+            self.reserve.liquidity.mint_decimals,
+        )
     }
+
     pub fn into_mint_user_collateral_ctx(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
         let cpi_accounts = MintTo {
             mint: self.reserve_collateral_mint.to_account_info(),
             to: self.destination_collateral.to_account_info(),
-            authority: self.user_transfer_authority.to_account_info(),
+            authority: self.lending_market_authority.to_account_info(),
         };
         CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
     }
@@ -69,15 +94,27 @@ pub fn process_deposit_reserve_liquidity(
 ) -> Result<()> {
     ctx.accounts.reserve.last_update.mark_stale();
 
+    // transfer feeが引かれた後に届いた分だけをdepositする
+    let liquidity_amount = ctx.accounts.transfer_user_liquidity(liquidity_amount)?;
     let collateral_amount = ctx.accounts.reserve.deposit_liquidity(liquidity_amount)?;
-
-    transfer(
-        ctx.accounts.into_transfer_user_liquidity_ctx(),
-        liquidity_amount,
-    )?;
+    let lending_market_key = ctx.accounts.lending_market.key();
+    let authority_signer_seeds: &[&[u8]] = &[
+        lending_market_key.as_ref(),
+        &[ctx.accounts.lending_market.bump_seed],
+    ];
     mint_to(
-        ctx.accounts.into_mint_user_collateral_ctx(),
+        ctx.accounts
+            .into_mint_user_collateral_ctx()
+            .with_signer(&[authority_signer_seeds]),
         collateral_amount,
     )?;
+
+    emit!(DepositReserveLiquidityEvent {
+        lending_market: ctx.accounts.lending_market.key(),
+        reserve: ctx.accounts.reserve.key(),
+        liquidity_amount,
+        collateral_amount,
+        reserve_state: ReserveSnapshot::new(&ctx.accounts.reserve)?,
+    });
     Ok(())
 }
@@ -0,0 +1,106 @@
+use crate::{
+    account_data::{lending_market::LendingMarket, reserve::Reserve},
+    constants::{LENDING_MARKET_VERSION, RESERVE_VERSION},
+    errors::LendingError,
+    events::{DepositReserveLiquidityEvent, ReserveSnapshot},
+    utils::token_interface,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{mint_to, spl_token::native_mint, Mint, MintTo, Token, TokenAccount};
+
+/// Deposits lamports into a native SOL reserve, wrapping them into the liquidity supply
+#[derive(Accounts)]
+pub struct DepositReserveLiquidityNative<'info> {
+    /// Wallet the lamports are wrapped from
+    #[account(mut)]
+    pub source_wallet: Signer<'info>,
+    #[account(mut)]
+    pub destination_collateral: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = reserve.lending_market == lending_market.key() @ LendingError::InvalidAccountInput,
+        constraint = reserve.liquidity.mint_pubkey == native_mint::ID @ LendingError::ReserveNotNative,
+        constraint = reserve.liquidity.supply_pubkey == reserve_liquidity_supply.key() @ LendingError::InvalidAccountInput,
+        constraint = reserve.collateral.mint_pubkey == reserve_collateral_mint.key() @ LendingError::InvalidAccountInput,
+        constraint = reserve.collateral.supply_pubkey != destination_collateral.key() @ LendingError::InvalidAccountInput,
+        constraint = !reserve.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ReserveStale,
+        constraint = reserve.version == RESERVE_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub reserve: Box<Account<'info, Reserve>>,
+
+    /// CHECK: address checked against the reserve
+    #[account(mut)]
+    pub reserve_liquidity_supply: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub reserve_collateral_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+
+    /// CHECK:
+    #[account(
+        seeds = [lending_market.key().as_ref()],
+        bump = lending_market.bump_seed,
+    )]
+    pub lending_market_authority: UncheckedAccount<'info>,
+
+    #[account(
+        constraint = lending_market.token_program_id == *token_program.key
+    )]
+    pub token_program: Program<'info, Token>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> DepositReserveLiquidityNative<'info> {
+    pub fn into_mint_user_collateral_ctx(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        let cpi_accounts = MintTo {
+            mint: self.reserve_collateral_mint.to_account_info(),
+            to: self.destination_collateral.to_account_info(),
+            authority: self.lending_market_authority.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+pub fn process_deposit_reserve_liquidity_native(
+    ctx: Context<DepositReserveLiquidityNative>,
+    lamports: u64,
+) -> Result<()> {
+    ctx.accounts.reserve.last_update.mark_stale();
+
+    // native SOLはtransfer feeがないので送った分だけdepositされる
+    token_interface::wrap_lamports(
+        &ctx.accounts.source_wallet,
+        &ctx.accounts.reserve_liquidity_supply,
+        lamports,
+        &ctx.accounts.system_program,
+        &ctx.accounts.token_program,
+    )?;
+    let collateral_amount = ctx.accounts.reserve.deposit_liquidity(lamports)?;
+
+    let lending_market_key = ctx.accounts.lending_market.key();
+    let authority_signer_seeds: &[&[u8]] = &[
+        lending_market_key.as_ref(),
+        &[ctx.accounts.lending_market.bump_seed],
+    ];
+    mint_to(
+        ctx.accounts
+            .into_mint_user_collateral_ctx()
+            .with_signer(&[authority_signer_seeds]),
+        collateral_amount,
+    )?;
+
+    emit!(DepositReserveLiquidityEvent {
+        lending_market: ctx.accounts.lending_market.key(),
+        reserve: ctx.accounts.reserve.key(),
+        liquidity_amount: lamports,
+        collateral_amount,
+        reserve_state: ReserveSnapshot::new(&ctx.accounts.reserve)?,
+    });
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use crate::{
+    account_data::{lending_market::LendingMarket, reserve::Reserve},
+    constants::{LENDING_MARKET_VERSION, RESERVE_VERSION},
+    errors::LendingError,
+    events::FundInsuranceVaultEvent,
+    utils::token_interface,
+};
+use anchor_lang::prelude::*;
+
+/// Moves liquidity between a reserve's insurance vault and the lending market owner
+#[derive(Accounts)]
+pub struct UpdateInsuranceVault<'info> {
+    #[account(
+        mut,
+        has_one = lending_market @ LendingError::InvalidAccountInput,
+        constraint = reserve.version == RESERVE_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub reserve: Box<Account<'info, Reserve>>,
+
+    /// CHECK: address checked against the reserve
+    #[account(
+        mut,
+        address = reserve.liquidity.supply_pubkey @ LendingError::InvalidAccountInput,
+    )]
+    pub reserve_liquidity_supply: UncheckedAccount<'info>,
+
+    /// CHECK: address checked against the reserve
+    #[account(
+        mut,
+        constraint = reserve.insurance_vault == Some(insurance_vault.key()) @ LendingError::InsuranceVaultNotFound,
+    )]
+    pub insurance_vault: UncheckedAccount<'info>,
+
+    /// CHECK: address checked against the reserve
+    #[account(address = reserve.liquidity.mint_pubkey @ LendingError::InvalidAccountInput)]
+    pub reserve_liquidity_mint: UncheckedAccount<'info>,
+
+    /// CHECK: the owner's token account, checked by the liquidity token program
+    #[account(mut)]
+    pub owner_liquidity: UncheckedAccount<'info>,
+
+    #[account(
+        has_one = owner @ LendingError::InvalidMarketOwner,
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+
+    #[account(
+        seeds = [lending_market.key().as_ref()],
+        bump = lending_market.bump_seed,
+    )]
+    /// CHECK:
+    pub lending_market_authority: UncheckedAccount<'info>,
+
+    pub owner: Signer<'info>,
+
+    /// CHECK: token program recorded by the reserve
+    #[account(address = reserve.liquidity.token_program_id @ LendingError::InvalidTokenProgram)]
+    pub liquidity_token_program: UncheckedAccount<'info>,
+}
+
+impl<'info> UpdateInsuranceVault<'info> {
+    /// Transfer out of the insurance vault or the liquidity supply
+    pub(crate) fn transfer_from_market(
+        &self,
+        from: &AccountInfo<'info>,
+        to: &AccountInfo<'info>,
+        amount: u64,
+    ) -> Result<()> {
+        let lending_market_key = self.lending_market.key();
+        let authority_signer_seeds: &[&[u8]] = &[
+            lending_market_key.as_ref(),
+            &[self.lending_market.bump_seed],
+        ];
+        token_interface::transfer_checked(
+            &self.liquidity_token_program,
+            from,
+            &self.reserve_liquidity_mint,
+            to,
+            &self.lending_market_authority,
+            amount,
+            self.reserve.liquidity.mint_decimals,
+            &[authority_signer_seeds],
+        )
+    }
+
+    /// Move the insurance share of interest and liquidation bonuses that the supply can pay
+    /// out into the vault and return how much was taken from the supply
+    pub(crate) fn sweep_insurance(&mut self) -> Result<u64> {
+        let swept_amount = self.reserve.sweep_insurance()?;
+        if swept_amount > 0 {
+            self.transfer_from_market(
+                &self.reserve_liquidity_supply,
+                &self.insurance_vault,
+                swept_amount,
+            )?;
+        }
+        Ok(swept_amount)
+    }
+}
+
+/// Tops up a reserve's insurance vault from the lending market owner
+pub fn process_fund_insurance_vault(ctx: Context<UpdateInsuranceVault>, amount: u64) -> Result<()> {
+    require_neq!(amount, 0, LendingError::InvalidAmount);
+    let swept_amount = ctx.accounts.sweep_insurance()?;
+
+    // transfer feeが引かれることがあるので実際に届いた分を記録する
+    let received_amount = token_interface::transfer_checked_received(
+        &ctx.accounts.liquidity_token_program,
+        &ctx.accounts.owner_liquidity,
+        &ctx.accounts.reserve_liquidity_mint,
+        &ctx.accounts.insurance_vault,
+        &ctx.accounts.owner,
+        amount,
+        ctx.accounts.reserve.liquidity.mint_decimals,
+    )?;
+
+    emit!(FundInsuranceVaultEvent {
+        reserve: ctx.accounts.reserve.key(),
+        amount: received_amount,
+        swept_amount,
+    });
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use crate::{
+    account_data::{lending_market::LendingMarket, reward_pool::RewardPool},
+    constants::{LENDING_MARKET_VERSION, REWARD_POOL_VERSION},
+    errors::LendingError,
+    events::FundRewardPoolEvent,
+    utils::token_interface::{self, LiquidityMint},
+};
+use anchor_lang::prelude::*;
+
+/// Adds rewards to a reward pool and sets its emission by the lending market owner
+#[derive(Accounts)]
+pub struct FundRewardPool<'info> {
+    #[account(
+        mut,
+        has_one = lending_market @ LendingError::InvalidAccountInput,
+        constraint = reward_pool.version == REWARD_POOL_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub reward_pool: Box<Account<'info, RewardPool>>,
+
+    /// CHECK: address checked against the reward pool
+    #[account(
+        mut,
+        address = reward_pool.vault @ LendingError::InvalidAccountInput,
+    )]
+    pub reward_vault: UncheckedAccount<'info>,
+
+    /// CHECK: address checked against the reward pool
+    #[account(address = reward_pool.reward_mint @ LendingError::InvalidAccountInput)]
+    pub reward_mint: UncheckedAccount<'info>,
+
+    /// CHECK: checked by the reward token program
+    #[account(mut)]
+    pub source_reward: UncheckedAccount<'info>,
+
+    #[account(
+        has_one = owner @ LendingError::InvalidMarketOwner,
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+
+    pub owner: Signer<'info>,
+
+    /// CHECK: token program recorded by the reward pool
+    #[account(address = reward_pool.reward_token_program @ LendingError::InvalidTokenProgram)]
+    pub reward_token_program: UncheckedAccount<'info>,
+}
+
+pub fn process_fund_reward_pool(
+    ctx: Context<FundRewardPool>,
+    amount: u64,
+    emission_per_slot: u64,
+    end_slot: u64,
+) -> Result<()> {
+    let current_slot = Clock::get()?.slot;
+    // これまでの分は古いemissionで配る
+    ctx.accounts.reward_pool.accrue(current_slot)?;
+
+    let mut received_amount = 0;
+    if amount > 0 {
+        received_amount = token_interface::transfer_checked_received(
+            &ctx.accounts.reward_token_program,
+            &ctx.accounts.source_reward,
+            &ctx.accounts.reward_mint,
+            &ctx.accounts.reward_vault,
+            &ctx.accounts.owner,
+            amount,
+            LiquidityMint::load(&ctx.accounts.reward_mint)?.decimals,
+        )?;
+        ctx.accounts.reward_pool.fund(received_amount)?;
+    }
+    ctx.accounts
+        .reward_pool
+        .set_emission(current_slot, emission_per_slot, end_slot)?;
+
+    emit!(FundRewardPoolEvent {
+        reward_pool: ctx.accounts.reward_pool.key(),
+        amount: received_amount,
+        emission_per_slot,
+        end_slot,
+    });
+    Ok(())
+}
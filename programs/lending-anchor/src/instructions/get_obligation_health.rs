@@ -0,0 +1,42 @@
+use crate::{
+    account_data::obligation::Obligation, constants::OBLIGATION_VERSION, errors::LendingError,
+    math::rate::Rate, views::ObligationHealth,
+};
+use anchor_lang::{prelude::*, solana_program::program::set_return_data};
+
+/// Returns the health of an obligation refreshed in the same transaction
+#[derive(Accounts)]
+pub struct GetObligationHealth<'info> {
+    #[account(
+        constraint = !obligation.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ObligationStale,
+        constraint = obligation.version == OBLIGATION_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub obligation: Box<Account<'info, Obligation>>,
+}
+
+pub fn process_get_obligation_health(ctx: Context<GetObligationHealth>) -> Result<()> {
+    let obligation = &ctx.accounts.obligation;
+
+    let health_factor_wads = if obligation.borrowed_value == 0 {
+        u128::MAX
+    } else {
+        Rate::from_ratio(obligation.unhealthy_borrow_value, obligation.borrowed_value)?
+            .to_scaled_val()
+    };
+
+    let health = ObligationHealth {
+        deposited_value: obligation.deposited_value,
+        borrowed_value: obligation.borrowed_value,
+        unweighted_borrowed_value: obligation.unweighted_borrowed_value,
+        allowed_borrow_value: obligation.allowed_borrow_value,
+        unhealthy_borrow_value: obligation.unhealthy_borrow_value,
+        remaining_borrow_value: obligation
+            .allowed_borrow_value
+            .saturating_sub(obligation.borrowed_value),
+        health_factor_wads,
+        liquidatable: obligation.is_unhealthy(),
+    };
+    set_return_data(&health.try_to_vec()?);
+
+    Ok(())
+}
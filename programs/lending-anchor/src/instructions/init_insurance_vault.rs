@@ -0,0 +1,86 @@
+use crate::{
+    account_data::{lending_market::LendingMarket, reserve::Reserve},
+    constants::{INSURANCE_VAULT_SEED, LENDING_MARKET_VERSION, RESERVE_VERSION},
+    errors::LendingError,
+    events::InitInsuranceVaultEvent,
+    utils::token_interface::{self, LiquidityMint},
+};
+use anchor_lang::prelude::*;
+
+/// Creates the insurance vault of a reserve by the lending market owner
+#[derive(Accounts)]
+pub struct InitInsuranceVault<'info> {
+    /// CHECK: created in this instruction
+    #[account(
+        mut,
+        seeds = [INSURANCE_VAULT_SEED, reserve.key().as_ref()],
+        bump,
+    )]
+    pub insurance_vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = lending_market @ LendingError::InvalidAccountInput,
+        constraint = reserve.insurance_vault.is_none() @ LendingError::InsuranceVaultExists,
+        constraint = reserve.version == RESERVE_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub reserve: Box<Account<'info, Reserve>>,
+
+    /// CHECK: address checked against the reserve
+    #[account(address = reserve.liquidity.mint_pubkey @ LendingError::InvalidAccountInput)]
+    pub reserve_liquidity_mint: UncheckedAccount<'info>,
+
+    #[account(
+        has_one = owner @ LendingError::InvalidMarketOwner,
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+
+    #[account(
+        seeds = [lending_market.key().as_ref()],
+        bump = lending_market.bump_seed,
+    )]
+    /// CHECK:
+    pub lending_market_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: token program recorded by the reserve
+    #[account(address = reserve.liquidity.token_program_id @ LendingError::InvalidTokenProgram)]
+    pub liquidity_token_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+pub fn process_init_insurance_vault(ctx: Context<InitInsuranceVault>) -> Result<()> {
+    let reserve_key = ctx.accounts.reserve.key();
+    let vault_bump_seed = *ctx.bumps.get("insurance_vault").unwrap();
+    let vault_signer_seeds: &[&[u8]] = &[
+        INSURANCE_VAULT_SEED,
+        reserve_key.as_ref(),
+        &[vault_bump_seed],
+    ];
+    // lending_market_authorityが持つliquidityのtoken accountにする
+    token_interface::create_account(
+        &ctx.accounts.owner.to_account_info(),
+        &ctx.accounts.insurance_vault,
+        &ctx.accounts.reserve_liquidity_mint,
+        &LiquidityMint::load(&ctx.accounts.reserve_liquidity_mint)?,
+        &ctx.accounts.lending_market_authority,
+        &ctx.accounts.liquidity_token_program,
+        &ctx.accounts.system_program.to_account_info(),
+        &ctx.accounts.rent.to_account_info(),
+        &[vault_signer_seeds],
+    )?;
+    ctx.accounts.reserve.insurance_vault = Some(ctx.accounts.insurance_vault.key());
+
+    emit!(InitInsuranceVaultEvent {
+        lending_market: ctx.accounts.lending_market.key(),
+        reserve: reserve_key,
+        insurance_vault: ctx.accounts.insurance_vault.key(),
+    });
+    Ok(())
+}
@@ -1,4 +1,6 @@
-use crate::{account_data::lending_market::*, utils::byte_length::ByteLength};
+use crate::{
+    account_data::lending_market::*, events::InitLendingMarketEvent, utils::byte_length::ByteLength,
+};
 use anchor_lang::prelude::*;
 use anchor_spl::token::Token;
 
@@ -36,5 +38,11 @@ pub fn process_init_lending_market(
         token_program_id: ctx.accounts.token_program.key(),
         oracle_program_id: ctx.accounts.oracle.key(),
     });
+
+    emit!(InitLendingMarketEvent {
+        lending_market: *lending_market,
+        owner: ctx.accounts.owner.key(),
+        quote_currency,
+    });
     Ok(())
 }
@@ -3,23 +3,34 @@ use crate::{
         lending_market::LendingMarket,
         obligation::{InitObligationParams, Obligation},
     },
+    constants::LENDING_MARKET_VERSION,
     errors::LendingError,
+    utils::byte_length::ByteLength,
 };
 use anchor_lang::prelude::*;
 use anchor_spl::token::Token;
 
 #[derive(Accounts)]
 pub struct InitObligation<'info> {
+    #[account(
+        init,
+        payer = obligation_owner,
+        space = Obligation::LEN + 8,
+    )]
     pub obligation: Box<Account<'info, Obligation>>,
 
     #[account(
         constraint = lending_market.token_program_id == token_program.key() @ LendingError::InvalidTokenProgram,
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
     )]
     pub lending_market: Box<Account<'info, LendingMarket>>,
 
+    #[account(mut)]
     pub obligation_owner: Signer<'info>,
 
     pub token_program: Program<'info, Token>,
+
+    pub system_program: Program<'info, System>,
 }
 
 pub fn process_init_obligation(ctx: Context<InitObligation>) -> Result<()> {
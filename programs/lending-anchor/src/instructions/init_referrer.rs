@@ -0,0 +1,47 @@
+use crate::{
+    account_data::{lending_market::LendingMarket, referrer::Referrer},
+    constants::{LENDING_MARKET_VERSION, REFERRER_SEED},
+    errors::LendingError,
+    events::InitReferrerEvent,
+    utils::byte_length::ByteLength,
+};
+use anchor_lang::prelude::*;
+
+/// Registers the signer as a referrer of the lending market
+#[derive(Accounts)]
+pub struct InitReferrer<'info> {
+    #[account(
+        init,
+        seeds = [REFERRER_SEED, lending_market.key().as_ref(), owner.key().as_ref()],
+        bump,
+        payer = owner,
+        space = Referrer::LEN + 8,
+    )]
+    pub referrer: Box<Account<'info, Referrer>>,
+
+    #[account(
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn process_init_referrer(ctx: Context<InitReferrer>) -> Result<()> {
+    let bump_seed = *ctx.bumps.get("referrer").unwrap();
+    ctx.accounts.referrer.init(
+        bump_seed,
+        ctx.accounts.lending_market.key(),
+        ctx.accounts.owner.key(),
+    );
+
+    emit!(InitReferrerEvent {
+        lending_market: ctx.accounts.lending_market.key(),
+        referrer: ctx.accounts.referrer.key(),
+        owner: ctx.accounts.owner.key(),
+    });
+    Ok(())
+}
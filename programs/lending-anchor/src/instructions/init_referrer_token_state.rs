@@ -0,0 +1,119 @@
+use crate::{
+    account_data::{
+        lending_market::LendingMarket,
+        referrer::{Referrer, ReferrerTokenState},
+        reserve::Reserve,
+    },
+    constants::{
+        LENDING_MARKET_VERSION, REFERRER_TOKEN_ACCOUNT_SEED, REFERRER_TOKEN_STATE_SEED,
+        REFERRER_VERSION, RESERVE_VERSION,
+    },
+    errors::LendingError,
+    events::InitReferrerTokenStateEvent,
+    utils::{
+        byte_length::ByteLength,
+        token_interface::{self, LiquidityMint},
+    },
+};
+use anchor_lang::prelude::*;
+
+/// Creates the account a referrer's host fees in the liquidity of `reserve` are paid into.
+/// Anyone can pay for it.
+#[derive(Accounts)]
+pub struct InitReferrerTokenState<'info> {
+    #[account(
+        init,
+        seeds = [REFERRER_TOKEN_STATE_SEED, referrer.key().as_ref(), reserve_liquidity_mint.key().as_ref()],
+        bump,
+        payer = payer,
+        space = ReferrerTokenState::LEN + 8,
+    )]
+    pub referrer_token_state: Box<Account<'info, ReferrerTokenState>>,
+
+    /// CHECK: created in this instruction
+    #[account(
+        mut,
+        seeds = [REFERRER_TOKEN_ACCOUNT_SEED, referrer.key().as_ref(), reserve_liquidity_mint.key().as_ref()],
+        bump,
+    )]
+    pub referrer_token_account: UncheckedAccount<'info>,
+
+    #[account(
+        constraint = referrer.lending_market == lending_market.key() @ LendingError::InvalidAccountInput,
+        constraint = referrer.version == REFERRER_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub referrer: Box<Account<'info, Referrer>>,
+
+    #[account(
+        constraint = reserve.lending_market == lending_market.key() @ LendingError::InvalidAccountInput,
+        constraint = reserve.version == RESERVE_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub reserve: Box<Account<'info, Reserve>>,
+
+    /// CHECK: address checked against the reserve
+    #[account(address = reserve.liquidity.mint_pubkey @ LendingError::InvalidAccountInput)]
+    pub reserve_liquidity_mint: UncheckedAccount<'info>,
+
+    #[account(
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+
+    #[account(
+        seeds = [lending_market.key().as_ref()],
+        bump = lending_market.bump_seed,
+    )]
+    /// CHECK:
+    pub lending_market_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: token program recorded by the reserve
+    #[account(address = reserve.liquidity.token_program_id @ LendingError::InvalidTokenProgram)]
+    pub liquidity_token_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+pub fn process_init_referrer_token_state(ctx: Context<InitReferrerTokenState>) -> Result<()> {
+    let referrer_key = ctx.accounts.referrer.key();
+    let mint_key = ctx.accounts.reserve_liquidity_mint.key();
+    let token_account_bump_seed = *ctx.bumps.get("referrer_token_account").unwrap();
+    let token_account_signer_seeds: &[&[u8]] = &[
+        REFERRER_TOKEN_ACCOUNT_SEED,
+        referrer_key.as_ref(),
+        mint_key.as_ref(),
+        &[token_account_bump_seed],
+    ];
+    let liquidity_mint = LiquidityMint::load(&ctx.accounts.reserve_liquidity_mint)?;
+    token_interface::create_account(
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.referrer_token_account,
+        &ctx.accounts.reserve_liquidity_mint,
+        &liquidity_mint,
+        &ctx.accounts.lending_market_authority,
+        &ctx.accounts.liquidity_token_program,
+        &ctx.accounts.system_program.to_account_info(),
+        &ctx.accounts.rent.to_account_info(),
+        &[token_account_signer_seeds],
+    )?;
+
+    let bump_seed = *ctx.bumps.get("referrer_token_state").unwrap();
+    ctx.accounts.referrer_token_state.init(
+        bump_seed,
+        referrer_key,
+        mint_key,
+        ctx.accounts.referrer_token_account.key(),
+    );
+
+    emit!(InitReferrerTokenStateEvent {
+        referrer: referrer_key,
+        referrer_token_state: ctx.accounts.referrer_token_state.key(),
+        mint: mint_key,
+        token_account: ctx.accounts.referrer_token_account.key(),
+    });
+    Ok(())
+}
@@ -8,36 +8,29 @@ use crate::{
             InitReserveParams, Reserve,
         },
     },
+    constants::LENDING_MARKET_VERSION,
     errors::LendingError,
-    math::common::WAD,
+    events::{InitReserveEvent, ReserveSnapshot},
     pyth::{get_pyth_price, get_pyth_product_quote_currency},
-    require_lt_100, require_lte_100,
-    utils::byte_length::ByteLength,
+    utils::{
+        byte_length::ByteLength,
+        token_interface::{self, is_token_program, LiquidityMint},
+    },
 };
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{mint_to, transfer, Mint, MintTo, Token, TokenAccount, Transfer},
+    token::{mint_to, Mint, MintTo, Token, TokenAccount},
 };
 use pyth_sdk_solana::state::load_product_account;
 
 /// Initializes a new lending market reserve.
 #[derive(Accounts)]
 pub struct InitReserve<'info> {
-    /// Source liquidity token account
+    /// CHECK: Source liquidity token account, checked by the liquidity token program
     /// $authority can transfer $liquidity_amount.
     #[account(mut)]
-    pub source_liquidity: Box<Account<'info, TokenAccount>>,
-
-    /// Destination collateral token account
-    /// user's collateral token account
-    #[account(
-        init,
-        associated_token::mint = reserve_collateral_mint,
-        associated_token::authority = user_transfer_authority,
-        payer = lending_market_owner,
-    )]
-    pub destination_collateral: Box<Account<'info, TokenAccount>>,
+    pub source_liquidity: UncheckedAccount<'info>,
 
     /// Reserve account
     #[account(
@@ -47,31 +40,24 @@ pub struct InitReserve<'info> {
     )]
     pub reserve: Box<Account<'info, Reserve>>,
 
-    /// Reserve liquidity SPL Token mint
-    pub reserve_liquidity_mint: Box<Account<'info, Mint>>,
-
-    /// Reserve liquidity supply SPL Token account
+    /// CHECK: Reserve liquidity mint of either token program, checked while loading it
     #[account(
-        init,
-        token::mint = reserve_liquidity_mint,
-        token::authority = lending_market_authority,
-        payer = lending_market_owner,
+        constraint = *reserve_liquidity_mint.owner == liquidity_token_program.key() @ LendingError::InvalidTokenProgram,
     )]
-    pub reserve_liquidity_supply: Box<Account<'info, TokenAccount>>,
+    pub reserve_liquidity_mint: UncheckedAccount<'info>,
 
-    /// Reserve liquidity fee receiver
-    #[account(
-        init,
-        token::mint = reserve_liquidity_mint,
-        token::authority = lending_market_authority,
-        payer = lending_market_owner,
-    )]
-    pub reserve_liquidity_fee_receiver: Box<Account<'info, TokenAccount>>,
+    /// Reserve liquidity supply token account, created under the liquidity token program
+    #[account(mut)]
+    pub reserve_liquidity_supply: Signer<'info>,
+
+    /// Reserve liquidity fee receiver, created under the liquidity token program
+    #[account(mut)]
+    pub reserve_liquidity_fee_receiver: Signer<'info>,
 
     /// Reserve collateral SPL Token mint
     #[account(
         init,
-        mint::decimals = reserve_liquidity_mint.decimals,
+        mint::decimals = LiquidityMint::load(&reserve_liquidity_mint)?.decimals,
         mint::authority = lending_market_authority,
         mint::freeze_authority = lending_market_authority,
         payer = lending_market_owner,
@@ -87,20 +73,33 @@ pub struct InitReserve<'info> {
     )]
     pub reserve_collateral_supply: Box<Account<'info, TokenAccount>>,
 
+    /// Destination collateral token account
+    /// user's collateral token account, initialized after the collateral mint
+    #[account(
+        init,
+        associated_token::mint = reserve_collateral_mint,
+        associated_token::authority = user_transfer_authority,
+        payer = lending_market_owner,
+    )]
+    pub destination_collateral: Box<Account<'info, TokenAccount>>,
+
     /// CHECK: Pyth product account
     #[account(
-        constraint = (if cfg!(feature = "anchor-test") { true } else { *pyth_product.owner == lending_market.oracle_program_id }) @ LendingError::InvalidOracleConfig,
+        constraint = *pyth_product.owner == lending_market.oracle_program_id @ LendingError::InvalidOracleConfig,
     )]
     pub pyth_product: UncheckedAccount<'info>,
 
     /// CHECK: Pyth price account
     /// This will be used as the reserve liquidity oracle account
     #[account(
-        constraint = (if cfg!(feature = "anchor-test") { true } else { *pyth_price.owner == lending_market.oracle_program_id }) @ LendingError::InvalidOracleConfig,
+        constraint = *pyth_price.owner == lending_market.oracle_program_id @ LendingError::InvalidOracleConfig,
     )]
     pub pyth_price: UncheckedAccount<'info>,
 
     /// Lending market account
+    #[account(
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
     pub lending_market: Box<Account<'info, LendingMarket>>,
 
     /// CHECK: Derived lending market authority
@@ -123,9 +122,15 @@ pub struct InitReserve<'info> {
     /// system program
     pub system_program: Program<'info, System>,
 
-    /// token program
+    /// token program of the collateral mint
     pub token_program: Program<'info, Token>,
 
+    /// CHECK: token program of the liquidity mint, either the legacy one or Token-2022
+    #[account(
+        constraint = is_token_program(liquidity_token_program.key) @ LendingError::InvalidTokenProgram,
+    )]
+    pub liquidity_token_program: UncheckedAccount<'info>,
+
     /// associated token program
     pub associated_token_program: Program<'info, AssociatedToken>,
 
@@ -134,15 +139,22 @@ pub struct InitReserve<'info> {
 }
 
 impl<'info> InitReserve<'info> {
-    pub fn into_transfer_user_liquidity_to_supply_ctx(
+    pub fn create_liquidity_token_account(
         &self,
-    ) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
-            from: self.source_liquidity.to_account_info(),
-            to: self.reserve_liquidity_supply.to_account_info(),
-            authority: self.user_transfer_authority.to_account_info(),
-        };
-        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+        account: &AccountInfo<'info>,
+        liquidity_mint: &LiquidityMint,
+    ) -> Result<()> {
+        token_interface::create_account(
+            &self.lending_market_owner.to_account_info(),
+            account,
+            &self.reserve_liquidity_mint.to_account_info(),
+            liquidity_mint,
+            &self.lending_market_authority.to_account_info(),
+            &self.liquidity_token_program.to_account_info(),
+            &self.system_program.to_account_info(),
+            &self.rent.to_account_info(),
+            &[],
+        )
     }
 
     pub fn into_mint_to_destination_ctx(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
@@ -165,25 +177,7 @@ pub fn process_init_reserve(
         0_u64,
         LendingError::ReserveNotInitializedWithLiquidity
     );
-    require_lt_100!(config.optimal_utilization_rate, LendingError::InvalidConfig);
-    require_lt_100!(config.loan_to_value_ratio, LendingError::InvalidConfig);
-    require_gt!(
-        config.liquidation_threshold,
-        config.loan_to_value_ratio,
-        LendingError::InvalidConfig
-    );
-    require_lte_100!(config.liquidation_threshold, LendingError::InvalidConfig);
-    require!(
-        config.min_borrow_rate <= config.optimal_borrow_rate
-            && config.optimal_borrow_rate <= config.max_borrow_rate,
-        LendingError::InvalidConfig
-    );
-    require_gt!(WAD, config.fees.borrow_fee_wad, LendingError::InvalidConfig);
-    require_gt!(
-        WAD,
-        config.fees.flash_loan_fee_wad,
-        LendingError::InvalidConfig
-    );
+    config.validate(&ctx.accounts.lending_market)?;
     require_keys_neq!(
         ctx.accounts.reserve_liquidity_supply.key(),
         ctx.accounts.source_liquidity.key(),
@@ -210,6 +204,19 @@ pub fn process_init_reserve(
 
     let market_price = get_pyth_price(&ctx.accounts.pyth_price.to_account_info())?;
 
+    // supplyとfee receiverはmintの拡張に合わせた大きさでliquidityのtoken programに作る
+    let liquidity_mint = LiquidityMint::load(&ctx.accounts.reserve_liquidity_mint)?;
+    ctx.accounts.create_liquidity_token_account(
+        &ctx.accounts.reserve_liquidity_supply.to_account_info(),
+        &liquidity_mint,
+    )?;
+    ctx.accounts.create_liquidity_token_account(
+        &ctx.accounts
+            .reserve_liquidity_fee_receiver
+            .to_account_info(),
+        &liquidity_mint,
+    )?;
+
     let lending_market_pubkey = ctx.accounts.lending_market.key();
     let authority_signer_seeds = &[
         lending_market_pubkey.as_ref(),
@@ -228,7 +235,8 @@ pub fn process_init_reserve(
         lending_market: ctx.accounts.lending_market.key(),
         liquidity: ReserveLiquidity::new(NewReserveLiquidityParams {
             mint_pubkey: ctx.accounts.reserve_liquidity_mint.key(),
-            mint_decimals: ctx.accounts.reserve_liquidity_mint.decimals,
+            mint_decimals: liquidity_mint.decimals,
+            token_program_id: ctx.accounts.liquidity_token_program.key(),
             supply_pubkey: ctx.accounts.reserve_liquidity_supply.key(),
             fee_receiver: ctx.accounts.reserve_liquidity_fee_receiver.key(),
             oracle_pubkey: ctx.accounts.pyth_price.key(),
@@ -242,21 +250,43 @@ pub fn process_init_reserve(
         config,
     });
 
-    // 実際にdepositとmintはしていないが，感覚としてはDB上（account上の変数）の値を変更させて同期させている
-    // depositとmintは下の2つのcpiでやる
-    let collateral_amount = ctx.accounts.reserve.deposit_liquidity(liquidity_amount)?;
-
     // userのliquidityをlending_marketが持っているliquidity_supplyに移動
-    transfer(
-        ctx.accounts.into_transfer_user_liquidity_to_supply_ctx(),
+    // transfer feeが引かれることがあるので実際に届いた分だけをdepositする
+    let received_amount = token_interface::transfer_checked_received(
+        &ctx.accounts.liquidity_token_program,
+        &ctx.accounts.source_liquidity,
+        &ctx.accounts.reserve_liquidity_mint,
+        &ctx.accounts.reserve_liquidity_supply,
+        &ctx.accounts.user_transfer_authority,
         liquidity_amount,
+        liquidity_mint.decimals,
     )?;
+    require_neq!(
+        received_amount,
+        0_u64,
+        LendingError::ReserveNotInitializedWithLiquidity
+    );
+
+    // 実際にmintはしていないが，感覚としてはDB上（account上の変数）の値を変更させて同期させている
+    // mintは下のcpiでやる
+    let collateral_amount = ctx.accounts.reserve.deposit_liquidity(received_amount)?;
 
     // 代わりにcollateralをmintしてあげる
     mint_to(
-        ctx.accounts.into_mint_to_destination_ctx(),
+        ctx.accounts
+            .into_mint_to_destination_ctx()
+            .with_signer(&[authority_signer_seeds]),
         collateral_amount,
     )?;
 
+    emit!(InitReserveEvent {
+        lending_market: ctx.accounts.lending_market.key(),
+        reserve: ctx.accounts.reserve.key(),
+        liquidity_mint: ctx.accounts.reserve_liquidity_mint.key(),
+        liquidity_amount: received_amount,
+        collateral_amount,
+        reserve_state: ReserveSnapshot::new(&ctx.accounts.reserve)?,
+    });
+
     Ok(())
 }
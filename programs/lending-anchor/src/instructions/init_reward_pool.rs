@@ -0,0 +1,117 @@
+use crate::{
+    account_data::{
+        lending_market::LendingMarket,
+        reserve::Reserve,
+        reward_pool::{InitRewardPoolParams, RewardPool, RewardSide},
+    },
+    constants::{LENDING_MARKET_VERSION, RESERVE_VERSION, REWARD_POOL_SEED, REWARD_VAULT_SEED},
+    errors::LendingError,
+    events::InitRewardPoolEvent,
+    utils::{
+        byte_length::ByteLength,
+        token_interface::{self, LiquidityMint},
+    },
+};
+use anchor_lang::prelude::*;
+
+/// Creates the reward pool of a reserve's deposits or borrows by the lending market owner.
+/// It emits nothing until funded.
+#[derive(Accounts)]
+#[instruction(side: RewardSide)]
+pub struct InitRewardPool<'info> {
+    #[account(
+        init,
+        seeds = [REWARD_POOL_SEED, reserve.key().as_ref(), &[side as u8]],
+        bump,
+        payer = owner,
+        space = RewardPool::LEN + 8,
+    )]
+    pub reward_pool: Box<Account<'info, RewardPool>>,
+
+    /// CHECK: created in this instruction
+    #[account(
+        mut,
+        seeds = [REWARD_VAULT_SEED, reward_pool.key().as_ref()],
+        bump,
+    )]
+    pub reward_vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = lending_market @ LendingError::InvalidAccountInput,
+        constraint = reserve.version == RESERVE_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub reserve: Box<Account<'info, Reserve>>,
+
+    /// CHECK: owner checked against the reward token program
+    #[account(owner = reward_token_program.key() @ LendingError::InvalidTokenProgram)]
+    pub reward_mint: UncheckedAccount<'info>,
+
+    #[account(
+        has_one = owner @ LendingError::InvalidMarketOwner,
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+
+    #[account(
+        seeds = [lending_market.key().as_ref()],
+        bump = lending_market.bump_seed,
+    )]
+    /// CHECK:
+    pub lending_market_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: either token program
+    #[account(
+        constraint = token_interface::is_token_program(reward_token_program.key) @ LendingError::InvalidTokenProgram,
+    )]
+    pub reward_token_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+pub fn process_init_reward_pool(ctx: Context<InitRewardPool>, side: RewardSide) -> Result<()> {
+    let reward_pool_key = ctx.accounts.reward_pool.key();
+    let vault_bump_seed = *ctx.bumps.get("reward_vault").unwrap();
+    let vault_signer_seeds: &[&[u8]] = &[
+        REWARD_VAULT_SEED,
+        reward_pool_key.as_ref(),
+        &[vault_bump_seed],
+    ];
+    token_interface::create_account(
+        &ctx.accounts.owner.to_account_info(),
+        &ctx.accounts.reward_vault,
+        &ctx.accounts.reward_mint,
+        &LiquidityMint::load(&ctx.accounts.reward_mint)?,
+        &ctx.accounts.lending_market_authority,
+        &ctx.accounts.reward_token_program,
+        &ctx.accounts.system_program.to_account_info(),
+        &ctx.accounts.rent.to_account_info(),
+        &[vault_signer_seeds],
+    )?;
+
+    ctx.accounts.reward_pool.init(InitRewardPoolParams {
+        current_slot: Clock::get()?.slot,
+        bump_seed: *ctx.bumps.get("reward_pool").unwrap(),
+        lending_market: ctx.accounts.lending_market.key(),
+        reserve: ctx.accounts.reserve.key(),
+        side,
+        reward_mint: ctx.accounts.reward_mint.key(),
+        reward_token_program: ctx.accounts.reward_token_program.key(),
+        vault: ctx.accounts.reward_vault.key(),
+    });
+    ctx.accounts.reserve.set_reward_pool(side, reward_pool_key);
+
+    emit!(InitRewardPoolEvent {
+        lending_market: ctx.accounts.lending_market.key(),
+        reserve: ctx.accounts.reserve.key(),
+        reward_pool: reward_pool_key,
+        side,
+        reward_mint: ctx.accounts.reward_mint.key(),
+    });
+    Ok(())
+}
@@ -0,0 +1,288 @@
+use crate::{
+    account_data::{
+        lending_market::LendingMarket,
+        obligation::Obligation,
+        reserve::{CalculateLiquidationResult, Reserve},
+        reward_pool::RewardSide,
+    },
+    constants::{LENDING_MARKET_VERSION, OBLIGATION_VERSION, RESERVE_VERSION},
+    errors::LendingError,
+    events::{LiquidateObligationEvent, ReserveSnapshot},
+    instructions::repay_obligation_liquidity::remove_isolated_debt,
+    math::rate::Rate,
+    utils::{rewards::update_obligation_rewards, token_interface},
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{transfer, Token, TokenAccount, Transfer};
+
+#[derive(Accounts)]
+pub struct LiquidateObligation<'info> {
+    /// CHECK: Liquidator's liquidity token account to repay from
+    #[account(mut)]
+    pub source_liquidity: UncheckedAccount<'info>,
+
+    /// Liquidator's collateral token account to receive the seized collateral
+    #[account(mut)]
+    pub destination_collateral: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = repay_reserve.lending_market.key() == lending_market.key() @ LendingError::InvalidAccountInput,
+        constraint = repay_reserve.liquidity.supply_pubkey != source_liquidity.key() @ LendingError::InvalidAccountInput,
+        constraint = repay_reserve.liquidity.supply_pubkey == repay_reserve_liquidity_supply.key() @ LendingError::InvalidAccountInput,
+        constraint = !repay_reserve.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ReserveStale,
+        constraint = !repay_reserve.price_guard.guarded @ LendingError::ReservePriceGuarded,
+        constraint = repay_reserve.version == RESERVE_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub repay_reserve: Box<Account<'info, Reserve>>,
+
+    /// CHECK: address checked against the reserve
+    #[account(mut)]
+    pub repay_reserve_liquidity_supply: UncheckedAccount<'info>,
+
+    /// CHECK: address checked against the reserve
+    #[account(address = repay_reserve.liquidity.mint_pubkey @ LendingError::InvalidAccountInput)]
+    pub repay_reserve_liquidity_mint: UncheckedAccount<'info>,
+
+    #[account(
+        constraint = withdraw_reserve.lending_market.key() == lending_market.key() @ LendingError::InvalidAccountInput,
+        constraint = withdraw_reserve.collateral.supply_pubkey != destination_collateral.key() @ LendingError::InvalidAccountInput,
+        constraint = withdraw_reserve.collateral.supply_pubkey == withdraw_reserve_collateral_supply.key() @ LendingError::InvalidAccountInput,
+        constraint = !withdraw_reserve.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ReserveStale,
+        constraint = !withdraw_reserve.price_guard.guarded @ LendingError::ReservePriceGuarded,
+        constraint = withdraw_reserve.version == RESERVE_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub withdraw_reserve: Box<Account<'info, Reserve>>,
+
+    #[account(mut)]
+    pub withdraw_reserve_collateral_supply: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = obligation.lending_market.key() == lending_market.key() @ LendingError::InvalidAccountInput,
+        constraint = !obligation.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ObligationStale,
+        constraint = !obligation.price_guarded @ LendingError::ReservePriceGuarded,
+        constraint = obligation.deposited_value != 0 @ LendingError::ObligationDepositsZero,
+        constraint = obligation.version == OBLIGATION_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub obligation: Box<Account<'info, Obligation>>,
+
+    #[account(
+        constraint = lending_market.token_program_id == token_program.key() @ LendingError::InvalidTokenProgram,
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+
+    /// CHECK:
+    #[account(
+        seeds = [lending_market.key().as_ref()],
+        bump = lending_market.bump_seed,
+    )]
+    pub lending_market_authority: UncheckedAccount<'info>,
+
+    pub user_transfer_authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: token program recorded by the repay reserve
+    #[account(address = repay_reserve.liquidity.token_program_id @ LendingError::InvalidTokenProgram)]
+    pub liquidity_token_program: UncheckedAccount<'info>,
+}
+
+impl<'info> LiquidateObligation<'info> {
+    /// Transfer `repay_amount` into the supply on top of any transfer fee and return how
+    /// much arrived
+    fn transfer_repay_liquidity(&self, repay_amount: u64) -> Result<u64> {
+        token_interface::transfer_checked_with_fee(
+            &self.liquidity_token_program,
+            &self.source_liquidity,
+            &self.repay_reserve_liquidity_mint,
+            &self.repay_reserve_liquidity_supply,
+            &self.user_transfer_authority,
+            repay_amount,
+        )
+    }
+
+    /// Transfer `repay_amount` into the supply with the insurance share of the liquidation
+    /// bonus on top, which is set aside for the repay reserve's insurance fund. Returns how
+    /// much of what arrived repays the borrow and the insurance share.
+    pub(crate) fn transfer_liquidation_repayment(
+        &mut self,
+        repay_amount: u64,
+        liquidation_bonus: Rate,
+    ) -> Result<(u64, u64)> {
+        let insurance_amount = self
+            .repay_reserve
+            .liquidation_insurance_amount(repay_amount, liquidation_bonus)?;
+        let received_amount = self.transfer_repay_liquidity(
+            repay_amount
+                .checked_add(insurance_amount)
+                .ok_or(LendingError::MathOverflow)?,
+        )?;
+        self.repay_reserve.deposit_insurance(insurance_amount)?;
+        Ok((received_amount - insurance_amount, insurance_amount))
+    }
+
+    fn into_transfer_collateral_ctx(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        let cpi_accounts = Transfer {
+            from: self.withdraw_reserve_collateral_supply.to_account_info(),
+            to: self.destination_collateral.to_account_info(),
+            authority: self.lending_market_authority.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+
+    /// Transfer the seized collateral to the liquidator
+    pub(crate) fn transfer_collateral(&self, withdraw_amount: u64) -> Result<()> {
+        let lending_market_key = self.lending_market.key();
+        let authority_signer_seeds: &[&[u8]] = &[
+            lending_market_key.as_ref(),
+            &[self.lending_market.bump_seed],
+        ];
+        transfer(
+            self.into_transfer_collateral_ctx()
+                .with_signer(&[authority_signer_seeds]),
+            withdraw_amount,
+        )
+    }
+
+    /// Close factor of a liquidation repaying a borrow worth `borrow_value`, as a percentage
+    pub(crate) fn close_factor(&self, borrow_value: u128) -> u8 {
+        self.lending_market.liquidation_close_factor(
+            self.withdraw_reserve.key(),
+            self.repay_reserve.key(),
+            borrow_value,
+        )
+    }
+
+    /// Liquidation bonus rate of the withdraw reserve's collateral for the repay reserve's
+    /// borrow, scaled with how unhealthy the obligation is
+    pub(crate) fn liquidation_bonus(&self) -> Result<Rate> {
+        // 組み合わせごとのbonusを優先し，e-modeのcategoryに属するcollateralはcategoryのbonusを使う
+        let emode_category = self.obligation.emode_category;
+        let (min_bonus, max_bonus) = if let Some(pair) = self
+            .lending_market
+            .find_liquidation_pair(self.withdraw_reserve.key(), self.repay_reserve.key())
+        {
+            (pair.min_liquidation_bonus, pair.liquidation_bonus)
+        } else if emode_category != 0
+            && self.withdraw_reserve.config.emode_category == emode_category
+        {
+            let category = self.lending_market.find_emode_category(emode_category)?;
+            (category.min_liquidation_bonus, category.liquidation_bonus)
+        } else {
+            let config = &self.withdraw_reserve.config;
+            (config.min_liquidation_bonus, config.liquidation_bonus)
+        };
+        let liquidation_bonus = self.obligation.liquidation_bonus(min_bonus, max_bonus)?;
+        // TWAPで評価している間はbonusを減らす
+        if self.obligation.price_fallback {
+            Ok(liquidation_bonus.min(Rate::from_percent(
+                self.withdraw_reserve.config.twap_liquidation_bonus,
+            )))
+        } else {
+            Ok(liquidation_bonus)
+        }
+    }
+}
+
+pub fn process_liquidate_obligation(
+    ctx: Context<LiquidateObligation>,
+    liquidity_amount: u64,
+) -> Result<()> {
+    require_neq!(liquidity_amount, 0, LendingError::InvalidAmount);
+
+    let obligation = &ctx.accounts.obligation;
+    require!(obligation.is_unhealthy(), LendingError::ObligationHealthy);
+
+    let (liquidity, liquidity_index) =
+        obligation.find_liquidity_in_borrows(ctx.accounts.repay_reserve.key())?;
+    require_neq!(
+        liquidity.market_value,
+        0,
+        LendingError::ObligationLiquidityEmpty
+    );
+    let (collateral, collateral_index) =
+        obligation.find_collateral_index_in_deposits(ctx.accounts.withdraw_reserve.key())?;
+    require_neq!(
+        collateral.market_value,
+        0,
+        LendingError::ObligationCollateralEmpty
+    );
+
+    let close_factor = ctx.accounts.close_factor(liquidity.market_value);
+    let liquidation_bonus = ctx.accounts.liquidation_bonus()?;
+
+    let CalculateLiquidationResult {
+        settle_amount,
+        repay_amount,
+        withdraw_amount,
+    } = ctx.accounts.withdraw_reserve.calculate_liquidation(
+        liquidity_amount,
+        obligation,
+        liquidity,
+        collateral,
+        close_factor,
+        liquidation_bonus,
+    )?;
+    require_neq!(repay_amount, 0, LendingError::LiquidationTooSmall);
+    require_neq!(withdraw_amount, 0, LendingError::LiquidationTooSmall);
+
+    // transfer feeとinsuranceの分も上乗せして送り，実際に届いた分をsupplyに戻す
+    let (received_amount, insurance_amount) = ctx
+        .accounts
+        .transfer_liquidation_repayment(repay_amount, liquidation_bonus)?;
+    ctx.accounts
+        .repay_reserve
+        .liquidity
+        .repay(received_amount, settle_amount)?;
+    ctx.accounts.repay_reserve.last_update.mark_stale();
+
+    ctx.accounts
+        .obligation
+        .repay(settle_amount, liquidity_index)?;
+
+    remove_isolated_debt(
+        &mut ctx.accounts.obligation,
+        ctx.accounts.repay_reserve.key(),
+        &ctx.accounts.repay_reserve,
+        ctx.remaining_accounts,
+        settle_amount,
+    )?;
+
+    ctx.accounts
+        .obligation
+        .withdraw(withdraw_amount, collateral_index)?;
+    ctx.accounts.obligation.last_update.mark_stale();
+    update_obligation_rewards(
+        &mut ctx.accounts.obligation,
+        ctx.accounts.repay_reserve.key(),
+        &ctx.accounts.repay_reserve,
+        RewardSide::Borrow,
+        ctx.remaining_accounts,
+    )?;
+    update_obligation_rewards(
+        &mut ctx.accounts.obligation,
+        ctx.accounts.withdraw_reserve.key(),
+        &ctx.accounts.withdraw_reserve,
+        RewardSide::Deposit,
+        ctx.remaining_accounts,
+    )?;
+
+    ctx.accounts.transfer_collateral(withdraw_amount)?;
+
+    emit!(LiquidateObligationEvent {
+        lending_market: ctx.accounts.lending_market.key(),
+        obligation: ctx.accounts.obligation.key(),
+        repay_reserve: ctx.accounts.repay_reserve.key(),
+        withdraw_reserve: ctx.accounts.withdraw_reserve.key(),
+        liquidator: ctx.accounts.user_transfer_authority.key(),
+        settle_amount,
+        repay_amount,
+        withdraw_amount,
+        insurance_amount,
+        reserve_state: ReserveSnapshot::new(&ctx.accounts.repay_reserve)?,
+    });
+
+    Ok(())
+}
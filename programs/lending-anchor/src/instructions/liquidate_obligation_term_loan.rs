@@ -0,0 +1,102 @@
+use crate::{
+    account_data::{reserve::CalculateLiquidationResult, reward_pool::RewardSide},
+    errors::LendingError,
+    events::{LiquidateObligationTermLoanEvent, ReserveSnapshot},
+    instructions::{
+        liquidate_obligation::LiquidateObligation, repay_obligation_term_loan::repay_term_loan,
+    },
+    utils::rewards::update_obligation_rewards,
+};
+use anchor_lang::prelude::*;
+
+/// Repays part of a term loan of an unhealthy obligation, or of an overdue term loan of any
+/// obligation, and seizes collateral worth it plus the liquidation bonus.
+/// Takes the same accounts as `liquidate_obligation`.
+pub fn process_liquidate_obligation_term_loan(
+    ctx: Context<LiquidateObligation>,
+    liquidity_amount: u64,
+) -> Result<()> {
+    require_neq!(liquidity_amount, 0, LendingError::InvalidAmount);
+    let current_slot = Clock::get()?.slot;
+
+    let obligation = &ctx.accounts.obligation;
+    let (term_loan, term_loan_index) =
+        obligation.find_term_loan(ctx.accounts.repay_reserve.key())?;
+    // 満期を過ぎたterm loanは健全なobligationでもliquidationできる
+    let overdue = term_loan.is_overdue(current_slot);
+    require!(
+        overdue || obligation.is_unhealthy(),
+        LendingError::ObligationHealthy
+    );
+    require_neq!(
+        term_loan.market_value,
+        0,
+        LendingError::ObligationLiquidityEmpty
+    );
+    let (collateral, collateral_index) =
+        obligation.find_collateral_index_in_deposits(ctx.accounts.withdraw_reserve.key())?;
+    require_neq!(
+        collateral.market_value,
+        0,
+        LendingError::ObligationCollateralEmpty
+    );
+
+    let close_factor = ctx.accounts.close_factor(term_loan.market_value);
+    let liquidation_bonus = ctx.accounts.liquidation_bonus()?;
+    let CalculateLiquidationResult {
+        settle_amount,
+        repay_amount,
+        withdraw_amount,
+    } = ctx.accounts.withdraw_reserve.calculate_liquidation(
+        liquidity_amount,
+        obligation,
+        &term_loan.as_liquidity(),
+        collateral,
+        close_factor,
+        liquidation_bonus,
+    )?;
+    require_neq!(repay_amount, 0, LendingError::LiquidationTooSmall);
+    require_neq!(withdraw_amount, 0, LendingError::LiquidationTooSmall);
+
+    // transfer feeとinsuranceの分も上乗せして送り，実際に届いた分をsupplyに戻す
+    let (received_amount, insurance_amount) = ctx
+        .accounts
+        .transfer_liquidation_repayment(repay_amount, liquidation_bonus)?;
+    repay_term_loan(
+        &mut ctx.accounts.obligation,
+        &mut ctx.accounts.repay_reserve,
+        ctx.remaining_accounts,
+        settle_amount,
+        received_amount,
+        term_loan_index,
+    )?;
+
+    ctx.accounts
+        .obligation
+        .withdraw(withdraw_amount, collateral_index)?;
+    update_obligation_rewards(
+        &mut ctx.accounts.obligation,
+        ctx.accounts.withdraw_reserve.key(),
+        &ctx.accounts.withdraw_reserve,
+        RewardSide::Deposit,
+        ctx.remaining_accounts,
+    )?;
+
+    ctx.accounts.transfer_collateral(withdraw_amount)?;
+
+    emit!(LiquidateObligationTermLoanEvent {
+        lending_market: ctx.accounts.lending_market.key(),
+        obligation: ctx.accounts.obligation.key(),
+        repay_reserve: ctx.accounts.repay_reserve.key(),
+        withdraw_reserve: ctx.accounts.withdraw_reserve.key(),
+        liquidator: ctx.accounts.user_transfer_authority.key(),
+        settle_amount,
+        repay_amount,
+        withdraw_amount,
+        insurance_amount,
+        overdue,
+        reserve_state: ReserveSnapshot::new(&ctx.accounts.repay_reserve)?,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use crate::{
+    account_data::lending_market::LendingMarket,
+    constants::LENDING_MARKET_VERSION,
+    events::MigrateAccountEvent,
+    utils::migration::{load_migrated, write_migrated},
+};
+use anchor_lang::prelude::*;
+
+/// Upgrades a lending market from an older layout, paid by anyone
+#[derive(Accounts)]
+pub struct MigrateLendingMarket<'info> {
+    /// CHECK: Lending market in an old layout, checked while loading it
+    #[account(mut, owner = crate::ID)]
+    pub lending_market: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn process_migrate_lending_market(ctx: Context<MigrateLendingMarket>) -> Result<()> {
+    let account_info = ctx.accounts.lending_market.to_account_info();
+    let (from_version, mut lending_market) = load_migrated::<LendingMarket>(&account_info)?;
+    lending_market.version = LENDING_MARKET_VERSION;
+    write_migrated(
+        &account_info,
+        &lending_market,
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    emit!(MigrateAccountEvent {
+        account: account_info.key(),
+        from_version,
+        to_version: lending_market.version,
+    });
+    Ok(())
+}
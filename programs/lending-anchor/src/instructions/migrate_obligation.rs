@@ -0,0 +1,67 @@
+use crate::{
+    account_data::{obligation::Obligation, reserve::Reserve},
+    constants::OBLIGATION_VERSION,
+    errors::LendingError,
+    events::MigrateAccountEvent,
+    utils::{
+        migration::{load_migrated, write_migrated},
+        remaining_accounts::load_remaining_account,
+    },
+};
+use anchor_lang::prelude::*;
+
+/// Upgrades an obligation from an older layout, paid by anyone.
+/// Remaining accounts are the borrow reserves in the order of the obligation, already
+/// migrated to the current version.
+#[derive(Accounts)]
+pub struct MigrateObligation<'info> {
+    /// CHECK: Obligation in an old layout, checked while loading it
+    #[account(mut, owner = crate::ID)]
+    pub obligation: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn process_migrate_obligation(ctx: Context<MigrateObligation>) -> Result<()> {
+    let account_info = ctx.accounts.obligation.to_account_info();
+    let (from_version, mut obligation) = load_migrated::<Obligation>(&account_info)?;
+    obligation.version = OBLIGATION_VERSION;
+
+    // 以前のborrowは利率を記録していなかったので、reserveの現在の利率から利息を数え始める
+    require_eq!(
+        ctx.remaining_accounts.len(),
+        obligation.borrows.len(),
+        LendingError::InvalidAccountInput
+    );
+    for (liquidity, reserve_info) in obligation.borrows.iter_mut().zip(ctx.remaining_accounts) {
+        require_keys_eq!(
+            reserve_info.key(),
+            liquidity.borrow_reserve,
+            LendingError::InvalidAccountInput
+        );
+        let reserve: Reserve = load_remaining_account(reserve_info)?;
+        require_keys_eq!(
+            reserve.lending_market,
+            obligation.lending_market,
+            LendingError::InvalidAccountInput
+        );
+        liquidity.cumulative_borrow_rate_wads = reserve.liquidity.cumulative_borrow_rate_wads;
+    }
+
+    write_migrated(
+        &account_info,
+        &obligation,
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    emit!(MigrateAccountEvent {
+        account: account_info.key(),
+        from_version,
+        to_version: obligation.version,
+    });
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use crate::{
+    account_data::reserve::Reserve,
+    constants::RESERVE_VERSION,
+    events::MigrateAccountEvent,
+    utils::migration::{load_migrated, write_migrated},
+};
+use anchor_lang::prelude::*;
+
+/// Upgrades a reserve from an older layout, paid by anyone
+#[derive(Accounts)]
+pub struct MigrateReserve<'info> {
+    /// CHECK: Reserve in an old layout, checked while loading it
+    #[account(mut, owner = crate::ID)]
+    pub reserve: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn process_migrate_reserve(ctx: Context<MigrateReserve>) -> Result<()> {
+    let account_info = ctx.accounts.reserve.to_account_info();
+    let (from_version, mut reserve) = load_migrated::<Reserve>(&account_info)?;
+    reserve.version = RESERVE_VERSION;
+    write_migrated(
+        &account_info,
+        &reserve,
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    emit!(MigrateAccountEvent {
+        account: account_info.key(),
+        from_version,
+        to_version: reserve.version,
+    });
+    Ok(())
+}
@@ -1,10 +1,45 @@
 pub mod borrow_obligation_liquidity;
+pub mod borrow_obligation_liquidity_native;
+pub mod borrow_obligation_liquidity_term;
+pub mod claim_referrer_fees;
+pub mod claim_rewards;
 pub mod deposit_obligation_collateral;
 pub mod deposit_reserve_liquidity;
+pub mod deposit_reserve_liquidity_native;
+pub mod fund_insurance_vault;
+pub mod fund_reward_pool;
+pub mod get_obligation_health;
+pub mod init_insurance_vault;
 pub mod init_lending_market;
 pub mod init_obligation;
+pub mod init_referrer;
+pub mod init_referrer_token_state;
 pub mod init_reserve;
+pub mod init_reward_pool;
+pub mod liquidate_obligation;
+pub mod liquidate_obligation_term_loan;
+pub mod migrate_lending_market;
+pub mod migrate_obligation;
+pub mod migrate_reserve;
+pub mod override_reserve_price_guard;
+pub mod quote_borrow;
+pub mod quote_repay;
+pub mod quote_withdraw;
 pub mod redeem_reserve_collateral;
+pub mod refresh_obligation;
+pub mod refresh_reserve;
 pub mod repay_obligation_liquidity;
+pub mod repay_obligation_liquidity_native;
+pub mod repay_obligation_term_loan;
+pub mod set_emode_category;
 pub mod set_lending_market_owner;
+pub mod set_liquidation_config;
+pub mod set_liquidation_pair;
+pub mod set_obligation_delegate;
+pub mod set_obligation_emode;
+pub mod set_obligation_referrer;
+pub mod socialize_bad_debt;
+pub mod sync_rewards;
+pub mod update_reserve_config;
+pub mod withdraw_insurance_vault;
 pub mod withdraw_obligation_collateral;
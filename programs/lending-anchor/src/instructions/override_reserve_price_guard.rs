@@ -0,0 +1,39 @@
+use crate::{
+    account_data::{lending_market::LendingMarket, reserve::Reserve},
+    constants::{LENDING_MARKET_VERSION, RESERVE_VERSION},
+    errors::LendingError,
+    events::OverrideReservePriceGuardEvent,
+};
+use anchor_lang::prelude::*;
+
+/// Accepts the price held back by a reserve's price guard by the lending market owner.
+/// The reserve has to be refreshed again afterwards.
+#[derive(Accounts)]
+pub struct OverrideReservePriceGuard<'info> {
+    #[account(
+        mut,
+        has_one = lending_market @ LendingError::InvalidAccountInput,
+        constraint = reserve.version == RESERVE_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub reserve: Box<Account<'info, Reserve>>,
+
+    #[account(
+        has_one = owner @ LendingError::InvalidMarketOwner,
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+
+    pub owner: Signer<'info>,
+}
+
+pub fn process_override_reserve_price_guard(ctx: Context<OverrideReservePriceGuard>) -> Result<()> {
+    let reserve = &mut ctx.accounts.reserve;
+    let market_price = reserve.override_price_guard(Clock::get()?.slot)?;
+
+    emit!(OverrideReservePriceGuardEvent {
+        lending_market: ctx.accounts.lending_market.key(),
+        reserve: reserve.key(),
+        market_price,
+    });
+    Ok(())
+}
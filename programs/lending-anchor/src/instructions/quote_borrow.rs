@@ -0,0 +1,79 @@
+use crate::{
+    account_data::{
+        lending_market::LendingMarket,
+        obligation::Obligation,
+        reserve::{CalculateBorrowResult, Reserve},
+    },
+    constants::{LENDING_MARKET_VERSION, OBLIGATION_VERSION, RESERVE_VERSION},
+    errors::LendingError,
+    instructions::borrow_obligation_liquidity::calculate_obligation_borrow,
+    utils::remaining_accounts::load_remaining_account,
+    views::BorrowQuote,
+};
+use anchor_lang::{prelude::*, solana_program::program::set_return_data};
+
+/// Returns what `borrow_obligation_liquidity` would borrow without transferring anything
+#[derive(Accounts)]
+pub struct QuoteBorrow<'info> {
+    #[account(
+        constraint = borrow_reserve.lending_market.key() == lending_market.key() @ LendingError::InvalidAccountInput,
+        constraint = !borrow_reserve.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ReserveStale,
+        constraint = borrow_reserve.version == RESERVE_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub borrow_reserve: Box<Account<'info, Reserve>>,
+
+    #[account(
+        constraint = obligation.lending_market.key() == lending_market.key() @ LendingError::InvalidAccountInput,
+        constraint = !obligation.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ObligationStale,
+        constraint = obligation.version == OBLIGATION_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub obligation: Box<Account<'info, Obligation>>,
+
+    #[account(
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+}
+
+pub fn process_quote_borrow(ctx: Context<QuoteBorrow>, liquidity_amount: u64) -> Result<()> {
+    let CalculateBorrowResult {
+        borrow_amount,
+        receive_amount,
+        borrow_fee,
+        host_fee,
+    } = calculate_obligation_borrow(
+        &ctx.accounts.obligation,
+        ctx.accounts.borrow_reserve.key(),
+        &ctx.accounts.borrow_reserve,
+        liquidity_amount,
+    )?;
+
+    // isolated reserveのdebt ceilingも書き込まずに確認する
+    if let Some(isolated_reserve) = ctx.accounts.obligation.isolated_reserve {
+        let account_info = ctx
+            .remaining_accounts
+            .first()
+            .ok_or(LendingError::InvalidAccountInput)?;
+        require_keys_eq!(
+            account_info.key(),
+            isolated_reserve,
+            LendingError::InvalidAccountInput
+        );
+        let mut reserve: Reserve = load_remaining_account(account_info)?;
+        reserve.add_isolated_debt(
+            ctx.accounts.borrow_reserve.key(),
+            &ctx.accounts.borrow_reserve,
+            borrow_amount,
+        )?;
+    }
+
+    let quote = BorrowQuote {
+        borrow_amount,
+        receive_amount,
+        borrow_fee,
+        host_fee,
+    };
+    set_return_data(&quote.try_to_vec()?);
+
+    Ok(())
+}
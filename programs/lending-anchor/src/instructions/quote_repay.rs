@@ -0,0 +1,58 @@
+use crate::{
+    account_data::{
+        lending_market::LendingMarket,
+        obligation::Obligation,
+        reserve::{CalculateRepayResult, Reserve},
+    },
+    constants::{LENDING_MARKET_VERSION, OBLIGATION_VERSION, RESERVE_VERSION},
+    errors::LendingError,
+    instructions::repay_obligation_liquidity::calculate_obligation_repay,
+    views::RepayQuote,
+};
+use anchor_lang::{prelude::*, solana_program::program::set_return_data};
+
+/// Returns what `repay_obligation_liquidity` would repay without transferring anything
+#[derive(Accounts)]
+pub struct QuoteRepay<'info> {
+    #[account(
+        constraint = repay_reserve.lending_market.key() == lending_market.key() @ LendingError::InvalidAccountInput,
+        constraint = !repay_reserve.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ReserveStale,
+        constraint = repay_reserve.version == RESERVE_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub repay_reserve: Box<Account<'info, Reserve>>,
+
+    #[account(
+        constraint = obligation.lending_market.key() == lending_market.key() @ LendingError::InvalidAccountInput,
+        constraint = !obligation.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ObligationStale,
+        constraint = obligation.version == OBLIGATION_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub obligation: Box<Account<'info, Obligation>>,
+
+    #[account(
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+}
+
+pub fn process_quote_repay(ctx: Context<QuoteRepay>, liquidity_amount: u64) -> Result<()> {
+    let (
+        CalculateRepayResult {
+            settle_amount,
+            repay_amount,
+        },
+        _,
+    ) = calculate_obligation_repay(
+        &ctx.accounts.obligation,
+        ctx.accounts.repay_reserve.key(),
+        &ctx.accounts.repay_reserve,
+        liquidity_amount,
+    )?;
+
+    let quote = RepayQuote {
+        settle_amount,
+        repay_amount,
+    };
+    set_return_data(&quote.try_to_vec()?);
+
+    Ok(())
+}
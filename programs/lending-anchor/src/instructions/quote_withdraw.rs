@@ -0,0 +1,53 @@
+use crate::{
+    account_data::{lending_market::LendingMarket, obligation::Obligation, reserve::Reserve},
+    constants::{LENDING_MARKET_VERSION, OBLIGATION_VERSION, RESERVE_VERSION},
+    errors::LendingError,
+    instructions::withdraw_obligation_collateral::calculate_obligation_withdraw,
+    views::WithdrawQuote,
+};
+use anchor_lang::{prelude::*, solana_program::program::set_return_data};
+
+/// Returns what `withdraw_obligation_collateral` would withdraw without transferring anything
+#[derive(Accounts)]
+pub struct QuoteWithdraw<'info> {
+    #[account(
+        constraint = withdraw_reserve.lending_market.key() == lending_market.key() @ LendingError::InvalidAccountInput,
+        constraint = !withdraw_reserve.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ReserveStale,
+        constraint = withdraw_reserve.version == RESERVE_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub withdraw_reserve: Box<Account<'info, Reserve>>,
+
+    #[account(
+        constraint = obligation.lending_market.key() == lending_market.key() @ LendingError::InvalidAccountInput,
+        constraint = !obligation.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ObligationStale,
+        constraint = obligation.version == OBLIGATION_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub obligation: Box<Account<'info, Obligation>>,
+
+    #[account(
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+}
+
+pub fn process_quote_withdraw(ctx: Context<QuoteWithdraw>, collateral_amount: u64) -> Result<()> {
+    let (withdraw_amount, _) = calculate_obligation_withdraw(
+        &ctx.accounts.obligation,
+        ctx.accounts.withdraw_reserve.key(),
+        &ctx.accounts.withdraw_reserve,
+        &ctx.accounts.lending_market,
+        collateral_amount,
+    )?;
+
+    let quote = WithdrawQuote {
+        collateral_amount: withdraw_amount,
+        liquidity_amount: ctx
+            .accounts
+            .withdraw_reserve
+            .collateral_exchange_rate()?
+            .collateral_to_liquidity(withdraw_amount)?,
+    };
+    set_return_data(&quote.try_to_vec()?);
+
+    Ok(())
+}
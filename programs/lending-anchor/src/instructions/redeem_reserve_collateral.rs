@@ -1,28 +1,43 @@
 use crate::{
     account_data::{lending_market::LendingMarket, reserve::Reserve},
+    constants::{LENDING_MARKET_VERSION, RESERVE_VERSION},
     errors::LendingError,
+    events::{RedeemReserveCollateralEvent, ReserveSnapshot},
+    utils::token_interface,
 };
 use anchor_lang::prelude::*;
-use anchor_spl::token::{burn, transfer, Burn, Mint, Token, TokenAccount, Transfer};
+use anchor_spl::token::{burn, Burn, Mint, Token, TokenAccount};
 
 #[derive(Accounts)]
 pub struct RedeemReserveCollateral<'info> {
+    #[account(mut)]
     pub source_collateral: Box<Account<'info, TokenAccount>>,
-    pub destination_liquidity: Box<Account<'info, TokenAccount>>,
+    /// CHECK: checked by the liquidity token program
+    #[account(mut)]
+    pub destination_liquidity: UncheckedAccount<'info>,
 
     #[account(
+        mut,
         constraint = !reserve.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ReserveStale,
         constraint = reserve.collateral.mint_pubkey == reserve_collateral_mint.key() @ LendingError::InvalidAccountInput,
         constraint = reserve.collateral.supply_pubkey != source_collateral.key() @ LendingError::InvalidAccountInput,
         constraint = reserve.liquidity.supply_pubkey == reserve_liquidity_supply.key() @ LendingError::InvalidAccountInput,
         constraint = reserve.liquidity.supply_pubkey != destination_liquidity.key() @ LendingError::InvalidAccountInput,
+        constraint = reserve.version == RESERVE_VERSION @ LendingError::OutdatedAccountVersion,
     )]
     pub reserve: Box<Account<'info, Reserve>>,
+    #[account(mut)]
     pub reserve_collateral_mint: Box<Account<'info, Mint>>,
-    pub reserve_liquidity_supply: Box<Account<'info, TokenAccount>>,
+    /// CHECK: address checked against the reserve
+    #[account(mut)]
+    pub reserve_liquidity_supply: UncheckedAccount<'info>,
+    /// CHECK: address checked against the reserve
+    #[account(address = reserve.liquidity.mint_pubkey @ LendingError::InvalidAccountInput)]
+    pub reserve_liquidity_mint: UncheckedAccount<'info>,
 
     #[account(
         address = reserve.lending_market,
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
     )]
     pub lending_market: Box<Account<'info, LendingMarket>>,
 
@@ -36,6 +51,9 @@ pub struct RedeemReserveCollateral<'info> {
     pub user_transfer_authority: UncheckedAccount<'info>,
 
     pub token_program: Program<'info, Token>,
+    /// CHECK: token program recorded by the reserve
+    #[account(address = reserve.liquidity.token_program_id @ LendingError::InvalidTokenProgram)]
+    pub liquidity_token_program: UncheckedAccount<'info>,
 }
 
 impl<'info> RedeemReserveCollateral<'info> {
@@ -48,15 +66,21 @@ impl<'info> RedeemReserveCollateral<'info> {
         CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
     }
 
-    pub fn into_transfer_user_liquidity_ctx(
+    pub fn transfer_user_liquidity(
         &self,
-    ) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
-            from: self.reserve_liquidity_supply.to_account_info(),
-            to: self.destination_liquidity.to_account_info(),
-            authority: self.user_transfer_authority.to_account_info(),
-        };
-        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+        liquidity_amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        token_interface::transfer_checked(
+            &self.liquidity_token_program,
+            &self.reserve_liquidity_supply,
+            &self.reserve_liquidity_mint,
+            &self.destination_liquidity,
+            &self.lending_market_authority,
+            liquidity_amount,
+            self.reserve.liquidity.mint_decimals,
+            signer_seeds,
+        )
     }
 }
 
@@ -72,10 +96,21 @@ pub fn process_redeem_reserve_collateral(
         ctx.accounts.into_burn_user_collateral_ctx(),
         collateral_amount,
     )?;
-    transfer(
-        ctx.accounts.into_transfer_user_liquidity_ctx(),
+    let lending_market_key = ctx.accounts.lending_market.key();
+    let authority_signer_seeds: &[&[u8]] = &[
+        lending_market_key.as_ref(),
+        &[ctx.accounts.lending_market.bump_seed],
+    ];
+    ctx.accounts
+        .transfer_user_liquidity(liquidity_amount, &[authority_signer_seeds])?;
+
+    emit!(RedeemReserveCollateralEvent {
+        lending_market: ctx.accounts.lending_market.key(),
+        reserve: ctx.accounts.reserve.key(),
+        collateral_amount,
         liquidity_amount,
-    )?;
+        reserve_state: ReserveSnapshot::new(&ctx.accounts.reserve)?,
+    });
 
     Ok(())
 }
@@ -0,0 +1,141 @@
+use crate::{
+    account_data::{lending_market::LendingMarket, obligation::Obligation, reserve::Reserve},
+    constants::{LENDING_MARKET_VERSION, OBLIGATION_VERSION},
+    errors::LendingError,
+    utils::remaining_accounts::load_remaining_account,
+};
+use anchor_lang::prelude::*;
+
+/// Recalculates deposit and borrow values of an obligation.
+/// Remaining accounts are the deposit reserves, the borrow reserves and the term loan
+/// reserves, in the order of the obligation. When the obligation is in an e-mode category
+/// with a shared oracle, they are followed by a refreshed reserve of the market priced by
+/// that oracle, whose guarded price values every position.
+#[derive(Accounts)]
+pub struct RefreshObligation<'info> {
+    #[account(
+        mut,
+        constraint = obligation.lending_market == lending_market.key() @ LendingError::InvalidAccountInput,
+        constraint = obligation.version == OBLIGATION_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub obligation: Box<Account<'info, Obligation>>,
+
+    #[account(
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+}
+
+pub fn process_refresh_obligation(ctx: Context<RefreshObligation>) -> Result<()> {
+    let current_slot = Clock::get()?.slot;
+    let lending_market = &ctx.accounts.lending_market;
+    let obligation = &mut ctx.accounts.obligation;
+
+    let reserve_keys = obligation
+        .deposits
+        .iter()
+        .map(|collateral| collateral.deposit_reserve)
+        .chain(
+            obligation
+                .borrows
+                .iter()
+                .map(|liquidity| liquidity.borrow_reserve),
+        )
+        .chain(
+            obligation
+                .term_loans
+                .iter()
+                .map(|term_loan| term_loan.borrow_reserve),
+        )
+        .collect::<Vec<_>>();
+    require_gte!(
+        ctx.remaining_accounts.len(),
+        reserve_keys.len(),
+        LendingError::InvalidAccountInput
+    );
+    let (reserve_infos, price_reserve_infos) = ctx.remaining_accounts.split_at(reserve_keys.len());
+
+    let mut reserves = Vec::with_capacity(reserve_keys.len());
+    for (reserve_key, reserve_info) in reserve_keys.iter().zip(reserve_infos) {
+        require_keys_eq!(
+            reserve_info.key(),
+            *reserve_key,
+            LendingError::InvalidAccountInput
+        );
+        let reserve: Reserve = load_remaining_account(reserve_info)?;
+        require_keys_eq!(
+            reserve.lending_market,
+            lending_market.key(),
+            LendingError::InvalidAccountInput
+        );
+        require!(
+            !reserve.last_update.is_stale(current_slot)?,
+            LendingError::ReserveStale
+        );
+        reserves.push(reserve);
+    }
+
+    // 全てのdepositとborrowが選択したcategoryに属する場合のみe-modeを適用する
+    let emode = if obligation.emode_category != 0
+        && reserves
+            .iter()
+            .all(|reserve| reserve.config.emode_category == obligation.emode_category)
+    {
+        Some(*lending_market.find_emode_category(obligation.emode_category)?)
+    } else {
+        None
+    };
+    // 共有oracleの価格はそのoracleを使うreserveの価格で読み，price guardとTWAPを通す
+    let emode_price_reserve = match emode.and_then(|category| category.oracle) {
+        Some(oracle) => {
+            let reserve_info = price_reserve_infos
+                .first()
+                .ok_or(LendingError::InvalidAccountInput)?;
+            let reserve: Reserve = load_remaining_account(reserve_info)?;
+            require_keys_eq!(
+                reserve.lending_market,
+                lending_market.key(),
+                LendingError::InvalidAccountInput
+            );
+            require_keys_eq!(
+                reserve.liquidity.oracle_pubkey,
+                oracle,
+                LendingError::InvalidOracleConfig
+            );
+            require!(
+                !reserve.last_update.is_stale(current_slot)?,
+                LendingError::ReserveStale
+            );
+            Some(reserve)
+        }
+        None => None,
+    };
+    let emode_price = emode_price_reserve
+        .as_ref()
+        .map(|reserve| reserve.liquidity.market_price);
+
+    let (deposit_reserves, reserves_borrowed) = reserves.split_at(obligation.deposits.len());
+    let (borrow_reserves, term_loan_reserves) =
+        reserves_borrowed.split_at(obligation.borrows.len());
+    obligation.refresh(
+        deposit_reserves,
+        borrow_reserves,
+        term_loan_reserves,
+        emode.as_ref(),
+        emode_price,
+        current_slot,
+    )?;
+    // 価格が保留されているreserveがあればborrowとliquidationを止める
+    obligation.price_guarded = reserves
+        .iter()
+        .chain(emode_price_reserve.as_ref())
+        .any(|reserve| reserve.price_guard.guarded);
+    // TWAPで評価したreserveがあればリスクを増やす操作を止める
+    obligation.price_fallback = reserves
+        .iter()
+        .chain(emode_price_reserve.as_ref())
+        .any(|reserve| reserve.twap.fallback);
+    obligation.last_update.update_slot(current_slot);
+
+    Ok(())
+}
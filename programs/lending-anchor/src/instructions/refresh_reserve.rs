@@ -0,0 +1,67 @@
+use crate::{
+    account_data::reserve::Reserve,
+    constants::RESERVE_VERSION,
+    errors::LendingError,
+    events::{ReservePriceFallbackEvent, ReservePriceGuardEvent},
+    pyth::get_pyth_price,
+};
+use anchor_lang::prelude::*;
+
+/// Accrues interest and updates the market price of a reserve.
+/// A price which moved too far is held back by the reserve's price guard.
+/// While the oracle is unavailable the market price falls back to the reserve's TWAP for a
+/// bounded number of slots.
+#[derive(Accounts)]
+pub struct RefreshReserve<'info> {
+    #[account(
+        mut,
+        constraint = reserve.version == RESERVE_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub reserve: Box<Account<'info, Reserve>>,
+
+    /// CHECK: Reserve liquidity oracle account
+    #[account(
+        constraint = reserve_liquidity_oracle.key() == reserve.liquidity.oracle_pubkey @ LendingError::InvalidOracleConfig,
+    )]
+    pub reserve_liquidity_oracle: UncheckedAccount<'info>,
+}
+
+pub fn process_refresh_reserve(ctx: Context<RefreshReserve>) -> Result<()> {
+    let current_slot = Clock::get()?.slot;
+    let reserve = &mut ctx.accounts.reserve;
+
+    let was_fallback = reserve.twap.fallback;
+    match get_pyth_price(&ctx.accounts.reserve_liquidity_oracle.to_account_info()) {
+        Ok(oracle_price) => {
+            let was_guarded = reserve.price_guard.guarded;
+            reserve.update_market_price(oracle_price, current_slot);
+
+            if reserve.price_guard.guarded != was_guarded {
+                emit!(ReservePriceGuardEvent {
+                    reserve: reserve.key(),
+                    market_price: reserve.liquidity.market_price,
+                    oracle_price,
+                    guarded: reserve.price_guard.guarded,
+                });
+            }
+        }
+        Err(error) => {
+            // TWAPが古すぎればreserveごと止める
+            if !reserve.fall_back_to_twap(current_slot) {
+                return Err(error);
+            }
+        }
+    }
+    reserve.accrue_interest(current_slot)?;
+    reserve.last_update.update_slot(current_slot);
+
+    if reserve.twap.fallback != was_fallback {
+        emit!(ReservePriceFallbackEvent {
+            reserve: reserve.key(),
+            market_price: reserve.liquidity.market_price,
+            fallback: reserve.twap.fallback,
+        });
+    }
+
+    Ok(())
+}
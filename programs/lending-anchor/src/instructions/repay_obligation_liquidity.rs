@@ -3,89 +3,196 @@ use crate::{
         lending_market::LendingMarket,
         obligation::Obligation,
         reserve::{CalculateRepayResult, Reserve},
+        reward_pool::RewardSide,
     },
+    constants::{LENDING_MARKET_VERSION, OBLIGATION_VERSION, RESERVE_VERSION},
     errors::LendingError,
+    events::{RepayObligationLiquidityEvent, ReserveSnapshot},
+    utils::{
+        remaining_accounts::update_remaining_account, rewards::update_obligation_rewards,
+        token_interface,
+    },
 };
 use anchor_lang::prelude::*;
-use anchor_spl::token::{transfer, Token, TokenAccount, Transfer};
 
 #[derive(Accounts)]
 pub struct RepayObligationLiquidity<'info> {
-    pub source_liquidity: Box<Account<'info, TokenAccount>>,
-    pub destination_liquidity: Box<Account<'info, TokenAccount>>,
+    /// CHECK: checked by the liquidity token program
+    #[account(mut)]
+    pub source_liquidity: UncheckedAccount<'info>,
+    /// CHECK: address checked against the reserve
+    #[account(mut)]
+    pub destination_liquidity: UncheckedAccount<'info>,
 
     #[account(
+        mut,
         constraint = repay_reserve.lending_market.key() == lending_market.key() @ LendingError::InvalidAccountInput,
         constraint = repay_reserve.liquidity.supply_pubkey != source_liquidity.key() @ LendingError::InvalidAccountInput,
         constraint = repay_reserve.liquidity.supply_pubkey == destination_liquidity.key() @ LendingError::InvalidAccountInput,
         constraint = !repay_reserve.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ReserveStale,
+        constraint = repay_reserve.version == RESERVE_VERSION @ LendingError::OutdatedAccountVersion,
     )]
     pub repay_reserve: Box<Account<'info, Reserve>>,
 
+    /// CHECK: address checked against the reserve
+    #[account(address = repay_reserve.liquidity.mint_pubkey @ LendingError::InvalidAccountInput)]
+    pub repay_reserve_liquidity_mint: UncheckedAccount<'info>,
+
     #[account(
+        mut,
         constraint = obligation.lending_market.key() == lending_market.key() @ LendingError::InvalidAccountInput,
         constraint = !obligation.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ObligationStale,
+        constraint = obligation.version == OBLIGATION_VERSION @ LendingError::OutdatedAccountVersion,
     )]
     pub obligation: Box<Account<'info, Obligation>>,
 
     #[account(
-        constraint = lending_market.token_program_id == token_program.key() @ LendingError::InvalidTokenProgram,
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
     )]
     pub lending_market: Box<Account<'info, LendingMarket>>,
 
     /// CHECK:
     pub user_transfer_authority: UncheckedAccount<'info>,
-    pub token_program: Program<'info, Token>,
+    /// CHECK: token program recorded by the reserve
+    #[account(address = repay_reserve.liquidity.token_program_id @ LendingError::InvalidTokenProgram)]
+    pub liquidity_token_program: UncheckedAccount<'info>,
 }
 
 impl<'info> RepayObligationLiquidity<'info> {
-    fn into_transfer_liquidity_ctx(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
-            from: self.source_liquidity.to_account_info(),
-            to: self.destination_liquidity.to_account_info(),
-            authority: self.user_transfer_authority.to_account_info(),
-        };
-        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    /// Transfer `repay_amount` into the supply on top of any transfer fee and return how
+    /// much arrived
+    pub(crate) fn transfer_repay_liquidity(&self, repay_amount: u64) -> Result<u64> {
+        token_interface::transfer_checked_with_fee(
+            &self.liquidity_token_program,
+            &self.source_liquidity,
+            &self.repay_reserve_liquidity_mint,
+            &self.destination_liquidity,
+            &self.user_transfer_authority,
+            repay_amount,
+        )
     }
 }
 
-pub fn process_repay_obligation_liquidity(
-    ctx: Context<RepayObligationLiquidity>,
+/// Repay amounts for `liquidity_amount` of `repay_reserve`, with the borrow's index in the
+/// obligation's borrows. `u64::MAX` repays the whole borrow.
+pub fn calculate_obligation_repay(
+    obligation: &Obligation,
+    repay_reserve_key: Pubkey,
+    repay_reserve: &Reserve,
     liquidity_amount: u64,
-) -> Result<()> {
+) -> Result<(CalculateRepayResult, usize)> {
     require_neq!(liquidity_amount, 0, LendingError::InvalidAmount);
 
-    let (liquidity, liquidity_index) = ctx
-        .accounts
-        .obligation
-        .find_liquidity_in_borrows(ctx.accounts.repay_reserve.key())?;
+    let (liquidity, liquidity_index) = obligation.find_liquidity_in_borrows(repay_reserve_key)?;
     require_neq!(
-        liquidity.borrowed_amount_wads,
+        liquidity.borrowed_amount,
         0,
         LendingError::ObligationLiquidityEmpty
     );
 
-    let CalculateRepayResult {
-        settle_amount,
-        repay_amount,
-    } = ctx
-        .accounts
-        .repay_reserve
-        .calculate_repay(liquidity_amount, liquidity.borrowed_amount_wads)?;
-    require_neq!(repay_amount, 0, LendingError::RepayTooSmall);
-
-    ctx.accounts
-        .repay_reserve
+    let result = repay_reserve.calculate_repay(liquidity_amount, liquidity.borrowed_amount)?;
+    require_neq!(result.repay_amount, 0, LendingError::RepayTooSmall);
+
+    Ok((result, liquidity_index))
+}
+
+/// Remove `settle_amount` repaid to `repay_reserve` from the isolated reserve's debt.
+/// The isolated reserve is the first of `remaining_accounts`.
+pub fn remove_isolated_debt(
+    obligation: &mut Obligation,
+    repay_reserve_key: Pubkey,
+    repay_reserve: &Reserve,
+    remaining_accounts: &[AccountInfo],
+    settle_amount: u128,
+) -> Result<()> {
+    // isolated collateralに対する借入を減らす
+    if let Some(isolated_reserve) = obligation.isolated_reserve {
+        let removed_amount = obligation.remove_isolated_debt(repay_reserve_key, settle_amount);
+        update_remaining_account(
+            remaining_accounts.first(),
+            isolated_reserve,
+            |reserve: &mut Reserve| {
+                reserve.remove_isolated_debt(repay_reserve_key, repay_reserve, removed_amount)
+            },
+        )?;
+    }
+    Ok(())
+}
+
+/// Settle `settle_amount` of the borrow at `liquidity_index` with `received_amount` that
+/// reached the supply. The isolated reserve is the first of `remaining_accounts`.
+pub fn repay_obligation(
+    obligation: &mut Obligation,
+    repay_reserve_key: Pubkey,
+    repay_reserve: &mut Reserve,
+    remaining_accounts: &[AccountInfo],
+    settle_amount: u128,
+    received_amount: u64,
+    liquidity_index: usize,
+) -> Result<()> {
+    repay_reserve
         .liquidity
-        .repay(repay_amount, settle_amount)?;
-    ctx.accounts.repay_reserve.last_update.mark_stale();
+        .repay(received_amount, settle_amount)?;
+    repay_reserve.last_update.mark_stale();
+
+    obligation.repay(settle_amount, liquidity_index)?;
+    obligation.last_update.mark_stale();
+
+    remove_isolated_debt(
+        obligation,
+        repay_reserve_key,
+        repay_reserve,
+        remaining_accounts,
+        settle_amount,
+    )?;
+
+    update_obligation_rewards(
+        obligation,
+        repay_reserve_key,
+        repay_reserve,
+        RewardSide::Borrow,
+        remaining_accounts,
+    )
+}
+
+pub fn process_repay_obligation_liquidity(
+    ctx: Context<RepayObligationLiquidity>,
+    liquidity_amount: u64,
+) -> Result<()> {
+    let (
+        CalculateRepayResult {
+            settle_amount,
+            repay_amount,
+        },
+        liquidity_index,
+    ) = calculate_obligation_repay(
+        &ctx.accounts.obligation,
+        ctx.accounts.repay_reserve.key(),
+        &ctx.accounts.repay_reserve,
+        liquidity_amount,
+    )?;
 
-    ctx.accounts
-        .obligation
-        .repay(settle_amount, liquidity_index)?;
-    ctx.accounts.obligation.last_update.mark_stale();
+    // transfer feeの分も上乗せして送り，実際に届いた分をsupplyに戻す
+    let received_amount = ctx.accounts.transfer_repay_liquidity(repay_amount)?;
+    let repay_reserve_key = ctx.accounts.repay_reserve.key();
+    repay_obligation(
+        &mut ctx.accounts.obligation,
+        repay_reserve_key,
+        &mut ctx.accounts.repay_reserve,
+        ctx.remaining_accounts,
+        settle_amount,
+        received_amount,
+        liquidity_index,
+    )?;
 
-    transfer(ctx.accounts.into_transfer_liquidity_ctx(), repay_amount)?;
+    emit!(RepayObligationLiquidityEvent {
+        lending_market: ctx.accounts.lending_market.key(),
+        obligation: ctx.accounts.obligation.key(),
+        reserve: ctx.accounts.repay_reserve.key(),
+        settle_amount,
+        repay_amount,
+        reserve_state: ReserveSnapshot::new(&ctx.accounts.repay_reserve)?,
+    });
 
     Ok(())
 }
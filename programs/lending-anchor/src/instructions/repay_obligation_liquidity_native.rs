@@ -0,0 +1,99 @@
+use crate::{
+    account_data::{
+        lending_market::LendingMarket,
+        obligation::Obligation,
+        reserve::{CalculateRepayResult, Reserve},
+    },
+    constants::{LENDING_MARKET_VERSION, OBLIGATION_VERSION, RESERVE_VERSION},
+    errors::LendingError,
+    events::{RepayObligationLiquidityEvent, ReserveSnapshot},
+    instructions::repay_obligation_liquidity::{calculate_obligation_repay, repay_obligation},
+    utils::token_interface,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{spl_token::native_mint, Token};
+
+/// Repays a native SOL borrow with lamports, wrapping them into the liquidity supply
+#[derive(Accounts)]
+pub struct RepayObligationLiquidityNative<'info> {
+    /// Wallet the lamports are wrapped from
+    #[account(mut)]
+    pub source_wallet: Signer<'info>,
+    /// CHECK: address checked against the reserve
+    #[account(mut)]
+    pub destination_liquidity: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = repay_reserve.lending_market.key() == lending_market.key() @ LendingError::InvalidAccountInput,
+        constraint = repay_reserve.liquidity.mint_pubkey == native_mint::ID @ LendingError::ReserveNotNative,
+        constraint = repay_reserve.liquidity.supply_pubkey == destination_liquidity.key() @ LendingError::InvalidAccountInput,
+        constraint = !repay_reserve.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ReserveStale,
+        constraint = repay_reserve.version == RESERVE_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub repay_reserve: Box<Account<'info, Reserve>>,
+
+    #[account(
+        mut,
+        constraint = obligation.lending_market.key() == lending_market.key() @ LendingError::InvalidAccountInput,
+        constraint = !obligation.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ObligationStale,
+        constraint = obligation.version == OBLIGATION_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub obligation: Box<Account<'info, Obligation>>,
+
+    #[account(
+        constraint = lending_market.token_program_id == token_program.key() @ LendingError::InvalidTokenProgram,
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn process_repay_obligation_liquidity_native(
+    ctx: Context<RepayObligationLiquidityNative>,
+    liquidity_amount: u64,
+) -> Result<()> {
+    let (
+        CalculateRepayResult {
+            settle_amount,
+            repay_amount,
+        },
+        liquidity_index,
+    ) = calculate_obligation_repay(
+        &ctx.accounts.obligation,
+        ctx.accounts.repay_reserve.key(),
+        &ctx.accounts.repay_reserve,
+        liquidity_amount,
+    )?;
+
+    token_interface::wrap_lamports(
+        &ctx.accounts.source_wallet,
+        &ctx.accounts.destination_liquidity,
+        repay_amount,
+        &ctx.accounts.system_program,
+        &ctx.accounts.token_program,
+    )?;
+    let repay_reserve_key = ctx.accounts.repay_reserve.key();
+    repay_obligation(
+        &mut ctx.accounts.obligation,
+        repay_reserve_key,
+        &mut ctx.accounts.repay_reserve,
+        ctx.remaining_accounts,
+        settle_amount,
+        repay_amount,
+        liquidity_index,
+    )?;
+
+    emit!(RepayObligationLiquidityEvent {
+        lending_market: ctx.accounts.lending_market.key(),
+        obligation: ctx.accounts.obligation.key(),
+        reserve: ctx.accounts.repay_reserve.key(),
+        settle_amount,
+        repay_amount,
+        reserve_state: ReserveSnapshot::new(&ctx.accounts.repay_reserve)?,
+    });
+
+    Ok(())
+}
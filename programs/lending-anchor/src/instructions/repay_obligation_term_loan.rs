@@ -0,0 +1,85 @@
+use crate::{
+    account_data::{
+        obligation::Obligation,
+        reserve::{CalculateRepayResult, Reserve},
+    },
+    errors::LendingError,
+    events::{RepayObligationTermLoanEvent, ReserveSnapshot},
+    instructions::repay_obligation_liquidity::{remove_isolated_debt, RepayObligationLiquidity},
+};
+use anchor_lang::prelude::*;
+
+/// Settle `settle_amount` of the term loan at `term_loan_index` with `received_amount` that
+/// reached the supply, returning the principal repaid. The isolated reserve is the first of
+/// `remaining_accounts`.
+pub fn repay_term_loan(
+    obligation: &mut Obligation,
+    repay_reserve: &mut Reserve,
+    remaining_accounts: &[AccountInfo],
+    settle_amount: u128,
+    received_amount: u64,
+    term_loan_index: usize,
+) -> Result<u64> {
+    let repay_reserve_key = obligation.term_loans[term_loan_index].borrow_reserve;
+    let principal_amount = obligation.repay_term_loan(settle_amount, term_loan_index)?;
+    obligation.last_update.mark_stale();
+
+    // 元本を超えて返済された分は利息として預金者のものになる
+    repay_reserve.repay_term(received_amount, principal_amount)?;
+    repay_reserve.last_update.mark_stale();
+
+    remove_isolated_debt(
+        obligation,
+        repay_reserve_key,
+        repay_reserve,
+        remaining_accounts,
+        settle_amount,
+    )?;
+    Ok(principal_amount)
+}
+
+/// Repays `liquidity_amount` of the obligation's term loan from the reserve, before or after
+/// maturity. `u64::MAX` repays the whole loan.
+/// Takes the same accounts as `repay_obligation_liquidity`.
+pub fn process_repay_obligation_term_loan(
+    ctx: Context<RepayObligationLiquidity>,
+    liquidity_amount: u64,
+) -> Result<()> {
+    require_neq!(liquidity_amount, 0, LendingError::InvalidAmount);
+
+    let (term_loan, term_loan_index) = ctx
+        .accounts
+        .obligation
+        .find_term_loan(ctx.accounts.repay_reserve.key())?;
+    let CalculateRepayResult {
+        settle_amount,
+        repay_amount,
+    } = ctx
+        .accounts
+        .repay_reserve
+        .calculate_repay(liquidity_amount, term_loan.borrowed_amount)?;
+    require_neq!(repay_amount, 0, LendingError::RepayTooSmall);
+
+    // transfer feeの分も上乗せして送り，実際に届いた分をsupplyに戻す
+    let received_amount = ctx.accounts.transfer_repay_liquidity(repay_amount)?;
+    let principal_amount = repay_term_loan(
+        &mut ctx.accounts.obligation,
+        &mut ctx.accounts.repay_reserve,
+        ctx.remaining_accounts,
+        settle_amount,
+        received_amount,
+        term_loan_index,
+    )?;
+
+    emit!(RepayObligationTermLoanEvent {
+        lending_market: ctx.accounts.lending_market.key(),
+        obligation: ctx.accounts.obligation.key(),
+        reserve: ctx.accounts.repay_reserve.key(),
+        settle_amount,
+        repay_amount,
+        principal_amount,
+        reserve_state: ReserveSnapshot::new(&ctx.accounts.repay_reserve)?,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use crate::{
+    account_data::{emode_category::EModeCategory, lending_market::*},
+    constants::LENDING_MARKET_VERSION,
+    errors::LendingError,
+    require_lt_100, require_lte_100,
+};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetEModeCategory<'info> {
+    #[account(
+        mut,
+        has_one = owner @ LendingError::InvalidMarketOwner,
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+    pub owner: Signer<'info>,
+}
+
+pub fn process_set_emode_category(
+    ctx: Context<SetEModeCategory>,
+    category: EModeCategory,
+) -> Result<()> {
+    require_neq!(category.id, 0, LendingError::InvalidEModeCategory);
+    require_lt_100!(category.loan_to_value_ratio, LendingError::InvalidConfig);
+    require_gt!(
+        category.liquidation_threshold,
+        category.loan_to_value_ratio,
+        LendingError::InvalidConfig
+    );
+    require_lte_100!(category.liquidation_threshold, LendingError::InvalidConfig);
+    require_lt_100!(category.liquidation_bonus, LendingError::InvalidConfig);
+    require_gte!(
+        category.liquidation_bonus,
+        category.min_liquidation_bonus,
+        LendingError::InvalidConfig
+    );
+
+    ctx.accounts.lending_market.set_emode_category(category)
+}
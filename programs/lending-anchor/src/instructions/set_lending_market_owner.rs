@@ -1,9 +1,16 @@
-use crate::{account_data::lending_market::*, errors::LendingError::*};
+use crate::{
+    account_data::lending_market::*, constants::LENDING_MARKET_VERSION, errors::LendingError::*,
+    events::SetLendingMarketOwnerEvent,
+};
 use anchor_lang::prelude::*;
 
 #[derive(Accounts)]
 pub struct SetLendingMarketOwner<'info> {
-    #[account(mut, has_one = owner @ InvalidMarketOwner)]
+    #[account(
+        mut,
+        has_one = owner @ InvalidMarketOwner,
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ OutdatedAccountVersion,
+    )]
     pub lending_market: Account<'info, LendingMarket>,
     pub owner: Signer<'info>,
 }
@@ -12,6 +19,13 @@ pub fn process_set_lending_market_owner(
     ctx: Context<SetLendingMarketOwner>,
     new_owner: Pubkey,
 ) -> Result<()> {
+    let old_owner = ctx.accounts.lending_market.owner;
     ctx.accounts.lending_market.owner = new_owner;
+
+    emit!(SetLendingMarketOwnerEvent {
+        lending_market: ctx.accounts.lending_market.key(),
+        old_owner,
+        new_owner,
+    });
     Ok(())
 }
@@ -0,0 +1,30 @@
+use crate::{
+    account_data::lending_market::*, constants::LENDING_MARKET_VERSION, errors::LendingError,
+    require_lte_100,
+};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetLiquidationConfig<'info> {
+    #[account(
+        mut,
+        has_one = owner @ LendingError::InvalidMarketOwner,
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+    pub owner: Signer<'info>,
+}
+
+pub fn process_set_liquidation_config(
+    ctx: Context<SetLiquidationConfig>,
+    close_factor: u8,
+    full_close_value: u128,
+) -> Result<()> {
+    require_neq!(close_factor, 0, LendingError::InvalidConfig);
+    require_lte_100!(close_factor, LendingError::InvalidConfig);
+
+    let lending_market = &mut ctx.accounts.lending_market;
+    lending_market.liquidation_close_factor = close_factor;
+    lending_market.liquidation_full_close_value = full_close_value;
+    Ok(())
+}
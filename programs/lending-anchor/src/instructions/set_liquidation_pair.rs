@@ -0,0 +1,55 @@
+use crate::{
+    account_data::{lending_market::*, liquidation_pair::LiquidationPairConfig, reserve::Reserve},
+    constants::LENDING_MARKET_VERSION,
+    errors::LendingError,
+    require_lt_100, require_lte_100,
+};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetLiquidationPair<'info> {
+    #[account(
+        mut,
+        has_one = owner @ LendingError::InvalidMarketOwner,
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+
+    /// Reserve whose collateral is seized
+    #[account(
+        constraint = deposit_reserve.lending_market == lending_market.key() @ LendingError::InvalidAccountInput,
+    )]
+    pub deposit_reserve: Box<Account<'info, Reserve>>,
+
+    /// Reserve whose borrow is repaid
+    #[account(
+        constraint = borrow_reserve.lending_market == lending_market.key() @ LendingError::InvalidAccountInput,
+    )]
+    pub borrow_reserve: Box<Account<'info, Reserve>>,
+
+    pub owner: Signer<'info>,
+}
+
+/// Sets the close factor and liquidation bonus of liquidations seizing the deposit reserve's
+/// collateral to repay the borrow reserve's borrows, or goes back to the market's with `None`
+pub fn process_set_liquidation_pair(
+    ctx: Context<SetLiquidationPair>,
+    config: Option<LiquidationPairConfig>,
+) -> Result<()> {
+    if let Some(config) = config {
+        require_neq!(config.close_factor, 0, LendingError::InvalidConfig);
+        require_lte_100!(config.close_factor, LendingError::InvalidConfig);
+        require_lt_100!(config.liquidation_bonus, LendingError::InvalidConfig);
+        require_gte!(
+            config.liquidation_bonus,
+            config.min_liquidation_bonus,
+            LendingError::InvalidConfig
+        );
+    }
+
+    let deposit_reserve = ctx.accounts.deposit_reserve.key();
+    let borrow_reserve = ctx.accounts.borrow_reserve.key();
+    ctx.accounts
+        .lending_market
+        .set_liquidation_pair(deposit_reserve, borrow_reserve, config)
+}
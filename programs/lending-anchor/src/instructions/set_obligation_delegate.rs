@@ -0,0 +1,45 @@
+use crate::{
+    account_data::obligation::{obligation_delegate::ObligationDelegate, Obligation},
+    constants::OBLIGATION_VERSION,
+    errors::LendingError,
+    events::SetObligationDelegateEvent,
+};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetObligationDelegate<'info> {
+    #[account(
+        mut,
+        constraint = obligation.owner == obligation_owner.key() @ LendingError::InvalidObligationOwner,
+        constraint = obligation.version == OBLIGATION_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub obligation: Box<Account<'info, Obligation>>,
+
+    pub obligation_owner: Signer<'info>,
+}
+
+pub fn process_set_obligation_delegate(
+    ctx: Context<SetObligationDelegate>,
+    delegate: Option<ObligationDelegate>,
+) -> Result<()> {
+    if let Some(delegate) = delegate {
+        require_keys_neq!(
+            delegate.authority,
+            ctx.accounts.obligation.owner,
+            LendingError::InvalidAccountInput
+        );
+        require!(
+            delegate.permissions & !ObligationDelegate::ALL == 0,
+            LendingError::InvalidConfig
+        );
+    }
+
+    ctx.accounts.obligation.delegate = delegate;
+
+    emit!(SetObligationDelegateEvent {
+        lending_market: ctx.accounts.obligation.lending_market,
+        obligation: ctx.accounts.obligation.key(),
+        delegate,
+    });
+    Ok(())
+}
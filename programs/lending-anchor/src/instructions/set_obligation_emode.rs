@@ -0,0 +1,41 @@
+use crate::{
+    account_data::{lending_market::LendingMarket, obligation::Obligation},
+    constants::{LENDING_MARKET_VERSION, OBLIGATION_VERSION},
+    errors::LendingError,
+};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetObligationEMode<'info> {
+    #[account(
+        mut,
+        constraint = obligation.lending_market == lending_market.key() @ LendingError::InvalidAccountInput,
+        constraint = obligation.owner == obligation_owner.key() @ LendingError::InvalidObligationOwner,
+        constraint = !obligation.has_borrows() @ LendingError::ObligationBorrowsNotEmpty,
+        constraint = obligation.version == OBLIGATION_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub obligation: Box<Account<'info, Obligation>>,
+
+    #[account(
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+
+    pub obligation_owner: Signer<'info>,
+}
+
+pub fn process_set_obligation_emode(
+    ctx: Context<SetObligationEMode>,
+    emode_category: u8,
+) -> Result<()> {
+    if emode_category != 0 {
+        ctx.accounts
+            .lending_market
+            .find_emode_category(emode_category)?;
+    }
+
+    ctx.accounts.obligation.emode_category = emode_category;
+    ctx.accounts.obligation.last_update.mark_stale();
+
+    Ok(())
+}
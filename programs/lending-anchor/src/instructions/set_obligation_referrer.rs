@@ -0,0 +1,39 @@
+use crate::{
+    account_data::{obligation::Obligation, referrer::Referrer},
+    constants::{OBLIGATION_VERSION, REFERRER_VERSION},
+    errors::LendingError,
+    events::SetObligationReferrerEvent,
+};
+use anchor_lang::prelude::*;
+
+/// Attributes the obligation to a referrer. The referrer cannot be changed once set.
+#[derive(Accounts)]
+pub struct SetObligationReferrer<'info> {
+    #[account(
+        mut,
+        constraint = obligation.owner == obligation_owner.key() @ LendingError::InvalidObligationOwner,
+        constraint = obligation.referrer.is_none() @ LendingError::ReferrerAlreadySet,
+        constraint = obligation.version == OBLIGATION_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub obligation: Box<Account<'info, Obligation>>,
+
+    #[account(
+        constraint = referrer.lending_market == obligation.lending_market @ LendingError::InvalidAccountInput,
+        constraint = referrer.owner != obligation.owner @ LendingError::InvalidReferrer,
+        constraint = referrer.version == REFERRER_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub referrer: Box<Account<'info, Referrer>>,
+
+    pub obligation_owner: Signer<'info>,
+}
+
+pub fn process_set_obligation_referrer(ctx: Context<SetObligationReferrer>) -> Result<()> {
+    ctx.accounts.obligation.referrer = Some(ctx.accounts.referrer.key());
+
+    emit!(SetObligationReferrerEvent {
+        lending_market: ctx.accounts.obligation.lending_market,
+        obligation: ctx.accounts.obligation.key(),
+        referrer: ctx.accounts.referrer.key(),
+    });
+    Ok(())
+}
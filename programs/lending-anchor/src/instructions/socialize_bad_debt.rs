@@ -0,0 +1,194 @@
+use crate::{
+    account_data::{lending_market::LendingMarket, obligation::Obligation, reserve::Reserve},
+    constants::{
+        INSURANCE_VAULT_SEED, LENDING_MARKET_VERSION, OBLIGATION_VERSION, RESERVE_VERSION,
+    },
+    errors::LendingError,
+    events::{ReserveSnapshot, SocializeBadDebtEvent},
+    instructions::{
+        repay_obligation_liquidity::repay_obligation, repay_obligation_term_loan::repay_term_loan,
+    },
+    utils::token_interface,
+};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SocializeBadDebt<'info> {
+    #[account(
+        mut,
+        constraint = obligation.lending_market.key() == lending_market.key() @ LendingError::InvalidAccountInput,
+        constraint = !obligation.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ObligationStale,
+        constraint = obligation.deposits.is_empty() @ LendingError::ObligationHasDeposits,
+        constraint = obligation.version == OBLIGATION_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub obligation: Box<Account<'info, Obligation>>,
+
+    #[account(
+        mut,
+        constraint = reserve.lending_market.key() == lending_market.key() @ LendingError::InvalidAccountInput,
+        constraint = !reserve.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ReserveStale,
+        constraint = reserve.version == RESERVE_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub reserve: Box<Account<'info, Reserve>>,
+
+    /// CHECK: address checked against the reserve
+    #[account(
+        mut,
+        address = reserve.liquidity.supply_pubkey @ LendingError::InvalidAccountInput,
+    )]
+    pub reserve_liquidity_supply: UncheckedAccount<'info>,
+
+    /// CHECK: insurance vault of the reserve, which may not have been created
+    #[account(
+        mut,
+        seeds = [INSURANCE_VAULT_SEED, reserve.key().as_ref()],
+        bump,
+    )]
+    pub reserve_insurance_vault: UncheckedAccount<'info>,
+
+    /// CHECK: address checked against the reserve
+    #[account(address = reserve.liquidity.mint_pubkey @ LendingError::InvalidAccountInput)]
+    pub reserve_liquidity_mint: UncheckedAccount<'info>,
+
+    #[account(
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+
+    #[account(
+        seeds = [lending_market.key().as_ref()],
+        bump = lending_market.bump_seed,
+    )]
+    /// CHECK:
+    pub lending_market_authority: UncheckedAccount<'info>,
+
+    /// CHECK: token program recorded by the reserve
+    #[account(address = reserve.liquidity.token_program_id @ LendingError::InvalidTokenProgram)]
+    pub liquidity_token_program: UncheckedAccount<'info>,
+}
+
+impl<'info> SocializeBadDebt<'info> {
+    /// Cover up to `amount` from the insurance fund, returning the part of the insurance
+    /// share the supply holds that was used and how much arrived from the vault
+    fn cover_from_insurance(&mut self, amount: u64) -> Result<(u64, u64)> {
+        // supplyに残っている取り分は総供給量に入っていないので，そのまま使える
+        let pending_amount = self.reserve.take_insurance_pending(amount);
+        let amount = amount - pending_amount;
+        if amount == 0 || self.reserve.insurance_vault.is_none() {
+            return Ok((pending_amount, 0));
+        }
+        let amount = amount.min(token_interface::token_account_amount(
+            &self.reserve_insurance_vault,
+        )?);
+        if amount == 0 {
+            return Ok((pending_amount, 0));
+        }
+        let lending_market_key = self.lending_market.key();
+        let authority_signer_seeds: &[&[u8]] = &[
+            lending_market_key.as_ref(),
+            &[self.lending_market.bump_seed],
+        ];
+        let balance_before = token_interface::token_account_amount(&self.reserve_liquidity_supply)?;
+        token_interface::transfer_checked(
+            &self.liquidity_token_program,
+            &self.reserve_insurance_vault,
+            &self.reserve_liquidity_mint,
+            &self.reserve_liquidity_supply,
+            &self.lending_market_authority,
+            amount,
+            self.reserve.liquidity.mint_decimals,
+            &[authority_signer_seeds],
+        )?;
+        let received_amount =
+            token_interface::token_account_amount(&self.reserve_liquidity_supply)?
+                .checked_sub(balance_before)
+                .ok_or(LendingError::MathOverflow)?;
+        Ok((pending_amount, received_amount))
+    }
+}
+
+/// Writes off what an obligation with no deposits left still owes to the reserve, variable
+/// and fixed. The reserve's insurance fund covers what it can, starting with its share still in
+/// the supply, and the rest is taken out of the total supply, lowering the exchange rate for
+/// every cToken holder.
+pub fn process_socialize_bad_debt(ctx: Context<SocializeBadDebt>) -> Result<()> {
+    let reserve_key = ctx.accounts.reserve.key();
+    let borrow = ctx
+        .accounts
+        .obligation
+        .borrows
+        .iter()
+        .position(|liquidity| liquidity.borrow_reserve == reserve_key);
+    let term_loan = ctx
+        .accounts
+        .obligation
+        .term_loans
+        .iter()
+        .position(|term_loan| term_loan.borrow_reserve == reserve_key);
+    require!(
+        borrow.is_some() || term_loan.is_some(),
+        LendingError::InvalidObligationLiquidity
+    );
+    let total_supply = ctx.accounts.reserve.total_supply()?;
+
+    let mut settle_amount = 0_u128;
+    let mut covered_amount = 0_u64;
+    // insuranceはterm loan，variableな借入の順に充てる
+    if let Some(term_loan_index) = term_loan {
+        let owed_amount = ctx.accounts.obligation.term_loans[term_loan_index].borrowed_amount;
+        let (pending_amount, received_amount) = ctx
+            .accounts
+            .cover_from_insurance(u64::try_from(owed_amount).unwrap_or(u64::MAX))?;
+        repay_term_loan(
+            &mut ctx.accounts.obligation,
+            &mut ctx.accounts.reserve,
+            ctx.remaining_accounts,
+            owed_amount,
+            received_amount,
+            term_loan_index,
+        )?;
+        settle_amount = owed_amount;
+        covered_amount = pending_amount + received_amount;
+    }
+    if let Some(liquidity_index) = borrow {
+        let owed_amount = ctx.accounts.obligation.borrows[liquidity_index].borrowed_amount;
+        let (pending_amount, received_amount) = ctx
+            .accounts
+            .cover_from_insurance(u64::try_from(owed_amount).unwrap_or(u64::MAX))?;
+        repay_obligation(
+            &mut ctx.accounts.obligation,
+            reserve_key,
+            &mut ctx.accounts.reserve,
+            ctx.remaining_accounts,
+            owed_amount,
+            received_amount,
+            liquidity_index,
+        )?;
+        settle_amount = settle_amount
+            .checked_add(owed_amount)
+            .ok_or(LendingError::MathOverflow)?;
+        covered_amount = covered_amount
+            .checked_add(pending_amount + received_amount)
+            .ok_or(LendingError::MathOverflow)?;
+    }
+
+    let obligation = &mut ctx.accounts.obligation;
+    if !obligation.has_borrows() {
+        obligation.isolated_reserve = None;
+    }
+
+    // insuranceで埋まらなかった分だけ総供給量が減り，cTokenの保有者が負担する
+    let socialized_amount = total_supply.saturating_sub(ctx.accounts.reserve.total_supply()?);
+
+    emit!(SocializeBadDebtEvent {
+        lending_market: ctx.accounts.lending_market.key(),
+        obligation: ctx.accounts.obligation.key(),
+        reserve: reserve_key,
+        settle_amount,
+        covered_amount,
+        socialized_amount,
+        reserve_state: ReserveSnapshot::new(&ctx.accounts.reserve)?,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use crate::{
+    account_data::{obligation::Obligation, reward_pool::RewardPool},
+    constants::{OBLIGATION_VERSION, REWARD_POOL_VERSION},
+    errors::LendingError,
+    events::SyncRewardsEvent,
+};
+use anchor_lang::prelude::*;
+
+/// Stakes an obligation's position in a reward pool, so positions opened before the pool
+/// start earning without waiting for their owner. Anyone can run it.
+#[derive(Accounts)]
+pub struct SyncRewards<'info> {
+    #[account(
+        mut,
+        constraint = obligation.lending_market == reward_pool.lending_market @ LendingError::InvalidAccountInput,
+        constraint = obligation.version == OBLIGATION_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub obligation: Box<Account<'info, Obligation>>,
+
+    #[account(
+        mut,
+        constraint = reward_pool.version == REWARD_POOL_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub reward_pool: Box<Account<'info, RewardPool>>,
+}
+
+pub fn process_sync_rewards(ctx: Context<SyncRewards>) -> Result<()> {
+    let reward_pool_key = ctx.accounts.reward_pool.key();
+    let reward_pool = &mut ctx.accounts.reward_pool;
+    let obligation = &mut ctx.accounts.obligation;
+    reward_pool.accrue(Clock::get()?.slot)?;
+    let staked_amount = obligation.staked_amount(reward_pool.reserve, reward_pool.side)?;
+    obligation.update_reward(reward_pool_key, reward_pool, staked_amount)?;
+
+    emit!(SyncRewardsEvent {
+        obligation: ctx.accounts.obligation.key(),
+        reward_pool: reward_pool_key,
+        staked_amount,
+    });
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use crate::{
+    account_data::{
+        lending_market::*,
+        reserve::{reserve_config::ReserveConfig, Reserve},
+    },
+    constants::{LENDING_MARKET_VERSION, RESERVE_VERSION},
+    errors::LendingError,
+    events::UpdateReserveConfigEvent,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+
+/// Updates the config of a reserve by the lending market owner.
+/// Isolation mode can only change while no collateral of the reserve is deposited.
+#[derive(Accounts)]
+pub struct UpdateReserveConfig<'info> {
+    #[account(
+        mut,
+        has_one = lending_market @ LendingError::InvalidAccountInput,
+        constraint = reserve.version == RESERVE_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub reserve: Box<Account<'info, Reserve>>,
+
+    #[account(
+        constraint = reserve.collateral.supply_pubkey == collateral_supply.key() @ LendingError::InvalidAccountInput,
+    )]
+    pub collateral_supply: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        has_one = owner @ LendingError::InvalidMarketOwner,
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
+    )]
+    pub lending_market: Box<Account<'info, LendingMarket>>,
+
+    pub owner: Signer<'info>,
+}
+
+pub fn process_update_reserve_config(
+    ctx: Context<UpdateReserveConfig>,
+    config: ReserveConfig,
+) -> Result<()> {
+    config.validate(&ctx.accounts.lending_market)?;
+
+    // 預けられたcollateralがあると、既存のobligationの孤立とdebtの記録が合わなくなる
+    if config.isolated != ctx.accounts.reserve.config.isolated {
+        require_eq!(
+            ctx.accounts.collateral_supply.amount,
+            0,
+            LendingError::ReserveHasCollateralDeposits
+        );
+    }
+
+    let reserve = &mut ctx.accounts.reserve;
+    let old_config = reserve.config.clone();
+    reserve.config = config.clone();
+
+    emit!(UpdateReserveConfigEvent {
+        lending_market: ctx.accounts.lending_market.key(),
+        reserve: reserve.key(),
+        old_config,
+        new_config: config,
+    });
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use crate::{
+    errors::LendingError, events::WithdrawInsuranceVaultEvent,
+    instructions::fund_insurance_vault::UpdateInsuranceVault, utils::token_interface,
+};
+use anchor_lang::prelude::*;
+
+/// Withdraws from a reserve's insurance vault to the lending market owner, after sweeping the
+/// insurance share the supply holds into it. `u64::MAX` withdraws the whole vault.
+/// Takes the same accounts as `fund_insurance_vault`.
+pub fn process_withdraw_insurance_vault(
+    ctx: Context<UpdateInsuranceVault>,
+    amount: u64,
+) -> Result<()> {
+    require_neq!(amount, 0, LendingError::InvalidAmount);
+    let swept_amount = ctx.accounts.sweep_insurance()?;
+
+    let vault_amount = token_interface::token_account_amount(&ctx.accounts.insurance_vault)?;
+    let amount = if amount == u64::MAX {
+        vault_amount
+    } else {
+        amount
+    };
+    require_gte!(vault_amount, amount, LendingError::InsufficientLiquidity);
+    if amount > 0 {
+        ctx.accounts.transfer_from_market(
+            &ctx.accounts.insurance_vault,
+            &ctx.accounts.owner_liquidity,
+            amount,
+        )?;
+    }
+
+    emit!(WithdrawInsuranceVaultEvent {
+        reserve: ctx.accounts.reserve.key(),
+        amount,
+        swept_amount,
+    });
+    Ok(())
+}
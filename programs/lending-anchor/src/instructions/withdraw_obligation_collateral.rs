@@ -1,34 +1,49 @@
 use crate::{
-    account_data::{lending_market::LendingMarket, obligation::Obligation, reserve::Reserve},
+    account_data::{
+        lending_market::LendingMarket,
+        obligation::{obligation_delegate::ObligationDelegate, Obligation},
+        reserve::Reserve,
+        reward_pool::RewardSide,
+    },
+    constants::{LENDING_MARKET_VERSION, OBLIGATION_VERSION, RESERVE_VERSION},
     errors::LendingError,
-    math::{common::Decimal, rate::Rate},
+    events::WithdrawObligationCollateralEvent,
+    math::rate::Rate,
+    utils::rewards::update_obligation_rewards,
 };
 use anchor_lang::prelude::*;
 use anchor_spl::token::{transfer, Token, TokenAccount, Transfer};
 
 #[derive(Accounts)]
 pub struct WithdrawObligationCollateral<'info> {
+    #[account(mut)]
     pub source_collateral: Box<Account<'info, TokenAccount>>,
 
+    #[account(mut)]
     pub destination_collateral: Box<Account<'info, TokenAccount>>,
 
     #[account(
         constraint = withdraw_reserve.lending_market.key() == lending_market.key() @ LendingError::InvalidAccountInput,
         constraint = withdraw_reserve.collateral.supply_pubkey == source_collateral.key() @ LendingError::InvalidAccountInput,
         constraint = withdraw_reserve.collateral.supply_pubkey != destination_collateral.key() @ LendingError::InvalidAccountInput,
-        constraint = !withdraw_reserve.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ReserveStale
+        constraint = !withdraw_reserve.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ReserveStale,
+        constraint = withdraw_reserve.version == RESERVE_VERSION @ LendingError::OutdatedAccountVersion,
     )]
     pub withdraw_reserve: Box<Account<'info, Reserve>>,
 
     #[account(
+        mut,
         constraint = obligation.lending_market.key() == lending_market.key() @ LendingError::InvalidAccountInput,
-        constraint = obligation.owner == obligation_owner.key() @ LendingError::InvalidAccountInput,
-        constraint = !obligation.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ObligationStale
+        constraint = obligation.is_authorized(obligation_authority.key(), ObligationDelegate::WITHDRAW) @ LendingError::InvalidAccountInput,
+        constraint = !obligation.last_update.is_stale(Clock::get()?.slot)? @ LendingError::ObligationStale,
+        constraint = !obligation.price_fallback || !obligation.has_borrows() @ LendingError::ReservePriceFallback,
+        constraint = obligation.version == OBLIGATION_VERSION @ LendingError::OutdatedAccountVersion,
     )]
     pub obligation: Box<Account<'info, Obligation>>,
 
     #[account(
-        constraint = lending_market.token_program_id == token_program.key() @ LendingError::InvalidTokenProgram
+        constraint = lending_market.token_program_id == token_program.key() @ LendingError::InvalidTokenProgram,
+        constraint = lending_market.version == LENDING_MARKET_VERSION @ LendingError::OutdatedAccountVersion,
     )]
     pub lending_market: Box<Account<'info, LendingMarket>>,
 
@@ -39,7 +54,11 @@ pub struct WithdrawObligationCollateral<'info> {
     )]
     pub lending_market_authority: UncheckedAccount<'info>,
 
-    pub obligation_owner: Signer<'info>,
+    /// Obligation owner or its delegate, which can only withdraw to the owner
+    #[account(
+        constraint = obligation.owner == obligation_authority.key() || destination_collateral.owner == obligation.owner @ LendingError::InvalidDelegateDestination,
+    )]
+    pub obligation_authority: Signer<'info>,
 
     pub token_program: Program<'info, Token>,
 }
@@ -55,56 +74,57 @@ impl<'info> WithdrawObligationCollateral<'info> {
     }
 }
 
-pub fn process_withdraw_obligation_collateral(
-    ctx: Context<WithdrawObligationCollateral>,
+/// Collateral amount of `withdraw_reserve` that can be withdrawn for `collateral_amount`,
+/// with its index in the obligation's deposits. `u64::MAX` withdraws as much as allowed.
+pub fn calculate_obligation_withdraw(
+    obligation: &Obligation,
+    withdraw_reserve_key: Pubkey,
+    withdraw_reserve: &Reserve,
+    lending_market: &LendingMarket,
     collateral_amount: u64,
-) -> Result<()> {
-    let (collateral, collateral_index) = ctx
-        .accounts
-        .obligation
-        .find_collateral_index_in_deposits(ctx.accounts.withdraw_reserve.key())?;
+) -> Result<(u64, usize)> {
+    let (collateral, collateral_index) =
+        obligation.find_collateral_index_in_deposits(withdraw_reserve_key)?;
     require_neq!(
         collateral.deposited_amount,
         0,
         LendingError::ObligationCollateralEmpty
     );
 
-    let withdraw_amount = if ctx.accounts.obligation.borrows.is_empty() {
+    let withdraw_amount = if !obligation.has_borrows() {
         if collateral_amount == u64::MAX {
             collateral.deposited_amount
         } else {
             collateral.deposited_amount.min(collateral_amount)
         }
-    } else if ctx.accounts.obligation.deposited_value == 0 {
+    } else if obligation.deposited_value == 0 {
         return Err(LendingError::ObligationDepositsZero.into());
     } else {
-        let max_withdraw_value = ctx
-            .accounts
-            .obligation
-            .max_withdraw_value(Rate::from_percent(
-                ctx.accounts.withdraw_reserve.config.loan_to_value_ratio,
-            ))?;
+        // e-modeのcategoryに属するcollateralはcategoryのLTVを使う
+        let emode_category = obligation.emode_category;
+        let loan_to_value_ratio =
+            if emode_category != 0 && withdraw_reserve.config.emode_category == emode_category {
+                lending_market
+                    .find_emode_category(emode_category)?
+                    .loan_to_value_ratio
+            } else {
+                withdraw_reserve.config.loan_to_value_ratio
+            };
+        let max_withdraw_value =
+            obligation.max_withdraw_value(Rate::from_percent(loan_to_value_ratio))?;
         require_neq!(max_withdraw_value, 0, LendingError::WithdrawTooLarge);
 
         let withdraw_amount = if collateral_amount == u64::MAX {
             let withdraw_value = max_withdraw_value.min(collateral.market_value);
-            let withdraw_pct = withdraw_value
-                .checked_div(collateral.market_value as u128)
-                .ok_or(LendingError::MathOverflow)?;
-            withdraw_pct
-                .checked_mul(collateral.deposited_amount as u128)
-                .ok_or(LendingError::MathOverflow)?
-                .try_floor_u64()?
+            let withdraw_pct = Rate::from_ratio(withdraw_value, collateral.market_value)?;
+            u64::try_from(withdraw_pct.try_apply(collateral.deposited_amount as u128)?)
+                .map_err(|_| LendingError::MathOverflow)?
                 .min(collateral.deposited_amount)
         } else {
             let withdraw_amount = collateral_amount.min(collateral.deposited_amount);
-            let withdraw_pct = withdraw_amount
-                .checked_div(collateral.deposited_amount)
-                .ok_or(LendingError::MathOverflow)?;
-            let withdraw_value = collateral
-                .market_value
-                .checked_mul(withdraw_pct as u128)
-                .ok_or(LendingError::MathOverflow)?;
+            let withdraw_pct =
+                Rate::from_ratio(withdraw_amount as u128, collateral.deposited_amount as u128)?;
+            let withdraw_value = withdraw_pct.try_apply(collateral.market_value)?;
             require_gte!(
                 max_withdraw_value,
                 withdraw_value,
@@ -117,12 +137,51 @@ pub fn process_withdraw_obligation_collateral(
         withdraw_amount
     };
 
+    Ok((withdraw_amount, collateral_index))
+}
+
+pub fn process_withdraw_obligation_collateral(
+    ctx: Context<WithdrawObligationCollateral>,
+    collateral_amount: u64,
+) -> Result<()> {
+    let (withdraw_amount, collateral_index) = calculate_obligation_withdraw(
+        &ctx.accounts.obligation,
+        ctx.accounts.withdraw_reserve.key(),
+        &ctx.accounts.withdraw_reserve,
+        &ctx.accounts.lending_market,
+        collateral_amount,
+    )?;
+
     ctx.accounts
         .obligation
         .withdraw(withdraw_amount, collateral_index)?;
     ctx.accounts.obligation.last_update.mark_stale();
-
-    transfer(ctx.accounts.into_transfer_collateral_ctx(), withdraw_amount)?;
+    update_obligation_rewards(
+        &mut ctx.accounts.obligation,
+        ctx.accounts.withdraw_reserve.key(),
+        &ctx.accounts.withdraw_reserve,
+        RewardSide::Deposit,
+        ctx.remaining_accounts,
+    )?;
+
+    let lending_market_key = ctx.accounts.lending_market.key();
+    let authority_signer_seeds: &[&[u8]] = &[
+        lending_market_key.as_ref(),
+        &[ctx.accounts.lending_market.bump_seed],
+    ];
+    transfer(
+        ctx.accounts
+            .into_transfer_collateral_ctx()
+            .with_signer(&[authority_signer_seeds]),
+        withdraw_amount,
+    )?;
+
+    emit!(WithdrawObligationCollateralEvent {
+        lending_market: ctx.accounts.lending_market.key(),
+        obligation: ctx.accounts.obligation.key(),
+        reserve: ctx.accounts.withdraw_reserve.key(),
+        collateral_amount: withdraw_amount,
+    });
 
     Ok(())
 }
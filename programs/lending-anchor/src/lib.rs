@@ -1,17 +1,37 @@
 pub mod account_data;
 pub mod constants;
 pub mod errors;
+pub mod events;
 pub mod instructions;
 pub mod math;
 pub mod pyth;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
 pub mod utils;
+pub mod views;
 
 use crate::{
-    account_data::reserve::reserve_config::ReserveConfig,
+    account_data::{
+        emode_category::EModeCategory, liquidation_pair::LiquidationPairConfig,
+        obligation::obligation_delegate::ObligationDelegate,
+        reserve::reserve_config::ReserveConfig, reward_pool::RewardSide,
+    },
     instructions::{
-        borrow_obligation_liquidity::*, deposit_obligation_collateral::*,
-        deposit_reserve_liquidity::*, init_lending_market::*, init_obligation::*, init_reserve::*,
-        redeem_reserve_collateral::*, repay_obligation_liquidity::*, set_lending_market_owner::*,
+        borrow_obligation_liquidity::*, borrow_obligation_liquidity_native::*,
+        borrow_obligation_liquidity_term::*, claim_referrer_fees::*, claim_rewards::*,
+        deposit_obligation_collateral::*, deposit_reserve_liquidity::*,
+        deposit_reserve_liquidity_native::*, fund_insurance_vault::*, fund_reward_pool::*,
+        get_obligation_health::*, init_insurance_vault::*, init_lending_market::*,
+        init_obligation::*, init_referrer::*, init_referrer_token_state::*, init_reserve::*,
+        init_reward_pool::*, liquidate_obligation::*, liquidate_obligation_term_loan::*,
+        migrate_lending_market::*, migrate_obligation::*, migrate_reserve::*,
+        override_reserve_price_guard::*, quote_borrow::*, quote_repay::*, quote_withdraw::*,
+        redeem_reserve_collateral::*, refresh_obligation::*, refresh_reserve::*,
+        repay_obligation_liquidity::*, repay_obligation_liquidity_native::*,
+        repay_obligation_term_loan::*, set_emode_category::*, set_lending_market_owner::*,
+        set_liquidation_config::*, set_liquidation_pair::*, set_obligation_delegate::*,
+        set_obligation_emode::*, set_obligation_referrer::*, socialize_bad_debt::*,
+        sync_rewards::*, update_reserve_config::*, withdraw_insurance_vault::*,
         withdraw_obligation_collateral::*,
     },
 };
@@ -45,6 +65,17 @@ pub mod lending_anchor {
         process_init_reserve(ctx, liquidity_amount, reserve_config)
     }
 
+    pub fn update_reserve_config(
+        ctx: Context<UpdateReserveConfig>,
+        reserve_config: ReserveConfig,
+    ) -> Result<()> {
+        process_update_reserve_config(ctx, reserve_config)
+    }
+
+    pub fn override_reserve_price_guard(ctx: Context<OverrideReservePriceGuard>) -> Result<()> {
+        process_override_reserve_price_guard(ctx)
+    }
+
     pub fn deposit_reserve_liquidity(
         ctx: Context<DepositReserveLiquidity>,
         liquidity_amount: u64,
@@ -52,6 +83,13 @@ pub mod lending_anchor {
         process_deposit_reserve_liquidity(ctx, liquidity_amount)
     }
 
+    pub fn deposit_reserve_liquidity_native(
+        ctx: Context<DepositReserveLiquidityNative>,
+        lamports: u64,
+    ) -> Result<()> {
+        process_deposit_reserve_liquidity_native(ctx, lamports)
+    }
+
     pub fn redeem_reserve_collateral(
         ctx: Context<RedeemReserveCollateral>,
         collateral_amount: u64,
@@ -77,17 +115,186 @@ pub mod lending_anchor {
         process_withdraw_obligation_collateral(ctx, collateral_amount)
     }
 
-    pub fn borrow_obligation_liquidity(
-        ctx: Context<BorrowObligationLiquidity>,
+    pub fn borrow_obligation_liquidity<'info>(
+        ctx: Context<'_, '_, '_, 'info, BorrowObligationLiquidity<'info>>,
         liquidity_amount: u64,
     ) -> Result<()> {
         process_borrow_obligation_liquidity(ctx, liquidity_amount)
     }
 
+    pub fn borrow_obligation_liquidity_native<'info>(
+        ctx: Context<'_, '_, '_, 'info, BorrowObligationLiquidityNative<'info>>,
+        liquidity_amount: u64,
+    ) -> Result<()> {
+        process_borrow_obligation_liquidity_native(ctx, liquidity_amount)
+    }
+
+    pub fn borrow_obligation_liquidity_term<'info>(
+        ctx: Context<'_, '_, '_, 'info, BorrowObligationLiquidity<'info>>,
+        liquidity_amount: u64,
+        term_slots: u64,
+        rate_bps: u32,
+    ) -> Result<()> {
+        process_borrow_obligation_liquidity_term(ctx, liquidity_amount, term_slots, rate_bps)
+    }
+
     pub fn repay_obligation_liquidity(
         ctx: Context<RepayObligationLiquidity>,
         liquidity_amount: u64,
     ) -> Result<()> {
         process_repay_obligation_liquidity(ctx, liquidity_amount)
     }
+
+    pub fn repay_obligation_liquidity_native(
+        ctx: Context<RepayObligationLiquidityNative>,
+        liquidity_amount: u64,
+    ) -> Result<()> {
+        process_repay_obligation_liquidity_native(ctx, liquidity_amount)
+    }
+
+    pub fn repay_obligation_term_loan(
+        ctx: Context<RepayObligationLiquidity>,
+        liquidity_amount: u64,
+    ) -> Result<()> {
+        process_repay_obligation_term_loan(ctx, liquidity_amount)
+    }
+
+    pub fn liquidate_obligation(
+        ctx: Context<LiquidateObligation>,
+        liquidity_amount: u64,
+    ) -> Result<()> {
+        process_liquidate_obligation(ctx, liquidity_amount)
+    }
+
+    pub fn liquidate_obligation_term_loan(
+        ctx: Context<LiquidateObligation>,
+        liquidity_amount: u64,
+    ) -> Result<()> {
+        process_liquidate_obligation_term_loan(ctx, liquidity_amount)
+    }
+
+    pub fn socialize_bad_debt(ctx: Context<SocializeBadDebt>) -> Result<()> {
+        process_socialize_bad_debt(ctx)
+    }
+
+    pub fn refresh_reserve(ctx: Context<RefreshReserve>) -> Result<()> {
+        process_refresh_reserve(ctx)
+    }
+
+    pub fn refresh_obligation(ctx: Context<RefreshObligation>) -> Result<()> {
+        process_refresh_obligation(ctx)
+    }
+
+    pub fn set_emode_category(
+        ctx: Context<SetEModeCategory>,
+        category: EModeCategory,
+    ) -> Result<()> {
+        process_set_emode_category(ctx, category)
+    }
+
+    pub fn set_liquidation_config(
+        ctx: Context<SetLiquidationConfig>,
+        close_factor: u8,
+        full_close_value: u128,
+    ) -> Result<()> {
+        process_set_liquidation_config(ctx, close_factor, full_close_value)
+    }
+
+    pub fn set_liquidation_pair(
+        ctx: Context<SetLiquidationPair>,
+        config: Option<LiquidationPairConfig>,
+    ) -> Result<()> {
+        process_set_liquidation_pair(ctx, config)
+    }
+
+    pub fn set_obligation_emode(
+        ctx: Context<SetObligationEMode>,
+        emode_category: u8,
+    ) -> Result<()> {
+        process_set_obligation_emode(ctx, emode_category)
+    }
+
+    pub fn set_obligation_delegate(
+        ctx: Context<SetObligationDelegate>,
+        delegate: Option<ObligationDelegate>,
+    ) -> Result<()> {
+        process_set_obligation_delegate(ctx, delegate)
+    }
+
+    pub fn init_referrer(ctx: Context<InitReferrer>) -> Result<()> {
+        process_init_referrer(ctx)
+    }
+
+    pub fn init_referrer_token_state(ctx: Context<InitReferrerTokenState>) -> Result<()> {
+        process_init_referrer_token_state(ctx)
+    }
+
+    pub fn set_obligation_referrer(ctx: Context<SetObligationReferrer>) -> Result<()> {
+        process_set_obligation_referrer(ctx)
+    }
+
+    pub fn claim_referrer_fees(ctx: Context<ClaimReferrerFees>) -> Result<()> {
+        process_claim_referrer_fees(ctx)
+    }
+
+    pub fn init_reward_pool(ctx: Context<InitRewardPool>, side: RewardSide) -> Result<()> {
+        process_init_reward_pool(ctx, side)
+    }
+
+    pub fn fund_reward_pool(
+        ctx: Context<FundRewardPool>,
+        amount: u64,
+        emission_per_slot: u64,
+        end_slot: u64,
+    ) -> Result<()> {
+        process_fund_reward_pool(ctx, amount, emission_per_slot, end_slot)
+    }
+
+    pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+        process_claim_rewards(ctx)
+    }
+
+    pub fn sync_rewards(ctx: Context<SyncRewards>) -> Result<()> {
+        process_sync_rewards(ctx)
+    }
+
+    pub fn init_insurance_vault(ctx: Context<InitInsuranceVault>) -> Result<()> {
+        process_init_insurance_vault(ctx)
+    }
+
+    pub fn fund_insurance_vault(ctx: Context<UpdateInsuranceVault>, amount: u64) -> Result<()> {
+        process_fund_insurance_vault(ctx, amount)
+    }
+
+    pub fn withdraw_insurance_vault(ctx: Context<UpdateInsuranceVault>, amount: u64) -> Result<()> {
+        process_withdraw_insurance_vault(ctx, amount)
+    }
+
+    pub fn migrate_lending_market(ctx: Context<MigrateLendingMarket>) -> Result<()> {
+        process_migrate_lending_market(ctx)
+    }
+
+    pub fn migrate_reserve(ctx: Context<MigrateReserve>) -> Result<()> {
+        process_migrate_reserve(ctx)
+    }
+
+    pub fn migrate_obligation(ctx: Context<MigrateObligation>) -> Result<()> {
+        process_migrate_obligation(ctx)
+    }
+
+    pub fn get_obligation_health(ctx: Context<GetObligationHealth>) -> Result<()> {
+        process_get_obligation_health(ctx)
+    }
+
+    pub fn quote_borrow(ctx: Context<QuoteBorrow>, liquidity_amount: u64) -> Result<()> {
+        process_quote_borrow(ctx, liquidity_amount)
+    }
+
+    pub fn quote_withdraw(ctx: Context<QuoteWithdraw>, collateral_amount: u64) -> Result<()> {
+        process_quote_withdraw(ctx, collateral_amount)
+    }
+
+    pub fn quote_repay(ctx: Context<QuoteRepay>, liquidity_amount: u64) -> Result<()> {
+        process_quote_repay(ctx, liquidity_amount)
+    }
 }
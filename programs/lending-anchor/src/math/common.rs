@@ -6,6 +6,11 @@ pub const WAD: u64 = 1_000_000_000_000_000_000;
 
 pub const PERCENT_SCALER: u64 = 10_000_000_000_000_000;
 
+pub const BPS_SCALER: u64 = 100_000_000_000_000;
+
+/// 100% in basis points
+pub const BPS_SCALE: u16 = 10_000;
+
 pub trait Decimal {
     fn try_floor_u64(&self) -> Result<u64>;
     fn try_ceil_u64(&self) -> Result<u64>;
@@ -27,6 +32,8 @@ impl Decimal for u128 {
                 .checked_sub(1)
                 .ok_or(LendingError::MathOverflow)?
                 .checked_add(*self)
+                .ok_or(LendingError::MathOverflow)?
+                .checked_div(WAD as u128)
                 .ok_or(LendingError::MathOverflow)?,
         )
         .map_err(|_| LendingError::MathOverflow)?)
@@ -34,7 +41,8 @@ impl Decimal for u128 {
 
     fn try_round_u64(&self) -> Result<u64> {
         Ok(u64::try_from(
-            self.checked_add(*self)
+            (WAD as u128 / 2)
+                .checked_add(*self)
                 .ok_or(LendingError::MathOverflow)?
                 .checked_div(WAD as u128)
                 .ok_or(LendingError::MathOverflow)?,
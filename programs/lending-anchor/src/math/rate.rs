@@ -1,4 +1,6 @@
-use super::common::{PERCENT_SCALER, WAD};
+use super::common::{BPS_SCALER, PERCENT_SCALER, WAD};
+use crate::errors::LendingError;
+use anchor_lang::prelude::*;
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Eq, Ord)]
 pub struct Rate(u128);
@@ -8,6 +10,10 @@ impl Rate {
         Self((percent as u64 * PERCENT_SCALER) as u128)
     }
 
+    pub fn from_bps(bps: u32) -> Self {
+        Self(u128::from(bps) * u128::from(BPS_SCALER))
+    }
+
     pub fn zero() -> Self {
         Self(0)
     }
@@ -23,6 +29,92 @@ impl Rate {
     pub fn from_scaled_val(scaled_val: u64) -> Self {
         Self(u128::from(scaled_val))
     }
+
+    pub fn to_scaled_val(&self) -> u128 {
+        self.0
+    }
+
+    pub fn try_add(self, rhs: Self) -> Result<Self> {
+        Ok(Self(
+            self.0
+                .checked_add(rhs.0)
+                .ok_or(LendingError::MathOverflow)?,
+        ))
+    }
+
+    pub fn try_sub(self, rhs: Self) -> Result<Self> {
+        Ok(Self(
+            self.0
+                .checked_sub(rhs.0)
+                .ok_or(LendingError::MathOverflow)?,
+        ))
+    }
+
+    pub fn try_mul(self, rhs: Self) -> Result<Self> {
+        Ok(Self(
+            self.0
+                .checked_mul(rhs.0)
+                .ok_or(LendingError::MathOverflow)?
+                .checked_div(Self::wad())
+                .ok_or(LendingError::MathOverflow)?,
+        ))
+    }
+
+    pub fn try_div(self, rhs: Self) -> Result<Self> {
+        Ok(Self(
+            self.0
+                .checked_mul(Self::wad())
+                .ok_or(LendingError::MathOverflow)?
+                .checked_div(rhs.0)
+                .ok_or(LendingError::MathOverflow)?,
+        ))
+    }
+
+    /// Exponentiation by squaring
+    pub fn try_pow(self, mut exp: u64) -> Result<Self> {
+        let mut base = self;
+        let mut result = if exp & 1 == 1 { base } else { Self::one() };
+
+        exp >>= 1;
+        while exp != 0 {
+            base = base.try_mul(base)?;
+            if exp & 1 == 1 {
+                result = result.try_mul(base)?;
+            }
+            exp >>= 1;
+        }
+
+        Ok(result)
+    }
+
+    /// Apply this rate to an amount: amount * rate
+    pub fn try_apply(self, amount: u128) -> Result<u128> {
+        amount
+            .checked_mul(self.0)
+            .ok_or(LendingError::MathOverflow)?
+            .checked_div(Self::wad())
+            .ok_or(error!(LendingError::MathOverflow))
+    }
+
+    /// Remove this rate from an amount: amount / rate
+    pub fn try_unapply(self, amount: u128) -> Result<u128> {
+        amount
+            .checked_mul(Self::wad())
+            .ok_or(LendingError::MathOverflow)?
+            .checked_div(self.0)
+            .ok_or(error!(LendingError::MathOverflow))
+    }
+
+    /// Ratio of two amounts as a rate: numerator / denominator
+    pub fn from_ratio(numerator: u128, denominator: u128) -> Result<Self> {
+        Ok(Self(
+            numerator
+                .checked_mul(Self::wad())
+                .ok_or(LendingError::MathOverflow)?
+                .checked_div(denominator)
+                .ok_or(LendingError::MathOverflow)?,
+        ))
+    }
 }
 
 impl From<u64> for Rate {
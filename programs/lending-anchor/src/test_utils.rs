@@ -0,0 +1,105 @@
+//! Pyth account fixtures for program tests and localnet bootstrap
+use anchor_lang::prelude::*;
+use bytemuck::Zeroable;
+use pyth_sdk_solana::state::{
+    AccKey, AccountType, CorpAction, PriceAccount, PriceInfo, PriceStatus, PriceType,
+    ProductAccount, MAGIC, PROD_ATTR_SIZE, VERSION_2,
+};
+
+/// Pyth product account with `key=value` attributes such as `quote_currency`
+#[derive(Clone, Debug)]
+pub struct PythProduct {
+    pub price_account: Pubkey,
+    pub attributes: Vec<(String, String)>,
+}
+
+impl PythProduct {
+    pub fn new(price_account: Pubkey, quote_currency: &str) -> Self {
+        Self {
+            price_account,
+            attributes: vec![("quote_currency".to_string(), quote_currency.to_string())],
+        }
+    }
+
+    pub fn with_attribute(mut self, key: &str, value: &str) -> Self {
+        self.attributes.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Attributes are stored as length prefixed key and value strings
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut attr = Vec::new();
+        for (key, value) in &self.attributes {
+            for s in [key, value] {
+                attr.push(u8::try_from(s.len()).expect("attribute longer than 255 bytes"));
+                attr.extend_from_slice(s.as_bytes());
+            }
+        }
+        assert!(
+            attr.len() <= PROD_ATTR_SIZE,
+            "attributes exceed {} bytes",
+            PROD_ATTR_SIZE
+        );
+
+        let mut product = ProductAccount::zeroed();
+        product.magic = MAGIC;
+        product.ver = VERSION_2;
+        product.atype = AccountType::Product as u32;
+        product.size = (std::mem::size_of::<ProductAccount>() - PROD_ATTR_SIZE + attr.len()) as u32;
+        product.px_acc = AccKey {
+            val: self.price_account.to_bytes(),
+        };
+        product.attr[..attr.len()].copy_from_slice(&attr);
+        bytemuck::bytes_of(&product).to_vec()
+    }
+}
+
+/// Pyth price account whose aggregate price is `price * 10^expo`
+#[derive(Clone, Debug)]
+pub struct PythPrice {
+    pub product_account: Pubkey,
+    pub price: i64,
+    pub expo: i32,
+    pub conf: u64,
+    pub status: PriceStatus,
+    pub ptype: PriceType,
+    pub valid_slot: u64,
+}
+
+impl PythPrice {
+    /// Trading price with no confidence interval
+    pub fn new(product_account: Pubkey, price: i64, expo: i32, valid_slot: u64) -> Self {
+        Self {
+            product_account,
+            price,
+            expo,
+            conf: 0,
+            status: PriceStatus::Trading,
+            ptype: PriceType::Price,
+            valid_slot,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut price = PriceAccount::zeroed();
+        price.magic = MAGIC;
+        price.ver = VERSION_2;
+        price.atype = AccountType::Price as u32;
+        price.size = std::mem::size_of::<PriceAccount>() as u32;
+        price.ptype = self.ptype;
+        price.expo = self.expo;
+        price.valid_slot = self.valid_slot;
+        price.last_slot = self.valid_slot;
+        price.prod = AccKey {
+            val: self.product_account.to_bytes(),
+        };
+        price.agg = PriceInfo {
+            price: self.price,
+            conf: self.conf,
+            status: self.status,
+            corp_act: CorpAction::NoCorpAct,
+            pub_slot: self.valid_slot,
+        };
+        bytemuck::bytes_of(&price).to_vec()
+    }
+}
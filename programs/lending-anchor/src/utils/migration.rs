@@ -0,0 +1,120 @@
+use crate::{
+    errors::LendingError,
+    utils::{byte_length::ByteLength, versioned::Versioned},
+};
+use anchor_lang::{
+    error::ErrorCode,
+    prelude::*,
+    system_program::{transfer, Transfer},
+    Discriminator,
+};
+
+/// Version an account of type `T` was written with, checking its discriminator
+pub fn account_version<T: Discriminator>(account_info: &AccountInfo) -> Result<u8> {
+    let data = account_info.try_borrow_data()?;
+    require!(
+        data.len() > 8 && data[..8] == T::discriminator(),
+        ErrorCode::AccountDiscriminatorMismatch
+    );
+    // 全てのaccountはdiscriminatorの直後にversionを持つ
+    Ok(data[8])
+}
+
+/// Layout an account type was stored in over a range of versions, built from the layout it
+/// replaced. Each layout converts only from the one before it, so adding a layout never
+/// changes the older ones.
+pub trait Layout: AnchorDeserialize + From<Self::Previous> {
+    /// Layout this one replaced, or itself for the first layout of the account type
+    type Previous: Layout;
+
+    /// First version written with this layout
+    const SINCE: u8;
+}
+
+/// Deserialize account data written with `version`, after the discriminator, and convert it
+/// layout by layout up to `L`
+pub fn upgrade_layout<L: Layout>(data: &[u8], version: u8) -> Result<L> {
+    if version >= L::SINCE {
+        return L::deserialize(&mut &data[..])
+            .map_err(|_| error!(ErrorCode::AccountDidNotDeserialize));
+    }
+    // 最初のlayoutより前のversionは存在しない
+    require!(
+        L::Previous::SINCE < L::SINCE,
+        LendingError::UnsupportedAccountVersion
+    );
+    Ok(upgrade_layout::<L::Previous>(data, version)?.into())
+}
+
+/// Deserialize an account of type `T` written with an older version in the current layout,
+/// returning the version it was written with
+pub fn load_migrated<T>(account_info: &AccountInfo) -> Result<(u8, T)>
+where
+    T: Discriminator + Layout + Versioned,
+{
+    let from_version = account_version::<T>(account_info)?;
+    require!(
+        from_version < T::VERSION,
+        LendingError::UnsupportedAccountVersion
+    );
+    let data = account_info.try_borrow_data()?;
+    let account = upgrade_layout::<T>(&data[8..], from_version)?;
+    Ok((from_version, account))
+}
+
+/// Resize the account to the current layout of `T`, topping up rent from `payer`, and write it
+pub fn write_migrated<'info, T>(
+    account_info: &AccountInfo<'info>,
+    account: &T,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()>
+where
+    T: AccountSerialize + ByteLength,
+{
+    let new_len = T::LEN + 8;
+    let rent = Rent::get()?.minimum_balance(new_len);
+    let lamports = account_info.lamports();
+    if rent > lamports {
+        transfer(
+            CpiContext::new(
+                system_program.clone(),
+                Transfer {
+                    from: payer.clone(),
+                    to: account_info.clone(),
+                },
+            ),
+            rent - lamports,
+        )?;
+    }
+
+    if account_info.data_len() < new_len {
+        realloc(account_info, new_len)?;
+    }
+    account.try_serialize(&mut &mut account_info.try_borrow_mut_data()?[..])
+}
+
+/// on-chainではruntimeがdataの前に長さと余白を持っている
+/// bpfのtoolchainはtarget_arch、sbfのtoolchainはtarget_osで判定する
+#[cfg(not(all(
+    feature = "test-utils",
+    not(any(target_arch = "bpf", target_os = "solana"))
+)))]
+fn realloc(account_info: &AccountInfo, new_len: usize) -> Result<()> {
+    Ok(account_info.realloc(new_len, true)?)
+}
+
+/// ネイティブ実行(solana-program-test)ではdataの前に長さが無く`AccountInfo::realloc`は
+/// 範囲外に書いてしまうので、sliceごと差し替える。差し替えたdataは命令の後に
+/// program-testがcopyするまで生きている必要があるのでleakさせる。テスト専用
+#[cfg(all(
+    feature = "test-utils",
+    not(any(target_arch = "bpf", target_os = "solana"))
+))]
+fn realloc(account_info: &AccountInfo, new_len: usize) -> Result<()> {
+    let mut data = account_info.try_borrow_mut_data()?;
+    let mut new_data = data.to_vec();
+    new_data.resize(new_len, 0);
+    *data = Box::leak(new_data.into_boxed_slice());
+    Ok(())
+}
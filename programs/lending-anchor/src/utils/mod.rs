@@ -1,2 +1,8 @@
 pub mod byte_length;
+pub mod migration;
+pub mod referral;
+pub mod remaining_accounts;
 pub mod require;
+pub mod rewards;
+pub mod token_interface;
+pub mod versioned;
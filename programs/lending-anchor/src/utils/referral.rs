@@ -0,0 +1,73 @@
+use crate::{
+    account_data::{obligation::Obligation, referrer::ReferrerTokenState},
+    errors::LendingError,
+    utils::{
+        remaining_accounts::{load_remaining_account, update_remaining_account},
+        token_interface,
+    },
+};
+use anchor_lang::prelude::*;
+
+/// Referrer accounts the host fee of a borrow is paid through
+pub struct ReferrerAccounts<'a, 'info> {
+    pub token_state: &'a AccountInfo<'info>,
+    pub token_account: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> ReferrerAccounts<'a, 'info> {
+    /// Referrer token state and token account of `mint`, following the isolated reserve in
+    /// `remaining_accounts`. None if the obligation has no referrer.
+    pub fn load(
+        obligation: &Obligation,
+        mint: Pubkey,
+        remaining_accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Option<Self>> {
+        let referrer = match obligation.referrer {
+            Some(referrer) => referrer,
+            None => return Ok(None),
+        };
+
+        let offset = obligation.isolated_reserve.is_some() as usize;
+        let token_state = remaining_accounts
+            .get(offset)
+            .ok_or(LendingError::InvalidReferrer)?;
+        let token_account = remaining_accounts
+            .get(offset + 1)
+            .ok_or(LendingError::InvalidReferrer)?;
+
+        let state: ReferrerTokenState = load_remaining_account(token_state)?;
+        require_keys_eq!(state.referrer, referrer, LendingError::InvalidReferrer);
+        require_keys_eq!(state.mint, mint, LendingError::InvalidReferrer);
+        require_keys_eq!(
+            token_account.key(),
+            state.token_account,
+            LendingError::InvalidReferrer
+        );
+        require!(token_account.is_writable, LendingError::InvalidAccountInput);
+
+        Ok(Some(Self {
+            token_state,
+            token_account,
+        }))
+    }
+
+    /// Credit the referrer with what `pay` actually delivered to its token account
+    pub fn accrue<F>(&self, pay: F) -> Result<u64>
+    where
+        F: FnOnce(&AccountInfo<'info>) -> Result<()>,
+    {
+        let balance_before = token_interface::token_account_amount(self.token_account)?;
+        pay(self.token_account)?;
+        // transfer feeを差し引いて届いた分だけ記録する
+        let received_amount = token_interface::token_account_amount(self.token_account)?
+            .checked_sub(balance_before)
+            .ok_or(LendingError::MathOverflow)?;
+
+        update_remaining_account(
+            Some(self.token_state),
+            self.token_state.key(),
+            |state: &mut ReferrerTokenState| state.accrue(received_amount),
+        )?;
+        Ok(received_amount)
+    }
+}
@@ -0,0 +1,49 @@
+use crate::{
+    errors::LendingError,
+    utils::{migration::account_version, versioned::Versioned},
+};
+use anchor_lang::{prelude::*, Discriminator};
+
+/// Deserialize a program account passed in `remaining_accounts`
+pub fn load_remaining_account<T>(account_info: &AccountInfo) -> Result<T>
+where
+    T: AccountDeserialize + Discriminator + Owner + Versioned,
+{
+    require_keys_eq!(
+        *account_info.owner,
+        T::owner(),
+        LendingError::InvalidAccountOwner
+    );
+    // 古いlayoutはdeserializeできないので先にversionを見る
+    require_eq!(
+        account_version::<T>(account_info)?,
+        T::VERSION,
+        LendingError::OutdatedAccountVersion
+    );
+    T::try_deserialize(&mut &account_info.try_borrow_data()?[..])
+}
+
+/// Deserialize a program account passed in `remaining_accounts`, apply `f` and write it back
+pub fn update_remaining_account<T, F>(
+    account_info: Option<&AccountInfo>,
+    expected_key: Pubkey,
+    f: F,
+) -> Result<()>
+where
+    T: AccountSerialize + AccountDeserialize + Discriminator + Owner + Versioned,
+    F: FnOnce(&mut T) -> Result<()>,
+{
+    let account_info = account_info.ok_or(LendingError::InvalidAccountInput)?;
+    require_keys_eq!(
+        account_info.key(),
+        expected_key,
+        LendingError::InvalidAccountInput
+    );
+    require!(account_info.is_writable, LendingError::InvalidAccountInput);
+
+    let mut account: T = load_remaining_account(account_info)?;
+    f(&mut account)?;
+    account.try_serialize(&mut &mut account_info.try_borrow_mut_data()?[..])?;
+
+    Ok(())
+}
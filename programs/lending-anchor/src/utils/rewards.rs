@@ -0,0 +1,37 @@
+use crate::{
+    account_data::{
+        obligation::Obligation,
+        reserve::Reserve,
+        reward_pool::{RewardPool, RewardSide},
+    },
+    utils::remaining_accounts::update_remaining_account,
+};
+use anchor_lang::prelude::*;
+
+/// Settle the obligation's rewards from the pool of `reserve` on `side` after its position
+/// changed. The pool can be anywhere in `remaining_accounts` and has to be there if the
+/// reserve has one.
+pub fn update_obligation_rewards(
+    obligation: &mut Obligation,
+    reserve_key: Pubkey,
+    reserve: &Reserve,
+    side: RewardSide,
+    remaining_accounts: &[AccountInfo],
+) -> Result<()> {
+    let reward_pool_key = match reserve.reward_pool(side) {
+        Some(reward_pool_key) => reward_pool_key,
+        None => return Ok(()),
+    };
+    let staked_amount = obligation.staked_amount(reserve_key, side)?;
+    let current_slot = Clock::get()?.slot;
+    update_remaining_account(
+        remaining_accounts
+            .iter()
+            .find(|account_info| account_info.key() == reward_pool_key),
+        reward_pool_key,
+        |reward_pool: &mut RewardPool| {
+            reward_pool.accrue(current_slot)?;
+            obligation.update_reward(reward_pool_key, reward_pool, staked_amount)
+        },
+    )
+}
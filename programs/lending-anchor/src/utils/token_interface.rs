@@ -0,0 +1,391 @@
+//! Liquidity token accounts under either the legacy token program or Token-2022.
+//! Both programs share the account, mint and instruction layouts of the legacy program,
+//! and Token-2022 appends its extensions after them.
+
+use crate::{errors::LendingError, math::common::BPS_SCALE};
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        instruction::Instruction,
+        program::{invoke, invoke_signed},
+        program_pack::Pack,
+    },
+    system_program,
+};
+use anchor_spl::token::{
+    self,
+    spl_token::{
+        instruction::{sync_native, TokenInstruction},
+        state::{Account as SplAccount, Mint as SplMint},
+    },
+};
+
+pub mod token_2022 {
+    anchor_lang::declare_id!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+}
+
+/// Mint extensions are stored as type-length-value entries after the account type
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const EXTENSION_TYPE_UNINITIALIZED: u16 = 0;
+const EXTENSION_TYPE_TRANSFER_FEE_CONFIG: u16 = 1;
+const EXTENSION_TYPE_MINT_CLOSE_AUTHORITY: u16 = 3;
+const EXTENSION_TYPE_METADATA_POINTER: u16 = 18;
+const EXTENSION_TYPE_TOKEN_METADATA: u16 = 19;
+const TLV_HEADER_LEN: usize = 4;
+const TRANSFER_FEE_CONFIG_LEN: usize = 108;
+/// `TransferFeeAmount` every token account of a transfer fee mint needs
+const TRANSFER_FEE_AMOUNT_LEN: usize = 8;
+
+pub fn is_token_program(program_id: &Pubkey) -> bool {
+    *program_id == token::ID || *program_id == token_2022::ID
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TransferFee {
+    pub epoch: u64,
+    pub maximum_fee: u64,
+    pub transfer_fee_basis_points: u16,
+}
+
+impl TransferFee {
+    fn unpack(data: &[u8]) -> Self {
+        Self {
+            epoch: u64::from_le_bytes(data[..8].try_into().unwrap()),
+            maximum_fee: u64::from_le_bytes(data[8..16].try_into().unwrap()),
+            transfer_fee_basis_points: u16::from_le_bytes(data[16..18].try_into().unwrap()),
+        }
+    }
+
+    /// Fee withheld from a transfer of `amount`, rounded up
+    pub fn fee(&self, amount: u64) -> Result<u64> {
+        if self.transfer_fee_basis_points == 0 || amount == 0 {
+            return Ok(0);
+        }
+        let fee = (amount as u128)
+            .checked_mul(self.transfer_fee_basis_points as u128)
+            .and_then(|fee| fee.checked_add(BPS_SCALE as u128 - 1))
+            .ok_or(LendingError::MathOverflow)?
+            / BPS_SCALE as u128;
+        Ok(u64::try_from(fee)
+            .map_err(|_| LendingError::MathOverflow)?
+            .min(self.maximum_fee))
+    }
+
+    /// Amount to transfer for `received_amount` to arrive after the fee
+    pub fn amount_with_fee(&self, received_amount: u64) -> Result<u64> {
+        if self.transfer_fee_basis_points == 0 || received_amount == 0 {
+            return Ok(received_amount);
+        }
+        let fee = if self.transfer_fee_basis_points >= BPS_SCALE {
+            self.maximum_fee
+        } else {
+            let remaining_bps = BPS_SCALE as u128 - self.transfer_fee_basis_points as u128;
+            let amount = (received_amount as u128)
+                .checked_mul(BPS_SCALE as u128)
+                .and_then(|amount| amount.checked_add(remaining_bps - 1))
+                .ok_or(LendingError::MathOverflow)?
+                / remaining_bps;
+            u64::try_from(amount - received_amount as u128)
+                .map_err(|_| LendingError::MathOverflow)?
+                .min(self.maximum_fee)
+        };
+        received_amount
+            .checked_add(fee)
+            .ok_or(error!(LendingError::MathOverflow))
+    }
+}
+
+/// Liquidity mint with the extensions the reserve accounting depends on
+#[derive(Clone, Copy, Debug)]
+pub struct LiquidityMint {
+    pub decimals: u8,
+    /// Older and newer transfer fee, if the mint charges one
+    pub transfer_fees: Option<(TransferFee, TransferFee)>,
+}
+
+impl LiquidityMint {
+    /// Unpack a mint owned by either token program, rejecting extensions that would let
+    /// the supply move or change without the reserve knowing
+    pub fn load(mint_info: &AccountInfo) -> Result<Self> {
+        require!(
+            is_token_program(mint_info.owner),
+            LendingError::InvalidTokenProgram
+        );
+        let data = mint_info.try_borrow_data()?;
+        require_gte!(data.len(), SplMint::LEN, LendingError::InvalidAccountInput);
+        let mint = SplMint::unpack_from_slice(&data[..SplMint::LEN])?;
+        require!(mint.is_initialized, LendingError::InvalidAccountInput);
+
+        let mut transfer_fees = None;
+        if data.len() > SplMint::LEN {
+            // extensionはtoken accountと同じ長さまでpaddingした後に置かれる
+            require!(
+                data.len() > SplAccount::LEN && data[SplAccount::LEN] == ACCOUNT_TYPE_MINT,
+                LendingError::InvalidAccountInput
+            );
+            let mut tlv = &data[SplAccount::LEN + 1..];
+            while tlv.len() >= TLV_HEADER_LEN {
+                let extension_type = u16::from_le_bytes([tlv[0], tlv[1]]);
+                let length = u16::from_le_bytes([tlv[2], tlv[3]]) as usize;
+                if extension_type == EXTENSION_TYPE_UNINITIALIZED {
+                    break;
+                }
+                let value = tlv
+                    .get(TLV_HEADER_LEN..TLV_HEADER_LEN + length)
+                    .ok_or(LendingError::InvalidAccountInput)?;
+                match extension_type {
+                    EXTENSION_TYPE_TRANSFER_FEE_CONFIG => {
+                        require_gte!(
+                            value.len(),
+                            TRANSFER_FEE_CONFIG_LEN,
+                            LendingError::InvalidAccountInput
+                        );
+                        // authority 2つとwithheld amountの後にolderとnewerのfeeが続く
+                        transfer_fees = Some((
+                            TransferFee::unpack(&value[72..90]),
+                            TransferFee::unpack(&value[90..108]),
+                        ));
+                    }
+                    EXTENSION_TYPE_MINT_CLOSE_AUTHORITY
+                    | EXTENSION_TYPE_METADATA_POINTER
+                    | EXTENSION_TYPE_TOKEN_METADATA => {}
+                    _ => {
+                        msg!("Mint extension {} is not supported", extension_type);
+                        return err!(LendingError::UnsupportedMintExtension);
+                    }
+                }
+                tlv = &tlv[TLV_HEADER_LEN + length..];
+            }
+        }
+
+        Ok(Self {
+            decimals: mint.decimals,
+            transfer_fees,
+        })
+    }
+
+    /// Transfer fee in effect at `epoch`
+    pub fn transfer_fee(&self, epoch: u64) -> TransferFee {
+        match self.transfer_fees {
+            Some((_, newer)) if epoch >= newer.epoch => newer,
+            Some((older, _)) => older,
+            None => TransferFee::default(),
+        }
+    }
+
+    /// Size of a token account of this mint with the extensions the mint requires
+    pub fn token_account_len(&self) -> usize {
+        if self.transfer_fees.is_some() {
+            SplAccount::LEN + 1 + TLV_HEADER_LEN + TRANSFER_FEE_AMOUNT_LEN
+        } else {
+            SplAccount::LEN
+        }
+    }
+}
+
+/// Base state of a token account of either token program, without extensions
+fn unpack_token_account(account_info: &AccountInfo) -> Result<SplAccount> {
+    require!(
+        is_token_program(account_info.owner),
+        LendingError::InvalidTokenProgram
+    );
+    let data = account_info.try_borrow_data()?;
+    require_gte!(
+        data.len(),
+        SplAccount::LEN,
+        LendingError::InvalidAccountInput
+    );
+    Ok(SplAccount::unpack_from_slice(&data[..SplAccount::LEN])?)
+}
+
+/// Token amount held by a token account of either token program
+pub fn token_account_amount(account_info: &AccountInfo) -> Result<u64> {
+    Ok(unpack_token_account(account_info)?.amount)
+}
+
+/// Owner of a token account of either token program
+pub fn token_account_owner(account_info: &AccountInfo) -> Result<Pubkey> {
+    Ok(unpack_token_account(account_info)?.owner)
+}
+
+/// Allocate `account` for `token_program` and initialize it as a token account of `mint`.
+/// `signer_seeds` sign for `account` when it is a PDA.
+#[allow(clippy::too_many_arguments)]
+pub fn create_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    liquidity_mint: &LiquidityMint,
+    authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    rent: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let space = liquidity_mint.token_account_len();
+    system_program::create_account(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::CreateAccount {
+                from: payer.clone(),
+                to: account.clone(),
+            },
+            signer_seeds,
+        ),
+        Rent::get()?.minimum_balance(space),
+        space as u64,
+        token_program.key,
+    )?;
+    initialize_account(token_program, account, mint, authority, rent)
+}
+
+/// Initialize `account`, already allocated for `token_program`, as a token account of `mint`
+pub fn initialize_account<'info>(
+    token_program: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    rent: &AccountInfo<'info>,
+) -> Result<()> {
+    let instruction = Instruction {
+        program_id: token_program.key(),
+        accounts: vec![
+            AccountMeta::new(account.key(), false),
+            AccountMeta::new_readonly(mint.key(), false),
+            AccountMeta::new_readonly(authority.key(), false),
+            AccountMeta::new_readonly(rent.key(), false),
+        ],
+        data: TokenInstruction::InitializeAccount.pack(),
+    };
+    invoke(
+        &instruction,
+        &[
+            account.clone(),
+            mint.clone(),
+            authority.clone(),
+            rent.clone(),
+            token_program.clone(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// `transfer_checked` through whichever token program owns the mint
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_checked<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    amount: u64,
+    decimals: u8,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let instruction = Instruction {
+        program_id: token_program.key(),
+        accounts: vec![
+            AccountMeta::new(from.key(), false),
+            AccountMeta::new_readonly(mint.key(), false),
+            AccountMeta::new(to.key(), false),
+            AccountMeta::new_readonly(authority.key(), true),
+        ],
+        data: TokenInstruction::TransferChecked { amount, decimals }.pack(),
+    };
+    invoke_signed(
+        &instruction,
+        &[
+            from.clone(),
+            mint.clone(),
+            to.clone(),
+            authority.clone(),
+            token_program.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// Transfer into `to` and return how much arrived after any transfer fee
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_checked_received<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    amount: u64,
+    decimals: u8,
+) -> Result<u64> {
+    let balance_before = token_account_amount(to)?;
+    transfer_checked(
+        token_program,
+        from,
+        mint,
+        to,
+        authority,
+        amount,
+        decimals,
+        &[],
+    )?;
+    token_account_amount(to)?
+        .checked_sub(balance_before)
+        .ok_or(error!(LendingError::MathOverflow))
+}
+
+/// Wrap `lamports` of `from` into `token_account` of the native mint
+pub fn wrap_lamports<'info>(
+    from: &AccountInfo<'info>,
+    token_account: &AccountInfo<'info>,
+    lamports: u64,
+    system_program: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+) -> Result<()> {
+    system_program::transfer(
+        CpiContext::new(
+            system_program.clone(),
+            system_program::Transfer {
+                from: from.clone(),
+                to: token_account.clone(),
+            },
+        ),
+        lamports,
+    )?;
+    // lamportsを送っただけではtoken amountに反映されない
+    invoke(
+        &sync_native(token_program.key, token_account.key)?,
+        &[token_account.clone(), token_program.clone()],
+    )?;
+    Ok(())
+}
+
+/// Transfer enough into `to` for `amount` to arrive after the transfer fee of the current
+/// epoch, and return how much arrived
+pub fn transfer_checked_with_fee<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<u64> {
+    let liquidity_mint = LiquidityMint::load(mint)?;
+    let amount_with_fee = liquidity_mint
+        .transfer_fee(Clock::get()?.epoch)
+        .amount_with_fee(amount)?;
+    let received_amount = transfer_checked_received(
+        token_program,
+        from,
+        mint,
+        to,
+        authority,
+        amount_with_fee,
+        liquidity_mint.decimals,
+    )?;
+    require_gte!(
+        received_amount,
+        amount,
+        LendingError::InsufficientLiquidityReceived
+    );
+    Ok(received_amount)
+}
@@ -0,0 +1,4 @@
+pub trait Versioned {
+    /// Version of the current layout, stored right after the discriminator
+    const VERSION: u8;
+}
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+
+/// Returned by `get_obligation_health`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub struct ObligationHealth {
+    pub deposited_value: u128,
+
+    /// Borrowed value weighted by each reserve's borrow weight
+    pub borrowed_value: u128,
+
+    /// Borrowed value at face market value
+    pub unweighted_borrowed_value: u128,
+
+    pub allowed_borrow_value: u128,
+
+    pub unhealthy_borrow_value: u128,
+
+    /// Value that can still be borrowed before reaching `allowed_borrow_value`
+    pub remaining_borrow_value: u128,
+
+    /// `unhealthy_borrow_value / borrowed_value` scaled by WAD, `u128::MAX` without borrows
+    pub health_factor_wads: u128,
+
+    /// Whether `liquidate_obligation` would accept the obligation
+    pub liquidatable: bool,
+}
+
+/// Returned by `quote_borrow`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub struct BorrowQuote {
+    /// Liquidity added to the obligation's debt, including fees
+    pub borrow_amount: u128,
+
+    /// Liquidity transferred to the borrower
+    pub receive_amount: u64,
+
+    pub borrow_fee: u64,
+
+    pub host_fee: u64,
+}
+
+/// Returned by `quote_withdraw`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub struct WithdrawQuote {
+    /// Collateral transferred out of the obligation
+    pub collateral_amount: u64,
+
+    /// Liquidity the collateral redeems for at the current exchange rate
+    pub liquidity_amount: u64,
+}
+
+/// Returned by `quote_repay`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub struct RepayQuote {
+    /// Debt removed from the obligation
+    pub settle_amount: u128,
+
+    /// Liquidity transferred from the repayer
+    pub repay_amount: u64,
+}
@@ -0,0 +1,86 @@
+use anchor_lang::prelude::*;
+use lending_anchor::{
+    account_data::{
+        obligation::{
+            obligation_collateral::ObligationCollateral, obligation_liquidity::ObligationLiquidity,
+            Obligation,
+        },
+        reserve::Reserve,
+    },
+    math::{
+        common::{BPS_SCALE, WAD},
+        rate::Rate,
+    },
+};
+
+/// Reserve priced at 1 per token with every other field zeroed
+fn reserve(borrow_weight: u16) -> Reserve {
+    // 余った分は読まれない
+    let mut reserve = Reserve::deserialize(&mut &[0; 4096][..]).unwrap();
+    reserve.liquidity.market_price = 1;
+    reserve.liquidity.available_amount = 100;
+    reserve.liquidity.cumulative_borrow_rate_wads = WAD as u128;
+    reserve.config.loan_to_value_ratio = 50;
+    reserve.config.liquidation_threshold = 55;
+    reserve.config.borrow_weight = borrow_weight;
+    reserve
+}
+
+#[test]
+fn test_weighted_borrowed_value() {
+    let deposit_reserves = [reserve(BPS_SCALE)];
+    let borrow_reserves = [reserve(2 * BPS_SCALE)];
+    let mut obligation = Obligation::deserialize(&mut &[0; 4096][..]).unwrap();
+    let mut collateral = ObligationCollateral::new(Pubkey::new_unique());
+    collateral.deposit(80).unwrap();
+    obligation.deposits.push(collateral);
+    let mut liquidity = ObligationLiquidity::new(Pubkey::new_unique(), WAD as u128);
+    liquidity.borrow(10).unwrap();
+    obligation.borrows.push(liquidity);
+
+    obligation
+        .refresh(&deposit_reserves, &borrow_reserves, &[], None, None, 0)
+        .unwrap();
+    // 借入の価値だけにweightが掛かる
+    assert_eq!(obligation.deposited_value, 80);
+    assert_eq!(obligation.unweighted_borrowed_value, 10);
+    assert_eq!(obligation.borrowed_value, 20);
+    assert_eq!(obligation.allowed_borrow_value, 40);
+    assert_eq!(obligation.remaining_borrow_value().unwrap(), 20);
+}
+
+#[test]
+fn test_weighted_borrow_limit() {
+    let reserve = reserve(2 * BPS_SCALE);
+    assert_eq!(reserve.calculate_borrow(10, 20).unwrap().borrow_amount, 10);
+    assert!(reserve.calculate_borrow(11, 20).is_err());
+
+    // 全額の借入はweightで割った分になる
+    let result = reserve.calculate_borrow(u64::MAX, 20).unwrap();
+    assert_eq!(result.borrow_amount, 10);
+    assert_eq!(result.receive_amount, 10);
+}
+
+#[test]
+fn test_weighted_liquidation_bonus() {
+    let deposit_reserves = [reserve(BPS_SCALE)];
+    let borrow_reserves = [reserve(2 * BPS_SCALE)];
+    let mut obligation = Obligation::deserialize(&mut &[0; 4096][..]).unwrap();
+    let mut collateral = ObligationCollateral::new(Pubkey::new_unique());
+    collateral.deposit(100).unwrap();
+    obligation.deposits.push(collateral);
+    let mut liquidity = ObligationLiquidity::new(Pubkey::new_unique(), WAD as u128);
+    liquidity.borrow(32).unwrap();
+    obligation.borrows.push(liquidity);
+
+    obligation
+        .refresh(&deposit_reserves, &borrow_reserves, &[], None, None, 0)
+        .unwrap();
+    // weightを掛けた借入64はunhealthy borrow value 55からcollateral 100までの1/5にあたる
+    assert_eq!(obligation.unhealthy_borrow_value, 55);
+    assert_eq!(obligation.borrowed_value, 64);
+    assert_eq!(
+        obligation.liquidation_bonus(2, 12).unwrap(),
+        Rate::from_percent(4)
+    );
+}
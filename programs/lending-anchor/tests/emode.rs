@@ -0,0 +1,112 @@
+use anchor_lang::prelude::*;
+use lending_anchor::{
+    account_data::{
+        emode_category::EModeCategory,
+        lending_market::LendingMarket,
+        obligation::{
+            obligation_collateral::ObligationCollateral, obligation_liquidity::ObligationLiquidity,
+            Obligation,
+        },
+        reserve::Reserve,
+    },
+    constants::MAX_EMODE_CATEGORIES,
+    math::common::{BPS_SCALE, WAD},
+};
+
+/// Account with every field zeroed, as freshly allocated
+fn zeroed<T: AnchorDeserialize>() -> T {
+    // 余った分は読まれない
+    T::deserialize(&mut &[0; 4096][..]).unwrap()
+}
+
+fn category(id: u8) -> EModeCategory {
+    EModeCategory {
+        id,
+        loan_to_value_ratio: 90,
+        liquidation_threshold: 95,
+        liquidation_bonus: 2,
+        oracle: None,
+        min_liquidation_bonus: 2,
+    }
+}
+
+fn reserve(market_price: u128, loan_to_value_ratio: u8, liquidation_threshold: u8) -> Reserve {
+    let mut reserve: Reserve = zeroed();
+    reserve.liquidity.market_price = market_price;
+    reserve.liquidity.cumulative_borrow_rate_wads = WAD as u128;
+    reserve.config.loan_to_value_ratio = loan_to_value_ratio;
+    reserve.config.liquidation_threshold = liquidation_threshold;
+    reserve.config.borrow_weight = BPS_SCALE;
+    reserve
+}
+
+#[test]
+fn test_set_emode_category() {
+    let mut lending_market: LendingMarket = zeroed();
+    lending_market.set_emode_category(category(1)).unwrap();
+
+    // 同じidのcategoryは置き換える
+    let mut replaced = category(1);
+    replaced.loan_to_value_ratio = 80;
+    lending_market.set_emode_category(replaced).unwrap();
+    assert_eq!(lending_market.emode_categories, vec![replaced]);
+    assert_eq!(*lending_market.find_emode_category(1).unwrap(), replaced);
+    assert!(lending_market.find_emode_category(2).is_err());
+
+    for id in 2..=MAX_EMODE_CATEGORIES as u8 {
+        lending_market.set_emode_category(category(id)).unwrap();
+    }
+    assert!(lending_market
+        .set_emode_category(category(MAX_EMODE_CATEGORIES as u8 + 1))
+        .is_err());
+}
+
+#[test]
+fn test_emode_refresh() {
+    let deposit_reserves = [reserve(20, 50, 55)];
+    let borrow_reserves = [reserve(1, 0, 0)];
+    let mut obligation: Obligation = zeroed();
+    let mut collateral = ObligationCollateral::new(Pubkey::new_unique());
+    collateral.deposit(4_000_000).unwrap();
+    obligation.deposits.push(collateral);
+    let mut liquidity = ObligationLiquidity::new(Pubkey::new_unique(), WAD as u128);
+    liquidity.borrow(10_000_000).unwrap();
+    obligation.borrows.push(liquidity);
+
+    obligation
+        .refresh(&deposit_reserves, &borrow_reserves, &[], None, None, 0)
+        .unwrap();
+    assert_eq!(obligation.deposited_value, 80_000_000);
+    assert_eq!(obligation.borrowed_value, 10_000_000);
+    assert_eq!(obligation.allowed_borrow_value, 40_000_000);
+    assert_eq!(obligation.unhealthy_borrow_value, 44_000_000);
+
+    // categoryのLTVとthresholdが各reserveの値の代わりに使われる
+    obligation
+        .refresh(
+            &deposit_reserves,
+            &borrow_reserves,
+            &[],
+            Some(&category(1)),
+            None,
+            0,
+        )
+        .unwrap();
+    assert_eq!(obligation.allowed_borrow_value, 72_000_000);
+    assert_eq!(obligation.unhealthy_borrow_value, 76_000_000);
+
+    // 共通のoracleがあれば全てその価格で評価する
+    obligation
+        .refresh(
+            &deposit_reserves,
+            &borrow_reserves,
+            &[],
+            Some(&category(1)),
+            Some(2),
+            0,
+        )
+        .unwrap();
+    assert_eq!(obligation.deposited_value, 8_000_000);
+    assert_eq!(obligation.borrowed_value, 20_000_000);
+    assert_eq!(obligation.allowed_borrow_value, 7_200_000);
+}
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AnchorDeserialize, Discriminator, Event};
+use lending_anchor::{
+    account_data::reserve::Reserve,
+    events::{BorrowObligationLiquidityEvent, ReserveSnapshot},
+    math::common::WAD,
+};
+
+/// Reserve with every field zeroed, as a freshly allocated account
+fn zeroed_reserve() -> Reserve {
+    // 余った分は読まれない
+    Reserve::deserialize(&mut &[0; 4096][..]).unwrap()
+}
+
+#[test]
+fn test_reserve_snapshot() {
+    let mut reserve = zeroed_reserve();
+    reserve.liquidity.market_price = 20;
+    assert_eq!(reserve.deposit_liquidity(1_000).unwrap(), 1_000);
+    reserve.liquidity.borrowed_amount = 500;
+
+    let snapshot = ReserveSnapshot::new(&reserve).unwrap();
+    // 借入の分だけcollateral 1つあたりのliquidityが増える
+    assert_eq!(
+        snapshot.collateral_exchange_rate,
+        WAD as u128 * 1_000 / 1_500
+    );
+    assert_eq!(snapshot.available_amount, 1_000);
+    assert_eq!(snapshot.borrowed_amount, 500);
+    assert_eq!(snapshot.collateral_mint_total_supply, 1_000);
+    assert_eq!(snapshot.market_price, 20);
+}
+
+#[test]
+fn test_event_log_data() {
+    let mut reserve = zeroed_reserve();
+    reserve.deposit_liquidity(1_000).unwrap();
+    let event = BorrowObligationLiquidityEvent {
+        lending_market: Pubkey::new_unique(),
+        obligation: Pubkey::new_unique(),
+        reserve: Pubkey::new_unique(),
+        borrow_amount: 101,
+        receive_amount: 100,
+        borrow_fee: 1,
+        host_fee: 0,
+        referrer: None,
+        reserve_state: ReserveSnapshot::new(&reserve).unwrap(),
+    };
+
+    // clientはdiscriminatorで種類を見分けて残りをdecodeする
+    let data = event.data();
+    assert_eq!(data[..8], BorrowObligationLiquidityEvent::discriminator());
+    let decoded = BorrowObligationLiquidityEvent::try_from_slice(&data[8..]).unwrap();
+    assert_eq!(decoded.obligation, event.obligation);
+    assert_eq!(decoded.borrow_amount, 101);
+    assert_eq!(decoded.receive_amount, 100);
+    assert_eq!(decoded.borrow_fee, 1);
+    assert_eq!(decoded.reserve_state.available_amount, 1_000);
+    assert_eq!(decoded.reserve_state.collateral_mint_total_supply, 1_000);
+}
@@ -0,0 +1,636 @@
+#![allow(dead_code)]
+
+mod native_cpi;
+
+use anchor_lang::{prelude::*, solana_program::program_pack::Pack, InstructionData};
+use anchor_spl::{associated_token::get_associated_token_address, token::spl_token};
+use lending_anchor::{
+    account_data::{
+        lending_market::LendingMarket,
+        obligation::Obligation,
+        reserve::{
+            borrow_rate_point::BorrowRatePoint, reserve_config::ReserveConfig,
+            reserve_fees::ReserveFees, Reserve,
+        },
+    },
+    errors::LendingError,
+    test_utils::{PythPrice, PythProduct},
+};
+use lending_client::instruction;
+use mock_oracle::OracleStatus;
+use pyth_sdk_solana::state::{PriceAccount, ProductAccount};
+use solana_program_test::{processor, tokio, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::{Transaction, TransactionError},
+    transport::TransportError,
+};
+use std::mem::size_of;
+
+pub const QUOTE_CURRENCY: &str = "USD";
+pub const LIQUIDITY_DECIMALS: u8 = 6;
+
+/// Owner of the synthesized Pyth accounts, registered as the market's oracle program
+pub fn oracle_program_id() -> Pubkey {
+    mock_oracle::ID
+}
+
+pub fn quote_currency() -> [u8; 32] {
+    let mut quote_currency = [0u8; 32];
+    quote_currency[..QUOTE_CURRENCY.len()].copy_from_slice(QUOTE_CURRENCY.as_bytes());
+    quote_currency
+}
+
+pub fn program_test() -> ProgramTest {
+    let mut test = ProgramTest::new(
+        "lending_anchor",
+        lending_anchor::ID,
+        processor!(lending_anchor::entry),
+    );
+    // The bundled BPF token program crashes the 1.9 interpreter on current toolchains
+    test.add_program(
+        "spl_token",
+        spl_token::ID,
+        processor!(spl_token::processor::Processor::process),
+    );
+    test.add_program(
+        "mock_oracle",
+        mock_oracle::ID,
+        processor!(mock_oracle::entry),
+    );
+    test
+}
+
+/// Start the test validator with CPIs the native program makes supported
+pub async fn start(test: ProgramTest) -> ProgramTestContext {
+    let mut ctx = test.start_with_context().await;
+    native_cpi::install(&mut ctx).await;
+    ctx
+}
+
+pub fn test_reserve_config() -> ReserveConfig {
+    ReserveConfig {
+        loan_to_value_ratio: 50,
+        liquidation_bonus: 5,
+        liquidation_threshold: 55,
+        borrow_rate_curve: vec![
+            BorrowRatePoint::new(0, 0),
+            BorrowRatePoint::new(8_000, 400),
+            BorrowRatePoint::new(10_000, 3_000),
+        ],
+        fees: ReserveFees {
+            borrow_fee_wad: 0,
+            flash_loan_fee_wad: 0,
+            host_fee_percentage: 0,
+        },
+        isolated: false,
+        debt_ceiling: 0,
+        borrowable_in_isolation: false,
+        emode_category: 0,
+        borrow_weight: 10_000,
+        price_guard_bps_per_slot: 0,
+        price_guard_confirmations: 0,
+        twap_fallback_max_slots: 0,
+        twap_liquidation_bonus: 0,
+        term_offers: vec![],
+        term_penalty_rate_bps: 0,
+        insurance_interest_bps: 0,
+        insurance_liquidation_bps: 0,
+        min_liquidation_bonus: 5,
+    }
+}
+
+/// In-process Pyth product and price accounts
+#[derive(Clone)]
+pub struct TestOracle {
+    pub product: Pubkey,
+    pub price: Pubkey,
+}
+
+impl TestOracle {
+    /// Price in whole quote units, published at `valid_slot`
+    pub fn add(test: &mut ProgramTest, price: i64, valid_slot: u64) -> Self {
+        let oracle = Self {
+            product: Pubkey::new_unique(),
+            price: Pubkey::new_unique(),
+        };
+        test.add_account(oracle.product, oracle.product_account());
+        test.add_account(oracle.price, oracle.price_account(price, valid_slot));
+        oracle
+    }
+
+    /// Create the accounts on-chain through the mock oracle's `init_oracle`
+    pub async fn create(ctx: &mut ProgramTestContext, price: i64) -> Self {
+        let product = Keypair::new();
+        let price_account = Keypair::new();
+        let rent = ctx.banks_client.get_rent().await.unwrap();
+        let payer = ctx.payer.pubkey();
+        let create = |account: &Keypair, space: usize| {
+            system_instruction::create_account(
+                &payer,
+                &account.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &mock_oracle::ID,
+            )
+        };
+        let init_ix = Instruction {
+            program_id: mock_oracle::ID,
+            accounts: mock_oracle::accounts::InitOracle {
+                product: product.pubkey(),
+                price: price_account.pubkey(),
+            }
+            .to_account_metas(None),
+            data: mock_oracle::instruction::InitOracle {
+                quote_currency: QUOTE_CURRENCY.to_string(),
+                price: price * 100_000_000,
+                expo: -8,
+                conf: 0,
+            }
+            .data(),
+        };
+        process(
+            ctx,
+            &[
+                create(&product, size_of::<ProductAccount>()),
+                create(&price_account, size_of::<PriceAccount>()),
+                init_ix,
+            ],
+            &[&product, &price_account],
+        )
+        .await
+        .unwrap();
+        Self {
+            product: product.pubkey(),
+            price: price_account.pubkey(),
+        }
+    }
+
+    pub fn product_account(&self) -> Account {
+        oracle_account(PythProduct::new(self.price, QUOTE_CURRENCY).to_bytes())
+    }
+
+    pub fn price_account(&self, price: i64, valid_slot: u64) -> Account {
+        oracle_account(PythPrice::new(self.product, price * 100_000_000, -8, valid_slot).to_bytes())
+    }
+
+    /// Publish a new price at the current slot through the mock oracle
+    pub async fn set_price(&self, ctx: &mut ProgramTestContext, price: i64) {
+        let ix = Instruction {
+            program_id: mock_oracle::ID,
+            accounts: mock_oracle::accounts::UpdatePrice { price: self.price }
+                .to_account_metas(None),
+            data: mock_oracle::instruction::SetPrice {
+                price: price * 100_000_000,
+                conf: 0,
+            }
+            .data(),
+        };
+        process(ctx, &[ix], &[]).await.unwrap();
+    }
+
+    pub async fn set_status(&self, ctx: &mut ProgramTestContext, status: OracleStatus) {
+        let ix = Instruction {
+            program_id: mock_oracle::ID,
+            accounts: mock_oracle::accounts::UpdatePrice { price: self.price }
+                .to_account_metas(None),
+            data: mock_oracle::instruction::SetStatus { status }.data(),
+        };
+        process(ctx, &[ix], &[]).await.unwrap();
+    }
+}
+
+fn oracle_account(data: Vec<u8>) -> Account {
+    Account {
+        lamports: u32::MAX as u64,
+        data,
+        owner: oracle_program_id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+pub fn clone_keypair(keypair: &Keypair) -> Keypair {
+    Keypair::from_bytes(&keypair.to_bytes()).unwrap()
+}
+
+pub async fn process(
+    ctx: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> std::result::Result<(), TransportError> {
+    let payer = clone_keypair(&ctx.payer);
+    let mut all_signers = vec![&payer];
+    all_signers.extend_from_slice(signers);
+    let blockhash = ctx.banks_client.get_latest_blockhash().await?;
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    ctx.banks_client.process_transaction(transaction).await
+}
+
+/// Process a transaction ending with a view instruction and decode what it returned
+pub async fn process_view<T: AnchorDeserialize>(
+    ctx: &mut ProgramTestContext,
+    instructions: &[Instruction],
+) -> std::result::Result<T, TransportError> {
+    static VIEW: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+    let _view = VIEW.lock().await;
+    native_cpi::take_return_data();
+    process(ctx, instructions, &[]).await?;
+    let data = native_cpi::take_return_data().expect("no return data");
+    Ok(T::try_from_slice(&data).unwrap())
+}
+
+/// Process a transaction and decode the events of type `T` logged meanwhile. Other tests
+/// run concurrently, so callers pick out theirs by account.
+pub async fn process_events<T: anchor_lang::Event>(
+    ctx: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> std::result::Result<Vec<T>, TransportError> {
+    static EVENTS: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+    let _events = EVENTS.lock().await;
+    native_cpi::take_logged_data();
+    process(ctx, instructions, signers).await?;
+    Ok(native_cpi::take_logged_data()
+        .iter()
+        .filter(|data| data.starts_with(&T::discriminator()))
+        .map(|data| T::try_from_slice(&data[8..]).unwrap())
+        .collect())
+}
+
+pub fn assert_lending_error(result: std::result::Result<(), TransportError>, error: LendingError) {
+    let code = u32::from(error);
+    match result {
+        Err(TransportError::TransactionError(TransactionError::InstructionError(
+            _,
+            InstructionError::Custom(actual),
+        ))) => assert_eq!(actual, code, "expected {}, got {}", error, actual),
+        other => panic!("expected {}, got {:?}", error, other),
+    }
+}
+
+pub async fn get_account(ctx: &mut ProgramTestContext, pubkey: &Pubkey) -> Account {
+    ctx.banks_client
+        .get_account(*pubkey)
+        .await
+        .unwrap()
+        .expect("account not found")
+}
+
+pub async fn get_state<T: AccountDeserialize>(ctx: &mut ProgramTestContext, pubkey: &Pubkey) -> T {
+    let account = get_account(ctx, pubkey).await;
+    T::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+pub async fn token_balance(ctx: &mut ProgramTestContext, pubkey: &Pubkey) -> u64 {
+    let account = get_account(ctx, pubkey).await;
+    spl_token::state::Account::unpack(&account.data)
+        .unwrap()
+        .amount
+}
+
+pub async fn create_mint(ctx: &mut ProgramTestContext, authority: &Pubkey) -> Pubkey {
+    let mint = Keypair::new();
+    let rent = ctx.banks_client.get_rent().await.unwrap();
+    process(
+        ctx,
+        &[
+            system_instruction::create_account(
+                &ctx.payer.pubkey(),
+                &mint.pubkey(),
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint(
+                &spl_token::ID,
+                &mint.pubkey(),
+                authority,
+                None,
+                LIQUIDITY_DECIMALS,
+            )
+            .unwrap(),
+        ],
+        &[&mint],
+    )
+    .await
+    .unwrap();
+    mint.pubkey()
+}
+
+pub async fn create_token_account(
+    ctx: &mut ProgramTestContext,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> Pubkey {
+    let account = Keypair::new();
+    let rent = ctx.banks_client.get_rent().await.unwrap();
+    process(
+        ctx,
+        &[
+            system_instruction::create_account(
+                &ctx.payer.pubkey(),
+                &account.pubkey(),
+                rent.minimum_balance(spl_token::state::Account::LEN),
+                spl_token::state::Account::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_account(
+                &spl_token::ID,
+                &account.pubkey(),
+                mint,
+                owner,
+            )
+            .unwrap(),
+        ],
+        &[&account],
+    )
+    .await
+    .unwrap();
+    account.pubkey()
+}
+
+/// Wrapped SOL account holding `amount`, funded by the payer
+pub async fn create_native_token_account(
+    ctx: &mut ProgramTestContext,
+    owner: &Pubkey,
+    amount: u64,
+) -> Pubkey {
+    let account = Keypair::new();
+    let rent = ctx.banks_client.get_rent().await.unwrap();
+    process(
+        ctx,
+        &[
+            system_instruction::create_account(
+                &ctx.payer.pubkey(),
+                &account.pubkey(),
+                rent.minimum_balance(spl_token::state::Account::LEN) + amount,
+                spl_token::state::Account::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_account(
+                &spl_token::ID,
+                &account.pubkey(),
+                &spl_token::native_mint::ID,
+                owner,
+            )
+            .unwrap(),
+        ],
+        &[&account],
+    )
+    .await
+    .unwrap();
+    account.pubkey()
+}
+
+/// Mint `amount` with the payer as mint authority
+pub async fn mint_to(ctx: &mut ProgramTestContext, mint: &Pubkey, account: &Pubkey, amount: u64) {
+    let payer = ctx.payer.pubkey();
+    process(
+        ctx,
+        &[
+            spl_token::instruction::mint_to(&spl_token::ID, mint, account, &payer, &[], amount)
+                .unwrap(),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+}
+
+pub struct TestLendingMarket {
+    pub pubkey: Pubkey,
+    pub owner: Keypair,
+}
+
+impl TestLendingMarket {
+    /// Market owned by the payer
+    pub async fn init(ctx: &mut ProgramTestContext) -> Self {
+        let keypair = Keypair::new();
+        let owner = clone_keypair(&ctx.payer);
+        process(
+            ctx,
+            &[instruction::init_lending_market(
+                owner.pubkey(),
+                keypair.pubkey(),
+                oracle_program_id(),
+                quote_currency(),
+            )],
+            &[&keypair],
+        )
+        .await
+        .unwrap();
+        Self {
+            pubkey: keypair.pubkey(),
+            owner,
+        }
+    }
+
+    pub async fn get_state(&self, ctx: &mut ProgramTestContext) -> LendingMarket {
+        get_state(ctx, &self.pubkey).await
+    }
+
+    pub async fn bump_seed(&self, ctx: &mut ProgramTestContext) -> u8 {
+        self.get_state(ctx).await.bump_seed
+    }
+}
+
+#[derive(Clone)]
+pub struct TestReserve {
+    pub pubkey: Pubkey,
+    pub liquidity_mint: Pubkey,
+    /// Payer's liquidity and collateral token accounts
+    pub user_liquidity: Pubkey,
+    pub user_collateral: Pubkey,
+}
+
+impl TestReserve {
+    /// Reserve over a new liquidity mint, funding the payer with `user_liquidity_amount`
+    pub async fn init(
+        ctx: &mut ProgramTestContext,
+        lending_market: &TestLendingMarket,
+        oracle: &TestOracle,
+        config: ReserveConfig,
+        liquidity_amount: u64,
+        user_liquidity_amount: u64,
+    ) -> std::result::Result<Self, TransportError> {
+        let payer = ctx.payer.pubkey();
+        let liquidity_mint = create_mint(ctx, &payer).await;
+        let user_liquidity = create_token_account(ctx, &liquidity_mint, &payer).await;
+        mint_to(
+            ctx,
+            &liquidity_mint,
+            &user_liquidity,
+            liquidity_amount + user_liquidity_amount,
+        )
+        .await;
+        Self::init_with_liquidity(
+            ctx,
+            lending_market,
+            oracle,
+            config,
+            liquidity_mint,
+            user_liquidity,
+            liquidity_amount,
+        )
+        .await
+    }
+
+    /// Native SOL reserve, funding the payer's wrapped SOL account with `user_liquidity_amount`
+    pub async fn init_native(
+        ctx: &mut ProgramTestContext,
+        lending_market: &TestLendingMarket,
+        oracle: &TestOracle,
+        config: ReserveConfig,
+        liquidity_amount: u64,
+        user_liquidity_amount: u64,
+    ) -> std::result::Result<Self, TransportError> {
+        let payer = ctx.payer.pubkey();
+        let user_liquidity =
+            create_native_token_account(ctx, &payer, liquidity_amount + user_liquidity_amount)
+                .await;
+        Self::init_with_liquidity(
+            ctx,
+            lending_market,
+            oracle,
+            config,
+            spl_token::native_mint::ID,
+            user_liquidity,
+            liquidity_amount,
+        )
+        .await
+    }
+
+    async fn init_with_liquidity(
+        ctx: &mut ProgramTestContext,
+        lending_market: &TestLendingMarket,
+        oracle: &TestOracle,
+        config: ReserveConfig,
+        liquidity_mint: Pubkey,
+        user_liquidity: Pubkey,
+        liquidity_amount: u64,
+    ) -> std::result::Result<Self, TransportError> {
+        let payer = ctx.payer.pubkey();
+        let reserve = Keypair::new();
+        let liquidity_supply = Keypair::new();
+        let liquidity_fee_receiver = Keypair::new();
+        let collateral_mint = Keypair::new();
+        let collateral_supply = Keypair::new();
+        let bump_seed = lending_market.bump_seed(ctx).await;
+        process(
+            ctx,
+            &[instruction::init_reserve(
+                liquidity_amount,
+                config,
+                user_liquidity,
+                reserve.pubkey(),
+                liquidity_mint,
+                spl_token::ID,
+                liquidity_supply.pubkey(),
+                liquidity_fee_receiver.pubkey(),
+                collateral_mint.pubkey(),
+                collateral_supply.pubkey(),
+                oracle.product,
+                oracle.price,
+                lending_market.pubkey,
+                bump_seed,
+                lending_market.owner.pubkey(),
+                payer,
+            )
+            .unwrap()],
+            &[
+                &reserve,
+                &liquidity_supply,
+                &liquidity_fee_receiver,
+                &collateral_mint,
+                &collateral_supply,
+            ],
+        )
+        .await?;
+
+        Ok(Self {
+            pubkey: reserve.pubkey(),
+            liquidity_mint,
+            user_liquidity,
+            user_collateral: get_associated_token_address(&payer, &collateral_mint.pubkey()),
+        })
+    }
+
+    pub async fn get_state(&self, ctx: &mut ProgramTestContext) -> Reserve {
+        get_state(ctx, &self.pubkey).await
+    }
+
+    pub async fn refresh_ix(&self, ctx: &mut ProgramTestContext) -> Instruction {
+        instruction::refresh_reserve(self.pubkey, &self.get_state(ctx).await)
+    }
+}
+
+pub struct TestObligation {
+    pub pubkey: Pubkey,
+}
+
+impl TestObligation {
+    /// Obligation owned by the payer
+    pub async fn init(ctx: &mut ProgramTestContext, lending_market: &TestLendingMarket) -> Self {
+        let keypair = Keypair::new();
+        let payer = ctx.payer.pubkey();
+        process(
+            ctx,
+            &[instruction::init_obligation(
+                keypair.pubkey(),
+                lending_market.pubkey,
+                payer,
+            )],
+            &[&keypair],
+        )
+        .await
+        .unwrap();
+        Self {
+            pubkey: keypair.pubkey(),
+        }
+    }
+
+    pub async fn get_state(&self, ctx: &mut ProgramTestContext) -> Obligation {
+        get_state(ctx, &self.pubkey).await
+    }
+
+    /// Refresh instructions for every reserve of the obligation followed by the obligation
+    pub async fn refresh_ixs(&self, ctx: &mut ProgramTestContext) -> Vec<Instruction> {
+        let obligation = self.get_state(ctx).await;
+        let mut instructions = vec![];
+        for reserve in obligation
+            .deposits
+            .iter()
+            .map(|collateral| collateral.deposit_reserve)
+            .chain(
+                obligation
+                    .borrows
+                    .iter()
+                    .map(|liquidity| liquidity.borrow_reserve),
+            )
+            .chain(
+                obligation
+                    .term_loans
+                    .iter()
+                    .map(|term_loan| term_loan.borrow_reserve),
+            )
+        {
+            let reserve_state: Reserve = get_state(ctx, &reserve).await;
+            instructions.push(instruction::refresh_reserve(reserve, &reserve_state));
+        }
+        instructions.push(instruction::refresh_obligation(
+            self.pubkey,
+            &obligation,
+            None,
+        ));
+        instructions
+    }
+}
@@ -0,0 +1,253 @@
+//! solana-program-test 1.9 runs the program natively, and its invoke stub can neither find a
+//! callee program Anchor leaves out of the CPI account infos nor resize an account.
+//! These stubs wrap the ones installed by program-test to cover both for the program's CPIs,
+//! and record return data and logged data since its BanksClient has no way to read them back.
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        entrypoint::ProgramResult,
+        instruction::Instruction,
+        program_pack::Pack,
+        program_stubs::{self, SyscallStubs},
+        program_utils::limited_deserialize,
+        system_instruction::{self, SystemInstruction},
+        system_program,
+    },
+};
+use anchor_spl::{associated_token, token::spl_token};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::account::Account;
+use std::{
+    collections::HashMap,
+    sync::{Mutex, Once},
+};
+
+/// Programs the lending program invokes
+const CPI_PROGRAMS: [Pubkey; 3] = [system_program::ID, spl_token::ID, associated_token::ID];
+
+/// Data last returned by a program. The stubs are shared by every test and banks run
+/// transactions on their own threads, so readers have to take turns.
+static RETURN_DATA: Mutex<Option<Vec<u8>>> = Mutex::new(None);
+
+pub fn take_return_data() -> Option<Vec<u8>> {
+    RETURN_DATA.lock().unwrap().take()
+}
+
+/// Data logged by programs since it was last taken, shared the same way as the return data
+static LOGGED_DATA: Mutex<Vec<Vec<u8>>> = Mutex::new(Vec::new());
+
+pub fn take_logged_data() -> Vec<Vec<u8>> {
+    std::mem::take(&mut *LOGGED_DATA.lock().unwrap())
+}
+
+/// Install the stubs once program-test has installed its own
+pub async fn install(ctx: &mut ProgramTestContext) {
+    static INSTALL: Once = Once::new();
+    if INSTALL.is_completed() {
+        return;
+    }
+
+    let mut programs = HashMap::new();
+    for program_id in CPI_PROGRAMS {
+        let account = ctx
+            .banks_client
+            .get_account(program_id)
+            .await
+            .unwrap()
+            .expect("program not found");
+        programs.insert(program_id, account);
+    }
+    INSTALL.call_once(|| {
+        let inner = program_stubs::set_syscall_stubs(Box::new(DefaultStubs));
+        program_stubs::set_syscall_stubs(Box::new(NativeCpiStubs { inner, programs }));
+    });
+}
+
+struct DefaultStubs;
+impl SyscallStubs for DefaultStubs {}
+
+struct NativeCpiStubs {
+    inner: Box<dyn SyscallStubs>,
+    /// Program accounts passed along when a CPI leaves them out
+    programs: HashMap<Pubkey, Account>,
+}
+
+impl NativeCpiStubs {
+    fn invoke<'a>(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo<'a>],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let mut account_infos = account_infos.to_vec();
+        if !account_infos
+            .iter()
+            .any(|info| info.key == &instruction.program_id)
+        {
+            let program = &self.programs[&instruction.program_id];
+            // program-test copies the data back into the program account, so it has to match
+            account_infos.push(AccountInfo::new(
+                Box::leak(Box::new(instruction.program_id)),
+                false,
+                false,
+                Box::leak(Box::new(program.lamports)),
+                Box::leak(program.data.clone().into_boxed_slice()),
+                Box::leak(Box::new(program.owner)),
+                true,
+                program.rent_epoch,
+            ));
+        }
+        self.inner
+            .sol_invoke_signed(instruction, &account_infos, signers_seeds)
+    }
+
+    /// The lending program owns the account between assigning and handing it over,
+    /// which lets it resize the data itself.
+    fn create_account<'a>(
+        &self,
+        from: &AccountInfo<'a>,
+        to: &AccountInfo<'a>,
+        lamports: u64,
+        space: u64,
+        owner: &Pubkey,
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        if lamports > 0 {
+            self.invoke(
+                &system_instruction::transfer(from.key, to.key, lamports),
+                &[from.clone(), to.clone()],
+                signers_seeds,
+            )?;
+        }
+        let mut to = to.clone();
+        to.is_signer = true;
+        self.invoke(
+            &system_instruction::assign(to.key, &lending_anchor::ID),
+            &[to.clone()],
+            signers_seeds,
+        )?;
+
+        *to.data.borrow_mut() = Box::leak(vec![0; space as usize].into_boxed_slice());
+        // program-test hands over owners the same way after a CPI
+        #[allow(mutable_transmutes)]
+        let to_owner = unsafe { std::mem::transmute::<&Pubkey, &mut Pubkey>(to.owner) };
+        *to_owner = *owner;
+        Ok(())
+    }
+
+    fn create_associated_token_account<'a>(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo<'a>],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let find = |index: usize| {
+            let key = &instruction.accounts[index].pubkey;
+            account_infos
+                .iter()
+                .find(|info| info.key == key)
+                .ok_or(ProgramError::NotEnoughAccountKeys)
+        };
+        let (payer, account, wallet, mint, rent) =
+            (find(0)?, find(1)?, find(2)?, find(3)?, find(6)?);
+
+        let space = spl_token::state::Account::LEN;
+        self.create_account(
+            payer,
+            account,
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            &spl_token::ID,
+            signers_seeds,
+        )?;
+        self.invoke(
+            &spl_token::instruction::initialize_account(
+                &spl_token::ID,
+                account.key,
+                mint.key,
+                wallet.key,
+            )?,
+            &[account.clone(), mint.clone(), wallet.clone(), rent.clone()],
+            signers_seeds,
+        )
+    }
+}
+
+impl SyscallStubs for NativeCpiStubs {
+    fn sol_log(&self, message: &str) {
+        self.inner.sol_log(message)
+    }
+
+    fn sol_log_compute_units(&self) {
+        self.inner.sol_log_compute_units()
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        if instruction.program_id == associated_token::ID {
+            return self.create_associated_token_account(instruction, account_infos, signers_seeds);
+        }
+        if instruction.program_id == system_program::ID {
+            if let Ok(SystemInstruction::CreateAccount {
+                lamports,
+                space,
+                owner,
+            }) = limited_deserialize(&instruction.data, instruction.data.len() as u64)
+            {
+                let find = |key: &Pubkey| {
+                    account_infos
+                        .iter()
+                        .find(|info| info.key == key)
+                        .ok_or(ProgramError::NotEnoughAccountKeys)
+                };
+                return self.create_account(
+                    find(&instruction.accounts[0].pubkey)?,
+                    find(&instruction.accounts[1].pubkey)?,
+                    lamports,
+                    space,
+                    &owner,
+                    signers_seeds,
+                );
+            }
+        }
+        self.invoke(instruction, account_infos, signers_seeds)
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_clock_sysvar(var_addr)
+    }
+
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_epoch_schedule_sysvar(var_addr)
+    }
+
+    fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_fees_sysvar(var_addr)
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_rent_sysvar(var_addr)
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.inner.sol_get_return_data()
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        *RETURN_DATA.lock().unwrap() = Some(data.to_vec());
+        self.inner.sol_set_return_data(data)
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        LOGGED_DATA
+            .lock()
+            .unwrap()
+            .extend(fields.iter().map(|field| field.to_vec()));
+        self.inner.sol_log_data(fields)
+    }
+}
@@ -0,0 +1,86 @@
+use anchor_lang::{error::Error, prelude::*};
+use lending_anchor::{
+    account_data::{
+        obligation::{obligation_collateral::ObligationCollateral, Obligation},
+        reserve::Reserve,
+    },
+    errors::LendingError,
+};
+
+fn assert_error<T: std::fmt::Debug>(result: Result<T>, error: LendingError) {
+    assert_eq!(
+        ProgramError::from(result.unwrap_err()),
+        ProgramError::from(Error::from(error))
+    );
+}
+
+#[test]
+fn test_collateral_isolation() {
+    // 余った分は読まれない
+    let mut obligation = Obligation::deserialize(&mut &[0; 4096][..]).unwrap();
+    let isolated_reserve = Pubkey::new_unique();
+    let other_reserve = Pubkey::new_unique();
+
+    // 空のobligationにはどちらもdepositできる
+    obligation
+        .check_collateral_isolation(isolated_reserve, true)
+        .unwrap();
+    obligation
+        .check_collateral_isolation(other_reserve, false)
+        .unwrap();
+
+    // 他のcollateralがあるとisolated collateralはdepositできない
+    obligation
+        .deposits
+        .push(ObligationCollateral::new(other_reserve));
+    assert_error(
+        obligation.check_collateral_isolation(isolated_reserve, true),
+        LendingError::IsolatedCollateral,
+    );
+
+    // isolated collateralがあると他のcollateralはdepositできない
+    obligation.deposits = vec![ObligationCollateral::new(isolated_reserve)];
+    obligation.isolated_reserve = Some(isolated_reserve);
+    obligation
+        .check_collateral_isolation(isolated_reserve, true)
+        .unwrap();
+    assert_error(
+        obligation.check_collateral_isolation(other_reserve, false),
+        LendingError::IsolatedCollateral,
+    );
+}
+
+#[test]
+fn test_debt_ceiling() {
+    let mut reserve = Reserve::deserialize(&mut &[0; 4096][..]).unwrap();
+    reserve.config.isolated = true;
+    reserve.config.debt_ceiling = 100;
+    let mut borrow_reserve = Reserve::deserialize(&mut &[0; 4096][..]).unwrap();
+    borrow_reserve.liquidity.market_price = 1;
+    let borrow_reserve_key = Pubkey::new_unique();
+
+    reserve
+        .add_isolated_debt(borrow_reserve_key, &borrow_reserve, 60)
+        .unwrap();
+    reserve
+        .add_isolated_debt(borrow_reserve_key, &borrow_reserve, 40)
+        .unwrap();
+    assert_eq!(reserve.isolated_debt_value().unwrap(), 100);
+
+    // 返済で空いた分はまた借りられる
+    reserve
+        .remove_isolated_debt(borrow_reserve_key, &borrow_reserve, 30)
+        .unwrap();
+    reserve
+        .add_isolated_debt(borrow_reserve_key, &borrow_reserve, 30)
+        .unwrap();
+    assert_error(
+        reserve.add_isolated_debt(borrow_reserve_key, &borrow_reserve, 1),
+        LendingError::DebtCeilingExceeded,
+    );
+    reserve
+        .remove_isolated_debt(borrow_reserve_key, &borrow_reserve, 200)
+        .unwrap();
+    assert!(reserve.isolated_debts.is_empty());
+    assert_eq!(reserve.isolated_debt_value().unwrap(), 0);
+}
@@ -0,0 +1,197 @@
+mod helpers;
+
+use helpers::*;
+use lending_anchor::{
+    account_data::emode_category::EModeCategory, constants::LIQUIDATION_CLOSE_FACTOR,
+    errors::LendingError,
+};
+use lending_client::{instruction, pda::find_lending_market_authority};
+use solana_program_test::tokio;
+use solana_sdk::signature::{Keypair, Signer};
+
+fn test_emode_category() -> EModeCategory {
+    EModeCategory {
+        id: 1,
+        loan_to_value_ratio: 90,
+        liquidation_threshold: 95,
+        liquidation_bonus: 2,
+        oracle: None,
+        min_liquidation_bonus: 2,
+    }
+}
+
+#[tokio::test]
+async fn test_init_lending_market() {
+    let mut ctx = start(program_test()).await;
+    let lending_market = TestLendingMarket::init(&mut ctx).await;
+
+    let state = lending_market.get_state(&mut ctx).await;
+    assert_eq!(state.owner, ctx.payer.pubkey());
+    assert_eq!(state.quote_currency, quote_currency());
+    assert_eq!(state.oracle_program_id, oracle_program_id());
+    assert_eq!(state.token_program_id, anchor_spl::token::ID);
+    assert_eq!(
+        state.bump_seed,
+        find_lending_market_authority(&lending_market.pubkey).1
+    );
+    assert!(state.emode_categories.is_empty());
+    assert_eq!(state.liquidation_close_factor, LIQUIDATION_CLOSE_FACTOR);
+    assert_eq!(state.liquidation_full_close_value, 0);
+    assert!(state.liquidation_pairs.is_empty());
+}
+
+#[tokio::test]
+async fn test_set_lending_market_owner() {
+    let mut ctx = start(program_test()).await;
+    let lending_market = TestLendingMarket::init(&mut ctx).await;
+    let new_owner = Keypair::new();
+
+    process(
+        &mut ctx,
+        &[instruction::set_lending_market_owner(
+            lending_market.pubkey,
+            lending_market.owner.pubkey(),
+            new_owner.pubkey(),
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        lending_market.get_state(&mut ctx).await.owner,
+        new_owner.pubkey()
+    );
+
+    // 前のownerはもう変更できない
+    let result = process(
+        &mut ctx,
+        &[instruction::set_lending_market_owner(
+            lending_market.pubkey,
+            lending_market.owner.pubkey(),
+            lending_market.owner.pubkey(),
+        )],
+        &[],
+    )
+    .await;
+    assert_lending_error(result, LendingError::InvalidMarketOwner);
+}
+
+#[tokio::test]
+async fn test_set_emode_category() {
+    let mut ctx = start(program_test()).await;
+    let lending_market = TestLendingMarket::init(&mut ctx).await;
+    let category = test_emode_category();
+
+    process(
+        &mut ctx,
+        &[instruction::set_emode_category(
+            lending_market.pubkey,
+            lending_market.owner.pubkey(),
+            category,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        lending_market.get_state(&mut ctx).await.emode_categories,
+        vec![category]
+    );
+
+    let result = process(
+        &mut ctx,
+        &[instruction::set_emode_category(
+            lending_market.pubkey,
+            lending_market.owner.pubkey(),
+            EModeCategory { id: 0, ..category },
+        )],
+        &[],
+    )
+    .await;
+    assert_lending_error(result, LendingError::InvalidEModeCategory);
+
+    let result = process(
+        &mut ctx,
+        &[instruction::set_emode_category(
+            lending_market.pubkey,
+            lending_market.owner.pubkey(),
+            EModeCategory {
+                liquidation_threshold: 80,
+                ..category
+            },
+        )],
+        &[],
+    )
+    .await;
+    assert_lending_error(result, LendingError::InvalidConfig);
+}
+
+#[tokio::test]
+async fn test_set_emode_category_invalid_owner() {
+    let mut ctx = start(program_test()).await;
+    let lending_market = TestLendingMarket::init(&mut ctx).await;
+    let not_owner = Keypair::new();
+
+    let result = process(
+        &mut ctx,
+        &[instruction::set_emode_category(
+            lending_market.pubkey,
+            not_owner.pubkey(),
+            test_emode_category(),
+        )],
+        &[&not_owner],
+    )
+    .await;
+    assert_lending_error(result, LendingError::InvalidMarketOwner);
+}
+
+#[tokio::test]
+async fn test_set_liquidation_config() {
+    let mut ctx = start(program_test()).await;
+    let lending_market = TestLendingMarket::init(&mut ctx).await;
+
+    process(
+        &mut ctx,
+        &[instruction::set_liquidation_config(
+            lending_market.pubkey,
+            lending_market.owner.pubkey(),
+            100,
+            1_000,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let state = lending_market.get_state(&mut ctx).await;
+    assert_eq!(state.liquidation_close_factor, 100);
+    assert_eq!(state.liquidation_full_close_value, 1_000);
+
+    for close_factor in [0, 101] {
+        let result = process(
+            &mut ctx,
+            &[instruction::set_liquidation_config(
+                lending_market.pubkey,
+                lending_market.owner.pubkey(),
+                close_factor,
+                0,
+            )],
+            &[],
+        )
+        .await;
+        assert_lending_error(result, LendingError::InvalidConfig);
+    }
+
+    let not_owner = Keypair::new();
+    let result = process(
+        &mut ctx,
+        &[instruction::set_liquidation_config(
+            lending_market.pubkey,
+            not_owner.pubkey(),
+            50,
+            0,
+        )],
+        &[&not_owner],
+    )
+    .await;
+    assert_lending_error(result, LendingError::InvalidMarketOwner);
+}
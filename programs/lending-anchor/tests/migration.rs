@@ -0,0 +1,322 @@
+mod helpers;
+
+use anchor_lang::{prelude::*, Discriminator};
+use helpers::*;
+use lending_anchor::{
+    account_data::{
+        lending_market::LendingMarket,
+        obligation::Obligation,
+        reserve::{reserve_price_guard::ReservePriceGuard, reserve_twap::ReserveTwap, Reserve},
+        v1::{
+            LastUpdateV1, LendingMarketV1, ObligationCollateralV1, ObligationLiquidityV1,
+            ObligationV1, ReserveCollateralV1, ReserveConfigV1, ReserveFeesV1, ReserveLiquidityV1,
+            ReserveV1,
+        },
+    },
+    constants::{
+        LENDING_MARKET_VERSION, LIQUIDATION_CLOSE_FACTOR, OBLIGATION_VERSION, RESERVE_VERSION,
+    },
+    errors::LendingError,
+    math::common::WAD,
+    utils::byte_length::ByteLength,
+};
+use lending_client::instruction;
+use solana_program_test::{tokio, ProgramTest};
+use solana_sdk::{account::Account, signature::Signer};
+
+/// `test_reserve_config()`の金利カーブを以前の3点の設定で表したもの
+const OPTIMAL_UTILIZATION_RATE: u8 = 80;
+const MIN_BORROW_RATE: u8 = 0;
+const OPTIMAL_BORROW_RATE: u8 = 4;
+const MAX_BORROW_RATE: u8 = 30;
+
+fn add_program_account(test: &mut ProgramTest, data: Vec<u8>) -> Pubkey {
+    let pubkey = Pubkey::new_unique();
+    test.add_account(
+        pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: lending_anchor::ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    pubkey
+}
+
+fn v1_data<T: Discriminator>(account: impl AnchorSerialize) -> Vec<u8> {
+    let mut data = T::discriminator().to_vec();
+    data.extend(account.try_to_vec().unwrap());
+    data
+}
+
+fn v1_lending_market(owner: Pubkey) -> LendingMarketV1 {
+    LendingMarketV1 {
+        version: 1,
+        bump_seed: 255,
+        owner,
+        quote_currency: quote_currency(),
+        token_program_id: anchor_spl::token::ID,
+        oracle_program_id: oracle_program_id(),
+    }
+}
+
+fn v1_reserve(lending_market: Pubkey, oracle: &TestOracle) -> ReserveV1 {
+    let config = test_reserve_config();
+    ReserveV1 {
+        version: 1,
+        last_update: LastUpdateV1 {
+            slot: 0,
+            stale: true,
+        },
+        lending_market,
+        liquidity: ReserveLiquidityV1 {
+            mint_decimals: LIQUIDITY_DECIMALS,
+            oracle_pubkey: oracle.price,
+            available_amount: 1_000_000,
+            cumulative_borrow_rate_wads: WAD as u128,
+            ..ReserveLiquidityV1::default()
+        },
+        collateral: ReserveCollateralV1 {
+            mint_total_supply: 1_000_000,
+            ..ReserveCollateralV1::default()
+        },
+        config: ReserveConfigV1 {
+            optimal_utilization_rate: OPTIMAL_UTILIZATION_RATE,
+            loan_to_value_ratio: config.loan_to_value_ratio,
+            liquidation_bonus: config.liquidation_bonus,
+            liquidation_threshold: config.liquidation_threshold,
+            min_borrow_rate: MIN_BORROW_RATE,
+            optimal_borrow_rate: OPTIMAL_BORROW_RATE,
+            max_borrow_rate: MAX_BORROW_RATE,
+            fees: ReserveFeesV1 {
+                borrow_fee_wad: config.fees.borrow_fee_wad,
+                flash_loan_fee_wad: config.fees.flash_loan_fee_wad,
+                host_fee_percentage: config.fees.host_fee_percentage,
+            },
+        },
+    }
+}
+
+fn v1_obligation(lending_market: Pubkey, owner: Pubkey) -> ObligationV1 {
+    ObligationV1 {
+        version: 1,
+        last_update: LastUpdateV1 {
+            slot: 0,
+            stale: false,
+        },
+        lending_market,
+        owner,
+        deposits: vec![],
+        borrows: vec![],
+        deposited_value: 80,
+        borrowed_value: 30,
+        allowed_borrow_value: 40,
+        unhealthy_borrow_value: 44,
+    }
+}
+
+#[tokio::test]
+async fn test_migrate_lending_market() {
+    let mut test = program_test();
+    let owner = Pubkey::new_unique();
+    let lending_market = add_program_account(
+        &mut test,
+        v1_data::<LendingMarket>(v1_lending_market(owner)),
+    );
+    let mut ctx = start(test).await;
+    let payer = ctx.payer.pubkey();
+
+    let ix = instruction::migrate_lending_market(lending_market, payer);
+    process(&mut ctx, &[ix], &[]).await.unwrap();
+
+    let account = get_account(&mut ctx, &lending_market).await;
+    assert_eq!(account.data.len(), LendingMarket::LEN + 8);
+    assert!(Rent::default().is_exempt(account.lamports, account.data.len()));
+    let state: LendingMarket = get_state(&mut ctx, &lending_market).await;
+    assert_eq!(state.version, LENDING_MARKET_VERSION);
+    assert_eq!(state.bump_seed, 255);
+    assert_eq!(state.owner, owner);
+    assert_eq!(state.oracle_program_id, oracle_program_id());
+    assert!(state.emode_categories.is_empty());
+    assert_eq!(state.liquidation_close_factor, LIQUIDATION_CLOSE_FACTOR);
+    assert!(state.liquidation_pairs.is_empty());
+
+    // 移行済みのaccountはもう移行できない
+    ctx.warp_to_slot(3).unwrap();
+    let ix = instruction::migrate_lending_market(lending_market, payer);
+    let result = process(&mut ctx, &[ix], &[]).await;
+    assert_lending_error(result, LendingError::UnsupportedAccountVersion);
+}
+
+#[tokio::test]
+async fn test_migrate_reserve() {
+    let mut test = program_test();
+    let oracle = TestOracle::add(&mut test, 1, 0);
+    let mut old = v1_reserve(Pubkey::new_unique(), &oracle);
+    old.liquidity.borrowed_amount_wads = 250 * WAD as u128 + 1;
+    old.liquidity.market_price = 20;
+    let reserve = add_program_account(&mut test, v1_data::<Reserve>(old.clone()));
+    let mut ctx = start(test).await;
+    let payer = ctx.payer.pubkey();
+
+    let ix = instruction::migrate_reserve(reserve, payer);
+    process(&mut ctx, &[ix], &[]).await.unwrap();
+
+    let account = get_account(&mut ctx, &reserve).await;
+    assert_eq!(account.data.len(), Reserve::LEN + 8);
+    let state: Reserve = get_state(&mut ctx, &reserve).await;
+    assert_eq!(state.version, RESERVE_VERSION);
+    assert_eq!(state.lending_market, old.lending_market);
+    assert_eq!(state.liquidity.available_amount, 1_000_000);
+    // WAD単位の借入はtoken単位に切り上げる
+    assert_eq!(state.liquidity.borrowed_amount, 251);
+    assert_eq!(state.liquidity.token_program_id, anchor_spl::token::ID);
+    assert_eq!(state.collateral.mint_total_supply, 1_000_000);
+    // 3点の金利設定はbpsのカーブになる
+    assert_eq!(state.config, test_reserve_config());
+    assert!(state.isolated_debts.is_empty());
+    assert_eq!(state.deposit_reward_pool, None);
+    assert_eq!(state.borrow_reward_pool, None);
+    assert_eq!(state.price_guard, ReservePriceGuard::new(0));
+    assert_eq!(state.config.twap_fallback_max_slots, 0);
+    assert!(state.config.term_offers.is_empty());
+    assert_eq!(state.term_borrowed_amount, 0);
+    assert_eq!(state.insurance_vault, None);
+    // 以前と同じ固定のbonusになる
+    assert_eq!(
+        state.config.min_liquidation_bonus,
+        state.config.liquidation_bonus
+    );
+    // TWAPは最後に受け入れた価格から始まる
+    assert_eq!(state.twap, ReserveTwap::new(20, 0));
+
+    // 移行後は通常の命令で使える
+    let ix = instruction::refresh_reserve(reserve, &state);
+    process(&mut ctx, &[ix], &[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_migrate_obligation() {
+    let mut test = program_test();
+    let owner = Pubkey::new_unique();
+    let deposit_reserve = Pubkey::new_unique();
+    let mut old = v1_obligation(Pubkey::new_unique(), owner);
+    old.deposits.push(ObligationCollateralV1 {
+        deposit_reserve,
+        deposited_amount: 100,
+        market_value: 80,
+    });
+    let obligation = add_program_account(&mut test, v1_data::<Obligation>(old));
+    let mut ctx = start(test).await;
+    let payer = ctx.payer.pubkey();
+
+    let ix = instruction::migrate_obligation(obligation, &[], payer);
+    process(&mut ctx, &[ix], &[]).await.unwrap();
+
+    let account = get_account(&mut ctx, &obligation).await;
+    assert_eq!(account.data.len(), Obligation::LEN + 8);
+    let state: Obligation = get_state(&mut ctx, &obligation).await;
+    assert_eq!(state.version, OBLIGATION_VERSION);
+    assert_eq!(state.owner, owner);
+    assert_eq!(state.deposits[0].deposit_reserve, deposit_reserve);
+    assert_eq!(state.deposits[0].deposited_amount, 100);
+    assert_eq!(state.borrowed_value, 30);
+    assert_eq!(state.unweighted_borrowed_value, 30);
+    assert_eq!(state.isolated_reserve, None);
+    assert_eq!(state.emode_category, 0);
+    assert_eq!(state.delegate, None);
+    assert_eq!(state.referrer, None);
+    assert!(state.rewards.is_empty());
+    assert!(!state.price_guarded);
+    assert!(!state.price_fallback);
+    assert!(state.term_loans.is_empty());
+    assert!(state.last_update.stale);
+}
+
+#[tokio::test]
+async fn test_migrate_obligation_with_borrow() {
+    let mut test = program_test();
+    let oracle = TestOracle::add(&mut test, 1, 0);
+    let lending_market = add_program_account(
+        &mut test,
+        v1_data::<LendingMarket>(v1_lending_market(Pubkey::new_unique())),
+    );
+    let mut old_reserve = v1_reserve(lending_market, &oracle);
+    old_reserve.liquidity.borrowed_amount_wads = 100 * WAD as u128;
+    old_reserve.liquidity.cumulative_borrow_rate_wads = 3 * WAD as u128 / 2;
+    let reserve = add_program_account(&mut test, v1_data::<Reserve>(old_reserve));
+    let mut old = v1_obligation(lending_market, Pubkey::new_unique());
+    old.borrows.push(ObligationLiquidityV1 {
+        borrow_reserve: reserve,
+        cumulative_borrow_rate_wads: 1,
+        borrowed_amount_wads: 100 * WAD as u128,
+        market_value: 100,
+    });
+    let obligation = add_program_account(&mut test, v1_data::<Obligation>(old));
+    let mut ctx = start(test).await;
+    let payer = ctx.payer.pubkey();
+
+    // borrow reserveは先に移行しておく必要がある
+    let ix = instruction::migrate_obligation(obligation, &[reserve], payer);
+    let result = process(&mut ctx, &[ix], &[]).await;
+    assert_lending_error(result, LendingError::OutdatedAccountVersion);
+
+    let ixs = [
+        instruction::migrate_lending_market(lending_market, payer),
+        instruction::migrate_reserve(reserve, payer),
+        instruction::migrate_obligation(obligation, &[reserve], payer),
+    ];
+    process(&mut ctx, &ixs, &[]).await.unwrap();
+
+    // 借入はtoken単位になり、reserveの現在の利率から利息を数える
+    let state: Obligation = get_state(&mut ctx, &obligation).await;
+    assert_eq!(state.borrows[0].borrowed_amount, 100);
+    assert_eq!(
+        state.borrows[0].cumulative_borrow_rate_wads,
+        3 * WAD as u128 / 2
+    );
+
+    let reserve_state: Reserve = get_state(&mut ctx, &reserve).await;
+    let ixs = [
+        instruction::refresh_reserve(reserve, &reserve_state),
+        instruction::refresh_obligation(obligation, &state, None),
+    ];
+    process(&mut ctx, &ixs, &[]).await.unwrap();
+
+    // 数slot分の利息しか増えない
+    let reserve_state: Reserve = get_state(&mut ctx, &reserve).await;
+    let state: Obligation = get_state(&mut ctx, &obligation).await;
+    assert!((100..=101).contains(&state.borrows[0].borrowed_amount));
+    assert_eq!(
+        state.borrows[0].borrowed_amount,
+        reserve_state.liquidity.borrowed_amount
+    );
+    assert!(!state.last_update.stale);
+}
+
+#[tokio::test]
+async fn test_outdated_account_version() {
+    let mut test = program_test();
+    let oracle = TestOracle::add(&mut test, 1, 0);
+    // 現在のlayoutでもversionが古ければ拒否する
+    let mut outdated = Reserve::from(v1_reserve(Pubkey::new_unique(), &oracle));
+    outdated.version = 1;
+    let mut data = vec![];
+    outdated.try_serialize(&mut data).unwrap();
+    let reserve = add_program_account(&mut test, data);
+    let v1_reserve = add_program_account(
+        &mut test,
+        v1_data::<Reserve>(v1_reserve(Pubkey::new_unique(), &oracle)),
+    );
+    let mut ctx = start(test).await;
+
+    let ix = instruction::refresh_reserve(reserve, &outdated);
+    let result = process(&mut ctx, &[ix], &[]).await;
+    assert_lending_error(result, LendingError::OutdatedAccountVersion);
+
+    // 古いlayoutのままではdeserializeできない
+    let ix = instruction::refresh_reserve(v1_reserve, &outdated);
+    assert!(process(&mut ctx, &[ix], &[]).await.is_err());
+}
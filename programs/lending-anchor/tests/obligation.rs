@@ -0,0 +1,2556 @@
+mod helpers;
+
+use anchor_spl::token::spl_token;
+use helpers::*;
+use lending_anchor::{
+    account_data::{
+        emode_category::EModeCategory,
+        liquidation_pair::LiquidationPairConfig,
+        obligation::obligation_delegate::ObligationDelegate,
+        referrer::ReferrerTokenState,
+        reserve::{reserve_fees::ReserveFees, term_offer::TermOffer},
+        reward_pool::{RewardPool, RewardSide},
+    },
+    constants::MAX_OBLIGATION_RESERVE,
+    errors::LendingError,
+    events::{BorrowObligationLiquidityEvent, RepayObligationLiquidityEvent},
+    views::{BorrowQuote, ObligationHealth, RepayQuote, WithdrawQuote},
+};
+use lending_client::{calculate, instruction, pda};
+use mock_oracle::OracleStatus;
+use solana_program_test::{tokio, ProgramTestContext};
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+};
+
+/// Collateral priced at 20 and borrowed liquidity priced at 1
+const COLLATERAL_PRICE: i64 = 20;
+const BORROW_PRICE: i64 = 1;
+const COLLATERAL_LIQUIDITY_AMOUNT: u64 = 10_000_000;
+const BORROW_LIQUIDITY_AMOUNT: u64 = 100_000_000;
+
+struct TestSetup {
+    ctx: ProgramTestContext,
+    lending_market: TestLendingMarket,
+    collateral_oracle: TestOracle,
+    borrow_oracle: TestOracle,
+    collateral_reserve: TestReserve,
+    borrow_reserve: TestReserve,
+    obligation: TestObligation,
+    bump_seed: u8,
+}
+
+async fn setup() -> TestSetup {
+    let mut test = program_test();
+    let collateral_oracle = TestOracle::add(&mut test, COLLATERAL_PRICE, 0);
+    let borrow_oracle = TestOracle::add(&mut test, BORROW_PRICE, 0);
+    let mut ctx = start(test).await;
+    let lending_market = TestLendingMarket::init(&mut ctx).await;
+    let collateral_reserve = TestReserve::init(
+        &mut ctx,
+        &lending_market,
+        &collateral_oracle,
+        test_reserve_config(),
+        COLLATERAL_LIQUIDITY_AMOUNT,
+        0,
+    )
+    .await
+    .unwrap();
+    let borrow_reserve = TestReserve::init(
+        &mut ctx,
+        &lending_market,
+        &borrow_oracle,
+        test_reserve_config(),
+        BORROW_LIQUIDITY_AMOUNT,
+        BORROW_LIQUIDITY_AMOUNT,
+    )
+    .await
+    .unwrap();
+    let obligation = TestObligation::init(&mut ctx, &lending_market).await;
+    let bump_seed = lending_market.bump_seed(&mut ctx).await;
+
+    TestSetup {
+        ctx,
+        lending_market,
+        collateral_oracle,
+        borrow_oracle,
+        collateral_reserve,
+        borrow_reserve,
+        obligation,
+        bump_seed,
+    }
+}
+
+impl TestSetup {
+    async fn deposit_ixs(&mut self, reserve: &TestReserve, amount: u64) -> Vec<Instruction> {
+        let payer = self.ctx.payer.pubkey();
+        let reserve_state = reserve.get_state(&mut self.ctx).await;
+        let obligation_state = self.obligation.get_state(&mut self.ctx).await;
+        vec![
+            instruction::refresh_reserve(reserve.pubkey, &reserve_state),
+            instruction::deposit_obligation_collateral(
+                amount,
+                reserve.pubkey,
+                &reserve_state,
+                self.obligation.pubkey,
+                &obligation_state,
+                payer,
+                reserve.user_collateral,
+                payer,
+            ),
+        ]
+    }
+
+    async fn deposit(&mut self, amount: u64) {
+        let reserve = self.collateral_reserve.clone();
+        let instructions = self.deposit_ixs(&reserve, amount).await;
+        process(&mut self.ctx, &instructions, &[]).await.unwrap();
+    }
+
+    async fn borrow_ixs(&mut self, amount: u64) -> Vec<Instruction> {
+        let payer = self.ctx.payer.pubkey();
+        let destination = self.borrow_reserve.user_liquidity;
+        self.borrow_ixs_by(payer, destination, amount).await
+    }
+
+    /// Refresh the obligation together with the borrow reserve, which may not be borrowed yet
+    async fn borrow_ixs_by(
+        &mut self,
+        authority: Pubkey,
+        destination: Pubkey,
+        amount: u64,
+    ) -> Vec<Instruction> {
+        let mut instructions = vec![self.borrow_reserve.refresh_ix(&mut self.ctx).await];
+        instructions.extend(self.obligation.refresh_ixs(&mut self.ctx).await);
+
+        let reserve_state = self.borrow_reserve.get_state(&mut self.ctx).await;
+        let obligation_state = self.obligation.get_state(&mut self.ctx).await;
+        instructions.push(
+            instruction::borrow_obligation_liquidity(
+                amount,
+                self.borrow_reserve.pubkey,
+                &reserve_state,
+                self.obligation.pubkey,
+                &obligation_state,
+                authority,
+                self.bump_seed,
+                destination,
+            )
+            .unwrap(),
+        );
+        instructions
+    }
+
+    async fn withdraw_ixs(&mut self, amount: u64) -> Vec<Instruction> {
+        let payer = self.ctx.payer.pubkey();
+        let destination = self.collateral_reserve.user_collateral;
+        self.withdraw_ixs_by(payer, destination, amount).await
+    }
+
+    async fn withdraw_ixs_by(
+        &mut self,
+        authority: Pubkey,
+        destination: Pubkey,
+        amount: u64,
+    ) -> Vec<Instruction> {
+        let mut instructions = self.obligation.refresh_ixs(&mut self.ctx).await;
+        let reserve_state = self.collateral_reserve.get_state(&mut self.ctx).await;
+        let obligation_state = self.obligation.get_state(&mut self.ctx).await;
+        instructions.push(
+            instruction::withdraw_obligation_collateral(
+                amount,
+                self.collateral_reserve.pubkey,
+                &reserve_state,
+                self.obligation.pubkey,
+                &obligation_state,
+                authority,
+                self.bump_seed,
+                destination,
+            )
+            .unwrap(),
+        );
+        instructions
+    }
+
+    /// Create the insurance vault of the borrow reserve and fund it from the payer
+    async fn init_insurance_vault(&mut self, amount: u64) {
+        let payer = self.ctx.payer.pubkey();
+        let reserve_state = self.borrow_reserve.get_state(&mut self.ctx).await;
+        let instructions = [
+            instruction::init_insurance_vault(
+                self.borrow_reserve.pubkey,
+                &reserve_state,
+                self.bump_seed,
+                payer,
+            )
+            .unwrap(),
+            instruction::fund_insurance_vault(
+                amount,
+                self.borrow_reserve.pubkey,
+                &reserve_state,
+                self.bump_seed,
+                self.borrow_reserve.user_liquidity,
+                payer,
+            )
+            .unwrap(),
+        ];
+        process(&mut self.ctx, &instructions, &[]).await.unwrap();
+    }
+
+    async fn liquidate_ixs(&mut self, amount: u64) -> Vec<Instruction> {
+        let payer = self.ctx.payer.pubkey();
+        let mut instructions = self.obligation.refresh_ixs(&mut self.ctx).await;
+        let repay_reserve_state = self.borrow_reserve.get_state(&mut self.ctx).await;
+        let withdraw_reserve_state = self.collateral_reserve.get_state(&mut self.ctx).await;
+        let obligation_state = self.obligation.get_state(&mut self.ctx).await;
+        instructions.push(
+            instruction::liquidate_obligation(
+                amount,
+                self.borrow_reserve.pubkey,
+                &repay_reserve_state,
+                self.collateral_reserve.pubkey,
+                &withdraw_reserve_state,
+                self.obligation.pubkey,
+                &obligation_state,
+                self.bump_seed,
+                self.borrow_reserve.user_liquidity,
+                self.collateral_reserve.user_collateral,
+                payer,
+            )
+            .unwrap(),
+        );
+        instructions
+    }
+}
+
+#[tokio::test]
+async fn test_init_obligation() {
+    let mut setup = setup().await;
+
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.lending_market, setup.lending_market.pubkey);
+    assert_eq!(state.owner, setup.ctx.payer.pubkey());
+    assert!(state.deposits.is_empty());
+    assert!(state.borrows.is_empty());
+    assert_eq!(state.emode_category, 0);
+}
+
+#[tokio::test]
+async fn test_deposit_and_withdraw_collateral() {
+    let mut setup = setup().await;
+    let user_collateral = setup.collateral_reserve.user_collateral;
+
+    setup.deposit(4_000_000).await;
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.deposits.len(), 1);
+    assert_eq!(
+        state.deposits[0].deposit_reserve,
+        setup.collateral_reserve.pubkey
+    );
+    assert_eq!(state.deposits[0].deposited_amount, 4_000_000);
+    assert_eq!(
+        token_balance(&mut setup.ctx, &user_collateral).await,
+        COLLATERAL_LIQUIDITY_AMOUNT - 4_000_000
+    );
+
+    let instructions = setup.withdraw_ixs(1_000_000).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.deposits[0].deposited_amount, 3_000_000);
+
+    // 全額引き出すとdepositが消える
+    let instructions = setup.withdraw_ixs(u64::MAX).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert!(state.deposits.is_empty());
+    assert_eq!(
+        token_balance(&mut setup.ctx, &user_collateral).await,
+        COLLATERAL_LIQUIDITY_AMOUNT
+    );
+}
+
+#[tokio::test]
+async fn test_deposit_collateral_reserve_stale() {
+    let mut setup = setup().await;
+    let payer = setup.ctx.payer.pubkey();
+
+    let reserve_state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    let result = process(
+        &mut setup.ctx,
+        &[instruction::deposit_obligation_collateral(
+            1_000_000,
+            setup.collateral_reserve.pubkey,
+            &reserve_state,
+            setup.obligation.pubkey,
+            &obligation_state,
+            payer,
+            setup.collateral_reserve.user_collateral,
+            payer,
+        )],
+        &[],
+    )
+    .await;
+    assert_lending_error(result, LendingError::ReserveStale);
+}
+
+#[tokio::test]
+async fn test_refresh_obligation() {
+    let mut setup = setup().await;
+    setup.deposit(4_000_000).await;
+
+    let instructions = setup.obligation.refresh_ixs(&mut setup.ctx).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert!(!state.last_update.stale);
+    assert_eq!(state.deposited_value, 80);
+    assert_eq!(state.allowed_borrow_value, 40);
+    assert_eq!(state.unhealthy_borrow_value, 44);
+    assert_eq!(state.borrowed_value, 0);
+}
+
+#[tokio::test]
+async fn test_refresh_obligation_reserve_stale() {
+    let mut setup = setup().await;
+    setup.deposit(4_000_000).await;
+
+    // reserveをrefreshせずにslotを進める
+    setup.ctx.warp_to_slot(5).unwrap();
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    let result = process(
+        &mut setup.ctx,
+        &[instruction::refresh_obligation(
+            setup.obligation.pubkey,
+            &obligation_state,
+            None,
+        )],
+        &[],
+    )
+    .await;
+    assert_lending_error(result, LendingError::ReserveStale);
+}
+
+#[tokio::test]
+async fn test_borrow_and_repay() {
+    let mut setup = setup().await;
+    let user_liquidity = setup.borrow_reserve.user_liquidity;
+    let payer = setup.ctx.payer.pubkey();
+    setup.deposit(4_000_000).await;
+
+    let instructions = setup.borrow_ixs(30_000_000).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.borrows.len(), 1);
+    assert_eq!(state.borrows[0].borrow_reserve, setup.borrow_reserve.pubkey);
+    assert_eq!(state.borrows[0].borrowed_amount, 30_000_000);
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    assert_eq!(reserve_state.liquidity.borrowed_amount, 30_000_000);
+    assert_eq!(
+        reserve_state.liquidity.available_amount,
+        BORROW_LIQUIDITY_AMOUNT - 30_000_000
+    );
+    assert_eq!(
+        token_balance(&mut setup.ctx, &user_liquidity).await,
+        BORROW_LIQUIDITY_AMOUNT + 30_000_000
+    );
+
+    // 借入中は担保を全額引き出せない
+    let instructions = setup.withdraw_ixs(4_000_000).await;
+    let result = process(&mut setup.ctx, &instructions, &[]).await;
+    assert_lending_error(result, LendingError::WithdrawTooLarge);
+
+    let mut instructions = setup.obligation.refresh_ixs(&mut setup.ctx).await;
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    instructions.push(instruction::repay_obligation_liquidity(
+        u64::MAX,
+        setup.borrow_reserve.pubkey,
+        &reserve_state,
+        setup.obligation.pubkey,
+        &obligation_state,
+        user_liquidity,
+        payer,
+    ));
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert!(state.borrows.is_empty());
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    assert_eq!(reserve_state.liquidity.borrowed_amount, 0);
+    assert_eq!(
+        reserve_state.liquidity.available_amount,
+        BORROW_LIQUIDITY_AMOUNT
+    );
+    assert_eq!(
+        token_balance(&mut setup.ctx, &user_liquidity).await,
+        BORROW_LIQUIDITY_AMOUNT
+    );
+}
+
+#[tokio::test]
+async fn test_borrow_and_repay_events() {
+    let mut setup = setup().await;
+    let user_liquidity = setup.borrow_reserve.user_liquidity;
+    let payer = setup.ctx.payer.pubkey();
+    let mut config = test_reserve_config();
+    config.fees.borrow_fee_wad = 10_000_000_000_000_000;
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    process(
+        &mut setup.ctx,
+        &[instruction::update_reserve_config(
+            config,
+            setup.borrow_reserve.pubkey,
+            &reserve_state,
+            setup.lending_market.owner.pubkey(),
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    setup.deposit(4_000_000).await;
+
+    // 手数料は借入額に上乗せされ、イベントは実行後のリザーブの残高を持つ
+    let instructions = setup.borrow_ixs(30_000_000).await;
+    let events: Vec<BorrowObligationLiquidityEvent> =
+        process_events(&mut setup.ctx, &instructions, &[])
+            .await
+            .unwrap();
+    let event = events
+        .into_iter()
+        .find(|event| event.obligation == setup.obligation.pubkey)
+        .expect("no borrow event");
+    assert_eq!(event.lending_market, setup.lending_market.pubkey);
+    assert_eq!(event.reserve, setup.borrow_reserve.pubkey);
+    assert_eq!(event.borrow_amount, 30_300_000);
+    assert_eq!(event.receive_amount, 30_000_000);
+    assert_eq!(event.borrow_fee, 300_000);
+    assert_eq!(event.host_fee, 0);
+    assert_eq!(event.referrer, None);
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    assert_eq!(
+        event.reserve_state.available_amount,
+        reserve_state.liquidity.available_amount
+    );
+    assert_eq!(
+        event.reserve_state.borrowed_amount,
+        reserve_state.liquidity.borrowed_amount
+    );
+
+    let mut instructions = setup.obligation.refresh_ixs(&mut setup.ctx).await;
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    instructions.push(instruction::repay_obligation_liquidity(
+        u64::MAX,
+        setup.borrow_reserve.pubkey,
+        &reserve_state,
+        setup.obligation.pubkey,
+        &obligation_state,
+        user_liquidity,
+        payer,
+    ));
+    let events: Vec<RepayObligationLiquidityEvent> =
+        process_events(&mut setup.ctx, &instructions, &[])
+            .await
+            .unwrap();
+    let event = events
+        .into_iter()
+        .find(|event| event.obligation == setup.obligation.pubkey)
+        .expect("no repay event");
+    assert_eq!(event.reserve, setup.borrow_reserve.pubkey);
+    assert_eq!(event.settle_amount, 30_300_000);
+    assert_eq!(event.repay_amount, 30_300_000);
+    assert_eq!(event.reserve_state.borrowed_amount, 0);
+    assert_eq!(
+        event.reserve_state.available_amount,
+        BORROW_LIQUIDITY_AMOUNT
+    );
+}
+
+#[tokio::test]
+async fn test_client_calculate() {
+    let mut setup = setup().await;
+    let user_liquidity = setup.borrow_reserve.user_liquidity;
+    let payer = setup.ctx.payer.pubkey();
+    let mut config = test_reserve_config();
+    config.fees.borrow_fee_wad = 10_000_000_000_000_000;
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    process(
+        &mut setup.ctx,
+        &[instruction::update_reserve_config(
+            config,
+            setup.borrow_reserve.pubkey,
+            &reserve_state,
+            payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    setup.deposit(4_000_000).await;
+
+    // 同じslotで更新済みのstateからはprogramと同じ結果になる
+    let mut instructions = vec![setup.borrow_reserve.refresh_ix(&mut setup.ctx).await];
+    instructions.extend(setup.obligation.refresh_ixs(&mut setup.ctx).await);
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    let result = calculate::calculate_borrow(
+        &reserve_state,
+        setup.borrow_reserve.pubkey,
+        &obligation_state,
+        obligation_state.owner,
+        None,
+        30_000_000,
+    )
+    .unwrap();
+    let instructions = setup.borrow_ixs(30_000_000).await;
+    let events: Vec<BorrowObligationLiquidityEvent> =
+        process_events(&mut setup.ctx, &instructions, &[])
+            .await
+            .unwrap();
+    let event = events
+        .into_iter()
+        .find(|event| event.obligation == setup.obligation.pubkey)
+        .expect("no borrow event");
+    assert_eq!(event.borrow_amount, result.borrow_amount);
+    assert_eq!(event.receive_amount, result.receive_amount);
+    assert_eq!(event.borrow_fee, result.borrow_fee);
+    assert_eq!(event.host_fee, result.host_fee);
+
+    let instructions = setup.obligation.refresh_ixs(&mut setup.ctx).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    let result = calculate::calculate_repay(
+        &reserve_state,
+        setup.borrow_reserve.pubkey,
+        &obligation_state,
+        u64::MAX,
+    )
+    .unwrap();
+    let mut instructions = setup.obligation.refresh_ixs(&mut setup.ctx).await;
+    instructions.push(instruction::repay_obligation_liquidity(
+        u64::MAX,
+        setup.borrow_reserve.pubkey,
+        &reserve_state,
+        setup.obligation.pubkey,
+        &obligation_state,
+        user_liquidity,
+        payer,
+    ));
+    let events: Vec<RepayObligationLiquidityEvent> =
+        process_events(&mut setup.ctx, &instructions, &[])
+            .await
+            .unwrap();
+    let event = events
+        .into_iter()
+        .find(|event| event.obligation == setup.obligation.pubkey)
+        .expect("no repay event");
+    assert_eq!(event.settle_amount, result.settle_amount);
+    assert_eq!(event.repay_amount, result.repay_amount);
+}
+
+#[tokio::test]
+async fn test_borrow_weight() {
+    let mut setup = setup().await;
+    let payer = setup.ctx.payer.pubkey();
+    let mut config = test_reserve_config();
+    config.borrow_weight = 20_000;
+    let borrow_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    process(
+        &mut setup.ctx,
+        &[instruction::update_reserve_config(
+            config,
+            setup.borrow_reserve.pubkey,
+            &borrow_state,
+            payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    setup.deposit(4_000_000).await;
+    let instructions = setup.borrow_ixs(10_000_000).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+
+    // 重み付きの値はLTVの判定に，重み無しの値は表示に使われる
+    let instructions = setup.obligation.refresh_ixs(&mut setup.ctx).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.unweighted_borrowed_value, 10);
+    assert_eq!(state.borrowed_value, 20);
+    assert_eq!(state.allowed_borrow_value, 40);
+
+    // 重み無しなら残り30まで借りられるが，重み付きでは10までしか借りられない
+    let instructions = setup.borrow_ixs(11_000_000).await;
+    let result = process(&mut setup.ctx, &instructions, &[]).await;
+    assert_lending_error(result, LendingError::BorrowTooLarge);
+    let instructions = setup.borrow_ixs(9_000_000).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.borrows[0].borrowed_amount, 19_000_000);
+}
+
+#[tokio::test]
+async fn test_native_sol() {
+    let mut setup = setup().await;
+    let payer = setup.ctx.payer.pubkey();
+    let sol_oracle = TestOracle::create(&mut setup.ctx, BORROW_PRICE).await;
+    let sol_reserve = TestReserve::init_native(
+        &mut setup.ctx,
+        &setup.lending_market,
+        &sol_oracle,
+        test_reserve_config(),
+        BORROW_LIQUIDITY_AMOUNT,
+        0,
+    )
+    .await
+    .unwrap();
+    let sol_state = sol_reserve.get_state(&mut setup.ctx).await;
+    let supply = sol_state.liquidity.supply_pubkey;
+
+    // lamportsをそのままdepositできる
+    let ix = instruction::deposit_reserve_liquidity_native(
+        1_000_000,
+        sol_reserve.pubkey,
+        &sol_state,
+        setup.bump_seed,
+        payer,
+        sol_reserve.user_collateral,
+    )
+    .unwrap();
+    let refresh_ix = instruction::refresh_reserve(sol_reserve.pubkey, &sol_state);
+    process(&mut setup.ctx, &[refresh_ix, ix], &[])
+        .await
+        .unwrap();
+    let sol_state = sol_reserve.get_state(&mut setup.ctx).await;
+    assert_eq!(
+        sol_state.liquidity.available_amount,
+        BORROW_LIQUIDITY_AMOUNT + 1_000_000
+    );
+    assert_eq!(
+        token_balance(&mut setup.ctx, &supply).await,
+        BORROW_LIQUIDITY_AMOUNT + 1_000_000
+    );
+    assert_eq!(
+        token_balance(&mut setup.ctx, &sol_reserve.user_collateral).await,
+        BORROW_LIQUIDITY_AMOUNT + 1_000_000
+    );
+
+    // 借りたSOLはwalletにlamportsとして届く
+    setup.deposit(4_000_000).await;
+    let mut instructions = vec![instruction::refresh_reserve(sol_reserve.pubkey, &sol_state)];
+    instructions.extend(setup.obligation.refresh_ixs(&mut setup.ctx).await);
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    instructions.push(
+        instruction::borrow_obligation_liquidity_native(
+            30_000_000,
+            sol_reserve.pubkey,
+            &sol_state,
+            setup.obligation.pubkey,
+            &obligation_state,
+            payer,
+            setup.bump_seed,
+        )
+        .unwrap(),
+    );
+    let lamports = get_account(&mut setup.ctx, &payer).await.lamports;
+    #[allow(deprecated)]
+    let (fee_calculator, _, _) = setup.ctx.banks_client.get_fees().await.unwrap();
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    assert_eq!(
+        get_account(&mut setup.ctx, &payer).await.lamports,
+        lamports + 30_000_000 - fee_calculator.lamports_per_signature
+    );
+
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.borrows[0].borrow_reserve, sol_reserve.pubkey);
+    assert_eq!(state.borrows[0].borrowed_amount, 30_000_000);
+    assert_eq!(
+        token_balance(&mut setup.ctx, &supply).await,
+        BORROW_LIQUIDITY_AMOUNT + 1_000_000 - 30_000_000
+    );
+    let native_liquidity = lending_client::pda::native_liquidity(&setup.obligation.pubkey);
+    assert!(setup
+        .ctx
+        .banks_client
+        .get_account(native_liquidity)
+        .await
+        .unwrap()
+        .is_none());
+
+    // lamportsで返済できる
+    let mut instructions = setup.obligation.refresh_ixs(&mut setup.ctx).await;
+    let sol_state = sol_reserve.get_state(&mut setup.ctx).await;
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    instructions.push(instruction::repay_obligation_liquidity_native(
+        u64::MAX,
+        sol_reserve.pubkey,
+        &sol_state,
+        setup.obligation.pubkey,
+        &obligation_state,
+        payer,
+    ));
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert!(state.borrows.is_empty());
+    let sol_state = sol_reserve.get_state(&mut setup.ctx).await;
+    assert_eq!(
+        sol_state.liquidity.available_amount,
+        BORROW_LIQUIDITY_AMOUNT + 1_000_000
+    );
+    assert_eq!(
+        token_balance(&mut setup.ctx, &supply).await,
+        BORROW_LIQUIDITY_AMOUNT + 1_000_000
+    );
+
+    // native SOLでないreserveには使えない
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    let ix = instruction::deposit_reserve_liquidity_native(
+        1_000_000,
+        setup.borrow_reserve.pubkey,
+        &reserve_state,
+        setup.bump_seed,
+        payer,
+        setup.borrow_reserve.user_collateral,
+    )
+    .unwrap();
+    let result = process(&mut setup.ctx, &[ix], &[]).await;
+    assert_lending_error(result, LendingError::ReserveNotNative);
+}
+
+#[tokio::test]
+async fn test_obligation_health_and_quotes() {
+    let mut setup = setup().await;
+    setup.deposit(4_000_000).await;
+    let instructions = setup.borrow_ixs(30_000_000).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    let obligation_pubkey = setup.obligation.pubkey;
+    let borrow_reserve = setup.borrow_reserve.pubkey;
+    let collateral_reserve = setup.collateral_reserve.pubkey;
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+
+    let mut instructions = setup.obligation.refresh_ixs(&mut setup.ctx).await;
+    instructions.push(instruction::get_obligation_health(obligation_pubkey));
+    let health: ObligationHealth = process_view(&mut setup.ctx, &instructions).await.unwrap();
+    assert_eq!(
+        health,
+        ObligationHealth {
+            deposited_value: 80,
+            borrowed_value: 30,
+            unweighted_borrowed_value: 30,
+            allowed_borrow_value: 40,
+            unhealthy_borrow_value: 44,
+            remaining_borrow_value: 10,
+            health_factor_wads: 1_466_666_666_666_666_666,
+            liquidatable: false,
+        }
+    );
+
+    // 残りの借入可能額10まで借りられる
+    let mut instructions = setup.obligation.refresh_ixs(&mut setup.ctx).await;
+    instructions.push(instruction::quote_borrow(
+        u64::MAX,
+        borrow_reserve,
+        obligation_pubkey,
+        &obligation_state,
+    ));
+    let quote: BorrowQuote = process_view(&mut setup.ctx, &instructions).await.unwrap();
+    assert_eq!(quote.borrow_amount, 10_000_000);
+    assert_eq!(quote.receive_amount, 10_000_000);
+
+    let mut instructions = setup.obligation.refresh_ixs(&mut setup.ctx).await;
+    instructions.push(instruction::quote_borrow(
+        11_000_000,
+        borrow_reserve,
+        obligation_pubkey,
+        &obligation_state,
+    ));
+    let result = process_view::<BorrowQuote>(&mut setup.ctx, &instructions).await;
+    assert_lending_error(result.map(|_| ()), LendingError::BorrowTooLarge);
+
+    // 余裕のある価値10はLTV 50%で担保20、つまり4Mの1/4
+    let mut instructions = setup.obligation.refresh_ixs(&mut setup.ctx).await;
+    instructions.push(instruction::quote_withdraw(
+        u64::MAX,
+        collateral_reserve,
+        obligation_pubkey,
+        &obligation_state,
+    ));
+    let quote: WithdrawQuote = process_view(&mut setup.ctx, &instructions).await.unwrap();
+    assert_eq!(
+        quote,
+        WithdrawQuote {
+            collateral_amount: 1_000_000,
+            liquidity_amount: 1_000_000,
+        }
+    );
+
+    let mut instructions = setup.obligation.refresh_ixs(&mut setup.ctx).await;
+    instructions.push(instruction::quote_repay(
+        u64::MAX,
+        borrow_reserve,
+        obligation_pubkey,
+        &obligation_state,
+    ));
+    let quote: RepayQuote = process_view(&mut setup.ctx, &instructions).await.unwrap();
+    assert_eq!(
+        quote,
+        RepayQuote {
+            settle_amount: 30_000_000,
+            repay_amount: 30_000_000,
+        }
+    );
+
+    // quoteはobligationを変更しない
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.borrows[0].borrowed_amount, 30_000_000);
+    assert_eq!(state.deposits[0].deposited_amount, 4_000_000);
+}
+
+#[tokio::test]
+async fn test_borrow_too_large() {
+    let mut setup = setup().await;
+    setup.deposit(4_000_000).await;
+
+    // 担保価値80、LTV 50%なので40までしか借りられない
+    let instructions = setup.borrow_ixs(41_000_000).await;
+    let result = process(&mut setup.ctx, &instructions, &[]).await;
+    assert_lending_error(result, LendingError::BorrowTooLarge);
+}
+
+#[tokio::test]
+async fn test_borrow_obligation_stale() {
+    let mut setup = setup().await;
+    setup.deposit(4_000_000).await;
+
+    let mut instructions = setup.borrow_ixs(1_000_000).await;
+    // refresh_obligationを外す
+    instructions.remove(instructions.len() - 2);
+    let result = process(&mut setup.ctx, &instructions, &[]).await;
+    assert_lending_error(result, LendingError::ObligationStale);
+}
+
+#[tokio::test]
+async fn test_isolated_debt() {
+    let mut setup = setup().await;
+    let payer = setup.ctx.payer.pubkey();
+    let user_liquidity = setup.borrow_reserve.user_liquidity;
+
+    // collateralはborrow liquidity 20_000_000の価値までしか借入を支えない
+    let borrow_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    let collateral_state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+    let mut collateral_config = test_reserve_config();
+    collateral_config.isolated = true;
+    collateral_config.debt_ceiling = borrow_state.market_value(20_000_000).unwrap() as u64;
+    let mut borrow_config = test_reserve_config();
+    borrow_config.borrowable_in_isolation = true;
+    process(
+        &mut setup.ctx,
+        &[
+            instruction::update_reserve_config(
+                collateral_config,
+                setup.collateral_reserve.pubkey,
+                &collateral_state,
+                payer,
+            ),
+            instruction::update_reserve_config(
+                borrow_config,
+                setup.borrow_reserve.pubkey,
+                &borrow_state,
+                payer,
+            ),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    setup.deposit(4_000_000).await;
+    let instructions = setup.borrow_ixs(10_000_000).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+
+    // debtはborrow reserveごとにtokenの量で記録される
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(
+        state.isolated_reserve,
+        Some(setup.collateral_reserve.pubkey)
+    );
+    assert_eq!(state.isolated_debts.len(), 1);
+    assert_eq!(
+        state.isolated_debts[0].borrow_reserve,
+        setup.borrow_reserve.pubkey
+    );
+    assert_eq!(state.isolated_debts[0].borrowed_amount, 10_000_000);
+    let collateral_state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+    assert_eq!(collateral_state.isolated_debts.len(), 1);
+    assert_eq!(
+        collateral_state.isolated_debts[0].borrow_reserve,
+        setup.borrow_reserve.pubkey
+    );
+    assert_eq!(
+        collateral_state.isolated_debts[0].borrowed_amount,
+        10_000_000
+    );
+    assert_eq!(
+        collateral_state.isolated_debt_value().unwrap(),
+        borrow_state.market_value(10_000_000).unwrap()
+    );
+
+    // 価格が倍になると借入済みの10_000_000も今の価格で数えられ，ceilingを超える
+    setup.ctx.warp_to_slot(3).unwrap();
+    setup
+        .borrow_oracle
+        .set_price(&mut setup.ctx, BORROW_PRICE * 2)
+        .await;
+    let instructions = setup.borrow_ixs(1_000_000).await;
+    let result = process(&mut setup.ctx, &instructions, &[]).await;
+    assert_lending_error(result, LendingError::DebtCeilingExceeded);
+
+    // 価格が変わっても全額返済すればdebtは残らない
+    let mut instructions = setup.obligation.refresh_ixs(&mut setup.ctx).await;
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    instructions.push(instruction::repay_obligation_liquidity(
+        u64::MAX,
+        setup.borrow_reserve.pubkey,
+        &reserve_state,
+        setup.obligation.pubkey,
+        &obligation_state,
+        user_liquidity,
+        payer,
+    ));
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert!(state.isolated_debts.is_empty());
+    let collateral_state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+    assert!(collateral_state.isolated_debts.is_empty());
+    assert_eq!(collateral_state.isolated_debt_value().unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_isolation_rules() {
+    let mut setup = setup().await;
+    let payer = setup.ctx.payer.pubkey();
+    let collateral_state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+    let mut collateral_config = test_reserve_config();
+    collateral_config.isolated = true;
+    collateral_config.debt_ceiling = u64::MAX;
+    process(
+        &mut setup.ctx,
+        &[instruction::update_reserve_config(
+            collateral_config,
+            setup.collateral_reserve.pubkey,
+            &collateral_state,
+            payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    setup.deposit(4_000_000).await;
+
+    // isolated collateralは他の担保と組み合わせられない
+    let borrow_reserve = setup.borrow_reserve.clone();
+    let instructions = setup.deposit_ixs(&borrow_reserve, 1_000_000).await;
+    let result = process(&mut setup.ctx, &instructions, &[]).await;
+    assert_lending_error(result, LendingError::IsolatedCollateral);
+
+    // borrowable_in_isolationでないreserveからは借りられない
+    let instructions = setup.borrow_ixs(1_000_000).await;
+    let result = process(&mut setup.ctx, &instructions, &[]).await;
+    assert_lending_error(result, LendingError::NotBorrowableInIsolation);
+
+    let borrow_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    let mut borrow_config = test_reserve_config();
+    borrow_config.borrowable_in_isolation = true;
+    process(
+        &mut setup.ctx,
+        &[instruction::update_reserve_config(
+            borrow_config,
+            setup.borrow_reserve.pubkey,
+            &borrow_state,
+            payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let instructions = setup.borrow_ixs(2_000_000).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+
+    // 同じisolated collateralなら追加で預けられる
+    setup.deposit(1_000_000).await;
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.deposits.len(), 1);
+    assert_eq!(state.deposits[0].deposited_amount, 5_000_000);
+
+    // collateralが預けられている間は孤立モードを切り替えられない
+    let collateral_state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+    let result = process(
+        &mut setup.ctx,
+        &[instruction::update_reserve_config(
+            test_reserve_config(),
+            setup.collateral_reserve.pubkey,
+            &collateral_state,
+            payer,
+        )],
+        &[],
+    )
+    .await;
+    assert_lending_error(result, LendingError::ReserveHasCollateralDeposits);
+}
+
+#[tokio::test]
+async fn test_set_obligation_emode() {
+    let mut setup = setup().await;
+    let payer = setup.ctx.payer.pubkey();
+    let category = EModeCategory {
+        id: 1,
+        loan_to_value_ratio: 90,
+        liquidation_threshold: 95,
+        liquidation_bonus: 2,
+        oracle: None,
+        min_liquidation_bonus: 2,
+    };
+    process(
+        &mut setup.ctx,
+        &[instruction::set_emode_category(
+            setup.lending_market.pubkey,
+            payer,
+            category,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // 存在しないcategoryは選べない
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    let result = process(
+        &mut setup.ctx,
+        &[instruction::set_obligation_emode(
+            setup.obligation.pubkey,
+            &obligation_state,
+            2,
+        )],
+        &[],
+    )
+    .await;
+    assert_lending_error(result, LendingError::InvalidEModeCategory);
+
+    process(
+        &mut setup.ctx,
+        &[instruction::set_obligation_emode(
+            setup.obligation.pubkey,
+            &obligation_state,
+            1,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.emode_category, 1);
+
+    // 借入があるとcategoryは変更できない
+    setup.deposit(4_000_000).await;
+    let instructions = setup.borrow_ixs(1_000_000).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    let result = process(
+        &mut setup.ctx,
+        &[instruction::set_obligation_emode(
+            setup.obligation.pubkey,
+            &obligation_state,
+            0,
+        )],
+        &[],
+    )
+    .await;
+    assert_lending_error(result, LendingError::ObligationBorrowsNotEmpty);
+}
+
+#[tokio::test]
+async fn test_emode_valuation() {
+    let mut setup = setup().await;
+    let payer = setup.ctx.payer.pubkey();
+    let category = EModeCategory {
+        id: 1,
+        loan_to_value_ratio: 90,
+        liquidation_threshold: 95,
+        liquidation_bonus: 2,
+        oracle: None,
+        min_liquidation_bonus: 2,
+    };
+    let mut config = test_reserve_config();
+    config.emode_category = 1;
+    let collateral_state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    process(
+        &mut setup.ctx,
+        &[
+            instruction::set_emode_category(setup.lending_market.pubkey, payer, category),
+            instruction::update_reserve_config(
+                config.clone(),
+                setup.collateral_reserve.pubkey,
+                &collateral_state,
+                payer,
+            ),
+            instruction::set_obligation_emode(setup.obligation.pubkey, &obligation_state, 1),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    setup.deposit(4_000_000).await;
+    let instructions = setup.borrow_ixs(1_000_000).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+
+    // borrow reserveがcategory外ならreserveのLTVとthresholdのまま
+    let instructions = setup.obligation.refresh_ixs(&mut setup.ctx).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.allowed_borrow_value, state.deposited_value * 50 / 100);
+    assert_eq!(
+        state.unhealthy_borrow_value,
+        state.deposited_value * 55 / 100
+    );
+
+    // 全てのpositionがcategoryに入るとcategoryの値が使われる
+    let borrow_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    process(
+        &mut setup.ctx,
+        &[instruction::update_reserve_config(
+            config,
+            setup.borrow_reserve.pubkey,
+            &borrow_state,
+            payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    setup.ctx.warp_to_slot(3).unwrap();
+    let instructions = setup.obligation.refresh_ixs(&mut setup.ctx).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.allowed_borrow_value, state.deposited_value * 90 / 100);
+    assert_eq!(
+        state.unhealthy_borrow_value,
+        state.deposited_value * 95 / 100
+    );
+}
+
+#[tokio::test]
+async fn test_emode_shared_oracle() {
+    let mut setup = setup().await;
+    let payer = setup.ctx.payer.pubkey();
+
+    // 両方のreserveを同じcategoryに入れ，borrow reserveのoracleで評価する
+    let category = EModeCategory {
+        id: 1,
+        loan_to_value_ratio: 90,
+        liquidation_threshold: 95,
+        liquidation_bonus: 2,
+        oracle: Some(setup.borrow_oracle.price),
+        min_liquidation_bonus: 2,
+    };
+    let collateral_state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+    let borrow_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    let mut collateral_config = test_reserve_config();
+    collateral_config.emode_category = 1;
+    // borrow reserveはslotあたり1%までの変動を受け入れ、2回のrefreshで確認する
+    let mut borrow_config = test_reserve_config();
+    borrow_config.emode_category = 1;
+    borrow_config.price_guard_bps_per_slot = 100;
+    borrow_config.price_guard_confirmations = 2;
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    process(
+        &mut setup.ctx,
+        &[
+            instruction::set_emode_category(setup.lending_market.pubkey, payer, category),
+            instruction::update_reserve_config(
+                collateral_config,
+                setup.collateral_reserve.pubkey,
+                &collateral_state,
+                payer,
+            ),
+            instruction::update_reserve_config(
+                borrow_config,
+                setup.borrow_reserve.pubkey,
+                &borrow_state,
+                payer,
+            ),
+            instruction::set_obligation_emode(setup.obligation.pubkey, &obligation_state, 1),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    setup.deposit(4_000_000).await;
+
+    async fn refresh_ixs(setup: &mut TestSetup, price_reserve: Pubkey) -> Vec<Instruction> {
+        let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+        vec![
+            setup.collateral_reserve.refresh_ix(&mut setup.ctx).await,
+            setup.borrow_reserve.refresh_ix(&mut setup.ctx).await,
+            instruction::refresh_obligation(
+                setup.obligation.pubkey,
+                &obligation_state,
+                Some(price_reserve),
+            ),
+        ]
+    }
+
+    // 共有oracleを使わないreserveの価格では評価できない
+    let collateral_pubkey = setup.collateral_reserve.pubkey;
+    let instructions = refresh_ixs(&mut setup, collateral_pubkey).await;
+    let result = process(&mut setup.ctx, &instructions, &[]).await;
+    assert_lending_error(result, LendingError::InvalidOracleConfig);
+
+    let borrow_pubkey = setup.borrow_reserve.pubkey;
+    let instructions = refresh_ixs(&mut setup, borrow_pubkey).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    let collateral_state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+    let shared_value = collateral_state
+        .market_value_at(4_000_000, BORROW_PRICE as u128)
+        .unwrap();
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.deposited_value, shared_value);
+    assert!(!state.price_guarded);
+
+    // 共有oracleの急な変動はborrow reserveのprice guardで保留される
+    setup.ctx.warp_to_slot(3).unwrap();
+    setup
+        .borrow_oracle
+        .set_price(&mut setup.ctx, BORROW_PRICE * 10)
+        .await;
+    let instructions = refresh_ixs(&mut setup, borrow_pubkey).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.deposited_value, shared_value);
+    assert!(state.price_guarded);
+}
+
+#[tokio::test]
+async fn test_obligation_delegate() {
+    let mut setup = setup().await;
+    let delegate = Keypair::new();
+    setup.deposit(4_000_000).await;
+
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    process(
+        &mut setup.ctx,
+        &[instruction::set_obligation_delegate(
+            setup.obligation.pubkey,
+            &obligation_state,
+            Some(ObligationDelegate {
+                authority: delegate.pubkey(),
+                permissions: ObligationDelegate::BORROW | ObligationDelegate::WITHDRAW,
+                borrow_allowance: Some(10),
+            }),
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // 借入価値5を許容額10から使う
+    let user_liquidity = setup.borrow_reserve.user_liquidity;
+    let instructions = setup
+        .borrow_ixs_by(delegate.pubkey(), user_liquidity, 5_000_000)
+        .await;
+    process(&mut setup.ctx, &instructions, &[&delegate])
+        .await
+        .unwrap();
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.borrows[0].borrowed_amount, 5_000_000);
+    assert_eq!(state.delegate.unwrap().borrow_allowance, Some(5));
+
+    setup.ctx.warp_to_slot(3).unwrap();
+    let instructions = setup
+        .borrow_ixs_by(delegate.pubkey(), user_liquidity, 6_000_000)
+        .await;
+    let result = process(&mut setup.ctx, &instructions, &[&delegate]).await;
+    assert_lending_error(result, LendingError::DelegateAllowanceExceeded);
+
+    // delegateは自分のaccountには借りられない
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    let delegate_liquidity = create_token_account(
+        &mut setup.ctx,
+        &reserve_state.liquidity.mint_pubkey,
+        &delegate.pubkey(),
+    )
+    .await;
+    let instructions = setup
+        .borrow_ixs_by(delegate.pubkey(), delegate_liquidity, 1_000_000)
+        .await;
+    let result = process(&mut setup.ctx, &instructions, &[&delegate]).await;
+    assert_lending_error(result, LendingError::InvalidDelegateDestination);
+
+    // delegateは自分のaccountには引き出せない
+    let reserve_state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+    let delegate_collateral = create_token_account(
+        &mut setup.ctx,
+        &reserve_state.collateral.mint_pubkey,
+        &delegate.pubkey(),
+    )
+    .await;
+    let instructions = setup
+        .withdraw_ixs_by(delegate.pubkey(), delegate_collateral, 1_000_000)
+        .await;
+    let result = process(&mut setup.ctx, &instructions, &[&delegate]).await;
+    assert_lending_error(result, LendingError::InvalidDelegateDestination);
+
+    let user_collateral = setup.collateral_reserve.user_collateral;
+    let instructions = setup
+        .withdraw_ixs_by(delegate.pubkey(), user_collateral, 1_000_000)
+        .await;
+    process(&mut setup.ctx, &instructions, &[&delegate])
+        .await
+        .unwrap();
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.deposits[0].deposited_amount, 3_000_000);
+
+    // delegateを外すと借りられない
+    process(
+        &mut setup.ctx,
+        &[instruction::set_obligation_delegate(
+            setup.obligation.pubkey,
+            &state,
+            None,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let instructions = setup
+        .borrow_ixs_by(delegate.pubkey(), user_liquidity, 1_000_000)
+        .await;
+    let result = process(&mut setup.ctx, &instructions, &[&delegate]).await;
+    assert_lending_error(result, LendingError::InvalidObligationOwner);
+}
+
+#[tokio::test]
+async fn test_obligation_reserve_limit() {
+    let mut test = program_test();
+    let oracle = TestOracle::add(&mut test, COLLATERAL_PRICE, 0);
+    let mut ctx = start(test).await;
+    let lending_market = TestLendingMarket::init(&mut ctx).await;
+    let mut reserves = vec![];
+    for _ in 0..=MAX_OBLIGATION_RESERVE {
+        reserves.push(
+            TestReserve::init(
+                &mut ctx,
+                &lending_market,
+                &oracle,
+                test_reserve_config(),
+                COLLATERAL_LIQUIDITY_AMOUNT,
+                0,
+            )
+            .await
+            .unwrap(),
+        );
+    }
+    let obligation = TestObligation::init(&mut ctx, &lending_market).await;
+    let bump_seed = lending_market.bump_seed(&mut ctx).await;
+    let mut setup = TestSetup {
+        collateral_reserve: reserves[0].clone(),
+        borrow_reserve: reserves[0].clone(),
+        ctx,
+        lending_market,
+        collateral_oracle: oracle.clone(),
+        borrow_oracle: oracle,
+        obligation,
+        bump_seed,
+    };
+
+    let (last, reserves) = reserves.split_last().unwrap();
+    for reserve in reserves {
+        let instructions = setup.deposit_ixs(reserve, 1_000_000).await;
+        process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    }
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.deposits.len(), MAX_OBLIGATION_RESERVE);
+
+    let instructions = setup.deposit_ixs(last, 1_000_000).await;
+    let result = process(&mut setup.ctx, &instructions, &[]).await;
+    assert_lending_error(result, LendingError::ObligationReserveLimit);
+}
+
+#[tokio::test]
+async fn test_liquidate_obligation() {
+    let mut setup = setup().await;
+    let user_liquidity = setup.borrow_reserve.user_liquidity;
+    let user_collateral = setup.collateral_reserve.user_collateral;
+    setup.deposit(4_000_000).await;
+    let instructions = setup.borrow_ixs(40_000_000).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+
+    // 健全なobligationはliquidationできない
+    let instructions = setup.liquidate_ixs(u64::MAX).await;
+    let result = process(&mut setup.ctx, &instructions, &[]).await;
+    assert_lending_error(result, LendingError::ObligationHealthy);
+
+    // collateralの価格が半分になり借入がliquidation thresholdを超える
+    setup.ctx.warp_to_slot(3).unwrap();
+    setup
+        .collateral_oracle
+        .set_price(&mut setup.ctx, COLLATERAL_PRICE / 2)
+        .await;
+    let liquidity_before = token_balance(&mut setup.ctx, &user_liquidity).await;
+    let collateral_before = token_balance(&mut setup.ctx, &user_collateral).await;
+    let instructions = setup.liquidate_ixs(u64::MAX).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+
+    // close factorにより借入の半分を返済し、5%のbonusを加えたcollateralを受け取る
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.borrows[0].borrowed_amount, 20_000_000);
+    assert_eq!(state.deposits[0].deposited_amount, 4_000_000 - 2_100_000);
+    assert_eq!(
+        token_balance(&mut setup.ctx, &user_liquidity).await,
+        liquidity_before - 20_000_000
+    );
+    assert_eq!(
+        token_balance(&mut setup.ctx, &user_collateral).await,
+        collateral_before + 2_100_000
+    );
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    assert_eq!(reserve_state.liquidity.borrowed_amount, 20_000_000);
+}
+
+#[tokio::test]
+async fn test_liquidation_pair() {
+    let mut setup = setup().await;
+    let payer = setup.ctx.payer.pubkey();
+    let lending_market = setup.lending_market.pubkey;
+    let deposit_reserve = setup.collateral_reserve.pubkey;
+    let borrow_reserve = setup.borrow_reserve.pubkey;
+    let user_liquidity = setup.borrow_reserve.user_liquidity;
+    let user_collateral = setup.collateral_reserve.user_collateral;
+    setup.deposit(4_000_000).await;
+    let instructions = setup.borrow_ixs(40_000_000).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+
+    // この組み合わせでは借入の25%まで返済でき，bonusは10%
+    let config = LiquidationPairConfig {
+        close_factor: 25,
+        liquidation_bonus: 10,
+        min_liquidation_bonus: 10,
+    };
+    process(
+        &mut setup.ctx,
+        &[instruction::set_liquidation_pair(
+            lending_market,
+            payer,
+            deposit_reserve,
+            borrow_reserve,
+            Some(config),
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let state = setup.lending_market.get_state(&mut setup.ctx).await;
+    assert_eq!(state.liquidation_pairs.len(), 1);
+    assert_eq!(
+        state.find_liquidation_pair(deposit_reserve, borrow_reserve),
+        Some(&config)
+    );
+
+    // collateralの価格が16になり借入がliquidation thresholdを超える
+    setup.ctx.warp_to_slot(3).unwrap();
+    setup.collateral_oracle.set_price(&mut setup.ctx, 16).await;
+    let liquidity_before = token_balance(&mut setup.ctx, &user_liquidity).await;
+    let collateral_before = token_balance(&mut setup.ctx, &user_collateral).await;
+    let instructions = setup.liquidate_ixs(u64::MAX).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.borrows[0].borrowed_amount, 30_000_000);
+    assert_eq!(state.deposits[0].deposited_amount, 4_000_000 - 687_500);
+    assert_eq!(
+        token_balance(&mut setup.ctx, &user_liquidity).await,
+        liquidity_before - 10_000_000
+    );
+    assert_eq!(
+        token_balance(&mut setup.ctx, &user_collateral).await,
+        collateral_before + 687_500
+    );
+
+    // 残りの借入は少額なので，組み合わせのclose factorに関係なく一度に全て返済させる
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    let full_close_value = reserve_state.market_value(30_000_000).unwrap() + 1;
+    process(
+        &mut setup.ctx,
+        &[instruction::set_liquidation_config(
+            lending_market,
+            payer,
+            50,
+            full_close_value,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let state = setup.lending_market.get_state(&mut setup.ctx).await;
+    assert_eq!(state.liquidation_close_factor, 50);
+    assert_eq!(state.liquidation_full_close_value, full_close_value);
+
+    setup.ctx.warp_to_slot(20).unwrap();
+    setup.collateral_oracle.set_price(&mut setup.ctx, 16).await;
+    setup
+        .borrow_oracle
+        .set_price(&mut setup.ctx, BORROW_PRICE)
+        .await;
+    let instructions = setup.liquidate_ixs(u64::MAX).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+
+    // 価値30の借入に10%のbonusを加え，価値53のcollateralの33/53を切り捨てて渡す
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert!(state.borrows.is_empty());
+    assert_eq!(
+        state.deposits[0].deposited_amount,
+        4_000_000 - 687_500 - 2_062_499
+    );
+
+    // 組み合わせの設定を外すとmarketの設定に戻る
+    process(
+        &mut setup.ctx,
+        &[instruction::set_liquidation_pair(
+            lending_market,
+            payer,
+            deposit_reserve,
+            borrow_reserve,
+            None,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let state = setup.lending_market.get_state(&mut setup.ctx).await;
+    assert!(state.liquidation_pairs.is_empty());
+
+    // close factorは1%から100%まで，bonusは100%未満で最小のbonus以上
+    for config in [
+        LiquidationPairConfig {
+            close_factor: 0,
+            liquidation_bonus: 5,
+            min_liquidation_bonus: 5,
+        },
+        LiquidationPairConfig {
+            close_factor: 101,
+            liquidation_bonus: 5,
+            min_liquidation_bonus: 5,
+        },
+        LiquidationPairConfig {
+            close_factor: 50,
+            liquidation_bonus: 100,
+            min_liquidation_bonus: 100,
+        },
+        LiquidationPairConfig {
+            close_factor: 50,
+            liquidation_bonus: 5,
+            min_liquidation_bonus: 6,
+        },
+    ] {
+        let result = process(
+            &mut setup.ctx,
+            &[instruction::set_liquidation_pair(
+                lending_market,
+                payer,
+                deposit_reserve,
+                borrow_reserve,
+                Some(config),
+            )],
+            &[],
+        )
+        .await;
+        assert_lending_error(result, LendingError::InvalidConfig);
+    }
+
+    // market owner以外は設定できない
+    let not_owner = Keypair::new();
+    let result = process(
+        &mut setup.ctx,
+        &[instruction::set_liquidation_pair(
+            lending_market,
+            not_owner.pubkey(),
+            deposit_reserve,
+            borrow_reserve,
+            Some(config),
+        )],
+        &[&not_owner],
+    )
+    .await;
+    assert_lending_error(result, LendingError::InvalidMarketOwner);
+}
+
+#[tokio::test]
+async fn test_liquidation_bonus_scales_with_unhealthiness() {
+    /// Liquidate half of a borrow worth 4000 against collateral worth 8000 before its price
+    /// drops to `collateral_price`, returning the seized collateral. The bonus comes from a
+    /// liquidation pair if `pair` and from the collateral reserve otherwise.
+    async fn liquidate_at(collateral_price: i64, pair: bool) -> u64 {
+        let mut setup = setup().await;
+        let payer = setup.ctx.payer.pubkey();
+        let user_collateral = setup.collateral_reserve.user_collateral;
+
+        // bonusはliquidation thresholdで2%，借入がcollateralの価値に届くと12%
+        let instruction = if pair {
+            instruction::set_liquidation_pair(
+                setup.lending_market.pubkey,
+                payer,
+                setup.collateral_reserve.pubkey,
+                setup.borrow_reserve.pubkey,
+                Some(LiquidationPairConfig {
+                    close_factor: 50,
+                    liquidation_bonus: 12,
+                    min_liquidation_bonus: 2,
+                }),
+            )
+        } else {
+            let mut config = test_reserve_config();
+            config.min_liquidation_bonus = 2;
+            config.liquidation_bonus = 12;
+            let reserve_state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+            instruction::update_reserve_config(
+                config,
+                setup.collateral_reserve.pubkey,
+                &reserve_state,
+                payer,
+            )
+        };
+        process(&mut setup.ctx, &[instruction], &[]).await.unwrap();
+
+        // 価値に端数が出ないよう価格を100倍にする
+        setup.borrow_oracle.set_price(&mut setup.ctx, 100).await;
+        setup
+            .collateral_oracle
+            .set_price(&mut setup.ctx, 2_000)
+            .await;
+        setup.deposit(4_000_000).await;
+        let instructions = setup.borrow_ixs(40_000_000).await;
+        process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+
+        setup.ctx.warp_to_slot(3).unwrap();
+        setup
+            .collateral_oracle
+            .set_price(&mut setup.ctx, collateral_price)
+            .await;
+        let collateral_before = token_balance(&mut setup.ctx, &user_collateral).await;
+        let instructions = setup.liquidate_ixs(u64::MAX).await;
+        process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+        token_balance(&mut setup.ctx, &user_collateral).await - collateral_before
+    }
+
+    for pair in [false, true] {
+        // collateral 6400に対しunhealthy borrow valueは3520で，借入4000はその間の1/6にあたる
+        // bonusは2%に10%の1/6を足した約3.67%で，返済2000に対し価値2073のcollateralを渡す
+        assert_eq!(liquidate_at(1_600, pair).await, 1_295_625);
+        // 借入がcollateralの価値4000に届き，bonusは最大の12%
+        assert_eq!(liquidate_at(1_000, pair).await, 2_240_000);
+    }
+}
+
+#[tokio::test]
+async fn test_referral_host_fees() {
+    let mut setup = setup().await;
+    let payer = setup.ctx.payer.pubkey();
+    let lending_market = setup.lending_market.pubkey;
+    let referrer_owner = Keypair::new();
+
+    // 借入手数料1%のうち20%がhost fee
+    let mut config = test_reserve_config();
+    config.fees = ReserveFees {
+        borrow_fee_wad: 10_000_000_000_000_000,
+        flash_loan_fee_wad: 0,
+        host_fee_percentage: 20,
+    };
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    process(
+        &mut setup.ctx,
+        &[
+            instruction::update_reserve_config(
+                config,
+                setup.borrow_reserve.pubkey,
+                &reserve_state,
+                payer,
+            ),
+            system_instruction::transfer(&payer, &referrer_owner.pubkey(), 100_000_000),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let referrer = pda::referrer(&lending_market, &referrer_owner.pubkey());
+    let mint = setup.borrow_reserve.liquidity_mint;
+    process(
+        &mut setup.ctx,
+        &[
+            instruction::init_referrer(lending_market, referrer_owner.pubkey()),
+            instruction::init_referrer_token_state(
+                referrer,
+                setup.borrow_reserve.pubkey,
+                &reserve_state,
+                setup.bump_seed,
+                payer,
+            )
+            .unwrap(),
+        ],
+        &[&referrer_owner],
+    )
+    .await
+    .unwrap();
+
+    // 自分自身をreferrerにはできない
+    let self_referrer = pda::referrer(&lending_market, &payer);
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    let result = process(
+        &mut setup.ctx,
+        &[
+            instruction::init_referrer(lending_market, payer),
+            instruction::set_obligation_referrer(
+                setup.obligation.pubkey,
+                &obligation_state,
+                self_referrer,
+            ),
+        ],
+        &[],
+    )
+    .await;
+    assert_lending_error(result, LendingError::InvalidReferrer);
+
+    process(
+        &mut setup.ctx,
+        &[instruction::set_obligation_referrer(
+            setup.obligation.pubkey,
+            &obligation_state,
+            referrer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.referrer, Some(referrer));
+
+    // referrerは一度しか設定できない
+    setup.ctx.warp_to_slot(3).unwrap();
+    let result = process(
+        &mut setup.ctx,
+        &[instruction::set_obligation_referrer(
+            setup.obligation.pubkey,
+            &state,
+            referrer,
+        )],
+        &[],
+    )
+    .await;
+    assert_lending_error(result, LendingError::ReferrerAlreadySet);
+
+    setup.deposit(4_000_000).await;
+    let instructions = setup.borrow_ixs(10_000_000).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+
+    let token_state_pubkey = pda::referrer_token_state(&referrer, &mint);
+    let token_account = pda::referrer_token_account(&referrer, &mint);
+    let token_state: ReferrerTokenState = get_state(&mut setup.ctx, &token_state_pubkey).await;
+    assert_eq!(token_state.referrer, referrer);
+    assert_eq!(token_state.mint, mint);
+    assert_eq!(token_state.token_account, token_account);
+    assert_eq!(token_state.accrued_amount, 20_000);
+    assert_eq!(token_balance(&mut setup.ctx, &token_account).await, 20_000);
+    assert_eq!(
+        token_balance(&mut setup.ctx, &reserve_state.liquidity.fee_receiver).await,
+        80_000
+    );
+
+    let destination = create_token_account(&mut setup.ctx, &mint, &referrer_owner.pubkey()).await;
+    let claim_ix = instruction::claim_referrer_fees(
+        lending_market,
+        setup.bump_seed,
+        referrer_owner.pubkey(),
+        mint,
+        reserve_state.liquidity.token_program_id,
+        destination,
+    )
+    .unwrap();
+    process(&mut setup.ctx, &[claim_ix], &[&referrer_owner])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut setup.ctx, &destination).await, 20_000);
+    assert_eq!(token_balance(&mut setup.ctx, &token_account).await, 0);
+    let token_state: ReferrerTokenState = get_state(&mut setup.ctx, &token_state_pubkey).await;
+    assert_eq!(token_state.accrued_amount, 20_000);
+    assert_eq!(token_state.claimed_amount, 20_000);
+}
+
+#[tokio::test]
+async fn test_deposit_rewards() {
+    let mut setup = setup().await;
+    let payer = setup.ctx.payer.pubkey();
+    let reward_mint = create_mint(&mut setup.ctx, &payer).await;
+    let source_reward = create_token_account(&mut setup.ctx, &reward_mint, &payer).await;
+    mint_to(&mut setup.ctx, &reward_mint, &source_reward, 50_000).await;
+
+    setup.ctx.warp_to_slot(10).unwrap();
+    setup
+        .collateral_oracle
+        .set_price(&mut setup.ctx, COLLATERAL_PRICE)
+        .await;
+    let reserve_pubkey = setup.collateral_reserve.pubkey;
+    let reserve_state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+    let reward_pool_pubkey = pda::reward_pool(&reserve_pubkey, RewardSide::Deposit);
+    process(
+        &mut setup.ctx,
+        &[instruction::init_reward_pool(
+            RewardSide::Deposit,
+            reserve_pubkey,
+            &reserve_state,
+            setup.bump_seed,
+            reward_mint,
+            spl_token::ID,
+            payer,
+        )
+        .unwrap()],
+        &[],
+    )
+    .await
+    .unwrap();
+    let reserve_state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+    assert_eq!(reserve_state.deposit_reward_pool, Some(reward_pool_pubkey));
+    assert_eq!(reserve_state.borrow_reward_pool, None);
+
+    // slotあたり100を配る
+    let reward_pool: RewardPool = get_state(&mut setup.ctx, &reward_pool_pubkey).await;
+    process(
+        &mut setup.ctx,
+        &[instruction::fund_reward_pool(
+            50_000,
+            100,
+            10_000,
+            reward_pool_pubkey,
+            &reward_pool,
+            source_reward,
+            payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        token_balance(&mut setup.ctx, &reward_pool.vault).await,
+        50_000
+    );
+
+    setup.deposit(4_000_000).await;
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.rewards.len(), 1);
+    assert_eq!(state.rewards[0].reward_pool, reward_pool_pubkey);
+    assert_eq!(state.rewards[0].staked_amount, 4_000_000);
+    let reward_pool: RewardPool = get_state(&mut setup.ctx, &reward_pool_pubkey).await;
+    assert_eq!(reward_pool.total_staked, 4_000_000);
+
+    // 唯一のdepositなので100 slot分すべて受け取る
+    setup.ctx.warp_to_slot(110).unwrap();
+    let destination = create_token_account(&mut setup.ctx, &reward_mint, &payer).await;
+    let claim_ix = instruction::claim_rewards(
+        reward_pool_pubkey,
+        &reward_pool,
+        setup.obligation.pubkey,
+        &state,
+        setup.bump_seed,
+        destination,
+    )
+    .unwrap();
+    process(&mut setup.ctx, std::slice::from_ref(&claim_ix), &[])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut setup.ctx, &destination).await, 10_000);
+
+    // 残高を超えては配らない
+    setup.ctx.warp_to_slot(1_000).unwrap();
+    process(&mut setup.ctx, &[claim_ix], &[]).await.unwrap();
+    assert_eq!(token_balance(&mut setup.ctx, &destination).await, 50_000);
+    assert_eq!(token_balance(&mut setup.ctx, &reward_pool.vault).await, 0);
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.rewards[0].unclaimed_amount, 0);
+}
+
+#[tokio::test]
+async fn test_sync_rewards() {
+    let mut setup = setup().await;
+    let payer = setup.ctx.payer.pubkey();
+    let reward_mint = create_mint(&mut setup.ctx, &payer).await;
+    let source_reward = create_token_account(&mut setup.ctx, &reward_mint, &payer).await;
+    mint_to(&mut setup.ctx, &reward_mint, &source_reward, 50_000).await;
+
+    // reward poolより前からあるdeposit
+    setup.deposit(4_000_000).await;
+
+    setup.ctx.warp_to_slot(10).unwrap();
+    setup
+        .collateral_oracle
+        .set_price(&mut setup.ctx, COLLATERAL_PRICE)
+        .await;
+    let reserve_pubkey = setup.collateral_reserve.pubkey;
+    let reserve_state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+    let reward_pool_pubkey = pda::reward_pool(&reserve_pubkey, RewardSide::Deposit);
+    process(
+        &mut setup.ctx,
+        &[instruction::init_reward_pool(
+            RewardSide::Deposit,
+            reserve_pubkey,
+            &reserve_state,
+            setup.bump_seed,
+            reward_mint,
+            spl_token::ID,
+            payer,
+        )
+        .unwrap()],
+        &[],
+    )
+    .await
+    .unwrap();
+    let reward_pool: RewardPool = get_state(&mut setup.ctx, &reward_pool_pubkey).await;
+    process(
+        &mut setup.ctx,
+        &[instruction::fund_reward_pool(
+            50_000,
+            100,
+            10_000,
+            reward_pool_pubkey,
+            &reward_pool,
+            source_reward,
+            payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert!(state.rewards.is_empty());
+
+    // ownerの署名なしでstakeさせられる
+    let sync_ix = instruction::sync_rewards(reward_pool_pubkey, setup.obligation.pubkey);
+    assert!(sync_ix.accounts.iter().all(|meta| !meta.is_signer));
+    process(&mut setup.ctx, &[sync_ix], &[]).await.unwrap();
+    let state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert_eq!(state.rewards.len(), 1);
+    assert_eq!(state.rewards[0].reward_pool, reward_pool_pubkey);
+    assert_eq!(state.rewards[0].staked_amount, 4_000_000);
+    let reward_pool: RewardPool = get_state(&mut setup.ctx, &reward_pool_pubkey).await;
+    assert_eq!(reward_pool.total_staked, 4_000_000);
+
+    // stakeした後の100 slot分を受け取る
+    setup.ctx.warp_to_slot(110).unwrap();
+    let destination = create_token_account(&mut setup.ctx, &reward_mint, &payer).await;
+    let claim_ix = instruction::claim_rewards(
+        reward_pool_pubkey,
+        &reward_pool,
+        setup.obligation.pubkey,
+        &state,
+        setup.bump_seed,
+        destination,
+    )
+    .unwrap();
+    process(&mut setup.ctx, &[claim_ix], &[]).await.unwrap();
+    assert_eq!(token_balance(&mut setup.ctx, &destination).await, 10_000);
+}
+
+#[tokio::test]
+async fn test_price_guard() {
+    let mut setup = setup().await;
+    let payer = setup.ctx.payer.pubkey();
+    let reserve_pubkey = setup.collateral_reserve.pubkey;
+
+    // slotあたり1%までの変動を受け入れ、2回のrefreshで確認する
+    let mut config = test_reserve_config();
+    config.price_guard_bps_per_slot = 100;
+    config.price_guard_confirmations = 2;
+    let reserve_state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+    process(
+        &mut setup.ctx,
+        &[instruction::update_reserve_config(
+            config,
+            reserve_pubkey,
+            &reserve_state,
+            payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    setup.deposit(4_000_000).await;
+
+    setup.collateral_oracle.set_price(&mut setup.ctx, 40).await;
+    let instructions = setup.borrow_ixs(1_000_000).await;
+    let result = process(&mut setup.ctx, &instructions, &[]).await;
+    assert_lending_error(result, LendingError::ReservePriceGuarded);
+
+    let refresh_ix = setup.collateral_reserve.refresh_ix(&mut setup.ctx).await;
+    process(&mut setup.ctx, &[refresh_ix], &[]).await.unwrap();
+    let state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+    assert_eq!(state.liquidity.market_price, 20);
+    assert!(state.price_guard.guarded);
+    assert_eq!(state.price_guard.pending_price, 40);
+    assert_eq!(state.price_guard.confirmations, 0);
+
+    // 別々のslotで新しい価格が続けば受け入れる
+    for slot in [20, 30] {
+        setup.ctx.warp_to_slot(slot).unwrap();
+        setup.collateral_oracle.set_price(&mut setup.ctx, 40).await;
+        let refresh_ix = setup.collateral_reserve.refresh_ix(&mut setup.ctx).await;
+        process(&mut setup.ctx, &[refresh_ix], &[]).await.unwrap();
+    }
+    let state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+    assert_eq!(state.liquidity.market_price, 40);
+    assert!(!state.price_guard.guarded);
+    assert_eq!(state.price_guard.accepted_slot, 30);
+
+    setup
+        .borrow_oracle
+        .set_price(&mut setup.ctx, BORROW_PRICE)
+        .await;
+    let instructions = setup.borrow_ixs(1_000_000).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert!(!obligation_state.price_guarded);
+
+    let result = process(
+        &mut setup.ctx,
+        &[instruction::override_reserve_price_guard(
+            reserve_pubkey,
+            &state,
+            payer,
+        )],
+        &[],
+    )
+    .await;
+    assert_lending_error(result, LendingError::ReservePriceNotGuarded);
+
+    // ownerは保留中の価格をすぐに受け入れられる
+    setup.ctx.warp_to_slot(40).unwrap();
+    setup.collateral_oracle.set_price(&mut setup.ctx, 10).await;
+    let refresh_ix = setup.collateral_reserve.refresh_ix(&mut setup.ctx).await;
+    process(&mut setup.ctx, &[refresh_ix], &[]).await.unwrap();
+    process(
+        &mut setup.ctx,
+        &[instruction::override_reserve_price_guard(
+            reserve_pubkey,
+            &state,
+            payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+    assert_eq!(state.liquidity.market_price, 10);
+    assert!(!state.price_guard.guarded);
+    assert!(state.last_update.stale);
+}
+
+#[tokio::test]
+async fn test_twap_fallback() {
+    let mut setup = setup().await;
+    let payer = setup.ctx.payer.pubkey();
+    let reserve_pubkey = setup.collateral_reserve.pubkey;
+    let user_liquidity = setup.borrow_reserve.user_liquidity;
+    let user_collateral = setup.collateral_reserve.user_collateral;
+
+    // oracleが止まってから100 slotまではTWAPを使い、liquidationのbonusを10%から5%に減らす
+    let mut config = test_reserve_config();
+    config.liquidation_bonus = 10;
+    config.twap_fallback_max_slots = 100;
+    config.twap_liquidation_bonus = 5;
+    let reserve_state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+    process(
+        &mut setup.ctx,
+        &[instruction::update_reserve_config(
+            config,
+            reserve_pubkey,
+            &reserve_state,
+            payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    setup.deposit(4_000_000).await;
+    let instructions = setup.borrow_ixs(40_000_000).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+
+    // windowより後の価格はそのままTWAPになる
+    setup.ctx.warp_to_slot(200).unwrap();
+    setup
+        .collateral_oracle
+        .set_price(&mut setup.ctx, COLLATERAL_PRICE / 2)
+        .await;
+    let refresh_ix = setup.collateral_reserve.refresh_ix(&mut setup.ctx).await;
+    process(&mut setup.ctx, &[refresh_ix], &[]).await.unwrap();
+    let state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+    assert_eq!(state.twap.price, 10);
+    assert_eq!(state.twap.slot, 200);
+
+    setup.ctx.warp_to_slot(220).unwrap();
+    setup
+        .collateral_oracle
+        .set_status(&mut setup.ctx, OracleStatus::Halted)
+        .await;
+    setup
+        .borrow_oracle
+        .set_price(&mut setup.ctx, BORROW_PRICE)
+        .await;
+    let refresh_ix = setup.collateral_reserve.refresh_ix(&mut setup.ctx).await;
+    process(&mut setup.ctx, &[refresh_ix], &[]).await.unwrap();
+    let state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+    assert!(state.twap.fallback);
+    assert_eq!(state.liquidity.market_price, 10);
+
+    // リスクを増やす操作は止まる
+    let instructions = setup.borrow_ixs(1_000_000).await;
+    let result = process(&mut setup.ctx, &instructions, &[]).await;
+    assert_lending_error(result, LendingError::ReservePriceFallback);
+    let instructions = setup.withdraw_ixs(1_000).await;
+    let result = process(&mut setup.ctx, &instructions, &[]).await;
+    assert_lending_error(result, LendingError::ReservePriceFallback);
+    let instructions = setup.obligation.refresh_ixs(&mut setup.ctx).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert!(obligation_state.price_fallback);
+
+    // depositとliquidationは続けられる
+    setup.deposit(100_000).await;
+    let liquidity_before = token_balance(&mut setup.ctx, &user_liquidity).await;
+    let collateral_before = token_balance(&mut setup.ctx, &user_collateral).await;
+    let instructions = setup.liquidate_ixs(u64::MAX).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    // 利息の分だけ返済額は半分より少し多い
+    assert_eq!(
+        token_balance(&mut setup.ctx, &user_liquidity).await,
+        liquidity_before - 20_000_001
+    );
+    assert_eq!(
+        token_balance(&mut setup.ctx, &user_collateral).await,
+        collateral_before + 2_099_999
+    );
+
+    let mut instructions = setup.obligation.refresh_ixs(&mut setup.ctx).await;
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    instructions.push(instruction::repay_obligation_liquidity(
+        u64::MAX,
+        setup.borrow_reserve.pubkey,
+        &reserve_state,
+        setup.obligation.pubkey,
+        &obligation_state,
+        user_liquidity,
+        payer,
+    ));
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert!(obligation_state.borrows.is_empty());
+
+    // TWAPが古くなるとreserveは止まる
+    setup.ctx.warp_to_slot(320).unwrap();
+    let refresh_ix = setup.collateral_reserve.refresh_ix(&mut setup.ctx).await;
+    let result = process(&mut setup.ctx, &[refresh_ix], &[]).await;
+    assert_lending_error(result, LendingError::InvalidOracleConfig);
+
+    setup.ctx.warp_to_slot(340).unwrap();
+    setup
+        .collateral_oracle
+        .set_status(&mut setup.ctx, OracleStatus::Trading)
+        .await;
+    setup
+        .collateral_oracle
+        .set_price(&mut setup.ctx, COLLATERAL_PRICE)
+        .await;
+    let refresh_ix = setup.collateral_reserve.refresh_ix(&mut setup.ctx).await;
+    process(&mut setup.ctx, &[refresh_ix], &[]).await.unwrap();
+    let state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+    assert!(!state.twap.fallback);
+    assert_eq!(state.liquidity.market_price, COLLATERAL_PRICE as u128);
+}
+
+#[tokio::test]
+async fn test_term_loan() {
+    let mut setup = setup().await;
+    let payer = setup.ctx.payer.pubkey();
+    let reserve_pubkey = setup.borrow_reserve.pubkey;
+    let user_liquidity = setup.borrow_reserve.user_liquidity;
+
+    // 1000 slotで5%の固定金利を公開し，満期後は50%にする
+    let mut config = test_reserve_config();
+    config.term_offers = vec![TermOffer::new(1_000, 500)];
+    config.term_penalty_rate_bps = 5_000;
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    process(
+        &mut setup.ctx,
+        &[instruction::update_reserve_config(
+            config,
+            reserve_pubkey,
+            &reserve_state,
+            payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    setup.deposit(4_000_000).await;
+
+    async fn borrow_term_ixs(
+        setup: &mut TestSetup,
+        amount: u64,
+        term_slots: u64,
+        rate_bps: u32,
+    ) -> Vec<Instruction> {
+        let payer = setup.ctx.payer.pubkey();
+        let mut instructions = vec![setup.borrow_reserve.refresh_ix(&mut setup.ctx).await];
+        instructions.extend(setup.obligation.refresh_ixs(&mut setup.ctx).await);
+        let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+        let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+        instructions.push(
+            instruction::borrow_obligation_liquidity_term(
+                amount,
+                term_slots,
+                rate_bps,
+                setup.borrow_reserve.pubkey,
+                &reserve_state,
+                setup.obligation.pubkey,
+                &obligation_state,
+                payer,
+                setup.bump_seed,
+                setup.borrow_reserve.user_liquidity,
+            )
+            .unwrap(),
+        );
+        instructions
+    }
+
+    async fn liquidate_term_ixs(setup: &mut TestSetup, amount: u64) -> Vec<Instruction> {
+        let payer = setup.ctx.payer.pubkey();
+        let mut instructions = setup.obligation.refresh_ixs(&mut setup.ctx).await;
+        let repay_reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+        let withdraw_reserve_state = setup.collateral_reserve.get_state(&mut setup.ctx).await;
+        let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+        instructions.push(
+            instruction::liquidate_obligation_term_loan(
+                amount,
+                setup.borrow_reserve.pubkey,
+                &repay_reserve_state,
+                setup.collateral_reserve.pubkey,
+                &withdraw_reserve_state,
+                setup.obligation.pubkey,
+                &obligation_state,
+                setup.bump_seed,
+                setup.borrow_reserve.user_liquidity,
+                setup.collateral_reserve.user_collateral,
+                payer,
+            )
+            .unwrap(),
+        );
+        instructions
+    }
+
+    // 公開されていない金利では借りられない
+    let instructions = borrow_term_ixs(&mut setup, 10_000_000, 1_000, 400).await;
+    let result = process(&mut setup.ctx, &instructions, &[]).await;
+    assert_lending_error(result, LendingError::TermOfferNotFound);
+
+    let liquidity_before = token_balance(&mut setup.ctx, &user_liquidity).await;
+    let instructions = borrow_term_ixs(&mut setup, 10_000_000, 1_000, 500).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    assert_eq!(
+        token_balance(&mut setup.ctx, &user_liquidity).await,
+        liquidity_before + 10_000_000
+    );
+
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert!(obligation_state.borrows.is_empty());
+    let term_loan = obligation_state.term_loans[0].clone();
+    assert_eq!(term_loan.borrow_reserve, reserve_pubkey);
+    assert_eq!(term_loan.principal_amount, 10_000_000);
+    assert_eq!(term_loan.borrowed_amount, 10_000_000);
+    assert_eq!(term_loan.rate_bps, 500);
+    assert_eq!(term_loan.penalty_rate_bps, 5_000);
+    assert_eq!(term_loan.maturity_slot, term_loan.accrued_slot + 1_000);
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    assert_eq!(reserve_state.term_borrowed_amount, 10_000_000);
+    assert_eq!(
+        reserve_state.liquidity.available_amount,
+        BORROW_LIQUIDITY_AMOUNT - 10_000_000
+    );
+    assert_eq!(reserve_state.liquidity.borrowed_amount, 0);
+    assert_eq!(
+        reserve_state.total_supply().unwrap(),
+        BORROW_LIQUIDITY_AMOUNT as u128
+    );
+
+    // 同じreserveからのterm loanは1つまで
+    let instructions = borrow_term_ixs(&mut setup, 1_000_000, 1_000, 500).await;
+    let result = process(&mut setup.ctx, &instructions, &[]).await;
+    assert_lending_error(result, LendingError::TermLoanExists);
+
+    // 満期前の健全なobligationはliquidationできない
+    let instructions = liquidate_term_ixs(&mut setup, u64::MAX).await;
+    let result = process(&mut setup.ctx, &instructions, &[]).await;
+    assert_lending_error(result, LendingError::ObligationHealthy);
+
+    // 満期を過ぎるとpenalty rateで利息が増え，liquidationできるようになる
+    setup
+        .ctx
+        .warp_to_slot(term_loan.maturity_slot + 100)
+        .unwrap();
+    setup
+        .collateral_oracle
+        .set_price(&mut setup.ctx, COLLATERAL_PRICE)
+        .await;
+    setup
+        .borrow_oracle
+        .set_price(&mut setup.ctx, BORROW_PRICE)
+        .await;
+    let instructions = setup.obligation.refresh_ixs(&mut setup.ctx).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert!(!obligation_state.is_unhealthy());
+    let overdue_amount = obligation_state.term_loans[0].borrowed_amount;
+    // 満期までの1000 slotの5%と満期後の100 slotの50%の利息
+    assert_eq!(overdue_amount, 10_000_012);
+
+    let liquidity_before = token_balance(&mut setup.ctx, &user_liquidity).await;
+    let instructions = liquidate_term_ixs(&mut setup, u64::MAX).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    let liquidated_amount = liquidity_before - token_balance(&mut setup.ctx, &user_liquidity).await;
+    // close factorまで返済される
+    assert_eq!(liquidated_amount as u128, overdue_amount / 2);
+
+    // 残りを返済すると元本は全て戻り，利息の分だけreserveの総供給量が増える
+    let mut instructions = setup.obligation.refresh_ixs(&mut setup.ctx).await;
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    instructions.push(instruction::repay_obligation_term_loan(
+        u64::MAX,
+        reserve_pubkey,
+        &reserve_state,
+        setup.obligation.pubkey,
+        &obligation_state,
+        user_liquidity,
+        payer,
+    ));
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert!(obligation_state.term_loans.is_empty());
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    assert_eq!(reserve_state.term_borrowed_amount, 0);
+    assert_eq!(
+        reserve_state.total_supply().unwrap(),
+        BORROW_LIQUIDITY_AMOUNT as u128 + 12
+    );
+}
+
+#[tokio::test]
+async fn test_socialize_bad_debt() {
+    let mut setup = setup().await;
+    setup.deposit(4_000_000).await;
+    let instructions = setup.borrow_ixs(40_000_000).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+
+    async fn socialize_ixs(setup: &mut TestSetup) -> Vec<Instruction> {
+        let mut instructions = vec![setup.borrow_reserve.refresh_ix(&mut setup.ctx).await];
+        instructions.extend(setup.obligation.refresh_ixs(&mut setup.ctx).await);
+        let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+        let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+        instructions.push(
+            instruction::socialize_bad_debt(
+                setup.borrow_reserve.pubkey,
+                &reserve_state,
+                setup.obligation.pubkey,
+                &obligation_state,
+                setup.bump_seed,
+            )
+            .unwrap(),
+        );
+        instructions
+    }
+
+    // collateralが残っているうちは借入を消せない
+    let instructions = socialize_ixs(&mut setup).await;
+    let result = process(&mut setup.ctx, &instructions, &[]).await;
+    assert_lending_error(result, LendingError::ObligationHasDeposits);
+
+    // collateralの価格が1/20になり，liquidationでcollateralが全て差し押さえられる
+    setup.ctx.warp_to_slot(3).unwrap();
+    setup.collateral_oracle.set_price(&mut setup.ctx, 1).await;
+    let instructions = setup.liquidate_ixs(u64::MAX).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert!(obligation_state.deposits.is_empty());
+    let bad_debt = obligation_state.borrows[0].borrowed_amount;
+    assert_ne!(bad_debt, 0);
+
+    // insurance vaultにある分で先に埋め，残りを総供給量から減らす
+    setup.init_insurance_vault(1_000_000).await;
+    let insurance_vault = pda::insurance_vault(&setup.borrow_reserve.pubkey);
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    let total_supply = reserve_state.total_supply().unwrap();
+    let available_amount = reserve_state.liquidity.available_amount;
+    let instructions = socialize_ixs(&mut setup).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    assert!(obligation_state.borrows.is_empty());
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    assert_eq!(reserve_state.liquidity.borrowed_amount, 0);
+    assert_eq!(
+        reserve_state.liquidity.available_amount,
+        available_amount + 1_000_000
+    );
+    assert_eq!(
+        reserve_state.total_supply().unwrap(),
+        total_supply - bad_debt + 1_000_000
+    );
+    assert_eq!(token_balance(&mut setup.ctx, &insurance_vault).await, 0);
+
+    // 借入がなくなったobligationはもう対象にならない
+    let instructions = socialize_ixs(&mut setup).await;
+    let result = process(&mut setup.ctx, &instructions, &[]).await;
+    assert_lending_error(result, LendingError::InvalidObligationLiquidity);
+}
+
+#[tokio::test]
+async fn test_insurance_vault() {
+    let mut setup = setup().await;
+    let payer = setup.ctx.payer.pubkey();
+    let reserve_pubkey = setup.borrow_reserve.pubkey;
+    let user_liquidity = setup.borrow_reserve.user_liquidity;
+    let insurance_vault = pda::insurance_vault(&reserve_pubkey);
+
+    // 利息の半分とliquidation bonusの全てをinsuranceに回す
+    let mut config = test_reserve_config();
+    config.insurance_interest_bps = 5_000;
+    config.insurance_liquidation_bps = 10_000;
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    process(
+        &mut setup.ctx,
+        &[instruction::update_reserve_config(
+            config,
+            reserve_pubkey,
+            &reserve_state,
+            payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    setup.init_insurance_vault(1_000).await;
+    assert_eq!(token_balance(&mut setup.ctx, &insurance_vault).await, 1_000);
+
+    setup.deposit(4_000_000).await;
+    let instructions = setup.borrow_ixs(40_000_000).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+
+    // 利息の半分は総供給量に入らない
+    setup.ctx.warp_to_slot(100_000).unwrap();
+    setup
+        .collateral_oracle
+        .set_price(&mut setup.ctx, COLLATERAL_PRICE / 2)
+        .await;
+    setup
+        .borrow_oracle
+        .set_price(&mut setup.ctx, BORROW_PRICE)
+        .await;
+    let instructions = setup.obligation.refresh_ixs(&mut setup.ctx).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    let interest_amount = reserve_state.liquidity.borrowed_amount - 40_000_000;
+    assert_ne!(interest_amount, 0);
+    assert_eq!(
+        reserve_state.insurance_pending_amount() as u128,
+        interest_amount / 2
+    );
+    assert_eq!(
+        reserve_state.total_supply().unwrap(),
+        BORROW_LIQUIDITY_AMOUNT as u128 + interest_amount - interest_amount / 2
+    );
+
+    // liquidatorは5%のbonus分を返済に上乗せしてinsuranceに払う
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    let borrowed_amount = obligation_state.borrows[0].borrowed_amount;
+    let liquidity_before = token_balance(&mut setup.ctx, &user_liquidity).await;
+    let instructions = setup.liquidate_ixs(u64::MAX).await;
+    process(&mut setup.ctx, &instructions, &[]).await.unwrap();
+    let obligation_state = setup.obligation.get_state(&mut setup.ctx).await;
+    let repay_amount = (borrowed_amount - obligation_state.borrows[0].borrowed_amount) as u64;
+    let insurance_amount = repay_amount * 5 / 100;
+    assert_eq!(
+        token_balance(&mut setup.ctx, &user_liquidity).await,
+        liquidity_before - repay_amount - insurance_amount
+    );
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    let pending_amount = reserve_state.insurance_pending_amount();
+    assert_eq!(
+        pending_amount as u128,
+        interest_amount / 2 + insurance_amount as u128
+    );
+
+    // 引き出す前にsupplyにある取り分をvaultに移す
+    let total_supply = reserve_state.total_supply().unwrap();
+    let available_amount = reserve_state.liquidity.available_amount;
+    let liquidity_before = token_balance(&mut setup.ctx, &user_liquidity).await;
+    process(
+        &mut setup.ctx,
+        &[instruction::withdraw_insurance_vault(
+            u64::MAX,
+            reserve_pubkey,
+            &reserve_state,
+            setup.bump_seed,
+            user_liquidity,
+            payer,
+        )
+        .unwrap()],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        token_balance(&mut setup.ctx, &user_liquidity).await,
+        liquidity_before + pending_amount + 1_000
+    );
+    assert_eq!(token_balance(&mut setup.ctx, &insurance_vault).await, 0);
+    let reserve_state = setup.borrow_reserve.get_state(&mut setup.ctx).await;
+    assert_eq!(reserve_state.insurance_pending_amount(), 0);
+    assert_eq!(
+        reserve_state.liquidity.available_amount,
+        available_amount - pending_amount
+    );
+    assert_eq!(reserve_state.total_supply().unwrap(), total_supply);
+
+    // market ownerしか触れない
+    let other = Keypair::new();
+    let result = process(
+        &mut setup.ctx,
+        &[instruction::withdraw_insurance_vault(
+            1,
+            reserve_pubkey,
+            &reserve_state,
+            setup.bump_seed,
+            user_liquidity,
+            other.pubkey(),
+        )
+        .unwrap()],
+        &[&other],
+    )
+    .await;
+    assert_lending_error(result, LendingError::InvalidMarketOwner);
+}
@@ -0,0 +1,548 @@
+mod helpers;
+
+use anchor_spl::token::spl_token;
+use helpers::*;
+use lending_anchor::{
+    account_data::reserve::{borrow_rate_point::BorrowRatePoint, term_offer::TermOffer},
+    constants::{MAX_BORROW_RATE_POINTS, MAX_TERM_OFFERS},
+    errors::LendingError,
+    math::rate::Rate,
+};
+use lending_client::instruction;
+use mock_oracle::OracleStatus;
+use solana_program_test::tokio;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+const LIQUIDITY_AMOUNT: u64 = 1_000_000;
+const USER_LIQUIDITY_AMOUNT: u64 = 10_000_000;
+
+#[tokio::test]
+async fn test_init_reserve() {
+    let mut test = program_test();
+    let oracle = TestOracle::add(&mut test, 20, 0);
+    let mut ctx = start(test).await;
+    let lending_market = TestLendingMarket::init(&mut ctx).await;
+
+    let reserve = TestReserve::init(
+        &mut ctx,
+        &lending_market,
+        &oracle,
+        test_reserve_config(),
+        LIQUIDITY_AMOUNT,
+        USER_LIQUIDITY_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    let state = reserve.get_state(&mut ctx).await;
+    assert_eq!(state.lending_market, lending_market.pubkey);
+    assert_eq!(state.liquidity.mint_pubkey, reserve.liquidity_mint);
+    assert_eq!(state.liquidity.mint_decimals, LIQUIDITY_DECIMALS);
+    assert_eq!(state.liquidity.token_program_id, spl_token::ID);
+    assert_eq!(state.liquidity.oracle_pubkey, oracle.price);
+    assert_eq!(state.liquidity.available_amount, LIQUIDITY_AMOUNT);
+    assert_eq!(state.liquidity.market_price, 20);
+    assert_eq!(state.collateral.mint_total_supply, LIQUIDITY_AMOUNT);
+    assert_eq!(state.config.loan_to_value_ratio, 50);
+
+    assert_eq!(
+        token_balance(&mut ctx, &state.liquidity.supply_pubkey).await,
+        LIQUIDITY_AMOUNT
+    );
+    assert_eq!(
+        token_balance(&mut ctx, &reserve.user_liquidity).await,
+        USER_LIQUIDITY_AMOUNT
+    );
+    assert_eq!(
+        token_balance(&mut ctx, &reserve.user_collateral).await,
+        LIQUIDITY_AMOUNT
+    );
+}
+
+#[tokio::test]
+async fn test_init_reserve_mock_oracle() {
+    let mut ctx = start(program_test()).await;
+    let oracle = TestOracle::create(&mut ctx, 20).await;
+    let lending_market = TestLendingMarket::init(&mut ctx).await;
+
+    let reserve = TestReserve::init(
+        &mut ctx,
+        &lending_market,
+        &oracle,
+        test_reserve_config(),
+        LIQUIDITY_AMOUNT,
+        USER_LIQUIDITY_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    let state = reserve.get_state(&mut ctx).await;
+    assert_eq!(state.liquidity.oracle_pubkey, oracle.price);
+    assert_eq!(state.liquidity.market_price, 20);
+}
+
+#[tokio::test]
+async fn test_init_reserve_oracle_not_owned() {
+    let mut test = program_test();
+    let oracle = TestOracle::add(&mut test, 20, 0);
+    let mut ctx = start(test).await;
+    let lending_market = TestLendingMarket::init(&mut ctx).await;
+
+    // market の oracle program 以外が所有する product は使えない
+    let mut product = oracle.product_account();
+    product.owner = Pubkey::new_unique();
+    ctx.set_account(&oracle.product, &product.into());
+
+    let result = TestReserve::init(
+        &mut ctx,
+        &lending_market,
+        &oracle,
+        test_reserve_config(),
+        LIQUIDITY_AMOUNT,
+        USER_LIQUIDITY_AMOUNT,
+    )
+    .await;
+    assert_lending_error(result.map(|_| ()), LendingError::InvalidOracleConfig);
+}
+
+#[tokio::test]
+async fn test_init_reserve_invalid_config() {
+    let mut test = program_test();
+    let oracle = TestOracle::add(&mut test, 20, 0);
+    let mut ctx = start(test).await;
+    let lending_market = TestLendingMarket::init(&mut ctx).await;
+
+    let mut config = test_reserve_config();
+    config.liquidation_threshold = config.loan_to_value_ratio;
+    let result = TestReserve::init(
+        &mut ctx,
+        &lending_market,
+        &oracle,
+        config,
+        LIQUIDITY_AMOUNT,
+        USER_LIQUIDITY_AMOUNT,
+    )
+    .await;
+    assert_lending_error(result.map(|_| ()), LendingError::InvalidConfig);
+}
+
+#[tokio::test]
+async fn test_init_reserve_without_liquidity() {
+    let mut test = program_test();
+    let oracle = TestOracle::add(&mut test, 20, 0);
+    let mut ctx = start(test).await;
+    let lending_market = TestLendingMarket::init(&mut ctx).await;
+
+    let result = TestReserve::init(
+        &mut ctx,
+        &lending_market,
+        &oracle,
+        test_reserve_config(),
+        0,
+        USER_LIQUIDITY_AMOUNT,
+    )
+    .await;
+    assert_lending_error(
+        result.map(|_| ()),
+        LendingError::ReserveNotInitializedWithLiquidity,
+    );
+}
+
+#[tokio::test]
+async fn test_update_reserve_config() {
+    let mut test = program_test();
+    let oracle = TestOracle::add(&mut test, 20, 0);
+    let mut ctx = start(test).await;
+    let lending_market = TestLendingMarket::init(&mut ctx).await;
+    let reserve = TestReserve::init(
+        &mut ctx,
+        &lending_market,
+        &oracle,
+        test_reserve_config(),
+        LIQUIDITY_AMOUNT,
+        USER_LIQUIDITY_AMOUNT,
+    )
+    .await
+    .unwrap();
+    let state = reserve.get_state(&mut ctx).await;
+
+    let mut config = test_reserve_config();
+    config.loan_to_value_ratio = 70;
+    config.liquidation_threshold = 75;
+    process(
+        &mut ctx,
+        &[instruction::update_reserve_config(
+            config.clone(),
+            reserve.pubkey,
+            &state,
+            lending_market.owner.pubkey(),
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let state = reserve.get_state(&mut ctx).await;
+    assert_eq!(state.config.loan_to_value_ratio, 70);
+    assert_eq!(state.config.liquidation_threshold, 75);
+
+    // 検証に通らない設定は拒否される
+    config.loan_to_value_ratio = 100;
+    let result = process(
+        &mut ctx,
+        &[instruction::update_reserve_config(
+            config,
+            reserve.pubkey,
+            &state,
+            lending_market.owner.pubkey(),
+        )],
+        &[],
+    )
+    .await;
+    assert_lending_error(result, LendingError::InvalidConfig);
+
+    // 金利カーブはutilizationの順に並び、金利が下がってはいけない
+    let invalid_curves = [
+        vec![
+            BorrowRatePoint::new(0, 0),
+            BorrowRatePoint::new(9_000, 400),
+            BorrowRatePoint::new(8_000, 500),
+            BorrowRatePoint::new(10_000, 3_000),
+        ],
+        vec![
+            BorrowRatePoint::new(0, 400),
+            BorrowRatePoint::new(8_000, 200),
+            BorrowRatePoint::new(10_000, 3_000),
+        ],
+        vec![BorrowRatePoint::new(0, 0), BorrowRatePoint::new(8_000, 400)],
+        (0..=MAX_BORROW_RATE_POINTS as u16)
+            .map(|i| BorrowRatePoint::new(i * 1_000, 100))
+            .collect(),
+    ];
+    for borrow_rate_curve in invalid_curves {
+        let mut config = test_reserve_config();
+        config.borrow_rate_curve = borrow_rate_curve;
+        let result = process(
+            &mut ctx,
+            &[instruction::update_reserve_config(
+                config,
+                reserve.pubkey,
+                &state,
+                lending_market.owner.pubkey(),
+            )],
+            &[],
+        )
+        .await;
+        assert_lending_error(result, LendingError::InvalidConfig);
+    }
+
+    // term offerは期間の順に並び，penalty rateを超える金利は提示できない
+    let invalid_offers = [
+        vec![TermOffer::new(0, 500)],
+        vec![TermOffer::new(2_000, 500), TermOffer::new(1_000, 400)],
+        vec![TermOffer::new(1_000, 6_000)],
+        (1..=MAX_TERM_OFFERS as u64 + 1)
+            .map(|i| TermOffer::new(i * 1_000, 500))
+            .collect(),
+    ];
+    for term_offers in invalid_offers {
+        let mut config = test_reserve_config();
+        config.term_offers = term_offers;
+        config.term_penalty_rate_bps = 5_000;
+        let result = process(
+            &mut ctx,
+            &[instruction::update_reserve_config(
+                config,
+                reserve.pubkey,
+                &state,
+                lending_market.owner.pubkey(),
+            )],
+            &[],
+        )
+        .await;
+        assert_lending_error(result, LendingError::InvalidConfig);
+    }
+
+    // insuranceの取り分は100%まで
+    for (insurance_interest_bps, insurance_liquidation_bps) in [(10_001, 0), (0, 10_001)] {
+        let mut config = test_reserve_config();
+        config.insurance_interest_bps = insurance_interest_bps;
+        config.insurance_liquidation_bps = insurance_liquidation_bps;
+        let result = process(
+            &mut ctx,
+            &[instruction::update_reserve_config(
+                config,
+                reserve.pubkey,
+                &state,
+                lending_market.owner.pubkey(),
+            )],
+            &[],
+        )
+        .await;
+        assert_lending_error(result, LendingError::InvalidConfig);
+    }
+
+    // 最小のliquidation bonusは最大を超えられない
+    let mut config = test_reserve_config();
+    config.min_liquidation_bonus = config.liquidation_bonus + 1;
+    let result = process(
+        &mut ctx,
+        &[instruction::update_reserve_config(
+            config,
+            reserve.pubkey,
+            &state,
+            lending_market.owner.pubkey(),
+        )],
+        &[],
+    )
+    .await;
+    assert_lending_error(result, LendingError::InvalidConfig);
+
+    // market owner以外は変更できない
+    let not_owner = Keypair::new();
+    let result = process(
+        &mut ctx,
+        &[instruction::update_reserve_config(
+            test_reserve_config(),
+            reserve.pubkey,
+            &state,
+            not_owner.pubkey(),
+        )],
+        &[&not_owner],
+    )
+    .await;
+    assert_lending_error(result, LendingError::InvalidMarketOwner);
+}
+
+#[test]
+fn test_borrow_rate_curve() {
+    // 80%まで2%、90%で10%、100%で300%
+    let mut config = test_reserve_config();
+    config.borrow_rate_curve = vec![
+        BorrowRatePoint::new(0, 200),
+        BorrowRatePoint::new(8_000, 200),
+        BorrowRatePoint::new(9_000, 1_000),
+        BorrowRatePoint::new(10_000, 30_000),
+    ];
+    for (utilization_bps, rate_bps) in [
+        (0, 200),
+        (5_000, 200),
+        (8_000, 200),
+        (8_500, 600),
+        (9_000, 1_000),
+        (9_500, 15_500),
+        (10_000, 30_000),
+    ] {
+        assert_eq!(
+            config.borrow_rate(Rate::from_bps(utilization_bps)).unwrap(),
+            Rate::from_bps(rate_bps)
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_refresh_reserve() {
+    let mut test = program_test();
+    let oracle = TestOracle::add(&mut test, 20, 0);
+    let mut ctx = start(test).await;
+    let lending_market = TestLendingMarket::init(&mut ctx).await;
+    let reserve = TestReserve::init(
+        &mut ctx,
+        &lending_market,
+        &oracle,
+        test_reserve_config(),
+        LIQUIDITY_AMOUNT,
+        USER_LIQUIDITY_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    ctx.warp_to_slot(10).unwrap();
+    oracle.set_price(&mut ctx, 25).await;
+    let refresh_ix = reserve.refresh_ix(&mut ctx).await;
+    process(&mut ctx, &[refresh_ix], &[]).await.unwrap();
+
+    let state = reserve.get_state(&mut ctx).await;
+    assert_eq!(state.liquidity.market_price, 25);
+    assert_eq!(state.last_update.slot, 10);
+    assert!(!state.last_update.stale);
+}
+
+#[tokio::test]
+async fn test_refresh_reserve_stale_oracle() {
+    let mut test = program_test();
+    let oracle = TestOracle::add(&mut test, 20, 0);
+    let mut ctx = start(test).await;
+    let lending_market = TestLendingMarket::init(&mut ctx).await;
+    let reserve = TestReserve::init(
+        &mut ctx,
+        &lending_market,
+        &oracle,
+        test_reserve_config(),
+        LIQUIDITY_AMOUNT,
+        USER_LIQUIDITY_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    ctx.warp_to_slot(10).unwrap();
+    let refresh_ix = reserve.refresh_ix(&mut ctx).await;
+    let result = process(&mut ctx, &[refresh_ix], &[]).await;
+    assert_lending_error(result, LendingError::InvalidOracleConfig);
+}
+
+#[tokio::test]
+async fn test_refresh_reserve_halted_oracle() {
+    let mut test = program_test();
+    let oracle = TestOracle::add(&mut test, 20, 0);
+    let mut ctx = start(test).await;
+    let lending_market = TestLendingMarket::init(&mut ctx).await;
+    let reserve = TestReserve::init(
+        &mut ctx,
+        &lending_market,
+        &oracle,
+        test_reserve_config(),
+        LIQUIDITY_AMOUNT,
+        USER_LIQUIDITY_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    oracle.set_status(&mut ctx, OracleStatus::Halted).await;
+    let refresh_ix = reserve.refresh_ix(&mut ctx).await;
+    let result = process(&mut ctx, &[refresh_ix], &[]).await;
+    assert_lending_error(result, LendingError::InvalidOracleConfig);
+}
+
+#[tokio::test]
+async fn test_deposit_and_redeem() {
+    let mut test = program_test();
+    let oracle = TestOracle::add(&mut test, 20, 0);
+    let mut ctx = start(test).await;
+    let lending_market = TestLendingMarket::init(&mut ctx).await;
+    let reserve = TestReserve::init(
+        &mut ctx,
+        &lending_market,
+        &oracle,
+        test_reserve_config(),
+        LIQUIDITY_AMOUNT,
+        USER_LIQUIDITY_AMOUNT,
+    )
+    .await
+    .unwrap();
+    let bump_seed = lending_market.bump_seed(&mut ctx).await;
+    let payer = ctx.payer.pubkey();
+
+    let state = reserve.get_state(&mut ctx).await;
+    let refresh_ix = reserve.refresh_ix(&mut ctx).await;
+    process(
+        &mut ctx,
+        &[
+            refresh_ix.clone(),
+            instruction::deposit_reserve_liquidity(
+                500_000,
+                reserve.pubkey,
+                &state,
+                bump_seed,
+                reserve.user_liquidity,
+                reserve.user_collateral,
+                payer,
+            )
+            .unwrap(),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let state = reserve.get_state(&mut ctx).await;
+    assert_eq!(state.liquidity.available_amount, LIQUIDITY_AMOUNT + 500_000);
+    assert_eq!(
+        state.collateral.mint_total_supply,
+        LIQUIDITY_AMOUNT + 500_000
+    );
+    assert_eq!(
+        token_balance(&mut ctx, &reserve.user_liquidity).await,
+        USER_LIQUIDITY_AMOUNT - 500_000
+    );
+    assert_eq!(
+        token_balance(&mut ctx, &reserve.user_collateral).await,
+        LIQUIDITY_AMOUNT + 500_000
+    );
+
+    process(
+        &mut ctx,
+        &[
+            refresh_ix,
+            instruction::redeem_reserve_collateral(
+                300_000,
+                reserve.pubkey,
+                &state,
+                bump_seed,
+                reserve.user_collateral,
+                reserve.user_liquidity,
+                payer,
+            )
+            .unwrap(),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let state = reserve.get_state(&mut ctx).await;
+    assert_eq!(state.liquidity.available_amount, LIQUIDITY_AMOUNT + 200_000);
+    assert_eq!(
+        state.collateral.mint_total_supply,
+        LIQUIDITY_AMOUNT + 200_000
+    );
+    assert_eq!(
+        token_balance(&mut ctx, &reserve.user_liquidity).await,
+        USER_LIQUIDITY_AMOUNT - 200_000
+    );
+    assert_eq!(
+        token_balance(&mut ctx, &state.liquidity.supply_pubkey).await,
+        LIQUIDITY_AMOUNT + 200_000
+    );
+}
+
+#[tokio::test]
+async fn test_deposit_reserve_stale() {
+    let mut test = program_test();
+    let oracle = TestOracle::add(&mut test, 20, 0);
+    let mut ctx = start(test).await;
+    let lending_market = TestLendingMarket::init(&mut ctx).await;
+    let reserve = TestReserve::init(
+        &mut ctx,
+        &lending_market,
+        &oracle,
+        test_reserve_config(),
+        LIQUIDITY_AMOUNT,
+        USER_LIQUIDITY_AMOUNT,
+    )
+    .await
+    .unwrap();
+    let bump_seed = lending_market.bump_seed(&mut ctx).await;
+    let payer = ctx.payer.pubkey();
+
+    // refreshしていないreserveにはdepositできない
+    let state = reserve.get_state(&mut ctx).await;
+    let result = process(
+        &mut ctx,
+        &[instruction::deposit_reserve_liquidity(
+            500_000,
+            reserve.pubkey,
+            &state,
+            bump_seed,
+            reserve.user_liquidity,
+            reserve.user_collateral,
+            payer,
+        )
+        .unwrap()],
+        &[],
+    )
+    .await;
+    assert_lending_error(result, LendingError::ReserveStale);
+}
@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum MockOracleError {
+    #[msg("Oracle account is already initialized")]
+    AlreadyInitialized,
+
+    #[msg("Oracle account size is invalid")]
+    InvalidAccountSize,
+
+    #[msg("Oracle account is not a Pyth price account")]
+    InvalidPriceAccount,
+
+    #[msg("Product attributes do not fit in the account")]
+    AttributesTooLong,
+}
@@ -0,0 +1,163 @@
+//! Pyth product and price accounts whose price anyone can set.
+//! Only meant for localnet and program tests; never deploy it to a public cluster.
+pub mod errors;
+
+use crate::errors::MockOracleError;
+use anchor_lang::prelude::*;
+use pyth_sdk_solana::state::{
+    AccKey, AccountType, CorpAction, PriceAccount, PriceStatus, PriceType, ProductAccount, MAGIC,
+    PROD_ATTR_SIZE, VERSION_2,
+};
+use std::mem::size_of;
+
+declare_id!("9unXTMia7Zivwe9VwwNAfvUXjtur5oFKVBhN3mvPu5MZ");
+
+#[program]
+pub mod mock_oracle {
+    use super::*;
+
+    /// Write a product and a trading price into accounts created with this program as owner
+    pub fn init_oracle(
+        ctx: Context<InitOracle>,
+        quote_currency: String,
+        price: i64,
+        expo: i32,
+        conf: u64,
+    ) -> Result<()> {
+        let slot = Clock::get()?.slot;
+
+        let mut product_data = ctx.accounts.product.try_borrow_mut_data()?;
+        let product = load_zeroed_mut::<ProductAccount>(&mut product_data)?;
+        let mut attr = Vec::new();
+        for s in ["quote_currency", quote_currency.as_str()] {
+            let len = u8::try_from(s.len()).map_err(|_| MockOracleError::AttributesTooLong)?;
+            attr.push(len);
+            attr.extend_from_slice(s.as_bytes());
+        }
+        require_gte!(
+            PROD_ATTR_SIZE,
+            attr.len(),
+            MockOracleError::AttributesTooLong
+        );
+        product.magic = MAGIC;
+        product.ver = VERSION_2;
+        product.atype = AccountType::Product as u32;
+        product.size = (size_of::<ProductAccount>() - PROD_ATTR_SIZE + attr.len()) as u32;
+        product.px_acc = AccKey {
+            val: ctx.accounts.price.key().to_bytes(),
+        };
+        product.attr[..attr.len()].copy_from_slice(&attr);
+
+        let mut price_data = ctx.accounts.price.try_borrow_mut_data()?;
+        let price_account = load_zeroed_mut::<PriceAccount>(&mut price_data)?;
+        price_account.magic = MAGIC;
+        price_account.ver = VERSION_2;
+        price_account.atype = AccountType::Price as u32;
+        price_account.size = size_of::<PriceAccount>() as u32;
+        price_account.ptype = PriceType::Price;
+        price_account.expo = expo;
+        price_account.prod = AccKey {
+            val: ctx.accounts.product.key().to_bytes(),
+        };
+        price_account.agg.price = price;
+        price_account.agg.conf = conf;
+        price_account.agg.status = PriceStatus::Trading;
+        price_account.agg.corp_act = CorpAction::NoCorpAct;
+        publish(price_account, slot);
+        Ok(())
+    }
+
+    /// Publish a new aggregate price at the current slot
+    pub fn set_price(ctx: Context<UpdatePrice>, price: i64, conf: u64) -> Result<()> {
+        let slot = Clock::get()?.slot;
+        let mut data = ctx.accounts.price.try_borrow_mut_data()?;
+        let price_account = load_price_mut(&mut data)?;
+        price_account.agg.price = price;
+        price_account.agg.conf = conf;
+        publish(price_account, slot);
+        Ok(())
+    }
+
+    /// Change the aggregate status, e.g. to halt trading
+    pub fn set_status(ctx: Context<UpdatePrice>, status: OracleStatus) -> Result<()> {
+        let slot = Clock::get()?.slot;
+        let mut data = ctx.accounts.price.try_borrow_mut_data()?;
+        let price_account = load_price_mut(&mut data)?;
+        price_account.agg.status = status.into();
+        publish(price_account, slot);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitOracle<'info> {
+    /// CHECK: Pyth product account, zeroed and owned by this program
+    #[account(mut, owner = crate::ID)]
+    pub product: UncheckedAccount<'info>,
+
+    /// CHECK: Pyth price account, zeroed and owned by this program
+    #[account(mut, owner = crate::ID)]
+    pub price: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePrice<'info> {
+    /// CHECK: Pyth price account written by `init_oracle`
+    #[account(mut, owner = crate::ID)]
+    pub price: UncheckedAccount<'info>,
+}
+
+/// Pyth `PriceStatus` in a form the IDL can describe
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OracleStatus {
+    Unknown,
+    Trading,
+    Halted,
+    Auction,
+}
+
+impl From<OracleStatus> for PriceStatus {
+    fn from(status: OracleStatus) -> Self {
+        match status {
+            OracleStatus::Unknown => PriceStatus::Unknown,
+            OracleStatus::Trading => PriceStatus::Trading,
+            OracleStatus::Halted => PriceStatus::Halted,
+            OracleStatus::Auction => PriceStatus::Auction,
+        }
+    }
+}
+
+fn load_zeroed_mut<T: bytemuck::Pod>(data: &mut [u8]) -> Result<&mut T> {
+    require_eq!(
+        data.len(),
+        size_of::<T>(),
+        MockOracleError::InvalidAccountSize
+    );
+    require!(
+        data.iter().all(|byte| *byte == 0),
+        MockOracleError::AlreadyInitialized
+    );
+    bytemuck::try_from_bytes_mut(data).map_err(|_| ProgramError::InvalidAccountData.into())
+}
+
+fn load_price_mut(data: &mut [u8]) -> Result<&mut PriceAccount> {
+    require_eq!(
+        data.len(),
+        size_of::<PriceAccount>(),
+        MockOracleError::InvalidAccountSize
+    );
+    let price_account: &mut PriceAccount =
+        bytemuck::try_from_bytes_mut(data).map_err(|_| ProgramError::InvalidAccountData)?;
+    require!(
+        price_account.magic == MAGIC && price_account.atype == AccountType::Price as u32,
+        MockOracleError::InvalidPriceAccount
+    );
+    Ok(price_account)
+}
+
+/// 最新の価格として扱われるようにslotを揃える
+fn publish(price_account: &mut PriceAccount, slot: u64) {
+    price_account.valid_slot = slot;
+    price_account.last_slot = slot;
+    price_account.agg.pub_slot = slot;
+}
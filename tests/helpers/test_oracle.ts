@@ -1,11 +1,14 @@
-import { PublicKey, SystemProgram, AccountInfo } from "@solana/web3.js";
-import { BN } from "@project-serum/anchor";
-import { readKeypairFile } from "./util";
-import { readFileSync } from "fs";
-import { parsePriceData } from "@pythnetwork/client";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { BN, Program, workspace } from "@project-serum/anchor";
+import { MockOracle } from "../../target/types/mock_oracle";
+import { connection, getPayer } from "../common";
 
-export const SOL_PYTH_PRODUCT = "3Mnn2fX6rQyUsyELYms1sBJyChWofzSNRoqYzvgMVz5E";
-export const SOL_PYTH_PRICE = "J83w4HKfqxwcq3BEMMkPFSppX3gqekLyLJBexebFVkix";
+export const mockOracle = workspace.MockOracle as Program<MockOracle>;
+
+// Pyth ProductAccount / PriceAccount sizes
+const PRODUCT_SIZE = 512;
+const PRICE_SIZE = 3312;
+const EXPO = -8;
 
 export class TestOracle {
   constructor(
@@ -15,21 +18,40 @@ export class TestOracle {
   ) {}
 
   public static addSolOracle() {
-    return this.addOracle(new PublicKey(SOL_PYTH_PRODUCT), new PublicKey(SOL_PYTH_PRICE), new BN(20));
+    return this.addOracle(new BN(20));
   }
 
-  public static addOracle(productPubkey: PublicKey, pricePubkey: PublicKey, price: BN) {
-    const oracleProgramId = readKeypairFile("tests/fixtures/oracle_program_id.json");
-
-    // add pyth product account
-    // TODO
-
-    // add pyth price account
-    const filename = `tests/fixtures/${pricePubkey.toString()}.bin`;
-    const pythPriceData = readFileSync(filename);
-
-    const pythPrice = parsePriceData(pythPriceData);
+  /**
+   * Create Pyth product and price accounts owned by the mock oracle
+   * @param price - The price in whole quote units
+   */
+  public static async addOracle(price: BN) {
+    const payer = await getPayer();
+    const product = Keypair.generate();
+    const pythPrice = Keypair.generate();
+
+    const createAccount = async (account: Keypair, space: number) =>
+      SystemProgram.createAccount({
+        fromPubkey: payer.publicKey,
+        newAccountPubkey: account.publicKey,
+        lamports: await connection.getMinimumBalanceForRentExemption(space),
+        space,
+        programId: mockOracle.programId,
+      });
+
+    await mockOracle.methods
+      .initOracle("USD", scaled(price), EXPO, new BN(0))
+      .accounts({ product: product.publicKey, price: pythPrice.publicKey })
+      .preInstructions([await createAccount(product, PRODUCT_SIZE), await createAccount(pythPrice, PRICE_SIZE)])
+      .signers([payer, product, pythPrice])
+      .rpc();
+
+    return new TestOracle(product.publicKey, pythPrice.publicKey, price);
+  }
 
-    return new TestOracle(productPubkey, pricePubkey, price);
+  public async setPrice(price: BN) {
+    await mockOracle.methods.setPrice(scaled(price), new BN(0)).accounts({ price: this.pricePubkey }).rpc();
   }
 }
+
+const scaled = (price: BN) => price.mul(new BN(10).pow(new BN(-EXPO)));
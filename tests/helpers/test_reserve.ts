@@ -139,7 +139,7 @@ export class TestReserve {
   public async validateState() {
     const reserve = await program.account.reserve.fetch(this.pubkey);
     expect(reserve.lastUpdate.slot).toBeGreaterThan(0);
-    expect(reserve.version).toBe(constantOf("PROGRAM_VERSION"));
+    expect(reserve.version).toBe(constantOf("RESERVE_VERSION"));
     expect(reserve.lendingMarket).toEqual(this.lendingMarketPubkey);
     expect(reserve.liquidity.mintPubkey).toEqual(this.liquidityMintPubkey);
     expect(reserve.liquidity.supplyPubkey).toEqual(this.liquiditySupplyPubkey);
@@ -150,7 +150,7 @@ export class TestReserve {
     expect(reserve.config).toEqual(this.config);
     expect(reserve.liquidity.oraclePubkey).toEqual(this.liquidityOraclePubkey);
     expect(reserve.liquidity.cumulativeBorrowRateWads).toBe(new BN(1));
-    expect(reserve.liquidity.borrowedAmountWads).toBe(new BN(0));
+    expect(reserve.liquidity.borrowedAmount).toBe(new BN(0));
     expect(reserve.liquidity.availableAmount).toBeGreaterThan(0);
     expect(reserve.collateral.mintTotalSupply).toBeGreaterThan(0);
   }
@@ -169,14 +169,35 @@ export class TestReserve {
 }
 
 export interface ReserveConfig {
-  optimalUtilizationRate: number;
   loanToValueRatio: number;
   liquidationBonus: number;
   liquidationThreshold: number;
-  minBorrowRate: number;
-  optimalBorrowRate: number;
-  maxBorrowRate: number;
+  borrowRateCurve: BorrowRatePoint[];
   fees: ReserveFees;
+  isolated: boolean;
+  debtCeiling: BN;
+  borrowableInIsolation: boolean;
+  emodeCategory: number;
+  borrowWeight: number;
+  priceGuardBpsPerSlot: number;
+  priceGuardConfirmations: number;
+  twapFallbackMaxSlots: BN;
+  twapLiquidationBonus: number;
+  termOffers: TermOffer[];
+  termPenaltyRateBps: number;
+  insuranceInterestBps: number;
+  insuranceLiquidationBps: number;
+  minLiquidationBonus: number;
+}
+
+export interface BorrowRatePoint {
+  utilizationBps: number;
+  rateBps: number;
+}
+
+export interface TermOffer {
+  termSlots: BN;
+  rateBps: number;
 }
 
 export interface ReserveFees {
@@ -186,16 +207,31 @@ export interface ReserveFees {
 }
 
 export const TEST_RESERVE_CONFIG: ReserveConfig = {
-  optimalUtilizationRate: 80,
   loanToValueRatio: 50,
   liquidationBonus: 5,
   liquidationThreshold: 55,
-  minBorrowRate: 0,
-  optimalBorrowRate: 4,
-  maxBorrowRate: 30,
+  borrowRateCurve: [
+    { utilizationBps: 0, rateBps: 0 },
+    { utilizationBps: 8_000, rateBps: 400 },
+    { utilizationBps: 10_000, rateBps: 3_000 },
+  ],
   fees: {
     borrowFeeWad: 100_000_000_000,
     flashLoanFeeWad: 3_000_000_000_000_000,
     hostFeePercentage: 20,
   },
+  isolated: false,
+  debtCeiling: new BN(0),
+  borrowableInIsolation: false,
+  emodeCategory: 0,
+  borrowWeight: 10_000,
+  priceGuardBpsPerSlot: 0,
+  priceGuardConfirmations: 0,
+  twapFallbackMaxSlots: new BN(0),
+  twapLiquidationBonus: 0,
+  termOffers: [],
+  termPenaltyRateBps: 0,
+  insuranceInterestBps: 0,
+  insuranceLiquidationBps: 0,
+  minLiquidationBonus: 5,
 };
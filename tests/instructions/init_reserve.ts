@@ -16,7 +16,7 @@ describe("init_reserve", () => {
   beforeEach(async () => {
     lendingMarket = await (await TestLendingMarket.init()).createLendingMarket();
     userAccountsOwner = Keypair.generate();
-    oracle = TestOracle.addSolOracle();
+    oracle = await TestOracle.addSolOracle();
   });
 
   describe("proper initialization", () => {